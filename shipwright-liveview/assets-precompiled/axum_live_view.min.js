@@ -1 +1,1873 @@
-(()=>{"use strict";var e,t={579:function(e,t,n){var o=this&&this.__importDefault||function(e){return e&&e.__esModule?e:{default:e}};Object.defineProperty(t,"__esModule",{value:!0}),t.LiveView=void 0;const i=o(n(34));function r(e){if(null===document.getElementById("live-view-container"))return;var t;t=-1===location.protocol.indexOf("https")?"ws":"wss";const n=new WebSocket(`${t}://${window.location.host}${window.location.pathname}`);var o={};n.addEventListener("open",(()=>{!function(e,t){setInterval((()=>{t.debug&&console.time(a),u(e,{t:"h"})}),3e4)}(n,e)})),n.addEventListener("message",(t=>{!function(e,t,n,o){const i=JSON.parse(t.data);if("i"===i.t)n.viewState=i.d,p(e,n,o),function(e,t){const n=Object.values(l).map((e=>`[${e}]`)).join(", ");document.querySelectorAll(n).forEach((n=>{s(e,n,t)}))}(e,o);else if("r"===i.t){if(!n.viewState)return;if(!i.d)return;b(n.viewState,i.d),p(e,n,o)}else if("j"===i.t)for(const e of i.d)h(e);else"h"===i.t&&o.debug&&console.timeEnd(a)}(n,t,o,e)})),n.addEventListener("close",(()=>{!function(e){setTimeout((()=>{r(e)}),1e3)}(e)}))}t.LiveView=class{constructor(){this.options={debug:!1},r(this.options)}enableDebug(){this.options.debug=!0}disableDebug(){this.options.debug=!1}};const a="ping";function u(e,t,n){e.send(JSON.stringify(t))}const l={click:"axm-click",input:"axm-input",change:"axm-change",submit:"axm-submit",focus:"axm-focus",blur:"axm-blur",keydown:"axm-keydown",keyup:"axm-keyup",mouseenter:"axm-mouseenter",mouseover:"axm-mouseover",mouseleave:"axm-mouseleave",mouseout:"axm-mouseout",mousemove:"axm-mousemove"},c={keydown:"axm-window-keydown",keyup:"axm-window-keyup",focus:"axm-window-focus",blur:"axm-window-blur",scroll:"axm-scroll"};function s(e,t,n){t.hasAttribute(l.click)&&d(e,0,t,t,"click",l.click,(e=>({t:"click",m:e}))),(t instanceof HTMLInputElement||t instanceof HTMLTextAreaElement||t instanceof HTMLSelectElement)&&(t.hasAttribute(l.input)&&d(e,0,t,t,"input",l.input,(e=>({t:"input",m:e,d:{v:m(t)}}))),t.hasAttribute(l.change)&&d(e,0,t,t,"change",l.change,(e=>({t:"input",m:e,d:{v:m(t)}}))),t.hasAttribute(l.focus)&&d(e,0,t,t,"focus",l.focus,(e=>({t:"input",m:e,d:{v:m(t)}}))),t.hasAttribute(l.blur)&&d(e,0,t,t,"blur",l.blur,(e=>({t:"input",m:e,d:{v:m(t)}})))),t instanceof HTMLFormElement&&(t.hasAttribute(l.change)&&d(e,0,t,t,"change",l.change,(e=>{const n=new FormData(t);return{t:"form",m:e,d:{q:new URLSearchParams(n).toString()}}})),t.hasAttribute(l.submit)&&d(e,0,t,t,"submit",l.submit,(e=>{const n=new FormData(t);return{t:"form",m:e,d:{q:new URLSearchParams(n).toString()}}}))),[["mouseenter",l.mouseenter],["mouseover",l.mouseover],["mouseleave",l.mouseleave],["mouseout",l.mouseout],["mousemove",l.mousemove]].forEach((([n,o])=>{n&&o&&t.hasAttribute(o)&&d(e,0,t,t,n,o,((e,t)=>{if(t instanceof MouseEvent)return{t:"mouse",m:e,d:{cx:t.clientX,cy:t.clientY,px:t.pageX,py:t.pageY,ox:t.offsetX,oy:t.offsetY,mx:t.movementX,my:t.movementY,sx:t.screenX,sy:t.screenY}}}))})),[["keydown",l.keydown],["keyup",l.keyup]].forEach((([n,o])=>{n&&o&&t.hasAttribute(o)&&d(e,0,t,t,n,o,((e,n)=>{var o;if(n instanceof KeyboardEvent){if(t.hasAttribute("axm-key")&&(null===(o=null==t?void 0:t.getAttribute("axm-key"))||void 0===o?void 0:o.toLowerCase())!==n.key.toLowerCase())return;return{t:"key",m:e,d:{k:n.key,kc:n.code,a:n.altKey,c:n.ctrlKey,s:n.shiftKey,me:n.metaKey}}}}))}))}function d(e,t,n,o,i,r,a){var l=function(e,t){var n=v(e,"axm-debounce");return n?function(e,t){var n;return(...o)=>{n&&clearTimeout(n),n=setTimeout((()=>{e(...o)}),t)}}(t,n):(n=v(e,"axm-throttle"))?function(e,t){var n;return(...o)=>{n||(e(...o),n=setTimeout((()=>{n=null}),t))}}(t,n):t}(n,(t=>{t instanceof KeyboardEvent||t.preventDefault();const o=function(e,t){const n=e.getAttribute(t);if(n)try{return JSON.parse(n)}catch(e){return n}}(n,r);if(!o)return;const i=a(o,t);i&&u(e,i)}));document===o&&f.push({event:i,callback:l}),o.addEventListener(i,l)}var f=[];function m(e){if(e instanceof HTMLTextAreaElement)return e.value;if(e instanceof HTMLInputElement)return"radio"===e.getAttribute("type")||"checkbox"===e.getAttribute("type")?e.checked:e.value;if(e instanceof HTMLSelectElement)return e.hasAttribute("multiple")?Array.from(e.selectedOptions).map((e=>e.value)):e.value;throw"Input has no input value"}function v(e,t){const n=e.getAttribute(t);if(n){const e=parseInt(n,10);if(e)return e}return null}function p(e,t,n){if(!t.viewState)return;const o=function e(t){var n="";return t.f.forEach(((o,i)=>{if(n=n.concat(o),void 0===t.d)return;const r=t.d[i];if(null!=r)if("string"==typeof r)n=n.concat(r);else if("b"in r){const t=r.f;Object.values(r.b).forEach((o=>{const i={f:t,d:o};n=n.concat(e(i))}))}else n=n.concat(e(r))})),n}(t.viewState),r=document.querySelector("#live-view-container");r&&function(e,t,n){for(var o=0;o<f.length;o++){let e=f[o];e&&(document.removeEventListener(e.event,e.callback),f.splice(o,1))}(0,i.default)(t,n,{onNodeAdded:t=>(t instanceof Element&&s(e,t),t),onBeforeElUpdated:(e,t)=>{var n;return e instanceof HTMLInputElement&&t instanceof HTMLInputElement&&("radio"===t.getAttribute("type")||"checkbox"===t.getAttribute("type")?t.checked=e.checked:t.value=e.value),e instanceof HTMLTextAreaElement&&t instanceof HTMLTextAreaElement&&(t.value=e.value),e instanceof HTMLOptionElement&&t instanceof HTMLOptionElement&&(null===(n=t.closest("select"))||void 0===n?void 0:n.hasAttribute("multiple"))&&(t.selected=e.selected),e instanceof HTMLSelectElement&&t instanceof HTMLSelectElement&&!t.hasAttribute("multiple")&&(t.value=e.value),!0}});const r=Object.values(c).map((e=>`[${e}]`)).join(", ");document.querySelectorAll(r).forEach((t=>{!function(e,t,n){[["keydown",c.keydown],["keyup",c.keyup]].forEach((([n,o])=>{n&&o&&t.hasAttribute(o)&&d(e,0,t,document,n,o,((e,n)=>{var o;if(n instanceof KeyboardEvent){if(t.hasAttribute("axm-key")&&(null===(o=null==t?void 0:t.getAttribute("axm-key"))||void 0===o?void 0:o.toLowerCase())!==n.key.toLowerCase())return;return{t:"key",m:e,d:{k:n.key,kc:n.code,a:n.altKey,c:n.ctrlKey,s:n.shiftKey,me:n.metaKey}}}}))})),t.hasAttribute(c.focus)&&d(e,0,t,document,"focus",c.focus,(e=>({t:"window_focus",m:e}))),t.hasAttribute(c.blur)&&d(e,0,t,document,"blur",c.blur,(e=>({t:"window_blur",m:e}))),t.hasAttribute(c.scroll)&&d(e,0,t,document,"scroll",c.scroll,(e=>({t:"scroll",m:e,d:{sx:window.scrollX,sy:window.scrollY}})))}(e,t)}))}(e,r,o)}function b(e,t){function n(e,t){for(const[n,i]of Object.entries(t))if("string"==typeof i)e[n]=i;else if(null===i)delete e[n];else if("object"==typeof i){const t=e[n];if(void 0===t)continue;"d"in i?"string"==typeof t?e[n]=i:"d"in t?b(t,i):"b"in t?console.error("not implemented: b in current"):e[n]=i:"b"in i?"string"==typeof t?e[n]=i:"b"in t?o(t,i):e[n]={f:t.f,b:i.b}:"f"in i?"string"==typeof t?e[n]=i:"d"in t?b(t,i):"b"in t?console.error("not implemented: b in current, with f"):e[n]=i:console.error("unexpected diff value",i)}}function o(e,t){if(t.f&&(e.f=t.f),t.b)for(const[o,i]of Object.entries(t.b))if(null===i)delete e.b[o];else{const t=e.b[o];void 0===t||"string"==typeof t?e.b[o]=i:n(t,i)}}t.f&&(e.f=t.f),t.d&&null!==t.d&&n(e.d||{},t.d)}function h(e){const t=()=>{if("navigate_to"===e.kind.t){const t=e.kind.uri;t.startsWith("http")?window.location.href=t:window.location.pathname=t}else if("add_class"===e.kind.t){const{selector:t,klass:n}=e.kind;document.querySelectorAll(t).forEach((e=>{e.classList.add(n)}))}else if("remove_class"===e.kind.t){const{selector:t,klass:n}=e.kind;document.querySelectorAll(t).forEach((e=>{e.classList.remove(n)}))}else if("toggle_class"===e.kind.t){const{selector:t,klass:n}=e.kind;document.querySelectorAll(t).forEach((e=>{e.classList.toggle(n)}))}else if("clear_value"===e.kind.t){const{selector:t}=e.kind;document.querySelectorAll(t).forEach((e=>{(e instanceof HTMLInputElement||e instanceof HTMLSelectElement||e instanceof HTMLTextAreaElement)&&(e.value="")}))}else"set_title"===e.kind.t?document.title=e.kind.title:"history_push_state"===e.kind.t?window.history.pushState({},"",e.kind.uri):e.kind};e.delay_ms?setTimeout(t,e.delay_ms):t()}},34:(e,t,n)=>{n.r(t),n.d(t,{default:()=>b});var o,i="undefined"==typeof document?void 0:document,r=!!i&&"content"in i.createElement("template"),a=!!i&&i.createRange&&"createContextualFragment"in i.createRange();function u(e,t){var n,o,i=e.nodeName,r=t.nodeName;return i===r||(n=i.charCodeAt(0),o=r.charCodeAt(0),n<=90&&o>=97?i===r.toUpperCase():o<=90&&n>=97&&r===i.toUpperCase())}function l(e,t,n){e[n]!==t[n]&&(e[n]=t[n],e[n]?e.setAttribute(n,""):e.removeAttribute(n))}var c,s={OPTION:function(e,t){var n=e.parentNode;if(n){var o=n.nodeName.toUpperCase();"OPTGROUP"===o&&(o=(n=n.parentNode)&&n.nodeName.toUpperCase()),"SELECT"!==o||n.hasAttribute("multiple")||(e.hasAttribute("selected")&&!t.selected&&(e.setAttribute("selected","selected"),e.removeAttribute("selected")),n.selectedIndex=-1)}l(e,t,"selected")},INPUT:function(e,t){l(e,t,"checked"),l(e,t,"disabled"),e.value!==t.value&&(e.value=t.value),t.hasAttribute("value")||e.removeAttribute("value")},TEXTAREA:function(e,t){var n=t.value;e.value!==n&&(e.value=n);var o=e.firstChild;if(o){var i=o.nodeValue;if(i==n||!n&&i==e.placeholder)return;o.nodeValue=n}},SELECT:function(e,t){if(!t.hasAttribute("multiple")){for(var n,o,i=-1,r=0,a=e.firstChild;a;)if("OPTGROUP"===(o=a.nodeName&&a.nodeName.toUpperCase()))a=(n=a).firstChild;else{if("OPTION"===o){if(a.hasAttribute("selected")){i=r;break}r++}!(a=a.nextSibling)&&n&&(a=n.nextSibling,n=null)}e.selectedIndex=i}}},d=1,f=3,m=8;function v(){}function p(e){if(e)return e.getAttribute&&e.getAttribute("id")||e.id}const b=(c=function(e,t){var n,o,i,r,a=t.attributes;if(11!==t.nodeType&&11!==e.nodeType){for(var u=a.length-1;u>=0;u--)o=(n=a[u]).name,i=n.namespaceURI,r=n.value,i?(o=n.localName||o,e.getAttributeNS(i,o)!==r&&("xmlns"===n.prefix&&(o=n.name),e.setAttributeNS(i,o,r))):e.getAttribute(o)!==r&&e.setAttribute(o,r);for(var l=e.attributes,c=l.length-1;c>=0;c--)o=(n=l[c]).name,(i=n.namespaceURI)?(o=n.localName||o,t.hasAttributeNS(i,o)||e.removeAttributeNS(i,o)):t.hasAttribute(o)||e.removeAttribute(o)}},function(e,t,n){if(n||(n={}),"string"==typeof t)if("#document"===e.nodeName||"HTML"===e.nodeName||"BODY"===e.nodeName){var l=t;(t=i.createElement("html")).innerHTML=l}else b=(b=t).trim(),t=r?function(e){var t=i.createElement("template");return t.innerHTML=e,t.content.childNodes[0]}(b):a?function(e){return o||(o=i.createRange()).selectNode(i.body),o.createContextualFragment(e).childNodes[0]}(b):function(e){var t=i.createElement("body");return t.innerHTML=e,t.childNodes[0]}(b);else 11===t.nodeType&&(t=t.firstElementChild);var b,h=n.getNodeKey||p,y=n.onBeforeNodeAdded||v,g=n.onNodeAdded||v,A=n.onBeforeElUpdated||v,w=n.onElUpdated||v,k=n.onBeforeNodeDiscarded||v,E=n.onNodeDiscarded||v,x=n.onBeforeElChildrenUpdated||v,T=n.skipFromChildren||v,S=n.addChild||function(e,t){return e.appendChild(t)},N=!0===n.childrenOnly,L=Object.create(null),C=[];function O(e){C.push(e)}function M(e,t){if(e.nodeType===d)for(var n=e.firstChild;n;){var o=void 0;t&&(o=h(n))?O(o):(E(n),n.firstChild&&M(n,t)),n=n.nextSibling}}function H(e,t,n){!1!==k(e)&&(t&&t.removeChild(e),E(e),M(e,n))}function _(e){g(e);for(var t=e.firstChild;t;){var n=t.nextSibling,o=h(t);if(o){var i=L[o];i&&u(t,i)?(t.parentNode.replaceChild(i,t),I(i,t)):_(t)}else _(t);t=n}}function I(e,t,n){var o=h(t);if(o&&delete L[o],!n){if(!1===A(e,t))return;if(c(e,t),w(e),!1===x(e,t))return}"TEXTAREA"!==e.nodeName?function(e,t){var n,o,r,a,l,c=T(e),v=t.firstChild,p=e.firstChild;e:for(;v;){for(a=v.nextSibling,n=h(v);!c&&p;){if(r=p.nextSibling,v.isSameNode&&v.isSameNode(p)){v=a,p=r;continue e}o=h(p);var b=p.nodeType,g=void 0;if(b===v.nodeType&&(b===d?(n?n!==o&&((l=L[n])?r===l?g=!1:(e.insertBefore(l,p),o?O(o):H(p,e,!0),p=l):g=!1):o&&(g=!1),(g=!1!==g&&u(p,v))&&I(p,v)):b!==f&&b!=m||(g=!0,p.nodeValue!==v.nodeValue&&(p.nodeValue=v.nodeValue))),g){v=a,p=r;continue e}o?O(o):H(p,e,!0),p=r}if(n&&(l=L[n])&&u(l,v))c||S(e,l),I(l,v);else{var A=y(v);!1!==A&&(A&&(v=A),v.actualize&&(v=v.actualize(e.ownerDocument||i)),S(e,v),_(v))}v=a,p=r}!function(e,t,n){for(;t;){var o=t.nextSibling;(n=h(t))?O(n):H(t,e,!0),t=o}}(e,p,o);var w=s[e.nodeName];w&&w(e,t)}(e,t):s.TEXTAREA(e,t)}!function e(t){if(t.nodeType===d||11===t.nodeType)for(var n=t.firstChild;n;){var o=h(n);o&&(L[o]=n),e(n),n=n.nextSibling}}(e);var U,j,P=e,V=P.nodeType,R=t.nodeType;if(!N)if(V===d)R===d?u(e,t)||(E(e),P=function(e,t){for(var n=e.firstChild;n;){var o=n.nextSibling;t.appendChild(n),n=o}return t}(e,(U=t.nodeName,(j=t.namespaceURI)&&"http://www.w3.org/1999/xhtml"!==j?i.createElementNS(j,U):i.createElement(U)))):P=t;else if(V===f||V===m){if(R===V)return P.nodeValue!==t.nodeValue&&(P.nodeValue=t.nodeValue),P;P=t}if(P===t)E(e);else{if(t.isSameNode&&t.isSameNode(P))return;if(I(P,t,N),C)for(var K=0,D=C.length;K<D;K++){var q=L[C[K]];q&&H(q,q.parentNode,!1)}}return!N&&P!==e&&e.parentNode&&(P.actualize&&(P=P.actualize(e.ownerDocument||i)),e.parentNode.replaceChild(P,e)),P})}},n={};function o(e){var i=n[e];if(void 0!==i)return i.exports;var r=n[e]={exports:{}};return t[e].call(r.exports,r,r.exports,o),r.exports}o.n=e=>{var t=e&&e.__esModule?()=>e.default:()=>e;return o.d(t,{a:t}),t},o.d=(e,t)=>{for(var n in t)o.o(t,n)&&!o.o(e,n)&&Object.defineProperty(e,n,{enumerable:!0,get:t[n]})},o.o=(e,t)=>Object.prototype.hasOwnProperty.call(e,t),o.r=e=>{"undefined"!=typeof Symbol&&Symbol.toStringTag&&Object.defineProperty(e,Symbol.toStringTag,{value:"Module"}),Object.defineProperty(e,"__esModule",{value:!0})},e=o(579),window.liveView=new e.LiveView})();
\ No newline at end of file
+(() => {
+"use strict";
+const textEncoder = new TextEncoder();
+const textDecoder = new TextDecoder();
+function encodeMsgpack(value) {
+const writer = new Writer();
+writer.value(value);
+return writer.finish();
+}
+function decodeMsgpack(data) {
+const bytes = data instanceof Uint8Array ? data : new Uint8Array(data);
+const reader = new Reader(bytes);
+const value = reader.value();
+if (reader.offset !== bytes.length) {
+throw new Error("trailing bytes after MessagePack value");
+}
+return value;
+}
+class Writer {
+constructor() {
+this.bytes = new Uint8Array(64);
+this.view = new DataView(this.bytes.buffer);
+this.length = 0;
+}
+reserve(n) {
+if (this.length + n <= this.bytes.length) {
+return;
+}
+let size = this.bytes.length * 2;
+while (size < this.length + n) {
+size *= 2;
+}
+const bytes = new Uint8Array(size);
+bytes.set(this.bytes.subarray(0, this.length));
+this.bytes = bytes;
+this.view = new DataView(bytes.buffer);
+}
+u8(n) {
+this.reserve(1);
+this.view.setUint8(this.length, n);
+this.length += 1;
+}
+head(tag, n, size) {
+this.u8(tag);
+this.reserve(size);
+switch (size) {
+case 1:
+this.view.setUint8(this.length, n);
+break;
+case 2:
+this.view.setUint16(this.length, n);
+break;
+case 4:
+this.view.setUint32(this.length, n);
+break;
+default:
+this.view.setBigUint64(this.length, BigInt.asUintN(64, BigInt(n)));
+}
+this.length += size;
+}
+raw(bytes) {
+this.reserve(bytes.length);
+this.bytes.set(bytes, this.length);
+this.length += bytes.length;
+}
+sized(len, fix, fixMax, tags) {
+if (len <= fixMax) {
+this.u8(fix | len);
+} else if (tags[0] !== null && len <= 0xff) {
+this.head(tags[0], len, 1);
+} else if (len <= 0xffff) {
+this.head(tags[1], len, 2);
+} else {
+this.head(tags[2], len, 4);
+}
+}
+value(value) {
+if (value === null || value === undefined) {
+this.u8(0xc0);
+} else if (value === false) {
+this.u8(0xc2);
+} else if (value === true) {
+this.u8(0xc3);
+} else if (typeof value === "number") {
+this.number(value);
+} else if (typeof value === "bigint") {
+this.integer(value);
+} else if (typeof value === "string") {
+const bytes = textEncoder.encode(value);
+this.sized(bytes.length, 0xa0, 31, [0xd9, 0xda, 0xdb]);
+this.raw(bytes);
+} else if (value instanceof Uint8Array) {
+if (value.length <= 0xff) {
+this.head(0xc4, value.length, 1);
+} else if (value.length <= 0xffff) {
+this.head(0xc5, value.length, 2);
+} else {
+this.head(0xc6, value.length, 4);
+}
+this.raw(value);
+} else if (Array.isArray(value)) {
+this.sized(value.length, 0x90, 15, [null, 0xdc, 0xdd]);
+for (const item of value) {
+this.value(item);
+}
+} else {
+const entries = Object.entries(value).filter(([, item]) => item !== undefined);
+this.sized(entries.length, 0x80, 15, [null, 0xde, 0xdf]);
+for (const [key, item] of entries) {
+this.value(key);
+this.value(item);
+}
+}
+}
+number(n) {
+if (Number.isSafeInteger(n)) {
+this.integer(n);
+} else {
+this.u8(0xcb);
+this.reserve(8);
+this.view.setFloat64(this.length, n);
+this.length += 8;
+}
+}
+integer(n) {
+if (n >= 0) {
+if (n <= 0x7f) {
+this.u8(Number(n));
+} else if (n <= 0xff) {
+this.head(0xcc, Number(n), 1);
+} else if (n <= 0xffff) {
+this.head(0xcd, Number(n), 2);
+} else if (n <= 0xffffffff) {
+this.head(0xce, Number(n), 4);
+} else {
+this.head(0xcf, n, 8);
+}
+} else if (n >= -32) {
+this.u8(0x100 + Number(n));
+} else if (n >= -0x80) {
+this.head(0xd0, 0x100 + Number(n), 1);
+} else if (n >= -0x8000) {
+this.head(0xd1, 0x10000 + Number(n), 2);
+} else if (n >= -0x80000000) {
+this.head(0xd2, 0x100000000 + Number(n), 4);
+} else {
+this.head(0xd3, n, 8);
+}
+}
+finish() {
+return this.bytes.slice(0, this.length);
+}
+}
+class Reader {
+constructor(bytes) {
+this.bytes = bytes;
+this.view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+this.offset = 0;
+}
+take(n) {
+if (this.offset + n > this.bytes.length) {
+throw new Error("unexpected end of MessagePack value");
+}
+const offset = this.offset;
+this.offset += n;
+return offset;
+}
+u8() {
+return this.view.getUint8(this.take(1));
+}
+u16() {
+return this.view.getUint16(this.take(2));
+}
+u32() {
+return this.view.getUint32(this.take(4));
+}
+int64(n) {
+return n >= BigInt(Number.MIN_SAFE_INTEGER) && n <= BigInt(Number.MAX_SAFE_INTEGER) ? Number(n) : n;
+}
+str(len) {
+const start = this.take(len);
+return textDecoder.decode(this.bytes.subarray(start, start + len));
+}
+bin(len) {
+const start = this.take(len);
+return this.bytes.slice(start, start + len);
+}
+array(len) {
+const array = new Array(len);
+for (let i = 0; i < len; i++) {
+array[i] = this.value();
+}
+return array;
+}
+map(len) {
+const map = {};
+for (let i = 0; i < len; i++) {
+const key = this.value();
+map[key] = this.value();
+}
+return map;
+}
+value() {
+const tag = this.u8();
+if (tag <= 0x7f) {
+return tag;
+}
+if (tag >= 0xe0) {
+return tag - 0x100;
+}
+if (tag >= 0xa0 && tag <= 0xbf) {
+return this.str(tag & 0x1f);
+}
+if (tag >= 0x90 && tag <= 0x9f) {
+return this.array(tag & 0x0f);
+}
+if (tag >= 0x80 && tag <= 0x8f) {
+return this.map(tag & 0x0f);
+}
+switch (tag) {
+case 0xc0:
+return null;
+case 0xc2:
+return false;
+case 0xc3:
+return true;
+case 0xc4:
+return this.bin(this.u8());
+case 0xc5:
+return this.bin(this.u16());
+case 0xc6:
+return this.bin(this.u32());
+case 0xca:
+return this.view.getFloat32(this.take(4));
+case 0xcb:
+return this.view.getFloat64(this.take(8));
+case 0xcc:
+return this.u8();
+case 0xcd:
+return this.u16();
+case 0xce:
+return this.u32();
+case 0xcf:
+return this.int64(this.view.getBigUint64(this.take(8)));
+case 0xd0:
+return this.view.getInt8(this.take(1));
+case 0xd1:
+return this.view.getInt16(this.take(2));
+case 0xd2:
+return this.view.getInt32(this.take(4));
+case 0xd3:
+return this.int64(this.view.getBigInt64(this.take(8)));
+case 0xd9:
+return this.str(this.u8());
+case 0xda:
+return this.str(this.u16());
+case 0xdb:
+return this.str(this.u32());
+case 0xdc:
+return this.array(this.u16());
+case 0xdd:
+return this.array(this.u32());
+case 0xde:
+return this.map(this.u16());
+case 0xdf:
+return this.map(this.u32());
+default:
+throw new Error(`unsupported MessagePack type 0x${tag.toString(16)}`);
+}
+}
+}
+class LiveViewEventSource extends EventTarget {
+constructor(url) {
+super();
+this.url = url;
+this.readyState = WebSocket.CONNECTING;
+this.session = null;
+this.queue = Promise.resolve();
+this.source = new EventSource(url);
+this.source.addEventListener("session", (event) => {
+this.session = event.data;
+this.readyState = WebSocket.OPEN;
+this.dispatchEvent(new Event("open"));
+});
+this.source.addEventListener("message", (event) => {
+this.dispatchEvent(new MessageEvent("message", { data: event.data }));
+});
+this.source.addEventListener("close", (event) => {
+const { c, r } = JSON.parse(event.data);
+this.close(c, r);
+});
+this.source.addEventListener("error", () => this.close(1006, ""));
+}
+send(data) {
+if (this.readyState !== WebSocket.OPEN) {
+return;
+}
+const session = this.session;
+const contentType = typeof data === "string" ? "text/plain;charset=utf-8" : "application/octet-stream";
+this.queue = this.queue
+.then(() =>
+fetch(this.url, {
+method: "POST",
+headers: { "x-live-view-session": session, "content-type": contentType },
+body: data,
+}),
+)
+.then(
+(response) => {
+if (response.status === 404) {
+this.close(1006, "");
+}
+},
+() => this.close(1006, ""),
+);
+}
+close(code = 1000, reason = "") {
+if (this.readyState === WebSocket.CLOSED) {
+return;
+}
+this.readyState = WebSocket.CLOSED;
+this.source.close();
+this.dispatchEvent(new CloseEvent("close", { code, reason }));
+}
+}
+const HEARTBEAT_INTERVAL_MS = 30000;
+const RECONNECT_AFTER_MS = 1000;
+const MAX_RECONNECT_AFTER_MS = 30000;
+const MAX_REMOUNTS = 5;
+const USER_ACTIVITY = ["pointerdown", "keydown", "focus"];
+const PING_TIMER = "ping";
+const RELOADED_AT = "axm-reloaded-at";
+const RELOAD_AT_MOST_EVERY_MS = 10000;
+const CLOSE_GOING_AWAY = 1001;
+const CLOSE_ABNORMAL = 1006;
+const CLOSE_VIEW_ERROR = 4000;
+const CLOSE_REMOUNT = 4001;
+const CLOSE_FORBIDDEN = 4002;
+const CLOSE_IDLE_TIMEOUT = 4004;
+const CLOSE_DISCONNECTED = 4005;
+class Connection {
+constructor(views, options) {
+this.options = options;
+this.views = new Map(views.map((view) => [view.id, view]));
+this.multiplexed = views[0].id !== null;
+this.csrfToken = views[0].el.getAttribute("data-csrf-token");
+this.sse = false;
+this.msgpack = false;
+this.socket = null;
+this.reconnectAfter = null;
+this.reconnects = 0;
+this.pinging = false;
+for (const view of views) {
+view.connection = this;
+}
+}
+connect() {
+const query = new URLSearchParams();
+if (this.csrfToken) {
+query.set("_csrf", this.csrfToken);
+}
+let socket;
+this.msgpack = !this.sse;
+if (this.sse) {
+socket = new LiveViewEventSource(withQuery(window.location.pathname, query));
+} else {
+query.set("encoding", "msgpack");
+const protocol = window.location.protocol === "https:" ? "wss" : "ws";
+const path = withQuery(window.location.pathname, query);
+socket = new WebSocket(`${protocol}://${window.location.host}${path}`);
+socket.binaryType = "arraybuffer";
+}
+this.socket = socket;
+let opened = false;
+let heartbeat;
+socket.addEventListener("open", () => {
+opened = true;
+heartbeat = setInterval(() => this.ping(), HEARTBEAT_INTERVAL_MS);
+});
+socket.addEventListener("message", (event) => this.receive(event.data));
+socket.addEventListener("close", (event) => {
+clearInterval(heartbeat);
+this.socket = null;
+if (!opened && !this.sse && event.code === CLOSE_ABNORMAL) {
+this.sse = true;
+this.connect();
+return;
+}
+this.closed(this, "live view socket", event.code, event.reason, () => this.connect());
+});
+}
+send(view, msg) {
+if (!this.socket || this.socket.readyState !== WebSocket.OPEN) {
+return;
+}
+if (!this.msgpack) {
+if (this.multiplexed && view) {
+msg = { v: view.id, ...msg };
+}
+this.socket.send(JSON.stringify(msg));
+return;
+}
+this.sendBinary(view, encodeMsgpack(msg));
+}
+sendBinary(view, data) {
+if (!this.socket || this.socket.readyState !== WebSocket.OPEN) {
+return;
+}
+if (!this.multiplexed) {
+this.socket.send(data);
+return;
+}
+const id = new TextEncoder().encode(view ? view.id : "");
+const tagged = new Uint8Array(1 + id.length + data.length);
+tagged[0] = id.length;
+tagged.set(id, 1);
+tagged.set(data, 1 + id.length);
+this.socket.send(tagged);
+}
+join(view) {
+if (this.socket && this.socket.readyState === WebSocket.OPEN) {
+this.socket.send(JSON.stringify({ v: view.id, t: "join" }));
+}
+}
+receive(data) {
+const msg = parseMessage(data, this.multiplexed);
+const view = this.views.get(this.multiplexed ? msg.v : null);
+if (!view) {
+return;
+}
+if (msg.t === "c") {
+this.viewClosed(view, msg.d);
+return;
+}
+if (msg.t === "i") {
+this.reconnects = 0;
+view.reconnects = 0;
+}
+view.receive(msg);
+}
+ping() {
+if (this.options.debug) {
+console.time(PING_TIMER);
+}
+this.pinging = true;
+this.send(null, { t: "h" });
+}
+pong() {
+if (this.pinging && this.options.debug) {
+console.timeEnd(PING_TIMER);
+}
+this.pinging = false;
+}
+viewClosed(view, { c: code, r: reason }) {
+if (code === CLOSE_GOING_AWAY) {
+return;
+}
+this.closed(view, `live view ${view.id}`, code, reason, () => this.join(view));
+}
+closed(target, what, code, reason, reconnect) {
+switch (code) {
+case CLOSE_VIEW_ERROR:
+break;
+case CLOSE_FORBIDDEN:
+if (!reloadOnce()) {
+console.error(`${what} rejected: ${reason}`);
+}
+break;
+case CLOSE_DISCONNECTED:
+console.error(`${what} disconnected by the server`);
+break;
+case CLOSE_IDLE_TIMEOUT:
+onUserActivity(reconnect);
+break;
+default: {
+const attempts = target.reconnects;
+if ((code === CLOSE_REMOUNT || target !== this) && attempts >= MAX_REMOUNTS) {
+console.error(`${what} failed ${attempts} times, reload the page to try again`);
+break;
+}
+target.reconnects += 1;
+setTimeout(reconnect, this.reconnectDelay(attempts));
+}
+}
+}
+reconnectDelay(attempts) {
+if (this.reconnectAfter !== null) {
+const after = this.reconnectAfter;
+this.reconnectAfter = null;
+return after;
+}
+return Math.min(RECONNECT_AFTER_MS * 2 ** attempts, MAX_RECONNECT_AFTER_MS);
+}
+}
+function parseMessage(data, multiplexed) {
+if (typeof data === "string") {
+return JSON.parse(data);
+}
+const bytes = new Uint8Array(data);
+if (!multiplexed) {
+return decodeMsgpack(bytes);
+}
+const len = bytes[0];
+const id = new TextDecoder().decode(bytes.subarray(1, 1 + len));
+return { v: id, ...decodeMsgpack(bytes.subarray(1 + len)) };
+}
+function withQuery(path, query) {
+const search = query.toString();
+return search ? `${path}?${search}` : path;
+}
+function reloadOnce() {
+const now = Date.now();
+const last = Number(window.sessionStorage.getItem(RELOADED_AT));
+if (now - last < RELOAD_AT_MOST_EVERY_MS) {
+return false;
+}
+window.sessionStorage.setItem(RELOADED_AT, String(now));
+window.location.reload();
+return true;
+}
+function onUserActivity(f) {
+const listener = () => {
+for (const event of USER_ACTIVITY) {
+window.removeEventListener(event, listener);
+}
+f();
+};
+for (const event of USER_ACTIVITY) {
+window.addEventListener(event, listener);
+}
+}
+const boundLinks = new WeakSet();
+function livePatch(view, uri) {
+if (!navigates(view)) {
+window.location.assign(uri);
+return;
+}
+window.history.pushState({}, "", uri);
+view.path = window.location.pathname;
+view.send({ t: "lp", u: currentUri() });
+}
+function liveRedirect(view, uri) {
+if (!navigates(view)) {
+window.location.assign(uri);
+return;
+}
+window.history.pushState({}, "", uri);
+view.path = window.location.pathname;
+view.send({ t: "lr", u: currentUri() });
+}
+function bindLinks(view) {
+if (!navigates(view)) {
+return;
+}
+view.el.querySelectorAll("a[axm-patch], a[axm-redirect]").forEach((el) => {
+if (boundLinks.has(el)) {
+return;
+}
+boundLinks.add(el);
+el.addEventListener("click", (e) => followLink(view, el, e));
+});
+}
+function followLink(view, el, e) {
+if (e.defaultPrevented || e.button !== 0 || e.metaKey || e.ctrlKey || e.shiftKey || e.altKey) {
+return;
+}
+const url = new URL(el.href, window.location.href);
+if (url.origin !== window.location.origin) {
+return;
+}
+e.preventDefault();
+const uri = `${url.pathname}${url.search}${url.hash}`;
+if (el.hasAttribute("axm-redirect")) {
+liveRedirect(view, uri);
+} else if (el.hasAttribute("axm-patch")) {
+livePatch(view, uri);
+}
+}
+function listenForHistory(view) {
+if (!navigates(view)) {
+return;
+}
+view.path = window.location.pathname;
+window.addEventListener("popstate", () => {
+const t = window.location.pathname === view.path ? "lp" : "lr";
+view.path = window.location.pathname;
+view.send({ t, u: currentUri() });
+});
+}
+function navigates(view) {
+return view.id === null;
+}
+function currentUri() {
+return `${window.location.pathname}${window.location.search}`;
+}
+const FOCUSABLE = [
+"a[href]",
+"area[href]",
+"button:not([disabled])",
+"input:not([disabled]):not([type=hidden])",
+"select:not([disabled])",
+"textarea:not([disabled])",
+"iframe",
+"[contenteditable=true]",
+"[tabindex]:not([tabindex='-1'])",
+].join(", ");
+function runCommands(view, commands) {
+for (const command of commands) {
+if (command.delay_ms) {
+setTimeout(() => runCommand(view, command.kind), command.delay_ms);
+} else {
+runCommand(view, command.kind);
+}
+}
+}
+function runCommand(view, kind) {
+switch (kind.t) {
+case "navigate_to":
+window.location.assign(kind.uri);
+break;
+case "add_class":
+forEach(kind.selector, (el) => el.classList.add(kind.klass));
+break;
+case "remove_class":
+forEach(kind.selector, (el) => el.classList.remove(kind.klass));
+break;
+case "toggle_class":
+forEach(kind.selector, (el) => el.classList.toggle(kind.klass));
+break;
+case "clear_value":
+forEach(kind.selector, (el) => {
+if (el instanceof HTMLInputElement || el instanceof HTMLSelectElement || el instanceof HTMLTextAreaElement) {
+el.value = "";
+}
+});
+break;
+case "set_title":
+document.title = kind.title;
+break;
+case "history_push_state":
+window.history.pushState({}, "", kind.uri);
+break;
+case "live_patch":
+livePatch(view, kind.uri);
+break;
+case "live_redirect":
+liveRedirect(view, kind.uri);
+break;
+case "show":
+forEach(kind.selector, (el) => {
+el.style.display = kind.display || "";
+});
+break;
+case "hide":
+forEach(kind.selector, (el) => {
+el.style.display = "none";
+});
+break;
+case "transition":
+forEach(kind.selector, (el) => transition(el, kind));
+break;
+case "focus": {
+const el = document.querySelector(kind.selector);
+if (el) {
+el.focus();
+}
+break;
+}
+case "focus_first": {
+const el = document.querySelector(kind.selector);
+const focusable = el && el.querySelector(FOCUSABLE);
+if (focusable) {
+focusable.focus();
+}
+break;
+}
+case "blur":
+forEach(kind.selector, (el) => el.blur());
+break;
+case "set_attribute":
+forEach(kind.selector, (el) => el.setAttribute(kind.name, kind.value));
+break;
+case "remove_attribute":
+forEach(kind.selector, (el) => el.removeAttribute(kind.name));
+break;
+case "dispatch":
+forEach(kind.selector, (el) => {
+el.dispatchEvent(new CustomEvent(kind.event, { bubbles: true, detail: kind.detail }));
+});
+break;
+case "set_cookie":
+document.cookie = kind.cookie;
+break;
+case "push_event":
+dispatchHookEvent(view, kind.event, kind.payload);
+break;
+default:
+console.error("unknown JS command", kind);
+}
+}
+function forEach(selector, f) {
+document.querySelectorAll(selector).forEach(f);
+}
+function transition(el, { classes, start, end, duration_ms }) {
+const transitionClasses = splitClasses(classes);
+const startClasses = splitClasses(start);
+const endClasses = splitClasses(end);
+el.classList.add(...transitionClasses, ...startClasses);
+requestAnimationFrame(() => {
+requestAnimationFrame(() => {
+el.classList.remove(...startClasses);
+el.classList.add(...endClasses);
+});
+});
+setTimeout(() => el.classList.remove(...transitionClasses), duration_ms);
+}
+function splitClasses(classes) {
+return classes.split(" ").filter((klass) => klass.length > 0);
+}
+const BINDINGS = {
+click: "axm-click",
+input: "axm-input",
+change: "axm-change",
+submit: "axm-submit",
+focus: "axm-focus",
+blur: "axm-blur",
+keydown: "axm-keydown",
+keyup: "axm-keyup",
+mouseenter: "axm-mouseenter",
+mouseover: "axm-mouseover",
+mouseleave: "axm-mouseleave",
+mouseout: "axm-mouseout",
+mousemove: "axm-mousemove",
+};
+const WINDOW_BINDINGS = {
+keydown: "axm-window-keydown",
+keyup: "axm-window-keyup",
+focus: "axm-window-focus",
+blur: "axm-window-blur",
+scroll: "axm-scroll",
+};
+const BINDINGS_SELECTOR = Object.values(BINDINGS)
+.map((attr) => `[${attr}]`)
+.join(", ");
+const WINDOW_BINDINGS_SELECTOR = Object.values(WINDOW_BINDINGS)
+.map((attr) => `[${attr}]`)
+.join(", ");
+const boundEvents = new WeakMap();
+function parseBinding(value) {
+if (value.startsWith("js:")) {
+return { commands: JSON.parse(percentDecode(value.slice(3))) };
+}
+try {
+return { message: JSON.parse(value) };
+} catch {
+return { message: value };
+}
+}
+function percentDecode(value) {
+return value.replace(/(%[0-9a-fA-F]{2})+/g, (escaped) => {
+try {
+return decodeURIComponent(escaped);
+} catch {
+return escaped;
+}
+});
+}
+function bindElements(view) {
+view.el.querySelectorAll(BINDINGS_SELECTOR).forEach((el) => bindElement(view, el));
+}
+function bindElement(view, el) {
+if (el.hasAttribute(BINDINGS.click)) {
+listen(view, el, el, "click", BINDINGS.click, () => ({ t: "click" }));
+}
+if (el instanceof HTMLInputElement || el instanceof HTMLTextAreaElement || el instanceof HTMLSelectElement) {
+for (const event of ["input", "change", "focus", "blur"]) {
+if (el.hasAttribute(BINDINGS[event])) {
+listen(view, el, el, event, BINDINGS[event], () => ({ t: "input", d: { v: inputValue(el) } }));
+}
+}
+}
+if (el instanceof HTMLFormElement) {
+for (const event of ["change", "submit"]) {
+if (el.hasAttribute(BINDINGS[event])) {
+listen(view, el, el, event, BINDINGS[event], () => ({
+t: "form",
+d: { q: new URLSearchParams(new FormData(el)).toString() },
+}));
+}
+}
+}
+for (const event of ["mouseenter", "mouseover", "mouseleave", "mouseout", "mousemove"]) {
+if (el.hasAttribute(BINDINGS[event])) {
+listen(view, el, el, event, BINDINGS[event], mouseData);
+}
+}
+for (const event of ["keydown", "keyup"]) {
+if (el.hasAttribute(BINDINGS[event])) {
+listen(view, el, el, event, BINDINGS[event], (e) => keyData(el, e));
+}
+}
+}
+function bindWindow(view) {
+view.el.querySelectorAll(WINDOW_BINDINGS_SELECTOR).forEach((el) => {
+for (const event of ["keydown", "keyup"]) {
+if (el.hasAttribute(WINDOW_BINDINGS[event])) {
+listen(view, el, window, event, WINDOW_BINDINGS[event], (e) => keyData(el, e));
+}
+}
+if (el.hasAttribute(WINDOW_BINDINGS.focus)) {
+listen(view, el, window, "focus", WINDOW_BINDINGS.focus, () => ({ t: "window_focus" }));
+}
+if (el.hasAttribute(WINDOW_BINDINGS.blur)) {
+listen(view, el, window, "blur", WINDOW_BINDINGS.blur, () => ({ t: "window_blur" }));
+}
+if (el.hasAttribute(WINDOW_BINDINGS.scroll)) {
+listen(view, el, window, "scroll", WINDOW_BINDINGS.scroll, () => ({
+t: "scroll",
+d: { sx: window.scrollX, sy: window.scrollY },
+}));
+}
+});
+}
+function unbindWindow(view) {
+for (const { event, listener } of view.windowListeners) {
+window.removeEventListener(event, listener);
+}
+view.windowListeners = [];
+}
+function listen(view, el, target, event, attr, eventData) {
+if (target === window) {
+const listener = rateLimit(el, (e) => handleEvent(view, el, e, attr, eventData));
+view.windowListeners.push({ event, listener });
+window.addEventListener(event, listener);
+return;
+}
+let events = boundEvents.get(el);
+if (!events) {
+events = new Set();
+boundEvents.set(el, events);
+}
+if (events.has(event)) {
+return;
+}
+events.add(event);
+el.addEventListener(
+event,
+rateLimit(el, (e) => handleEvent(view, el, e, attr, eventData)),
+);
+}
+function handleEvent(view, el, e, attr, eventData) {
+if (!(e instanceof KeyboardEvent)) {
+e.preventDefault();
+}
+const value = el.getAttribute(attr);
+if (!value) {
+return;
+}
+const data = eventData(e);
+if (!data) {
+return;
+}
+const binding = parseBinding(value);
+if (binding.commands) {
+runCommands(view, binding.commands);
+} else {
+view.send({ m: binding.message, ...data });
+}
+}
+function rateLimit(el, f) {
+const debounce = positiveInt(el, "axm-debounce");
+if (debounce) {
+let timeout;
+return (...args) => {
+clearTimeout(timeout);
+timeout = setTimeout(() => f(...args), debounce);
+};
+}
+const throttle = positiveInt(el, "axm-throttle");
+if (throttle) {
+let timeout;
+return (...args) => {
+if (!timeout) {
+f(...args);
+timeout = setTimeout(() => {
+timeout = null;
+}, throttle);
+}
+};
+}
+return f;
+}
+function positiveInt(el, attr) {
+const value = parseInt(el.getAttribute(attr), 10);
+return value > 0 ? value : null;
+}
+function inputValue(el) {
+if (el instanceof HTMLTextAreaElement) {
+return el.value;
+}
+if (el instanceof HTMLInputElement) {
+const type = el.getAttribute("type");
+return type === "radio" || type === "checkbox" ? el.checked : el.value;
+}
+if (el instanceof HTMLSelectElement) {
+return el.hasAttribute("multiple") ? Array.from(el.selectedOptions).map((option) => option.value) : el.value;
+}
+throw new Error("element has no input value");
+}
+function mouseData(e) {
+if (!(e instanceof MouseEvent)) {
+return;
+}
+return {
+t: "mouse",
+d: {
+cx: e.clientX,
+cy: e.clientY,
+px: e.pageX,
+py: e.pageY,
+ox: e.offsetX,
+oy: e.offsetY,
+mx: e.movementX,
+my: e.movementY,
+sx: e.screenX,
+sy: e.screenY,
+},
+};
+}
+function keyData(el, e) {
+if (!(e instanceof KeyboardEvent)) {
+return;
+}
+const key = el.getAttribute("axm-key");
+if (key !== null && key.toLowerCase() !== e.key.toLowerCase()) {
+return;
+}
+return {
+t: "key",
+d: { k: e.key, kc: e.code, a: e.altKey, c: e.ctrlKey, s: e.shiftKey, me: e.metaKey },
+};
+}
+const HOOK_ATTR = "axm-hook";
+const HOOK_EVENT_ATTR = "axm-hook-event";
+function syncHooks(view) {
+const definitions = view.options.hooks || {};
+const present = new Set();
+view.el.querySelectorAll(`[${HOOK_ATTR}]`).forEach((el) => {
+const name = el.getAttribute(HOOK_ATTR);
+let hook = view.hooks.get(el);
+if (hook && hook.name !== name) {
+destroyHook(view, el, hook);
+hook = null;
+}
+if (hook) {
+present.add(el);
+callHook(hook, "updated");
+return;
+}
+const definition = definitions[name];
+if (!definition) {
+if (view.options.debug) {
+console.warn(`no hook named ${name}`);
+}
+return;
+}
+hook = newHook(view, el, name, definition);
+view.hooks.set(el, hook);
+present.add(el);
+callHook(hook, "mounted");
+});
+for (const [el, hook] of view.hooks) {
+if (!present.has(el)) {
+destroyHook(view, el, hook);
+}
+}
+}
+function dispatchHookEvent(view, event, payload) {
+for (const hook of view.hooks.values()) {
+for (const callback of hook.handlers.get(event) || []) {
+callback(payload);
+}
+}
+}
+function newHook(view, el, name, definition) {
+const hook = Object.create(definition);
+hook.el = el;
+hook.name = name;
+hook.handlers = new Map();
+hook.pushEvent = (event, payload = null) => {
+const value = el.getAttribute(HOOK_EVENT_ATTR);
+if (!value) {
+console.error(`hook ${name} pushed ${event} without an ${HOOK_EVENT_ATTR} message`);
+return;
+}
+const binding = parseBinding(value);
+if (binding.commands) {
+runCommands(view, binding.commands);
+} else {
+view.send({ m: binding.message, t: "hook", d: { e: event, p: payload } });
+}
+};
+hook.handleEvent = (event, callback) => {
+if (!hook.handlers.has(event)) {
+hook.handlers.set(event, []);
+}
+hook.handlers.get(event).push(callback);
+};
+return hook;
+}
+function destroyHook(view, el, hook) {
+view.hooks.delete(el);
+callHook(hook, "destroyed");
+}
+function callHook(hook, callback) {
+if (typeof hook[callback] !== "function") {
+return;
+}
+try {
+hook[callback]();
+} catch (err) {
+console.error(`hook ${hook.name} failed in ${callback}`, err);
+}
+}
+/*!
+ * Vendored from morphdom 2.6.1 (https://github.com/patrick-steele-idem/morphdom).
+ *
+ * The MIT License (MIT)
+ *
+ * Copyright (c) Patrick Steele-Idem <pnidem@gmail.com> (psteeleidem.com)
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+ * and associated documentation files (the "Software"), to deal in the Software without
+ * restriction, including without limitation the rights to use, copy, modify, merge, publish,
+ * distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+ * Software is furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all copies or
+ * substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+ * BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+ * NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+ * DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+const DOCUMENT_FRAGMENT_NODE = 11;
+const ELEMENT_NODE = 1;
+const TEXT_NODE = 3;
+const COMMENT_NODE = 8;
+const NS_XHTML = "http://www.w3.org/1999/xhtml";
+const doc = typeof document === "undefined" ? undefined : document;
+const HAS_TEMPLATE_SUPPORT = !!doc && "content" in doc.createElement("template");
+const HAS_RANGE_SUPPORT = !!doc && doc.createRange && "createContextualFragment" in doc.createRange();
+let range;
+function morphAttrs(fromNode, toNode) {
+if (toNode.nodeType === DOCUMENT_FRAGMENT_NODE || fromNode.nodeType === DOCUMENT_FRAGMENT_NODE) {
+return;
+}
+const toNodeAttrs = toNode.attributes;
+for (let i = toNodeAttrs.length - 1; i >= 0; i--) {
+const attr = toNodeAttrs[i];
+let attrName = attr.name;
+const attrNamespaceURI = attr.namespaceURI;
+const attrValue = attr.value;
+if (attrNamespaceURI) {
+attrName = attr.localName || attrName;
+const fromValue = fromNode.getAttributeNS(attrNamespaceURI, attrName);
+if (fromValue !== attrValue) {
+if (attr.prefix === "xmlns") {
+attrName = attr.name;
+}
+fromNode.setAttributeNS(attrNamespaceURI, attrName, attrValue);
+}
+} else if (fromNode.getAttribute(attrName) !== attrValue) {
+fromNode.setAttribute(attrName, attrValue);
+}
+}
+const fromNodeAttrs = fromNode.attributes;
+for (let i = fromNodeAttrs.length - 1; i >= 0; i--) {
+const attr = fromNodeAttrs[i];
+let attrName = attr.name;
+const attrNamespaceURI = attr.namespaceURI;
+if (attrNamespaceURI) {
+attrName = attr.localName || attrName;
+if (!toNode.hasAttributeNS(attrNamespaceURI, attrName)) {
+fromNode.removeAttributeNS(attrNamespaceURI, attrName);
+}
+} else if (!toNode.hasAttribute(attrName)) {
+fromNode.removeAttribute(attrName);
+}
+}
+}
+function createFragmentFromTemplate(str) {
+const template = doc.createElement("template");
+template.innerHTML = str;
+return template.content.childNodes[0];
+}
+function createFragmentFromRange(str) {
+if (!range) {
+range = doc.createRange();
+range.selectNode(doc.body);
+}
+const fragment = range.createContextualFragment(str);
+return fragment.childNodes[0];
+}
+function createFragmentFromWrap(str) {
+const fragment = doc.createElement("body");
+fragment.innerHTML = str;
+return fragment.childNodes[0];
+}
+function toElement(str) {
+str = str.trim();
+if (HAS_TEMPLATE_SUPPORT) {
+return createFragmentFromTemplate(str);
+} else if (HAS_RANGE_SUPPORT) {
+return createFragmentFromRange(str);
+}
+return createFragmentFromWrap(str);
+}
+function compareNodeNames(fromEl, toEl) {
+const fromNodeName = fromEl.nodeName;
+const toNodeName = toEl.nodeName;
+if (fromNodeName === toNodeName) {
+return true;
+}
+const fromCodeStart = fromNodeName.charCodeAt(0);
+const toCodeStart = toNodeName.charCodeAt(0);
+if (fromCodeStart <= 90 && toCodeStart >= 97) {
+return fromNodeName === toNodeName.toUpperCase();
+} else if (toCodeStart <= 90 && fromCodeStart >= 97) {
+return toNodeName === fromNodeName.toUpperCase();
+}
+return false;
+}
+function createElementNS(name, namespaceURI) {
+return !namespaceURI || namespaceURI === NS_XHTML
+? doc.createElement(name)
+: doc.createElementNS(namespaceURI, name);
+}
+function moveChildren(fromEl, toEl) {
+let curChild = fromEl.firstChild;
+while (curChild) {
+const nextChild = curChild.nextSibling;
+toEl.appendChild(curChild);
+curChild = nextChild;
+}
+return toEl;
+}
+function syncBooleanAttrProp(fromEl, toEl, name) {
+if (fromEl[name] !== toEl[name]) {
+fromEl[name] = toEl[name];
+if (fromEl[name]) {
+fromEl.setAttribute(name, "");
+} else {
+fromEl.removeAttribute(name);
+}
+}
+}
+const specialElHandlers = {
+OPTION(fromEl, toEl) {
+let parentNode = fromEl.parentNode;
+if (parentNode) {
+let parentName = parentNode.nodeName.toUpperCase();
+if (parentName === "OPTGROUP") {
+parentNode = parentNode.parentNode;
+parentName = parentNode && parentNode.nodeName.toUpperCase();
+}
+if (parentName === "SELECT" && !parentNode.hasAttribute("multiple")) {
+if (fromEl.hasAttribute("selected") && !toEl.selected) {
+fromEl.setAttribute("selected", "selected");
+fromEl.removeAttribute("selected");
+}
+parentNode.selectedIndex = -1;
+}
+}
+syncBooleanAttrProp(fromEl, toEl, "selected");
+},
+INPUT(fromEl, toEl) {
+syncBooleanAttrProp(fromEl, toEl, "checked");
+syncBooleanAttrProp(fromEl, toEl, "disabled");
+if (fromEl.value !== toEl.value) {
+fromEl.value = toEl.value;
+}
+if (!toEl.hasAttribute("value")) {
+fromEl.removeAttribute("value");
+}
+},
+TEXTAREA(fromEl, toEl) {
+const newValue = toEl.value;
+if (fromEl.value !== newValue) {
+fromEl.value = newValue;
+}
+const firstChild = fromEl.firstChild;
+if (firstChild) {
+const oldValue = firstChild.nodeValue;
+if (oldValue == newValue || (!newValue && oldValue == fromEl.placeholder)) {
+return;
+}
+firstChild.nodeValue = newValue;
+}
+},
+SELECT(fromEl, toEl) {
+if (!toEl.hasAttribute("multiple")) {
+let selectedIndex = -1;
+let i = 0;
+let curChild = fromEl.firstChild;
+let optgroup;
+let nodeName;
+while (curChild) {
+nodeName = curChild.nodeName && curChild.nodeName.toUpperCase();
+if (nodeName === "OPTGROUP") {
+optgroup = curChild;
+curChild = optgroup.firstChild;
+} else {
+if (nodeName === "OPTION") {
+if (curChild.hasAttribute("selected")) {
+selectedIndex = i;
+break;
+}
+i++;
+}
+curChild = curChild.nextSibling;
+if (!curChild && optgroup) {
+curChild = optgroup.nextSibling;
+optgroup = null;
+}
+}
+}
+fromEl.selectedIndex = selectedIndex;
+}
+},
+};
+function noop() {}
+function defaultGetNodeKey(node) {
+if (node) {
+return (node.getAttribute && node.getAttribute("id")) || node.id;
+}
+}
+function morphdom(fromNode, toNode, options) {
+if (!options) {
+options = {};
+}
+if (typeof toNode === "string") {
+if (fromNode.nodeName === "#document" || fromNode.nodeName === "HTML" || fromNode.nodeName === "BODY") {
+const toNodeHtml = toNode;
+toNode = doc.createElement("html");
+toNode.innerHTML = toNodeHtml;
+} else {
+toNode = toElement(toNode);
+}
+} else if (toNode.nodeType === DOCUMENT_FRAGMENT_NODE) {
+toNode = toNode.firstElementChild;
+}
+const getNodeKey = options.getNodeKey || defaultGetNodeKey;
+const onBeforeNodeAdded = options.onBeforeNodeAdded || noop;
+const onNodeAdded = options.onNodeAdded || noop;
+const onBeforeElUpdated = options.onBeforeElUpdated || noop;
+const onElUpdated = options.onElUpdated || noop;
+const onBeforeNodeDiscarded = options.onBeforeNodeDiscarded || noop;
+const onNodeDiscarded = options.onNodeDiscarded || noop;
+const onBeforeElChildrenUpdated = options.onBeforeElChildrenUpdated || noop;
+const skipFromChildren = options.skipFromChildren || noop;
+const addChild =
+options.addChild ||
+function (parent, child) {
+return parent.appendChild(child);
+};
+const childrenOnly = options.childrenOnly === true;
+const fromNodesLookup = Object.create(null);
+const keyedRemovalList = [];
+function addKeyedRemoval(key) {
+keyedRemovalList.push(key);
+}
+function walkDiscardedChildNodes(node, skipKeyedNodes) {
+if (node.nodeType === ELEMENT_NODE) {
+let curChild = node.firstChild;
+while (curChild) {
+let key = undefined;
+if (skipKeyedNodes && (key = getNodeKey(curChild))) {
+addKeyedRemoval(key);
+} else {
+onNodeDiscarded(curChild);
+if (curChild.firstChild) {
+walkDiscardedChildNodes(curChild, skipKeyedNodes);
+}
+}
+curChild = curChild.nextSibling;
+}
+}
+}
+function removeNode(node, parentNode, skipKeyedNodes) {
+if (onBeforeNodeDiscarded(node) === false) {
+return;
+}
+if (parentNode) {
+parentNode.removeChild(node);
+}
+onNodeDiscarded(node);
+walkDiscardedChildNodes(node, skipKeyedNodes);
+}
+function indexTree(node) {
+if (node.nodeType === ELEMENT_NODE || node.nodeType === DOCUMENT_FRAGMENT_NODE) {
+let curChild = node.firstChild;
+while (curChild) {
+const key = getNodeKey(curChild);
+if (key) {
+fromNodesLookup[key] = curChild;
+}
+indexTree(curChild);
+curChild = curChild.nextSibling;
+}
+}
+}
+indexTree(fromNode);
+function handleNodeAdded(el) {
+onNodeAdded(el);
+let curChild = el.firstChild;
+while (curChild) {
+const nextSibling = curChild.nextSibling;
+const key = getNodeKey(curChild);
+if (key) {
+const unmatchedFromEl = fromNodesLookup[key];
+if (unmatchedFromEl && compareNodeNames(curChild, unmatchedFromEl)) {
+curChild.parentNode.replaceChild(unmatchedFromEl, curChild);
+morphEl(unmatchedFromEl, curChild);
+} else {
+handleNodeAdded(curChild);
+}
+} else {
+handleNodeAdded(curChild);
+}
+curChild = nextSibling;
+}
+}
+function cleanupFromEl(fromEl, curFromNodeChild, curFromNodeKey) {
+while (curFromNodeChild) {
+const fromNextSibling = curFromNodeChild.nextSibling;
+if ((curFromNodeKey = getNodeKey(curFromNodeChild))) {
+addKeyedRemoval(curFromNodeKey);
+} else {
+removeNode(curFromNodeChild, fromEl, true /* skip keyed nodes */);
+}
+curFromNodeChild = fromNextSibling;
+}
+}
+function morphEl(fromEl, toEl, childrenOnly) {
+const toElKey = getNodeKey(toEl);
+if (toElKey) {
+delete fromNodesLookup[toElKey];
+}
+if (!childrenOnly) {
+if (onBeforeElUpdated(fromEl, toEl) === false) {
+return;
+}
+morphAttrs(fromEl, toEl);
+onElUpdated(fromEl);
+if (onBeforeElChildrenUpdated(fromEl, toEl) === false) {
+return;
+}
+}
+if (fromEl.nodeName !== "TEXTAREA") {
+morphChildren(fromEl, toEl);
+} else {
+specialElHandlers.TEXTAREA(fromEl, toEl);
+}
+}
+function morphChildren(fromEl, toEl) {
+const skipFrom = skipFromChildren(fromEl);
+let curToNodeChild = toEl.firstChild;
+let curFromNodeChild = fromEl.firstChild;
+let curToNodeKey;
+let curFromNodeKey;
+let fromNextSibling;
+let toNextSibling;
+let matchingFromEl;
+outer: while (curToNodeChild) {
+toNextSibling = curToNodeChild.nextSibling;
+curToNodeKey = getNodeKey(curToNodeChild);
+while (!skipFrom && curFromNodeChild) {
+fromNextSibling = curFromNodeChild.nextSibling;
+if (curToNodeChild.isSameNode && curToNodeChild.isSameNode(curFromNodeChild)) {
+curToNodeChild = toNextSibling;
+curFromNodeChild = fromNextSibling;
+continue outer;
+}
+curFromNodeKey = getNodeKey(curFromNodeChild);
+const curFromNodeType = curFromNodeChild.nodeType;
+let isCompatible = undefined;
+if (curFromNodeType === curToNodeChild.nodeType) {
+if (curFromNodeType === ELEMENT_NODE) {
+if (curToNodeKey) {
+if (curToNodeKey !== curFromNodeKey) {
+if ((matchingFromEl = fromNodesLookup[curToNodeKey])) {
+if (fromNextSibling === matchingFromEl) {
+isCompatible = false;
+} else {
+fromEl.insertBefore(matchingFromEl, curFromNodeChild);
+if (curFromNodeKey) {
+addKeyedRemoval(curFromNodeKey);
+} else {
+removeNode(curFromNodeChild, fromEl, true /* skip keyed nodes */);
+}
+curFromNodeChild = matchingFromEl;
+}
+} else {
+isCompatible = false;
+}
+}
+} else if (curFromNodeKey) {
+isCompatible = false;
+}
+isCompatible = isCompatible !== false && compareNodeNames(curFromNodeChild, curToNodeChild);
+if (isCompatible) {
+morphEl(curFromNodeChild, curToNodeChild);
+}
+} else if (curFromNodeType === TEXT_NODE || curFromNodeType == COMMENT_NODE) {
+isCompatible = true;
+if (curFromNodeChild.nodeValue !== curToNodeChild.nodeValue) {
+curFromNodeChild.nodeValue = curToNodeChild.nodeValue;
+}
+}
+}
+if (isCompatible) {
+curToNodeChild = toNextSibling;
+curFromNodeChild = fromNextSibling;
+continue outer;
+}
+if (curFromNodeKey) {
+addKeyedRemoval(curFromNodeKey);
+} else {
+removeNode(curFromNodeChild, fromEl, true /* skip keyed nodes */);
+}
+curFromNodeChild = fromNextSibling;
+}
+if (curToNodeKey && (matchingFromEl = fromNodesLookup[curToNodeKey]) && compareNodeNames(matchingFromEl, curToNodeChild)) {
+if (!skipFrom) {
+addChild(fromEl, matchingFromEl);
+}
+morphEl(matchingFromEl, curToNodeChild);
+} else {
+const onBeforeNodeAddedResult = onBeforeNodeAdded(curToNodeChild);
+if (onBeforeNodeAddedResult !== false) {
+if (onBeforeNodeAddedResult) {
+curToNodeChild = onBeforeNodeAddedResult;
+}
+if (curToNodeChild.actualize) {
+curToNodeChild = curToNodeChild.actualize(fromEl.ownerDocument || doc);
+}
+addChild(fromEl, curToNodeChild);
+handleNodeAdded(curToNodeChild);
+}
+}
+curToNodeChild = toNextSibling;
+curFromNodeChild = fromNextSibling;
+}
+cleanupFromEl(fromEl, curFromNodeChild, curFromNodeKey);
+const specialElHandler = specialElHandlers[fromEl.nodeName];
+if (specialElHandler) {
+specialElHandler(fromEl, toEl);
+}
+}
+let morphedNode = fromNode;
+const morphedNodeType = morphedNode.nodeType;
+const toNodeType = toNode.nodeType;
+if (!childrenOnly) {
+if (morphedNodeType === ELEMENT_NODE) {
+if (toNodeType === ELEMENT_NODE) {
+if (!compareNodeNames(fromNode, toNode)) {
+onNodeDiscarded(fromNode);
+morphedNode = moveChildren(fromNode, createElementNS(toNode.nodeName, toNode.namespaceURI));
+}
+} else {
+morphedNode = toNode;
+}
+} else if (morphedNodeType === TEXT_NODE || morphedNodeType === COMMENT_NODE) {
+if (toNodeType === morphedNodeType) {
+if (morphedNode.nodeValue !== toNode.nodeValue) {
+morphedNode.nodeValue = toNode.nodeValue;
+}
+return morphedNode;
+} else {
+morphedNode = toNode;
+}
+}
+}
+if (morphedNode === toNode) {
+onNodeDiscarded(fromNode);
+} else {
+if (toNode.isSameNode && toNode.isSameNode(morphedNode)) {
+return;
+}
+morphEl(morphedNode, toNode, childrenOnly);
+if (keyedRemovalList) {
+for (let i = 0, len = keyedRemovalList.length; i < len; i++) {
+const elToRemove = fromNodesLookup[keyedRemovalList[i]];
+if (elToRemove) {
+removeNode(elToRemove, elToRemove.parentNode, false);
+}
+}
+}
+}
+if (!childrenOnly && morphedNode !== fromNode && fromNode.parentNode) {
+if (morphedNode.actualize) {
+morphedNode = morphedNode.actualize(fromNode.ownerDocument || doc);
+}
+fromNode.parentNode.replaceChild(morphedNode, fromNode);
+}
+return morphedNode;
+}
+function applyDiff(html, diff) {
+if (diff.f) {
+html.f = diff.f;
+}
+if (diff.d) {
+html.d = html.d || {};
+applyParts(html.d, diff.d);
+}
+}
+function applyParts(parts, diff) {
+for (const [idx, value] of Object.entries(diff)) {
+if (value === null) {
+delete parts[idx];
+} else {
+parts[idx] = patchPart(parts[idx], value);
+}
+}
+}
+function patchPart(current, diff) {
+if (typeof diff !== "string" && isStream(diff)) {
+const rows = typeof current === "object" && isStream(current) && !diff.r ? streamRows(current) : [];
+return { s: applyStreamOps(rows, diff.s).map(([key, html]) => ({ i: key, a: -1, h: html })) };
+}
+if (typeof diff === "string" || current === undefined || typeof current === "string") {
+return diff;
+}
+if (isKeyedLoop(current)) {
+if ("r" in diff || "o" in diff || "u" in diff) {
+applyKeyedLoopDiff(current, diff);
+return current;
+}
+return diff;
+}
+if ("k" in diff) {
+return diff;
+}
+if (isLoop(current)) {
+if ("d" in diff) {
+return diff;
+}
+applyLoopDiff(current, diff);
+return current;
+}
+if ("b" in diff) {
+return diff;
+}
+applyDiff(current, diff);
+return current;
+}
+function isLoop(part) {
+return "b" in part && !isKeyedLoop(part);
+}
+function isKeyedLoop(part) {
+return "k" in part;
+}
+function isStream(part) {
+return "s" in part;
+}
+function applyLoopDiff(loop, diff) {
+if (diff.f && diff.f.length > 0) {
+loop.f = diff.f;
+}
+for (const [row, parts] of Object.entries(diff.b || {})) {
+if (parts === null) {
+delete loop.b[row];
+} else {
+loop.b[row] = loop.b[row] || {};
+applyParts(loop.b[row], parts);
+}
+}
+}
+function applyKeyedLoopDiff(loop, diff) {
+const rows = new Map(loop.k.map((key, idx) => [key, loop.b[idx]]));
+const removed = new Set(diff.r || []);
+const keys = loop.k.filter((key) => !removed.has(key));
+for (const op of diff.o || []) {
+if ("i" in op) {
+keys.splice(op.i, 0, op.k);
+rows.set(op.k, {});
+applyParts(rows.get(op.k), op.b);
+} else {
+keys.splice(keys.indexOf(op.k), 1);
+keys.splice(op.m, 0, op.k);
+}
+}
+for (const [key, parts] of Object.entries(diff.u || {})) {
+applyParts(rows.get(key), parts);
+}
+loop.k = keys;
+loop.b = Object.fromEntries(keys.map((key, idx) => [idx, rows.get(key)]));
+}
+function streamRows(stream) {
+return applyStreamOps([], stream.s);
+}
+function applyStreamOps(rows, ops) {
+for (const op of ops) {
+if ("d" in op) {
+rows = rows.filter(([key]) => key !== op.d);
+continue;
+}
+const existing = rows.find(([key]) => key === op.i);
+if (existing) {
+existing[1] = op.h;
+} else if (op.a < 0) {
+rows.push([op.i, op.h]);
+} else {
+rows.splice(Math.min(op.a, rows.length), 0, [op.i, op.h]);
+}
+}
+return rows;
+}
+function render(html, path = "") {
+let out = "";
+html.f.forEach((fixed, idx) => {
+out += fixed;
+const part = html.d && html.d[idx];
+if (part !== undefined && part !== null) {
+out += renderPart(part, `${path}.${idx}`);
+}
+});
+return out;
+}
+function renderPart(part, path) {
+if (typeof part === "string") {
+return part;
+}
+if (isKeyedLoop(part)) {
+const unique = new Set(part.k).size === part.k.length;
+return part.k
+.map((key, idx) => {
+const row = render({ f: part.f, d: part.b[idx] }, `${path}:${key}`);
+return unique ? withRowKey(row, `${path}:${key}`) : row;
+})
+.join("");
+}
+if (isStream(part)) {
+return streamRows(part)
+.map(([key, html]) => withRowKey(render(html, `${path}:${key}`), `${path}:${key}`))
+.join("");
+}
+if (isLoop(part)) {
+return Object.entries(part.b)
+.map(([idx, row]) => render({ f: part.f, d: row }, `${path}.${idx}`))
+.join("");
+}
+return render(part, path);
+}
+function withRowKey(row, key) {
+const tag = /^\s*<[a-zA-Z][^\s/>]*/.exec(row);
+if (!tag) {
+return row;
+}
+const escaped = key.replace(/&/g, "&amp;").replace(/"/g, "&quot;").replace(/</g, "&lt;");
+return `${tag[0]} axm-row="${escaped}"${row.slice(tag[0].length)}`;
+}
+const UPLOAD_ATTR = "axm-upload";
+const CHUNK_SIZE = 32 * 1024;
+const MAX_BUFFERED = 16 * CHUNK_SIZE;
+const boundInputs = new WeakSet();
+let nextUploadId = 1;
+function bindUploads(view) {
+view.el.querySelectorAll(`input[type="file"][${UPLOAD_ATTR}]`).forEach((input) => {
+if (boundInputs.has(input)) {
+return;
+}
+boundInputs.add(input);
+input.addEventListener("change", () => {
+cancelUploads(view, input);
+const name = input.getAttribute(UPLOAD_ATTR);
+for (const file of Array.from(input.files)) {
+startUpload(view, input, name, file);
+}
+});
+});
+}
+function startUpload(view, input, name, file) {
+const id = nextUploadId++;
+view.uploads.set(id, { input, file, socket: view.connection.socket });
+view.send({ t: "us", n: name, r: id, f: file.name, ct: file.type, s: file.size });
+}
+function cancelUploads(view, input) {
+for (const [id, upload] of view.uploads) {
+if (upload.input === input) {
+view.uploads.delete(id);
+view.send({ t: "uc", r: id });
+}
+}
+}
+function uploadReply(view, { r: id, e: error }) {
+const upload = view.uploads.get(id);
+if (!upload) {
+return;
+}
+if (error) {
+view.uploads.delete(id);
+if (view.options.debug) {
+console.warn(`upload of ${upload.file.name} rejected: ${error}`);
+}
+return;
+}
+sendChunks(view, id, upload).catch((err) => {
+view.uploads.delete(id);
+console.error(`failed to upload ${upload.file.name}`, err);
+});
+}
+async function sendChunks(view, id, upload) {
+const { file, socket } = upload;
+for (let offset = 0; offset < file.size; offset += CHUNK_SIZE) {
+await drained(socket);
+if (view.uploads.get(id) !== upload || view.connection.socket !== socket) {
+return;
+}
+const data = new Uint8Array(await file.slice(offset, offset + CHUNK_SIZE).arrayBuffer());
+view.connection.sendBinary(view, uploadChunk(id, data));
+}
+view.uploads.delete(id);
+}
+function uploadChunk(id, data) {
+const chunk = new Uint8Array(8 + data.length);
+new DataView(chunk.buffer).setBigUint64(0, BigInt(id));
+chunk.set(data, 8);
+return chunk;
+}
+async function drained(socket) {
+while (socket && socket.bufferedAmount > MAX_BUFFERED) {
+await new Promise((resolve) => setTimeout(resolve, 10));
+}
+}
+class View {
+constructor(el, id, options) {
+this.el = el;
+this.id = id;
+this.options = options;
+this.connection = null;
+this.state = null;
+this.windowListeners = [];
+this.path = null;
+this.uploads = new Map();
+this.hooks = new Map();
+this.reconnects = 0;
+}
+send(msg) {
+this.connection.send(this, msg);
+}
+receive(msg) {
+switch (msg.t) {
+case "i":
+this.state = msg.d;
+this.update();
+break;
+case "r":
+if (this.state && msg.d) {
+applyDiff(this.state, msg.d);
+this.update();
+}
+break;
+case "j":
+runCommands(this, msg.d);
+break;
+case "h":
+this.connection.pong();
+break;
+case "e":
+console.error(`live view error ${msg.d.c}: ${msg.d.m}`);
+this.showError(msg.d.m);
+break;
+case "g":
+this.connection.reconnectAfter = msg.d.r;
+break;
+case "u":
+uploadReply(this, msg.d);
+break;
+}
+}
+showError(message) {
+unbindWindow(this);
+const error = document.createElement("div");
+error.className = "axm-error";
+error.setAttribute("role", "alert");
+error.textContent = message;
+this.el.replaceChildren(error);
+syncHooks(this);
+}
+update() {
+unbindWindow(this);
+morphdom(this.el, render(this.state), {
+childrenOnly: this.id !== null,
+getNodeKey: nodeKey,
+onBeforeElUpdated: keepInputValues,
+});
+bindElements(this);
+bindLinks(this);
+bindUploads(this);
+bindWindow(this);
+syncHooks(this);
+}
+}
+function nodeKey(node) {
+if (node.nodeType === Node.ELEMENT_NODE) {
+return node.getAttribute("id") || node.getAttribute("axm-row") || undefined;
+}
+}
+function keepInputValues(fromEl, toEl) {
+if (fromEl instanceof HTMLInputElement && toEl instanceof HTMLInputElement) {
+const type = toEl.getAttribute("type");
+if (type === "file" && fromEl.type === "file") {
+return false;
+}
+if (type === "radio" || type === "checkbox") {
+toEl.checked = fromEl.checked;
+} else {
+toEl.value = fromEl.value;
+}
+}
+if (fromEl instanceof HTMLTextAreaElement && toEl instanceof HTMLTextAreaElement) {
+toEl.value = fromEl.value;
+}
+if (fromEl instanceof HTMLOptionElement && toEl instanceof HTMLOptionElement) {
+const select = toEl.closest("select");
+if (select && select.hasAttribute("multiple")) {
+toEl.selected = fromEl.selected;
+}
+}
+if (fromEl instanceof HTMLSelectElement && toEl instanceof HTMLSelectElement && !toEl.hasAttribute("multiple")) {
+toEl.value = fromEl.value;
+}
+return true;
+}
+class LiveView {
+constructor(options = {}) {
+this.options = { debug: false, ...options, hooks: { ...options.hooks } };
+const container = document.getElementById("live-view-container");
+const views = container
+? [new View(container, null, this.options)]
+: Array.from(
+document.querySelectorAll("[data-live-view-id]"),
+(el) => new View(el, el.getAttribute("data-live-view-id"), this.options),
+);
+this.views = views;
+this.connection = null;
+if (views.length > 0) {
+this.connection = new Connection(views, this.options);
+this.connection.connect();
+listenForHistory(views[0]);
+}
+}
+registerHook(name, hook) {
+this.options.hooks[name] = hook;
+for (const view of this.views) {
+if (view.state) {
+syncHooks(view);
+}
+}
+}
+enableDebug() {
+this.options.debug = true;
+}
+disableDebug() {
+this.options.debug = false;
+}
+}
+window.liveView = new LiveView();
+})();
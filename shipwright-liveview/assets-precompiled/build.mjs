@@ -0,0 +1,129 @@
+// Builds `axum_live_view.min.js`, its gzipped copy and its hash from the client in `../assets`.
+//
+// Run with `node build.mjs`, it has no dependencies.
+//
+// The client is written as plain ES modules so instead of a bundler the modules are concatenated
+// into one function scope, dependencies first. That requires:
+//
+// - Imports to be named imports of relative paths, like `import { a, b } from "./module.js";`.
+// - Top-level names to be unique across all modules.
+// - No template literals spanning several lines, since lines are trimmed.
+//
+// Comments are removed except for `/*! ... */` blocks, which hold licenses.
+
+import { createHash } from "node:crypto";
+import { readFileSync, writeFileSync } from "node:fs";
+import { dirname, relative, resolve } from "node:path";
+import { fileURLToPath } from "node:url";
+import { gzipSync } from "node:zlib";
+
+const HERE = dirname(fileURLToPath(import.meta.url));
+const ENTRY = resolve(HERE, "index.js");
+const OUTPUT = resolve(HERE, "axum_live_view.min.js");
+
+const IMPORT = /^import\s*\{([^}]*)\}\s*from\s*"(\.[^"]+)";?[ \t]*$/gm;
+const EXPORT = /^export (?=(?:async )?(?:function\*?|class|const|let) )/gm;
+const DECLARATION = /^(?:export )?(?:async )?(?:function\*?|class|const|let|var) ([\w$]+)/gm;
+
+function bundle(entry) {
+  const modules = [];
+  const visited = new Set();
+  const declaredIn = new Map();
+
+  function visit(path) {
+    if (visited.has(path)) {
+      return;
+    }
+    visited.add(path);
+
+    const name = relative(HERE, path);
+    let source = readFileSync(path, "utf8");
+
+    for (const [, names, specifier] of source.matchAll(IMPORT)) {
+      const dependency = resolve(dirname(path), specifier);
+      for (const imported of names.split(",").map((s) => s.trim()).filter(Boolean)) {
+        if (!/^[\w$]+$/.test(imported)) {
+          throw new Error(`${name}: unsupported import \`${imported}\``);
+        }
+        if (!exports(dependency).has(imported)) {
+          throw new Error(`${name}: \`${specifier}\` doesn't export \`${imported}\``);
+        }
+      }
+      visit(dependency);
+    }
+
+    source = source.replace(IMPORT, "");
+    if (/^(import|export)\b/m.test(source.replace(EXPORT, ""))) {
+      throw new Error(`${name}: unsupported import or export`);
+    }
+    source = source.replace(EXPORT, "");
+
+    for (const [, declared] of source.matchAll(DECLARATION)) {
+      if (declaredIn.has(declared)) {
+        throw new Error(`\`${declared}\` is declared in both ${declaredIn.get(declared)} and ${name}`);
+      }
+      declaredIn.set(declared, name);
+    }
+
+    modules.push(source);
+  }
+
+  visit(entry);
+  return `(() => {\n"use strict";\n${modules.join("\n")}\n})();\n`;
+}
+
+function exports(path) {
+  const source = readFileSync(path, "utf8");
+  const exported = /^export (?:async )?(?:function\*?|class|const|let) ([\w$]+)/gm;
+  return new Set([...source.matchAll(exported)].map(([, name]) => name));
+}
+
+function minify(source) {
+  const lines = [];
+  let comment = null;
+
+  for (const line of source.split("\n")) {
+    const trimmed = line.trim();
+
+    if (comment !== null) {
+      if (comment) {
+        lines.push(line);
+      }
+      if (trimmed.endsWith("*/")) {
+        comment = null;
+      }
+      continue;
+    }
+
+    if (trimmed.startsWith("/*")) {
+      // licenses are kept as they are
+      comment = trimmed.startsWith("/*!");
+      if (comment) {
+        lines.push(line);
+      }
+      if (trimmed.endsWith("*/")) {
+        comment = null;
+      }
+      continue;
+    }
+
+    if (trimmed === "" || trimmed.startsWith("//")) {
+      continue;
+    }
+    if ((trimmed.match(/`/g) || []).length % 2 !== 0) {
+      throw new Error(`template literal spans several lines: ${trimmed}`);
+    }
+    lines.push(trimmed);
+  }
+
+  return `${lines.join("\n")}\n`;
+}
+
+const js = minify(bundle(ENTRY));
+const hash = createHash("sha256").update(js).digest().readBigUInt64LE(0);
+
+writeFileSync(OUTPUT, js);
+writeFileSync(`${OUTPUT}.gz`, gzipSync(js, { level: 9 }));
+writeFileSync(resolve(HERE, "axum_live_view.hash.txt"), hash.toString());
+
+console.log(`wrote ${relative(process.cwd(), OUTPUT)} (${js.length} bytes)`);
@@ -1,3 +1,3 @@
-import { LiveView } from "axum-live-view"
+import { LiveView } from "../assets/src/index.js";
 
-window.liveView = new LiveView()
+window.liveView = new LiveView();
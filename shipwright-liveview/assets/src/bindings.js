@@ -0,0 +1,278 @@
+// Bindings send a message to the view when an event fires on an element, for example
+// `<button axm-click="...">`. The attribute value is the message, or `js:` followed by
+// percent-encoded JS commands that are run in the browser instead.
+
+import { runCommands } from "./commands.js";
+
+const BINDINGS = {
+  click: "axm-click",
+  input: "axm-input",
+  change: "axm-change",
+  submit: "axm-submit",
+  focus: "axm-focus",
+  blur: "axm-blur",
+  keydown: "axm-keydown",
+  keyup: "axm-keyup",
+  mouseenter: "axm-mouseenter",
+  mouseover: "axm-mouseover",
+  mouseleave: "axm-mouseleave",
+  mouseout: "axm-mouseout",
+  mousemove: "axm-mousemove",
+};
+
+const WINDOW_BINDINGS = {
+  keydown: "axm-window-keydown",
+  keyup: "axm-window-keyup",
+  focus: "axm-window-focus",
+  blur: "axm-window-blur",
+  scroll: "axm-scroll",
+};
+
+const BINDINGS_SELECTOR = Object.values(BINDINGS)
+  .map((attr) => `[${attr}]`)
+  .join(", ");
+
+const WINDOW_BINDINGS_SELECTOR = Object.values(WINDOW_BINDINGS)
+  .map((attr) => `[${attr}]`)
+  .join(", ");
+
+// the events each element already listens to
+const boundEvents = new WeakMap();
+
+/**
+ * Parse the value of a binding attribute.
+ *
+ * Returns `{commands}` for `js:` values and `{message}` otherwise.
+ */
+export function parseBinding(value) {
+  if (value.startsWith("js:")) {
+    return { commands: JSON.parse(percentDecode(value.slice(3))) };
+  }
+
+  try {
+    return { message: JSON.parse(value) };
+  } catch {
+    return { message: value };
+  }
+}
+
+// Like `decodeURIComponent` but leaves `%` that doesn't start an escape alone, because the server
+// only encodes the characters that can't appear in an attribute.
+function percentDecode(value) {
+  return value.replace(/(%[0-9a-fA-F]{2})+/g, (escaped) => {
+    try {
+      return decodeURIComponent(escaped);
+    } catch {
+      return escaped;
+    }
+  });
+}
+
+/** Listen for the events bound by the elements in `view`. */
+export function bindElements(view) {
+  view.el.querySelectorAll(BINDINGS_SELECTOR).forEach((el) => bindElement(view, el));
+}
+
+function bindElement(view, el) {
+  if (el.hasAttribute(BINDINGS.click)) {
+    listen(view, el, el, "click", BINDINGS.click, () => ({ t: "click" }));
+  }
+
+  if (el instanceof HTMLInputElement || el instanceof HTMLTextAreaElement || el instanceof HTMLSelectElement) {
+    for (const event of ["input", "change", "focus", "blur"]) {
+      if (el.hasAttribute(BINDINGS[event])) {
+        listen(view, el, el, event, BINDINGS[event], () => ({ t: "input", d: { v: inputValue(el) } }));
+      }
+    }
+  }
+
+  if (el instanceof HTMLFormElement) {
+    for (const event of ["change", "submit"]) {
+      if (el.hasAttribute(BINDINGS[event])) {
+        listen(view, el, el, event, BINDINGS[event], () => ({
+          t: "form",
+          d: { q: new URLSearchParams(new FormData(el)).toString() },
+        }));
+      }
+    }
+  }
+
+  for (const event of ["mouseenter", "mouseover", "mouseleave", "mouseout", "mousemove"]) {
+    if (el.hasAttribute(BINDINGS[event])) {
+      listen(view, el, el, event, BINDINGS[event], mouseData);
+    }
+  }
+
+  for (const event of ["keydown", "keyup"]) {
+    if (el.hasAttribute(BINDINGS[event])) {
+      listen(view, el, el, event, BINDINGS[event], (e) => keyData(el, e));
+    }
+  }
+}
+
+/**
+ * Listen for the window events bound by the elements in `view`.
+ *
+ * The listeners are removed again by `unbindWindow`.
+ */
+export function bindWindow(view) {
+  view.el.querySelectorAll(WINDOW_BINDINGS_SELECTOR).forEach((el) => {
+    for (const event of ["keydown", "keyup"]) {
+      if (el.hasAttribute(WINDOW_BINDINGS[event])) {
+        listen(view, el, window, event, WINDOW_BINDINGS[event], (e) => keyData(el, e));
+      }
+    }
+    if (el.hasAttribute(WINDOW_BINDINGS.focus)) {
+      listen(view, el, window, "focus", WINDOW_BINDINGS.focus, () => ({ t: "window_focus" }));
+    }
+    if (el.hasAttribute(WINDOW_BINDINGS.blur)) {
+      listen(view, el, window, "blur", WINDOW_BINDINGS.blur, () => ({ t: "window_blur" }));
+    }
+    if (el.hasAttribute(WINDOW_BINDINGS.scroll)) {
+      listen(view, el, window, "scroll", WINDOW_BINDINGS.scroll, () => ({
+        t: "scroll",
+        d: { sx: window.scrollX, sy: window.scrollY },
+      }));
+    }
+  });
+}
+
+/** Remove the window listeners added by `bindWindow`. */
+export function unbindWindow(view) {
+  for (const { event, listener } of view.windowListeners) {
+    window.removeEventListener(event, listener);
+  }
+  view.windowListeners = [];
+}
+
+// Call `eventData` when `event` fires on `target` and send its result to the view along with the
+// message in the `attr` attribute of `el`. `eventData` returns nothing to ignore the event.
+function listen(view, el, target, event, attr, eventData) {
+  if (target === window) {
+    const listener = rateLimit(el, (e) => handleEvent(view, el, e, attr, eventData));
+    view.windowListeners.push({ event, listener });
+    window.addEventListener(event, listener);
+    return;
+  }
+
+  // elements are kept by the DOM diffing so they only need a listener once, it reads the
+  // attribute when the event fires
+  let events = boundEvents.get(el);
+  if (!events) {
+    events = new Set();
+    boundEvents.set(el, events);
+  }
+  if (events.has(event)) {
+    return;
+  }
+  events.add(event);
+
+  el.addEventListener(
+    event,
+    rateLimit(el, (e) => handleEvent(view, el, e, attr, eventData)),
+  );
+}
+
+function handleEvent(view, el, e, attr, eventData) {
+  if (!(e instanceof KeyboardEvent)) {
+    e.preventDefault();
+  }
+
+  const value = el.getAttribute(attr);
+  if (!value) {
+    return;
+  }
+
+  const data = eventData(e);
+  if (!data) {
+    return;
+  }
+
+  const binding = parseBinding(value);
+  if (binding.commands) {
+    runCommands(view, binding.commands);
+  } else {
+    view.send({ m: binding.message, ...data });
+  }
+}
+
+function rateLimit(el, f) {
+  const debounce = positiveInt(el, "axm-debounce");
+  if (debounce) {
+    let timeout;
+    return (...args) => {
+      clearTimeout(timeout);
+      timeout = setTimeout(() => f(...args), debounce);
+    };
+  }
+
+  const throttle = positiveInt(el, "axm-throttle");
+  if (throttle) {
+    let timeout;
+    return (...args) => {
+      if (!timeout) {
+        f(...args);
+        timeout = setTimeout(() => {
+          timeout = null;
+        }, throttle);
+      }
+    };
+  }
+
+  return f;
+}
+
+function positiveInt(el, attr) {
+  const value = parseInt(el.getAttribute(attr), 10);
+  return value > 0 ? value : null;
+}
+
+function inputValue(el) {
+  if (el instanceof HTMLTextAreaElement) {
+    return el.value;
+  }
+  if (el instanceof HTMLInputElement) {
+    const type = el.getAttribute("type");
+    return type === "radio" || type === "checkbox" ? el.checked : el.value;
+  }
+  if (el instanceof HTMLSelectElement) {
+    return el.hasAttribute("multiple") ? Array.from(el.selectedOptions).map((option) => option.value) : el.value;
+  }
+  throw new Error("element has no input value");
+}
+
+function mouseData(e) {
+  if (!(e instanceof MouseEvent)) {
+    return;
+  }
+  return {
+    t: "mouse",
+    d: {
+      cx: e.clientX,
+      cy: e.clientY,
+      px: e.pageX,
+      py: e.pageY,
+      ox: e.offsetX,
+      oy: e.offsetY,
+      mx: e.movementX,
+      my: e.movementY,
+      sx: e.screenX,
+      sy: e.screenY,
+    },
+  };
+}
+
+// `axm-key` limits the binding to a single key
+function keyData(el, e) {
+  if (!(e instanceof KeyboardEvent)) {
+    return;
+  }
+  const key = el.getAttribute("axm-key");
+  if (key !== null && key.toLowerCase() !== e.key.toLowerCase()) {
+    return;
+  }
+  return {
+    t: "key",
+    d: { k: e.key, kc: e.code, a: e.altKey, c: e.ctrlKey, s: e.shiftKey, me: e.metaKey },
+  };
+}
@@ -0,0 +1,142 @@
+// JS commands sent by the server, or bound to events with `js:` attribute values. See
+// `js_command` in the crate for what each of them does.
+
+import { dispatchHookEvent } from "./hooks.js";
+import { livePatch, liveRedirect } from "./navigation.js";
+
+const FOCUSABLE = [
+  "a[href]",
+  "area[href]",
+  "button:not([disabled])",
+  "input:not([disabled]):not([type=hidden])",
+  "select:not([disabled])",
+  "textarea:not([disabled])",
+  "iframe",
+  "[contenteditable=true]",
+  "[tabindex]:not([tabindex='-1'])",
+].join(", ");
+
+/** Run `commands` for `view`, each after its delay if it has one. */
+export function runCommands(view, commands) {
+  for (const command of commands) {
+    if (command.delay_ms) {
+      setTimeout(() => runCommand(view, command.kind), command.delay_ms);
+    } else {
+      runCommand(view, command.kind);
+    }
+  }
+}
+
+function runCommand(view, kind) {
+  switch (kind.t) {
+    case "navigate_to":
+      window.location.assign(kind.uri);
+      break;
+    case "add_class":
+      forEach(kind.selector, (el) => el.classList.add(kind.klass));
+      break;
+    case "remove_class":
+      forEach(kind.selector, (el) => el.classList.remove(kind.klass));
+      break;
+    case "toggle_class":
+      forEach(kind.selector, (el) => el.classList.toggle(kind.klass));
+      break;
+    case "clear_value":
+      forEach(kind.selector, (el) => {
+        if (el instanceof HTMLInputElement || el instanceof HTMLSelectElement || el instanceof HTMLTextAreaElement) {
+          el.value = "";
+        }
+      });
+      break;
+    case "set_title":
+      document.title = kind.title;
+      break;
+    case "history_push_state":
+      window.history.pushState({}, "", kind.uri);
+      break;
+    case "live_patch":
+      livePatch(view, kind.uri);
+      break;
+    case "live_redirect":
+      liveRedirect(view, kind.uri);
+      break;
+    case "show":
+      // without a display the one from the stylesheet applies again
+      forEach(kind.selector, (el) => {
+        el.style.display = kind.display || "";
+      });
+      break;
+    case "hide":
+      forEach(kind.selector, (el) => {
+        el.style.display = "none";
+      });
+      break;
+    case "transition":
+      forEach(kind.selector, (el) => transition(el, kind));
+      break;
+    case "focus": {
+      const el = document.querySelector(kind.selector);
+      if (el) {
+        el.focus();
+      }
+      break;
+    }
+    case "focus_first": {
+      const el = document.querySelector(kind.selector);
+      const focusable = el && el.querySelector(FOCUSABLE);
+      if (focusable) {
+        focusable.focus();
+      }
+      break;
+    }
+    case "blur":
+      forEach(kind.selector, (el) => el.blur());
+      break;
+    case "set_attribute":
+      forEach(kind.selector, (el) => el.setAttribute(kind.name, kind.value));
+      break;
+    case "remove_attribute":
+      forEach(kind.selector, (el) => el.removeAttribute(kind.name));
+      break;
+    case "dispatch":
+      forEach(kind.selector, (el) => {
+        el.dispatchEvent(new CustomEvent(kind.event, { bubbles: true, detail: kind.detail }));
+      });
+      break;
+    case "set_cookie":
+      // such as the flash, carried over to the page loaded by a following `navigate_to`
+      document.cookie = kind.cookie;
+      break;
+    case "push_event":
+      dispatchHookEvent(view, kind.event, kind.payload);
+      break;
+    default:
+      console.error("unknown JS command", kind);
+  }
+}
+
+function forEach(selector, f) {
+  document.querySelectorAll(selector).forEach(f);
+}
+
+// The transition and start classes are added, on the next frame the start classes are swapped
+// for the end classes, and once the duration has passed the transition classes are removed.
+function transition(el, { classes, start, end, duration_ms }) {
+  const transitionClasses = splitClasses(classes);
+  const startClasses = splitClasses(start);
+  const endClasses = splitClasses(end);
+
+  el.classList.add(...transitionClasses, ...startClasses);
+  // the start classes have to be rendered before they are swapped, which takes two frames
+  requestAnimationFrame(() => {
+    requestAnimationFrame(() => {
+      el.classList.remove(...startClasses);
+      el.classList.add(...endClasses);
+    });
+  });
+  setTimeout(() => el.classList.remove(...transitionClasses), duration_ms);
+}
+
+function splitClasses(classes) {
+  return classes.split(" ").filter((klass) => klass.length > 0);
+}
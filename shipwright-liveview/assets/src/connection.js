@@ -0,0 +1,268 @@
+import { decodeMsgpack, encodeMsgpack } from "./msgpack.js";
+import { LiveViewEventSource } from "./sse.js";
+
+const HEARTBEAT_INTERVAL_MS = 30000;
+const RECONNECT_AFTER_MS = 1000;
+const MAX_RECONNECT_AFTER_MS = 30000;
+// views that keep failing to mount aren't mounted again after this many attempts
+const MAX_REMOUNTS = 5;
+const USER_ACTIVITY = ["pointerdown", "keydown", "focus"];
+const PING_TIMER = "ping";
+const RELOADED_AT = "axm-reloaded-at";
+const RELOAD_AT_MOST_EVERY_MS = 10000;
+
+// see `life_cycle::close_code` in the crate
+const CLOSE_GOING_AWAY = 1001;
+const CLOSE_ABNORMAL = 1006;
+const CLOSE_VIEW_ERROR = 4000;
+const CLOSE_REMOUNT = 4001;
+const CLOSE_FORBIDDEN = 4002;
+const CLOSE_IDLE_TIMEOUT = 4004;
+const CLOSE_DISCONNECTED = 4005;
+
+/**
+ * The socket shared by the views on the page.
+ *
+ * Reconnects when the socket is closed, waiting longer after each attempt, and falls back to Server-Sent Events if a WebSocket can't
+ * be opened. With several views, messages are tagged with the id of their view as described in
+ * `multiplex` in the crate.
+ *
+ * WebSockets ask for MessagePack, see `codec` in the crate. Server-Sent Events stay on JSON.
+ */
+export class Connection {
+  constructor(views, options) {
+    this.options = options;
+    this.views = new Map(views.map((view) => [view.id, view]));
+    this.multiplexed = views[0].id !== null;
+    // the token is only rendered in the page, not in the view once it has connected
+    this.csrfToken = views[0].el.getAttribute("data-csrf-token");
+    this.sse = false;
+    this.msgpack = false;
+    this.socket = null;
+    this.reconnectAfter = null;
+    // the attempts to reconnect since a view was last mounted
+    this.reconnects = 0;
+    this.pinging = false;
+
+    for (const view of views) {
+      view.connection = this;
+    }
+  }
+
+  connect() {
+    const query = new URLSearchParams();
+    if (this.csrfToken) {
+      query.set("_csrf", this.csrfToken);
+    }
+
+    let socket;
+    this.msgpack = !this.sse;
+    if (this.sse) {
+      socket = new LiveViewEventSource(withQuery(window.location.pathname, query));
+    } else {
+      query.set("encoding", "msgpack");
+      const protocol = window.location.protocol === "https:" ? "wss" : "ws";
+      const path = withQuery(window.location.pathname, query);
+      socket = new WebSocket(`${protocol}://${window.location.host}${path}`);
+      socket.binaryType = "arraybuffer";
+    }
+    this.socket = socket;
+
+    let opened = false;
+    let heartbeat;
+    socket.addEventListener("open", () => {
+      opened = true;
+      heartbeat = setInterval(() => this.ping(), HEARTBEAT_INTERVAL_MS);
+    });
+    socket.addEventListener("message", (event) => this.receive(event.data));
+    socket.addEventListener("close", (event) => {
+      clearInterval(heartbeat);
+      this.socket = null;
+
+      // a WebSocket that fails before it opens is likely blocked by a proxy
+      if (!opened && !this.sse && event.code === CLOSE_ABNORMAL) {
+        this.sse = true;
+        this.connect();
+        return;
+      }
+
+      this.closed(this, "live view socket", event.code, event.reason, () => this.connect());
+    });
+  }
+
+  send(view, msg) {
+    if (!this.socket || this.socket.readyState !== WebSocket.OPEN) {
+      return;
+    }
+    if (!this.msgpack) {
+      if (this.multiplexed && view) {
+        msg = { v: view.id, ...msg };
+      }
+      this.socket.send(JSON.stringify(msg));
+      return;
+    }
+
+    this.sendBinary(view, encodeMsgpack(msg));
+  }
+
+  // Send a binary message, such as a MessagePack message or a chunk of an upload.
+  sendBinary(view, data) {
+    if (!this.socket || this.socket.readyState !== WebSocket.OPEN) {
+      return;
+    }
+    if (!this.multiplexed) {
+      this.socket.send(data);
+      return;
+    }
+    // binary messages start with the length of their view's id and the id, which is empty for
+    // messages to every view
+    const id = new TextEncoder().encode(view ? view.id : "");
+    const tagged = new Uint8Array(1 + id.length + data.length);
+    tagged[0] = id.length;
+    tagged.set(id, 1);
+    tagged.set(data, 1 + id.length);
+    this.socket.send(tagged);
+  }
+
+  // Mount a closed view of a shared socket again, which is always done with a text message.
+  join(view) {
+    if (this.socket && this.socket.readyState === WebSocket.OPEN) {
+      this.socket.send(JSON.stringify({ v: view.id, t: "join" }));
+    }
+  }
+
+  receive(data) {
+    const msg = parseMessage(data, this.multiplexed);
+    const view = this.views.get(this.multiplexed ? msg.v : null);
+    if (!view) {
+      return;
+    }
+    if (msg.t === "c") {
+      this.viewClosed(view, msg.d);
+      return;
+    }
+    if (msg.t === "i") {
+      // the view was mounted, so it and the socket start over if they are closed again
+      this.reconnects = 0;
+      view.reconnects = 0;
+    }
+    view.receive(msg);
+  }
+
+  ping() {
+    if (this.options.debug) {
+      console.time(PING_TIMER);
+    }
+    this.pinging = true;
+    // sent without a view, so every view of a shared socket answers
+    this.send(null, { t: "h" });
+  }
+
+  pong() {
+    if (this.pinging && this.options.debug) {
+      console.timeEnd(PING_TIMER);
+    }
+    this.pinging = false;
+  }
+
+  // A view of a shared socket was closed while the socket stays open.
+  viewClosed(view, { c: code, r: reason }) {
+    // the socket is closed as well and rejoins all views when it reconnects
+    if (code === CLOSE_GOING_AWAY) {
+      return;
+    }
+    this.closed(view, `live view ${view.id}`, code, reason, () => this.join(view));
+  }
+
+  // Decide whether and when to reconnect the socket, when `target` is the connection, or to join
+  // a view of a shared socket again, when it is the view.
+  closed(target, what, code, reason, reconnect) {
+    switch (code) {
+      case CLOSE_VIEW_ERROR:
+        // the view failed for good, the error it was sent is shown in its place
+        break;
+      case CLOSE_FORBIDDEN:
+        // the CSRF token has most likely expired, the page comes with a new one
+        if (!reloadOnce()) {
+          console.error(`${what} rejected: ${reason}`);
+        }
+        break;
+      case CLOSE_DISCONNECTED:
+        console.error(`${what} disconnected by the server`);
+        break;
+      case CLOSE_IDLE_TIMEOUT:
+        onUserActivity(reconnect);
+        break;
+      default: {
+        const attempts = target.reconnects;
+        // the socket is reconnected until the network comes back, views that keep failing aren't
+        if ((code === CLOSE_REMOUNT || target !== this) && attempts >= MAX_REMOUNTS) {
+          console.error(`${what} failed ${attempts} times, reload the page to try again`);
+          break;
+        }
+        target.reconnects += 1;
+        setTimeout(reconnect, this.reconnectDelay(attempts));
+      }
+    }
+  }
+
+  // The server tells clients when to come back as it shuts down, so they don't all do so at once.
+  // Otherwise the delay doubles with each attempt.
+  reconnectDelay(attempts) {
+    if (this.reconnectAfter !== null) {
+      const after = this.reconnectAfter;
+      this.reconnectAfter = null;
+      return after;
+    }
+    return Math.min(RECONNECT_AFTER_MS * 2 ** attempts, MAX_RECONNECT_AFTER_MS);
+  }
+}
+
+/**
+ * Parse a message from the server, which is JSON text or MessagePack.
+ *
+ * The id of the view a binary message of a shared socket is for is added as its `v` field, like
+ * JSON messages have it.
+ */
+export function parseMessage(data, multiplexed) {
+  if (typeof data === "string") {
+    return JSON.parse(data);
+  }
+
+  const bytes = new Uint8Array(data);
+  if (!multiplexed) {
+    return decodeMsgpack(bytes);
+  }
+  const len = bytes[0];
+  const id = new TextDecoder().decode(bytes.subarray(1, 1 + len));
+  return { v: id, ...decodeMsgpack(bytes.subarray(1 + len)) };
+}
+
+function withQuery(path, query) {
+  const search = query.toString();
+  return search ? `${path}?${search}` : path;
+}
+
+// Reload the page unless it was reloaded for the same reason a moment ago.
+function reloadOnce() {
+  const now = Date.now();
+  const last = Number(window.sessionStorage.getItem(RELOADED_AT));
+  if (now - last < RELOAD_AT_MOST_EVERY_MS) {
+    return false;
+  }
+  window.sessionStorage.setItem(RELOADED_AT, String(now));
+  window.location.reload();
+  return true;
+}
+
+function onUserActivity(f) {
+  const listener = () => {
+    for (const event of USER_ACTIVITY) {
+      window.removeEventListener(event, listener);
+    }
+    f();
+  };
+  for (const event of USER_ACTIVITY) {
+    window.addEventListener(event, listener);
+  }
+}
@@ -0,0 +1,117 @@
+// Hooks connect JavaScript widgets to the elements of a view with `axm-hook="<name>"`, see
+// `# Hooks` in the crate. They are passed to the client with its `hooks` option or registered
+// later:
+//
+//     window.liveView.registerHook("Chart", {
+//       mounted() {
+//         this.chart = createChart(this.el);
+//         this.chart.on("zoom", (level) => this.pushEvent("zoom", { level }));
+//         this.handleEvent("points", (points) => this.chart.setData(points));
+//       },
+//       updated() {},
+//       destroyed() {},
+//     });
+//
+// `mounted` is called when an element with the hook is added, `updated` after each render that
+// keeps it, and `destroyed` once it has been removed or names another hook.
+
+import { parseBinding } from "./bindings.js";
+import { runCommands } from "./commands.js";
+
+const HOOK_ATTR = "axm-hook";
+const HOOK_EVENT_ATTR = "axm-hook-event";
+
+/** Mount, update and destroy the hooks of `view` after it has been rendered. */
+export function syncHooks(view) {
+  const definitions = view.options.hooks || {};
+  const present = new Set();
+
+  view.el.querySelectorAll(`[${HOOK_ATTR}]`).forEach((el) => {
+    const name = el.getAttribute(HOOK_ATTR);
+    let hook = view.hooks.get(el);
+    if (hook && hook.name !== name) {
+      destroyHook(view, el, hook);
+      hook = null;
+    }
+
+    if (hook) {
+      present.add(el);
+      callHook(hook, "updated");
+      return;
+    }
+
+    // the hook may be registered later
+    const definition = definitions[name];
+    if (!definition) {
+      if (view.options.debug) {
+        console.warn(`no hook named ${name}`);
+      }
+      return;
+    }
+    hook = newHook(view, el, name, definition);
+    view.hooks.set(el, hook);
+    present.add(el);
+    callHook(hook, "mounted");
+  });
+
+  for (const [el, hook] of view.hooks) {
+    if (!present.has(el)) {
+      destroyHook(view, el, hook);
+    }
+  }
+}
+
+/** Pass an event the view pushed to the hooks handling it. */
+export function dispatchHookEvent(view, event, payload) {
+  for (const hook of view.hooks.values()) {
+    for (const callback of hook.handlers.get(event) || []) {
+      callback(payload);
+    }
+  }
+}
+
+// The hook's `this`: its definition with the element and the functions to talk to the view.
+function newHook(view, el, name, definition) {
+  const hook = Object.create(definition);
+  hook.el = el;
+  hook.name = name;
+  hook.handlers = new Map();
+
+  hook.pushEvent = (event, payload = null) => {
+    const value = el.getAttribute(HOOK_EVENT_ATTR);
+    if (!value) {
+      console.error(`hook ${name} pushed ${event} without an ${HOOK_EVENT_ATTR} message`);
+      return;
+    }
+    const binding = parseBinding(value);
+    if (binding.commands) {
+      runCommands(view, binding.commands);
+    } else {
+      view.send({ m: binding.message, t: "hook", d: { e: event, p: payload } });
+    }
+  };
+  hook.handleEvent = (event, callback) => {
+    if (!hook.handlers.has(event)) {
+      hook.handlers.set(event, []);
+    }
+    hook.handlers.get(event).push(callback);
+  };
+
+  return hook;
+}
+
+function destroyHook(view, el, hook) {
+  view.hooks.delete(el);
+  callHook(hook, "destroyed");
+}
+
+function callHook(hook, callback) {
+  if (typeof hook[callback] !== "function") {
+    return;
+  }
+  try {
+    hook[callback]();
+  } catch (err) {
+    console.error(`hook ${hook.name} failed in ${callback}`, err);
+  }
+}
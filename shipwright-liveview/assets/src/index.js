@@ -0,0 +1,54 @@
+import { Connection } from "./connection.js";
+import { syncHooks } from "./hooks.js";
+import { listenForHistory } from "./navigation.js";
+import { View } from "./view.js";
+
+/**
+ * The client for the live views on the page.
+ *
+ * Connects as soon as it is created, to the single view in `#live-view-container` or to the views
+ * embedded in `[data-live-view-id]` containers.
+ */
+export class LiveView {
+  constructor(options = {}) {
+    this.options = { debug: false, ...options, hooks: { ...options.hooks } };
+
+    const container = document.getElementById("live-view-container");
+    const views = container
+      ? [new View(container, null, this.options)]
+      : Array.from(
+          document.querySelectorAll("[data-live-view-id]"),
+          (el) => new View(el, el.getAttribute("data-live-view-id"), this.options),
+        );
+
+    this.views = views;
+    this.connection = null;
+    if (views.length > 0) {
+      this.connection = new Connection(views, this.options);
+      this.connection.connect();
+      listenForHistory(views[0]);
+    }
+  }
+
+  /**
+   * Register the hook for elements with `axm-hook="<name>"`, see `hooks.js`.
+   *
+   * Elements that are already rendered get their hook right away.
+   */
+  registerHook(name, hook) {
+    this.options.hooks[name] = hook;
+    for (const view of this.views) {
+      if (view.state) {
+        syncHooks(view);
+      }
+    }
+  }
+
+  enableDebug() {
+    this.options.debug = true;
+  }
+
+  disableDebug() {
+    this.options.debug = false;
+  }
+}
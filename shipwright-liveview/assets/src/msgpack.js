@@ -0,0 +1,305 @@
+/**
+ * MessagePack encoding of the messages, see `codec` in the crate.
+ *
+ * Only what the protocol needs is supported: no extension types, and integers beyond
+ * `Number.MAX_SAFE_INTEGER` are decoded as `BigInt`s. Values are encoded in their most compact
+ * form, like the crate does.
+ */
+
+const textEncoder = new TextEncoder();
+const textDecoder = new TextDecoder();
+
+export function encodeMsgpack(value) {
+  const writer = new Writer();
+  writer.value(value);
+  return writer.finish();
+}
+
+// `data` is an `ArrayBuffer` or a `Uint8Array`.
+export function decodeMsgpack(data) {
+  const bytes = data instanceof Uint8Array ? data : new Uint8Array(data);
+  const reader = new Reader(bytes);
+  const value = reader.value();
+  if (reader.offset !== bytes.length) {
+    throw new Error("trailing bytes after MessagePack value");
+  }
+  return value;
+}
+
+class Writer {
+  constructor() {
+    this.bytes = new Uint8Array(64);
+    this.view = new DataView(this.bytes.buffer);
+    this.length = 0;
+  }
+
+  reserve(n) {
+    if (this.length + n <= this.bytes.length) {
+      return;
+    }
+    let size = this.bytes.length * 2;
+    while (size < this.length + n) {
+      size *= 2;
+    }
+    const bytes = new Uint8Array(size);
+    bytes.set(this.bytes.subarray(0, this.length));
+    this.bytes = bytes;
+    this.view = new DataView(bytes.buffer);
+  }
+
+  u8(n) {
+    this.reserve(1);
+    this.view.setUint8(this.length, n);
+    this.length += 1;
+  }
+
+  head(tag, n, size) {
+    this.u8(tag);
+    this.reserve(size);
+    switch (size) {
+      case 1:
+        this.view.setUint8(this.length, n);
+        break;
+      case 2:
+        this.view.setUint16(this.length, n);
+        break;
+      case 4:
+        this.view.setUint32(this.length, n);
+        break;
+      default:
+        this.view.setBigUint64(this.length, BigInt.asUintN(64, BigInt(n)));
+    }
+    this.length += size;
+  }
+
+  raw(bytes) {
+    this.reserve(bytes.length);
+    this.bytes.set(bytes, this.length);
+    this.length += bytes.length;
+  }
+
+  // The header of a string, array or map, using the `fix` form for lengths below `fixMax`.
+  sized(len, fix, fixMax, tags) {
+    if (len <= fixMax) {
+      this.u8(fix | len);
+    } else if (tags[0] !== null && len <= 0xff) {
+      this.head(tags[0], len, 1);
+    } else if (len <= 0xffff) {
+      this.head(tags[1], len, 2);
+    } else {
+      this.head(tags[2], len, 4);
+    }
+  }
+
+  value(value) {
+    if (value === null || value === undefined) {
+      this.u8(0xc0);
+    } else if (value === false) {
+      this.u8(0xc2);
+    } else if (value === true) {
+      this.u8(0xc3);
+    } else if (typeof value === "number") {
+      this.number(value);
+    } else if (typeof value === "bigint") {
+      this.integer(value);
+    } else if (typeof value === "string") {
+      const bytes = textEncoder.encode(value);
+      this.sized(bytes.length, 0xa0, 31, [0xd9, 0xda, 0xdb]);
+      this.raw(bytes);
+    } else if (value instanceof Uint8Array) {
+      if (value.length <= 0xff) {
+        this.head(0xc4, value.length, 1);
+      } else if (value.length <= 0xffff) {
+        this.head(0xc5, value.length, 2);
+      } else {
+        this.head(0xc6, value.length, 4);
+      }
+      this.raw(value);
+    } else if (Array.isArray(value)) {
+      this.sized(value.length, 0x90, 15, [null, 0xdc, 0xdd]);
+      for (const item of value) {
+        this.value(item);
+      }
+    } else {
+      // like `JSON.stringify`, fields that are `undefined` are left out
+      const entries = Object.entries(value).filter(([, item]) => item !== undefined);
+      this.sized(entries.length, 0x80, 15, [null, 0xde, 0xdf]);
+      for (const [key, item] of entries) {
+        this.value(key);
+        this.value(item);
+      }
+    }
+  }
+
+  number(n) {
+    if (Number.isSafeInteger(n)) {
+      this.integer(n);
+    } else {
+      this.u8(0xcb);
+      this.reserve(8);
+      this.view.setFloat64(this.length, n);
+      this.length += 8;
+    }
+  }
+
+  integer(n) {
+    if (n >= 0) {
+      if (n <= 0x7f) {
+        this.u8(Number(n));
+      } else if (n <= 0xff) {
+        this.head(0xcc, Number(n), 1);
+      } else if (n <= 0xffff) {
+        this.head(0xcd, Number(n), 2);
+      } else if (n <= 0xffffffff) {
+        this.head(0xce, Number(n), 4);
+      } else {
+        this.head(0xcf, n, 8);
+      }
+    } else if (n >= -32) {
+      this.u8(0x100 + Number(n));
+    } else if (n >= -0x80) {
+      this.head(0xd0, 0x100 + Number(n), 1);
+    } else if (n >= -0x8000) {
+      this.head(0xd1, 0x10000 + Number(n), 2);
+    } else if (n >= -0x80000000) {
+      this.head(0xd2, 0x100000000 + Number(n), 4);
+    } else {
+      this.head(0xd3, n, 8);
+    }
+  }
+
+  finish() {
+    return this.bytes.slice(0, this.length);
+  }
+}
+
+class Reader {
+  constructor(bytes) {
+    this.bytes = bytes;
+    this.view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+    this.offset = 0;
+  }
+
+  take(n) {
+    if (this.offset + n > this.bytes.length) {
+      throw new Error("unexpected end of MessagePack value");
+    }
+    const offset = this.offset;
+    this.offset += n;
+    return offset;
+  }
+
+  u8() {
+    return this.view.getUint8(this.take(1));
+  }
+
+  u16() {
+    return this.view.getUint16(this.take(2));
+  }
+
+  u32() {
+    return this.view.getUint32(this.take(4));
+  }
+
+  // 64 bit integers are numbers when they fit, `BigInt`s otherwise
+  int64(n) {
+    return n >= BigInt(Number.MIN_SAFE_INTEGER) && n <= BigInt(Number.MAX_SAFE_INTEGER) ? Number(n) : n;
+  }
+
+  str(len) {
+    const start = this.take(len);
+    return textDecoder.decode(this.bytes.subarray(start, start + len));
+  }
+
+  bin(len) {
+    const start = this.take(len);
+    return this.bytes.slice(start, start + len);
+  }
+
+  array(len) {
+    const array = new Array(len);
+    for (let i = 0; i < len; i++) {
+      array[i] = this.value();
+    }
+    return array;
+  }
+
+  map(len) {
+    const map = {};
+    for (let i = 0; i < len; i++) {
+      const key = this.value();
+      map[key] = this.value();
+    }
+    return map;
+  }
+
+  value() {
+    const tag = this.u8();
+    if (tag <= 0x7f) {
+      return tag;
+    }
+    if (tag >= 0xe0) {
+      return tag - 0x100;
+    }
+    if (tag >= 0xa0 && tag <= 0xbf) {
+      return this.str(tag & 0x1f);
+    }
+    if (tag >= 0x90 && tag <= 0x9f) {
+      return this.array(tag & 0x0f);
+    }
+    if (tag >= 0x80 && tag <= 0x8f) {
+      return this.map(tag & 0x0f);
+    }
+
+    switch (tag) {
+      case 0xc0:
+        return null;
+      case 0xc2:
+        return false;
+      case 0xc3:
+        return true;
+      case 0xc4:
+        return this.bin(this.u8());
+      case 0xc5:
+        return this.bin(this.u16());
+      case 0xc6:
+        return this.bin(this.u32());
+      case 0xca:
+        return this.view.getFloat32(this.take(4));
+      case 0xcb:
+        return this.view.getFloat64(this.take(8));
+      case 0xcc:
+        return this.u8();
+      case 0xcd:
+        return this.u16();
+      case 0xce:
+        return this.u32();
+      case 0xcf:
+        return this.int64(this.view.getBigUint64(this.take(8)));
+      case 0xd0:
+        return this.view.getInt8(this.take(1));
+      case 0xd1:
+        return this.view.getInt16(this.take(2));
+      case 0xd2:
+        return this.view.getInt32(this.take(4));
+      case 0xd3:
+        return this.int64(this.view.getBigInt64(this.take(8)));
+      case 0xd9:
+        return this.str(this.u8());
+      case 0xda:
+        return this.str(this.u16());
+      case 0xdb:
+        return this.str(this.u32());
+      case 0xdc:
+        return this.array(this.u16());
+      case 0xdd:
+        return this.array(this.u32());
+      case 0xde:
+        return this.map(this.u16());
+      case 0xdf:
+        return this.map(this.u32());
+      default:
+        throw new Error(`unsupported MessagePack type 0x${tag.toString(16)}`);
+    }
+  }
+}
@@ -0,0 +1,87 @@
+// Live navigation changes the URL without reloading the page, see `navigation` in the crate.
+//
+// Links with `axm-patch` patch the view and links with `axm-redirect` replace it with the view of
+// the new URL, over the same socket. Going back and forward through the history patches the view
+// if the path stays the same, and redirects otherwise.
+//
+// Only a page with a single view navigates like this, the URL of a page embedding several views
+// doesn't belong to any of them so their links load the page normally.
+
+// the elements whose clicks are already handled
+const boundLinks = new WeakSet();
+
+/** Change the URL to `uri` and tell the view about it. */
+export function livePatch(view, uri) {
+  if (!navigates(view)) {
+    window.location.assign(uri);
+    return;
+  }
+  window.history.pushState({}, "", uri);
+  view.path = window.location.pathname;
+  view.send({ t: "lp", u: currentUri() });
+}
+
+/** Change the URL to `uri` and replace the view with the view at that URL. */
+export function liveRedirect(view, uri) {
+  if (!navigates(view)) {
+    window.location.assign(uri);
+    return;
+  }
+  window.history.pushState({}, "", uri);
+  view.path = window.location.pathname;
+  view.send({ t: "lr", u: currentUri() });
+}
+
+/** Handle clicks on the `axm-patch` and `axm-redirect` links in `view`. */
+export function bindLinks(view) {
+  if (!navigates(view)) {
+    return;
+  }
+  view.el.querySelectorAll("a[axm-patch], a[axm-redirect]").forEach((el) => {
+    if (boundLinks.has(el)) {
+      return;
+    }
+    boundLinks.add(el);
+    el.addEventListener("click", (e) => followLink(view, el, e));
+  });
+}
+
+function followLink(view, el, e) {
+  // let the browser open links in new tabs and windows
+  if (e.defaultPrevented || e.button !== 0 || e.metaKey || e.ctrlKey || e.shiftKey || e.altKey) {
+    return;
+  }
+  const url = new URL(el.href, window.location.href);
+  if (url.origin !== window.location.origin) {
+    return;
+  }
+
+  e.preventDefault();
+  const uri = `${url.pathname}${url.search}${url.hash}`;
+  if (el.hasAttribute("axm-redirect")) {
+    liveRedirect(view, uri);
+  } else if (el.hasAttribute("axm-patch")) {
+    livePatch(view, uri);
+  }
+}
+
+/** Patch or redirect `view` when the user goes back or forward through the history. */
+export function listenForHistory(view) {
+  if (!navigates(view)) {
+    return;
+  }
+  view.path = window.location.pathname;
+  window.addEventListener("popstate", () => {
+    const t = window.location.pathname === view.path ? "lp" : "lr";
+    view.path = window.location.pathname;
+    view.send({ t, u: currentUri() });
+  });
+}
+
+function navigates(view) {
+  return view.id === null;
+}
+
+function currentUri() {
+  return `${window.location.pathname}${window.location.search}`;
+}
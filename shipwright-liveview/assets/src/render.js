@@ -0,0 +1,213 @@
+// A view is kept as the tree of fixed and dynamic parts the server renders it to, see
+// `Html` in the crate. Diffs are applied to the tree, and the view's HTML is rendered from it
+// again.
+//
+// - A template is `{f: [fixed, ...], d: {index: part}}`. Its HTML is the fixed strings with the
+//   dynamic parts in between.
+// - A part is a string, a template, or a loop `{f: [fixed, ...], b: {row: {index: part}}}` whose
+//   rows all use the same fixed strings.
+// - Keyed loops also have the keys of their rows, `{f: [...], k: [key, ...], b: {...}}`, and are
+//   diffed by removing, inserting and moving rows by key.
+// - Streams are `{r: reset, s: [op, ...]}` where each operation inserts `{i: key, a: at, h: html}`
+//   or deletes `{d: key}` a row. The server forgets rows once they are sent, so a stream is only
+//   in a diff when it has new operations, which are applied to the rows the client already has.
+//   The rows are kept as a stream of appends.
+//
+// The first element of each row of a keyed loop or stream is rendered with an `axm-row`
+// attribute, so the DOM diffing moves the element along with its row rather than changing every
+// row after it.
+
+/**
+ * Apply the diff of a template to `html`.
+ *
+ * `null` parts have been removed, the others are diffs of the part at the same index or new
+ * parts.
+ */
+export function applyDiff(html, diff) {
+  if (diff.f) {
+    html.f = diff.f;
+  }
+  if (diff.d) {
+    html.d = html.d || {};
+    applyParts(html.d, diff.d);
+  }
+}
+
+function applyParts(parts, diff) {
+  for (const [idx, value] of Object.entries(diff)) {
+    if (value === null) {
+      delete parts[idx];
+    } else {
+      parts[idx] = patchPart(parts[idx], value);
+    }
+  }
+}
+
+// Returns the part after applying `diff`, which is either a diff of `current` or a new part if
+// the kind of part has changed.
+function patchPart(current, diff) {
+  if (typeof diff !== "string" && isStream(diff)) {
+    const rows = typeof current === "object" && isStream(current) && !diff.r ? streamRows(current) : [];
+    return { s: applyStreamOps(rows, diff.s).map(([key, html]) => ({ i: key, a: -1, h: html })) };
+  }
+
+  if (typeof diff === "string" || current === undefined || typeof current === "string") {
+    return diff;
+  }
+
+  if (isKeyedLoop(current)) {
+    if ("r" in diff || "o" in diff || "u" in diff) {
+      applyKeyedLoopDiff(current, diff);
+      return current;
+    }
+    return diff;
+  }
+
+  if ("k" in diff) {
+    return diff;
+  }
+
+  if (isLoop(current)) {
+    if ("d" in diff) {
+      return diff;
+    }
+    applyLoopDiff(current, diff);
+    return current;
+  }
+
+  if ("b" in diff) {
+    return diff;
+  }
+  applyDiff(current, diff);
+  return current;
+}
+
+function isLoop(part) {
+  return "b" in part && !isKeyedLoop(part);
+}
+
+function isKeyedLoop(part) {
+  return "k" in part;
+}
+
+function isStream(part) {
+  return "s" in part;
+}
+
+function applyLoopDiff(loop, diff) {
+  // unchanged fixed strings are left out
+  if (diff.f && diff.f.length > 0) {
+    loop.f = diff.f;
+  }
+  for (const [row, parts] of Object.entries(diff.b || {})) {
+    if (parts === null) {
+      delete loop.b[row];
+    } else {
+      loop.b[row] = loop.b[row] || {};
+      applyParts(loop.b[row], parts);
+    }
+  }
+}
+
+// Removed rows are dropped first, then rows are inserted and moved in order, and finally the
+// remaining rows are updated by key. See `diff_keyed` in the crate.
+function applyKeyedLoopDiff(loop, diff) {
+  const rows = new Map(loop.k.map((key, idx) => [key, loop.b[idx]]));
+
+  const removed = new Set(diff.r || []);
+  const keys = loop.k.filter((key) => !removed.has(key));
+
+  for (const op of diff.o || []) {
+    if ("i" in op) {
+      keys.splice(op.i, 0, op.k);
+      rows.set(op.k, {});
+      applyParts(rows.get(op.k), op.b);
+    } else {
+      keys.splice(keys.indexOf(op.k), 1);
+      keys.splice(op.m, 0, op.k);
+    }
+  }
+
+  for (const [key, parts] of Object.entries(diff.u || {})) {
+    applyParts(rows.get(key), parts);
+  }
+
+  loop.k = keys;
+  loop.b = Object.fromEntries(keys.map((key, idx) => [idx, rows.get(key)]));
+}
+
+// The `[key, html]` rows of a stream.
+function streamRows(stream) {
+  return applyStreamOps([], stream.s);
+}
+
+// Inserting a key that exists replaces the row in place. See `StreamFragment::rows` in the crate.
+function applyStreamOps(rows, ops) {
+  for (const op of ops) {
+    if ("d" in op) {
+      rows = rows.filter(([key]) => key !== op.d);
+      continue;
+    }
+
+    const existing = rows.find(([key]) => key === op.i);
+    if (existing) {
+      existing[1] = op.h;
+    } else if (op.a < 0) {
+      rows.push([op.i, op.h]);
+    } else {
+      rows.splice(Math.min(op.a, rows.length), 0, [op.i, op.h]);
+    }
+  }
+  return rows;
+}
+
+/** Render a template to HTML. */
+export function render(html, path = "") {
+  let out = "";
+  html.f.forEach((fixed, idx) => {
+    out += fixed;
+    const part = html.d && html.d[idx];
+    if (part !== undefined && part !== null) {
+      out += renderPart(part, `${path}.${idx}`);
+    }
+  });
+  return out;
+}
+
+// `path` locates the part in the view, which makes the `axm-row` of keyed rows unique.
+function renderPart(part, path) {
+  if (typeof part === "string") {
+    return part;
+  }
+  if (isKeyedLoop(part)) {
+    // rows with the same key can't be told apart
+    const unique = new Set(part.k).size === part.k.length;
+    return part.k
+      .map((key, idx) => {
+        const row = render({ f: part.f, d: part.b[idx] }, `${path}:${key}`);
+        return unique ? withRowKey(row, `${path}:${key}`) : row;
+      })
+      .join("");
+  }
+  if (isStream(part)) {
+    return streamRows(part)
+      .map(([key, html]) => withRowKey(render(html, `${path}:${key}`), `${path}:${key}`))
+      .join("");
+  }
+  if (isLoop(part)) {
+    return Object.entries(part.b)
+      .map(([idx, row]) => render({ f: part.f, d: row }, `${path}.${idx}`))
+      .join("");
+  }
+  return render(part, path);
+}
+
+// Add the `axm-row` attribute to the first element of a row, if it starts with one.
+function withRowKey(row, key) {
+  const tag = /^\s*<[a-zA-Z][^\s/>]*/.exec(row);
+  if (!tag) {
+    return row;
+  }
+  const escaped = key.replace(/&/g, "&amp;").replace(/"/g, "&quot;").replace(/</g, "&lt;");
+  return `${tag[0]} axm-row="${escaped}"${row.slice(tag[0].length)}`;
+}
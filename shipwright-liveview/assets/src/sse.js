@@ -0,0 +1,65 @@
+/**
+ * A socket over Server-Sent Events, for when WebSockets can't be opened.
+ *
+ * Has the parts of the `WebSocket` interface the client uses. Messages from the server arrive as
+ * events and messages to the server are `POST`ed one at a time, see `sse` in the crate.
+ */
+export class LiveViewEventSource extends EventTarget {
+  constructor(url) {
+    super();
+    this.url = url;
+    this.readyState = WebSocket.CONNECTING;
+    this.session = null;
+    this.queue = Promise.resolve();
+
+    this.source = new EventSource(url);
+    this.source.addEventListener("session", (event) => {
+      this.session = event.data;
+      this.readyState = WebSocket.OPEN;
+      this.dispatchEvent(new Event("open"));
+    });
+    this.source.addEventListener("message", (event) => {
+      this.dispatchEvent(new MessageEvent("message", { data: event.data }));
+    });
+    this.source.addEventListener("close", (event) => {
+      const { c, r } = JSON.parse(event.data);
+      this.close(c, r);
+    });
+    this.source.addEventListener("error", () => this.close(1006, ""));
+  }
+
+  send(data) {
+    if (this.readyState !== WebSocket.OPEN) {
+      return;
+    }
+
+    const session = this.session;
+    const contentType = typeof data === "string" ? "text/plain;charset=utf-8" : "application/octet-stream";
+    this.queue = this.queue
+      .then(() =>
+        fetch(this.url, {
+          method: "POST",
+          headers: { "x-live-view-session": session, "content-type": contentType },
+          body: data,
+        }),
+      )
+      .then(
+        (response) => {
+          // the session has ended
+          if (response.status === 404) {
+            this.close(1006, "");
+          }
+        },
+        () => this.close(1006, ""),
+      );
+  }
+
+  close(code = 1000, reason = "") {
+    if (this.readyState === WebSocket.CLOSED) {
+      return;
+    }
+    this.readyState = WebSocket.CLOSED;
+    this.source.close();
+    this.dispatchEvent(new CloseEvent("close", { code, reason }));
+  }
+}
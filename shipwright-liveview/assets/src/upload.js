@@ -0,0 +1,99 @@
+// File inputs with `axm-upload="<name>"` send the files picked in them to the upload the view
+// allowed with that name, over the view's socket. See `upload` in the crate for the protocol.
+
+const UPLOAD_ATTR = "axm-upload";
+const CHUNK_SIZE = 32 * 1024;
+// chunks aren't read from the file while the socket has more than this waiting to be sent
+const MAX_BUFFERED = 16 * CHUNK_SIZE;
+
+// the inputs that already listen for files
+const boundInputs = new WeakSet();
+// ids only have to be unique per socket, but are never reused so replies can't be mixed up
+let nextUploadId = 1;
+
+/** Upload the files picked in the `axm-upload` inputs of `view`. */
+export function bindUploads(view) {
+  view.el.querySelectorAll(`input[type="file"][${UPLOAD_ATTR}]`).forEach((input) => {
+    if (boundInputs.has(input)) {
+      return;
+    }
+    boundInputs.add(input);
+
+    input.addEventListener("change", () => {
+      // picking files again replaces the ones still being uploaded
+      cancelUploads(view, input);
+      const name = input.getAttribute(UPLOAD_ATTR);
+      for (const file of Array.from(input.files)) {
+        startUpload(view, input, name, file);
+      }
+    });
+  });
+}
+
+function startUpload(view, input, name, file) {
+  const id = nextUploadId++;
+  view.uploads.set(id, { input, file, socket: view.connection.socket });
+  view.send({ t: "us", n: name, r: id, f: file.name, ct: file.type, s: file.size });
+}
+
+function cancelUploads(view, input) {
+  for (const [id, upload] of view.uploads) {
+    if (upload.input === input) {
+      view.uploads.delete(id);
+      view.send({ t: "uc", r: id });
+    }
+  }
+}
+
+/**
+ * Handle the server's reply to the start of an upload, `{r: <id>, e: <error or null>}`.
+ *
+ * The view is told about rejected files by the server so they are only forgotten here.
+ */
+export function uploadReply(view, { r: id, e: error }) {
+  const upload = view.uploads.get(id);
+  if (!upload) {
+    return;
+  }
+  if (error) {
+    view.uploads.delete(id);
+    if (view.options.debug) {
+      console.warn(`upload of ${upload.file.name} rejected: ${error}`);
+    }
+    return;
+  }
+  sendChunks(view, id, upload).catch((err) => {
+    view.uploads.delete(id);
+    console.error(`failed to upload ${upload.file.name}`, err);
+  });
+}
+
+async function sendChunks(view, id, upload) {
+  const { file, socket } = upload;
+  for (let offset = 0; offset < file.size; offset += CHUNK_SIZE) {
+    await drained(socket);
+    // cancelled, or the socket was closed which ends its uploads
+    if (view.uploads.get(id) !== upload || view.connection.socket !== socket) {
+      return;
+    }
+    const data = new Uint8Array(await file.slice(offset, offset + CHUNK_SIZE).arrayBuffer());
+    view.connection.sendBinary(view, uploadChunk(id, data));
+  }
+  view.uploads.delete(id);
+}
+
+/** A binary message with part of a file: the upload's id as a big endian `u64`, then the data. */
+export function uploadChunk(id, data) {
+  const chunk = new Uint8Array(8 + data.length);
+  new DataView(chunk.buffer).setBigUint64(0, BigInt(id));
+  chunk.set(data, 8);
+  return chunk;
+}
+
+// Wait for the socket to send what it has buffered, so large files aren't read into memory at
+// once. Server-Sent Events don't buffer, their messages are sent one at a time.
+async function drained(socket) {
+  while (socket && socket.bufferedAmount > MAX_BUFFERED) {
+    await new Promise((resolve) => setTimeout(resolve, 10));
+  }
+}
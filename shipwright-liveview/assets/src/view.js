@@ -0,0 +1,139 @@
+import { bindElements, bindWindow, unbindWindow } from "./bindings.js";
+import { runCommands } from "./commands.js";
+import { syncHooks } from "./hooks.js";
+import { morphdom } from "./morphdom.js";
+import { bindLinks } from "./navigation.js";
+import { applyDiff, render } from "./render.js";
+import { bindUploads, uploadReply } from "./upload.js";
+
+/**
+ * A live view rendered in a container element.
+ *
+ * Views of a page embedding several of them have the id from the container's
+ * `data-live-view-id`, a page with a single view uses `null`.
+ */
+export class View {
+  constructor(el, id, options) {
+    this.el = el;
+    this.id = id;
+    this.options = options;
+    this.connection = null;
+    this.state = null;
+    this.windowListeners = [];
+    // the path of the URL the view was last navigated to
+    this.path = null;
+    // the files being uploaded by their upload id
+    this.uploads = new Map();
+    // the mounted hooks by their element
+    this.hooks = new Map();
+    // the attempts to join the view of a shared socket again since it was last mounted
+    this.reconnects = 0;
+  }
+
+  /** Send a message to the view on the server. */
+  send(msg) {
+    this.connection.send(this, msg);
+  }
+
+  /** Handle a message from the view on the server. */
+  receive(msg) {
+    switch (msg.t) {
+      case "i":
+        this.state = msg.d;
+        this.update();
+        break;
+      case "r":
+        if (this.state && msg.d) {
+          applyDiff(this.state, msg.d);
+          this.update();
+        }
+        break;
+      case "j":
+        runCommands(this, msg.d);
+        break;
+      case "h":
+        this.connection.pong();
+        break;
+      case "e":
+        console.error(`live view error ${msg.d.c}: ${msg.d.m}`);
+        this.showError(msg.d.m);
+        break;
+      case "g":
+        this.connection.reconnectAfter = msg.d.r;
+        break;
+      case "u":
+        uploadReply(this, msg.d);
+        break;
+    }
+  }
+
+  /**
+   * Replace the view with the message of the error that closed it.
+   *
+   * The message is rendered in a `<div class="axm-error" role="alert">` that can be styled.
+   */
+  showError(message) {
+    unbindWindow(this);
+    const error = document.createElement("div");
+    error.className = "axm-error";
+    error.setAttribute("role", "alert");
+    error.textContent = message;
+    this.el.replaceChildren(error);
+    syncHooks(this);
+  }
+
+  update() {
+    unbindWindow(this);
+
+    // the rendered view is wrapped in `<div id="live-view-container">`, which replaces the
+    // container of a single view, while the containers of embedded views keep their own
+    // attributes
+    morphdom(this.el, render(this.state), {
+      childrenOnly: this.id !== null,
+      getNodeKey: nodeKey,
+      onBeforeElUpdated: keepInputValues,
+    });
+
+    bindElements(this);
+    bindLinks(this);
+    bindUploads(this);
+    bindWindow(this);
+    syncHooks(this);
+  }
+}
+
+// Rows of keyed loops are matched up by their key, like elements with an id.
+function nodeKey(node) {
+  if (node.nodeType === Node.ELEMENT_NODE) {
+    return node.getAttribute("id") || node.getAttribute("axm-row") || undefined;
+  }
+}
+
+// Don't overwrite what the user is typing with what the server last rendered.
+function keepInputValues(fromEl, toEl) {
+  if (fromEl instanceof HTMLInputElement && toEl instanceof HTMLInputElement) {
+    const type = toEl.getAttribute("type");
+    // the files picked in a file input can't be set on another element, so it is kept as it is
+    if (type === "file" && fromEl.type === "file") {
+      return false;
+    }
+    if (type === "radio" || type === "checkbox") {
+      toEl.checked = fromEl.checked;
+    } else {
+      toEl.value = fromEl.value;
+    }
+  }
+  if (fromEl instanceof HTMLTextAreaElement && toEl instanceof HTMLTextAreaElement) {
+    toEl.value = fromEl.value;
+  }
+  if (fromEl instanceof HTMLOptionElement && toEl instanceof HTMLOptionElement) {
+    const select = toEl.closest("select");
+    if (select && select.hasAttribute("multiple")) {
+      toEl.selected = fromEl.selected;
+    }
+  }
+  if (fromEl instanceof HTMLSelectElement && toEl instanceof HTMLSelectElement && !toEl.hasAttribute("multiple")) {
+    toEl.value = fromEl.value;
+  }
+  return true;
+}
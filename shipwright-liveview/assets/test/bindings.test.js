@@ -0,0 +1,21 @@
+import assert from "node:assert/strict";
+import { test } from "node:test";
+import { parseBinding } from "../src/bindings.js";
+
+test("messages are parsed as JSON if they can be", () => {
+  assert.deepEqual(parseBinding("%22Increment%22"), { message: "%22Increment%22" });
+  assert.deepEqual(parseBinding('{"Add":1}'), { message: { Add: 1 } });
+});
+
+test("js bindings are decoded to commands", () => {
+  // rendered by `html!` for `axm-click={ js_command::show_as("#menu", "flex") }`
+  const value = 'js:[{%22kind%22:{%22t%22:%22show%22,%22selector%22:%22#menu%22,%22display%22:%22flex%22},%22delay_ms%22:null}]';
+  assert.deepEqual(parseBinding(value), {
+    commands: [{ kind: { t: "show", selector: "#menu", display: "flex" }, delay_ms: null }],
+  });
+});
+
+test("percent signs that aren't escapes are kept", () => {
+  const value = "js:[{%22kind%22:{%22t%22:%22set_title%22,%22title%22:%22100%%20done%22},%22delay_ms%22:null}]";
+  assert.deepEqual(parseBinding(value).commands[0].kind.title, "100% done");
+});
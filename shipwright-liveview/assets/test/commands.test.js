@@ -0,0 +1,32 @@
+import assert from "node:assert/strict";
+import { test } from "node:test";
+import { runCommands } from "../src/commands.js";
+
+test("cookies are set before navigating away", () => {
+  const events = [];
+  globalThis.document = {
+    set cookie(cookie) {
+      events.push(["cookie", cookie]);
+    },
+  };
+  globalThis.window = {
+    location: {
+      assign(uri) {
+        events.push(["navigate", uri]);
+      },
+    },
+  };
+
+  // what the crate sends for a view that puts a flash message and navigates
+  runCommands(null, [
+    { kind: { t: "set_cookie", cookie: "axm_flash=abc; Path=/; Max-Age=60; SameSite=Lax" }, delay_ms: null },
+    { kind: { t: "navigate_to", uri: "/posts" }, delay_ms: null },
+  ]);
+
+  assert.deepEqual(events, [
+    ["cookie", "axm_flash=abc; Path=/; Max-Age=60; SameSite=Lax"],
+    ["navigate", "/posts"],
+  ]);
+  delete globalThis.document;
+  delete globalThis.window;
+});
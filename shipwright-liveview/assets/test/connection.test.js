@@ -0,0 +1,168 @@
+import assert from "node:assert/strict";
+import { test } from "node:test";
+import { Connection } from "../src/connection.js";
+import { View } from "../src/view.js";
+
+// the WebSockets opened by the client, whose closing is simulated with `close`
+const sockets = [];
+class FakeWebSocket {
+  static OPEN = 1;
+
+  constructor(url) {
+    this.url = url;
+    this.listeners = {};
+    sockets.push(this);
+  }
+
+  addEventListener(event, listener) {
+    this.listeners[event] = listener;
+  }
+
+  close(code, reason) {
+    this.listeners.close({ code, reason });
+  }
+}
+globalThis.WebSocket = FakeWebSocket;
+globalThis.window = { location: { protocol: "http:", host: "localhost", pathname: "/" } };
+
+// the views embedded with `response_many`, connected over a socket recording what is sent
+function sharedSocket(...ids) {
+  const views = ids.map((id) => ({
+    id,
+    el: { getAttribute: () => null },
+    reconnects: 0,
+    received: [],
+    receive(msg) {
+      this.received.push(msg);
+    },
+  }));
+  const connection = new Connection(views, {});
+  const sent = [];
+  connection.socket = { readyState: WebSocket.OPEN, send: (data) => sent.push(data) };
+  return { connection, views, sent };
+}
+
+test("messages are passed to the view they are tagged with", () => {
+  const { connection, views } = sharedSocket("cart", "menu");
+
+  connection.receive('{"v":"menu","t":"r","d":{}}');
+  connection.receive('{"v":"gone","t":"r","d":{}}');
+
+  assert.deepEqual(views[0].received, []);
+  assert.deepEqual(views[1].received, [{ v: "menu", t: "r", d: {} }]);
+});
+
+test("JSON messages are tagged with their view", () => {
+  const { connection, views, sent } = sharedSocket("cart", "menu");
+
+  connection.send(views[1], { m: "1", t: "click" });
+  connection.ping();
+
+  assert.deepEqual(sent, ['{"v":"menu","m":"1","t":"click"}', '{"t":"h"}']);
+});
+
+test("closed views are joined again", (t) => {
+  t.mock.timers.enable({ apis: ["setTimeout"] });
+  const { connection, views, sent } = sharedSocket("cart", "menu");
+
+  // see `close_code::REMOUNT` in the crate
+  connection.receive('{"v":"cart","t":"c","d":{"c":4001,"r":"remount"}}');
+  assert.deepEqual(sent, []);
+  assert.deepEqual(views[0].received, []);
+
+  t.mock.timers.tick(1000);
+  assert.deepEqual(sent, ['{"v":"cart","t":"join"}']);
+});
+
+test("views closed after an error show it and aren't joined again", (t) => {
+  t.mock.timers.enable({ apis: ["setTimeout"] });
+  t.mock.method(console, "error", () => {});
+  const { connection, views, sent } = sharedSocket("cart", "menu");
+
+  // see `close_code::VIEW_ERROR` in the crate
+  connection.receive('{"v":"cart","t":"e","d":{"c":4000,"m":"Cart unavailable"}}');
+  connection.receive('{"v":"cart","t":"c","d":{"c":4000,"r":"view error"}}');
+  t.mock.timers.tick(60000);
+
+  assert.deepEqual(views[0].received, [{ v: "cart", t: "e", d: { c: 4000, m: "Cart unavailable" } }]);
+  assert.deepEqual(sent, []);
+});
+
+test("error messages replace the view", (t) => {
+  t.mock.method(console, "error", () => {});
+  globalThis.document = {
+    createElement: (tag) => ({
+      tag,
+      attributes: {},
+      setAttribute(name, value) {
+        this.attributes[name] = value;
+      },
+    }),
+  };
+  const el = {
+    children: [],
+    replaceChildren(...children) {
+      this.children = children;
+    },
+    querySelectorAll: () => [],
+  };
+  const view = new View(el, null, {});
+
+  view.receive({ t: "e", d: { c: 4000, m: "Cart unavailable" } });
+
+  assert.equal(el.children.length, 1);
+  const [error] = el.children;
+  assert.equal(error.className, "axm-error");
+  assert.equal(error.attributes.role, "alert");
+  assert.equal(error.textContent, "Cart unavailable");
+});
+
+test("views that fail to mount are remounted less often until the client gives up", (t) => {
+  t.mock.timers.enable({ apis: ["setTimeout"] });
+  t.mock.method(console, "error", () => {});
+  const view = { id: null, el: { getAttribute: () => null }, reconnects: 0, receive() {} };
+  const connection = new Connection([view], {});
+  sockets.length = 0;
+  connection.connect();
+
+  for (const delay of [1000, 2000, 4000, 8000, 16000]) {
+    // see `close_code::REMOUNT` in the crate
+    sockets.at(-1).close(4001, "remount");
+    const opened = sockets.length;
+    t.mock.timers.tick(delay - 1);
+    assert.equal(sockets.length, opened);
+    t.mock.timers.tick(1);
+    assert.equal(sockets.length, opened + 1);
+  }
+
+  sockets.at(-1).close(4001, "remount");
+  t.mock.timers.tick(60000);
+  assert.equal(sockets.length, 6);
+  assert.equal(console.error.mock.callCount(), 1);
+});
+
+test("views of a shared socket are joined less often until the client gives up", (t) => {
+  t.mock.timers.enable({ apis: ["setTimeout"] });
+  t.mock.method(console, "error", () => {});
+  const { connection, sent } = sharedSocket("cart", "menu");
+  const remount = '{"v":"cart","t":"c","d":{"c":4001,"r":"remount"}}';
+
+  for (const delay of [1000, 2000, 4000]) {
+    connection.receive(remount);
+    t.mock.timers.tick(delay);
+  }
+  assert.equal(sent.length, 3);
+
+  // mounting the view starts over
+  connection.receive('{"v":"cart","t":"i","d":{}}');
+  for (const delay of [1000, 2000, 4000, 8000, 16000]) {
+    connection.receive(remount);
+    t.mock.timers.tick(delay);
+  }
+  assert.equal(sent.length, 8);
+
+  connection.receive(remount);
+  t.mock.timers.tick(60000);
+  assert.equal(sent.length, 8);
+  assert.equal(console.error.mock.callCount(), 1);
+});
@@ -0,0 +1,92 @@
+import assert from "node:assert/strict";
+import { test } from "node:test";
+import { runCommands } from "../src/commands.js";
+import { syncHooks } from "../src/hooks.js";
+
+function fakeElement(attrs) {
+  return { getAttribute: (name) => (name in attrs ? attrs[name] : null) };
+}
+
+// a view rendering `elements`, recording what it sends and which hook callbacks run
+function fakeView(definitions) {
+  const view = {
+    elements: [],
+    el: { querySelectorAll: () => view.elements },
+    options: { debug: false, hooks: definitions },
+    hooks: new Map(),
+    sent: [],
+    send: (msg) => view.sent.push(msg),
+  };
+  return view;
+}
+
+function recordingHook(calls) {
+  return {
+    mounted() {
+      calls.push(["mounted", this.el.getAttribute("id")]);
+    },
+    updated() {
+      calls.push(["updated", this.el.getAttribute("id")]);
+    },
+    destroyed() {
+      calls.push(["destroyed", this.el.getAttribute("id")]);
+    },
+  };
+}
+
+test("hooks are mounted, updated and destroyed with their elements", () => {
+  const calls = [];
+  const view = fakeView({ Chart: recordingHook(calls), Map: recordingHook(calls) });
+  const chart = fakeElement({ id: "chart", "axm-hook": "Chart" });
+  const map = fakeElement({ id: "map", "axm-hook": "Map" });
+
+  view.elements = [chart];
+  syncHooks(view);
+  view.elements = [chart, map];
+  syncHooks(view);
+  view.elements = [map];
+  syncHooks(view);
+
+  assert.deepEqual(calls, [
+    ["mounted", "chart"],
+    ["updated", "chart"],
+    ["mounted", "map"],
+    ["updated", "map"],
+    ["destroyed", "chart"],
+  ]);
+});
+
+test("hooks push events with the message of their element", () => {
+  let hook;
+  const view = fakeView({
+    Chart: {
+      mounted() {
+        hook = this;
+      },
+    },
+  });
+  view.elements = [fakeElement({ "axm-hook": "Chart", "axm-hook-event": '"Zoom"' })];
+  syncHooks(view);
+
+  hook.pushEvent("zoom", { level: 2 });
+  assert.deepEqual(view.sent, [{ m: "Zoom", t: "hook", d: { e: "zoom", p: { level: 2 } } }]);
+});
+
+test("pushed events reach the hooks handling them", () => {
+  const received = [];
+  const view = fakeView({
+    Chart: {
+      mounted() {
+        this.handleEvent("points", (points) => received.push(points));
+      },
+    },
+  });
+  view.elements = [fakeElement({ "axm-hook": "Chart" }), fakeElement({ "axm-hook": "Chart" })];
+  syncHooks(view);
+
+  runCommands(view, [
+    { kind: { t: "push_event", event: "points", payload: [[0, 1]] }, delay_ms: null },
+    { kind: { t: "push_event", event: "other", payload: null }, delay_ms: null },
+  ]);
+  assert.deepEqual(received, [[[0, 1]], [[0, 1]]]);
+});
@@ -0,0 +1,73 @@
+import assert from "node:assert/strict";
+import { readFileSync } from "node:fs";
+import { test } from "node:test";
+import { Connection, parseMessage } from "../src/connection.js";
+import { decodeMsgpack, encodeMsgpack } from "../src/msgpack.js";
+
+// written by `codec::tests::msgpack_client_fixture` in the crate
+const messages = JSON.parse(readFileSync(new URL("./fixtures/msgpack.json", import.meta.url)));
+
+function hex(bytes) {
+  return Buffer.from(bytes).toString("hex");
+}
+
+for (const { name, from, json, msgpack } of messages) {
+  if (from === "server") {
+    test(`decode message from the server: ${name}`, () => {
+      assert.deepEqual(decodeMsgpack(Buffer.from(msgpack, "hex")), json);
+    });
+  } else {
+    test(`encode message from the client: ${name}`, () => {
+      assert.equal(hex(encodeMsgpack(json)), msgpack);
+    });
+  }
+}
+
+test("values round trip", () => {
+  const values = [
+    -(2 ** 40),
+    -(2 ** 31) - 1,
+    2 ** 32,
+    Number.MAX_SAFE_INTEGER,
+    2n ** 63n,
+    -(2n ** 63n),
+    Math.PI,
+    "x".repeat(70000),
+    new Uint8Array([1, 2, 3]),
+    Array.from({ length: 70000 }, (_, i) => i),
+  ];
+  for (const value of values) {
+    assert.deepEqual(decodeMsgpack(encodeMsgpack(value)), value);
+  }
+});
+
+test("rejects truncated values", () => {
+  const bytes = encodeMsgpack({ t: "click" });
+  assert.throws(() => decodeMsgpack(bytes.subarray(0, bytes.length - 1)), /unexpected end/);
+});
+
+test("binary messages of shared sockets are tagged with their view", () => {
+  const msg = encodeMsgpack({ t: "r", d: {} });
+  const tagged = new Uint8Array([4, ...new TextEncoder().encode("cart"), ...msg]);
+  assert.deepEqual(parseMessage(tagged.buffer, true), { v: "cart", t: "r", d: {} });
+  assert.deepEqual(parseMessage(msg.buffer, false), { t: "r", d: {} });
+  assert.deepEqual(parseMessage('{"v":"cart","t":"h"}', true), { v: "cart", t: "h" });
+});
+
+test("messages to shared sockets are tagged with their view", () => {
+  globalThis.WebSocket ??= { OPEN: 1 };
+  const views = ["cart", "menu"].map((id) => ({ id, el: { getAttribute: () => null } }));
+  const connection = new Connection(views, {});
+  const sent = [];
+  connection.msgpack = true;
+  connection.socket = { readyState: WebSocket.OPEN, send: (data) => sent.push(data) };
+
+  connection.send(views[0], { t: "click", m: "1" });
+  connection.ping();
+  connection.join(views[1]);
+
+  assert.deepEqual(sent[0], new Uint8Array([4, ...new TextEncoder().encode("cart"), ...encodeMsgpack({ t: "click", m: "1" })]));
+  // heartbeats go to every view
+  assert.deepEqual(sent[1], new Uint8Array([0, ...encodeMsgpack({ t: "h" })]));
+  assert.equal(sent[2], '{"v":"menu","t":"join"}');
+});
@@ -0,0 +1,85 @@
+import assert from "node:assert/strict";
+import { beforeEach, test } from "node:test";
+import { listenForHistory, livePatch, liveRedirect } from "../src/navigation.js";
+
+// a window with just enough of `location` and `history` to navigate
+function fakeWindow(href) {
+  const listeners = {};
+  const win = {
+    location: null,
+    assigned: null,
+    history: {
+      pushState(_state, _title, uri) {
+        win.location = locationOf(new URL(uri, win.location.href));
+      },
+    },
+    addEventListener(event, listener) {
+      listeners[event] = listener;
+    },
+    // go back or forward to `uri`
+    pop(uri) {
+      win.location = locationOf(new URL(uri, win.location.href));
+      listeners.popstate();
+    },
+  };
+  win.location = locationOf(new URL(href));
+  return win;
+
+  function locationOf(url) {
+    return {
+      href: url.href,
+      origin: url.origin,
+      pathname: url.pathname,
+      search: url.search,
+      assign(uri) {
+        win.assigned = uri;
+      },
+    };
+  }
+}
+
+function fakeView(id = null) {
+  const view = { id, path: null, sent: [] };
+  view.send = (msg) => view.sent.push(msg);
+  return view;
+}
+
+beforeEach(() => {
+  globalThis.window = fakeWindow("http://localhost/users");
+});
+
+test("patching sends the new URI", () => {
+  const view = fakeView();
+  livePatch(view, "/users?page=2");
+  assert.equal(window.location.search, "?page=2");
+  assert.deepEqual(view.sent, [{ t: "lp", u: "/users?page=2" }]);
+});
+
+test("redirecting sends the new URI", () => {
+  const view = fakeView();
+  liveRedirect(view, "/settings#profile");
+  assert.equal(window.location.pathname, "/settings");
+  assert.deepEqual(view.sent, [{ t: "lr", u: "/settings" }]);
+});
+
+test("embedded views load the page normally", () => {
+  const view = fakeView("sidebar");
+  liveRedirect(view, "/settings");
+  assert.equal(window.assigned, "/settings");
+  assert.deepEqual(view.sent, []);
+});
+
+test("going back patches the same path and redirects to others", () => {
+  const view = fakeView();
+  listenForHistory(view);
+  livePatch(view, "/users?page=2");
+  liveRedirect(view, "/settings");
+  view.sent = [];
+
+  window.pop("/users?page=2");
+  window.pop("/users");
+  assert.deepEqual(view.sent, [
+    { t: "lr", u: "/users?page=2" },
+    { t: "lp", u: "/users" },
+  ]);
+});
@@ -0,0 +1,62 @@
+import assert from "node:assert/strict";
+import { readFileSync } from "node:fs";
+import { test } from "node:test";
+import { applyDiff, render } from "../src/render.js";
+
+// written by `html::tests::keyed_loop_client_fixture` in the crate
+const keyedLoops = JSON.parse(readFileSync(new URL("./fixtures/keyed-loops.json", import.meta.url)));
+
+// written by `html::tests::stream_client_fixture` in the crate
+const streams = JSON.parse(readFileSync(new URL("./fixtures/streams.json", import.meta.url)));
+
+function withoutRowKeys(html) {
+  return html.replace(/ axm-row="[^"]*"/g, "");
+}
+
+for (const { name, old, diffs, html } of keyedLoops) {
+  test(`keyed loop diff: ${name}`, () => {
+    const state = structuredClone(old);
+    for (const diff of diffs) {
+      applyDiff(state, diff);
+    }
+    assert.equal(withoutRowKeys(render(state)), html);
+  });
+}
+
+for (const { name, old, diffs, html } of streams) {
+  test(`stream diff: ${name}`, () => {
+    const state = structuredClone(old);
+    for (const diff of diffs) {
+      applyDiff(state, diff);
+    }
+    assert.equal(withoutRowKeys(render(state)), html);
+  });
+}
+
+test("rows of streams are rendered with their key", () => {
+  const { old } = streams.find(({ name }) => name === "append and delete");
+  assert.equal(render(old), '<h1>step 0</h1><ul><li axm-row=".1.0:1">a</li><li axm-row=".1.0:2">b</li></ul>');
+});
+
+test("rows of keyed loops are rendered with their key", () => {
+  const { old } = keyedLoops.find(({ name }) => name === "nested");
+  assert.equal(
+    render(old),
+    '<section axm-row=".0:1"><p axm-row=".0:1.0:1">alice</p><p axm-row=".0:1.0:2">bob</p></section>' +
+      '<section axm-row=".0:2"><p axm-row=".0:2.0:3">cindy</p></section>',
+  );
+});
+
+test("rows with duplicate keys are rendered without them", () => {
+  const { old } = keyedLoops.find(({ name }) => name === "duplicate keys");
+  assert.equal(render(old), "<ul><li>alice</li><li>bob</li></ul>");
+});
+
+test("loops and templates are patched", () => {
+  const state = {
+    f: ["<p>", "</p><ul>", "</ul>"],
+    d: { 0: "hi", 1: { f: ["<li>", "</li>"], b: { 0: { 0: "a" }, 1: { 0: "b" } } } },
+  };
+  applyDiff(state, { d: { 0: { f: ["<b>", "</b>"], d: { 0: "bold" } }, 1: { b: { 1: null, 2: { 0: "c" } } } } });
+  assert.equal(render(state), "<p><b>bold</b></p><ul><li>a</li><li>c</li></ul>");
+});
@@ -0,0 +1,110 @@
+import assert from "node:assert/strict";
+import { test } from "node:test";
+import { bindUploads, uploadChunk, uploadReply } from "../src/upload.js";
+
+// an `<input type="file" axm-upload="...">` with the files the user picked
+function fakeInput(name) {
+  const listeners = {};
+  return {
+    files: [],
+    getAttribute: (attr) => (attr === "axm-upload" ? name : null),
+    addEventListener(event, listener) {
+      listeners[event] = listener;
+    },
+    pick(...files) {
+      this.files = files;
+      listeners.change();
+    },
+  };
+}
+
+// a view with its inputs, recording what it sends
+function fakeView(...inputs) {
+  const socket = { bufferedAmount: 0 };
+  const view = {
+    el: { querySelectorAll: () => inputs },
+    options: { debug: false },
+    uploads: new Map(),
+    sent: [],
+    chunks: [],
+    send: (msg) => view.sent.push(msg),
+    connection: {
+      socket,
+      sendBinary: (_view, data) => view.chunks.push(data),
+    },
+  };
+  return view;
+}
+
+function flush() {
+  return new Promise((resolve) => setTimeout(resolve, 0));
+}
+
+test("chunks start with the upload id", () => {
+  const chunk = uploadChunk(258, new Uint8Array([7, 8]));
+  assert.deepEqual(chunk, new Uint8Array([0, 0, 0, 0, 0, 0, 1, 2, 7, 8]));
+});
+
+test("files are sent in chunks once the upload is accepted", async () => {
+  const input = fakeInput("avatar");
+  const view = fakeView(input);
+  bindUploads(view);
+  bindUploads(view);
+
+  const file = new File([new Uint8Array(40 * 1024).fill(1)], "me.png", { type: "image/png" });
+  input.pick(file);
+
+  assert.equal(view.sent.length, 1);
+  const [start] = view.sent;
+  assert.deepEqual(start, { t: "us", n: "avatar", r: start.r, f: "me.png", ct: "image/png", s: 40 * 1024 });
+  assert.equal(view.chunks.length, 0);
+
+  uploadReply(view, { r: start.r, e: null });
+  await flush();
+  await flush();
+
+  assert.deepEqual(
+    view.chunks.map((chunk) => chunk.length),
+    [8 + 32 * 1024, 8 + 8 * 1024],
+  );
+  assert.deepEqual(view.chunks[0].subarray(0, 8), uploadChunk(start.r, new Uint8Array()));
+  assert.equal(view.uploads.size, 0);
+});
+
+test("rejected files are forgotten", () => {
+  const input = fakeInput("avatar");
+  const view = fakeView(input);
+  bindUploads(view);
+
+  input.pick(new File(["big"], "big.png"));
+  const [start] = view.sent;
+  uploadReply(view, { r: start.r, e: "too_large" });
+
+  assert.equal(view.uploads.size, 0);
+  assert.equal(view.chunks.length, 0);
+});
+
+test("picking files again cancels the uploads of the input", () => {
+  const avatar = fakeInput("avatar");
+  const attachments = fakeInput("attachments");
+  const view = fakeView(avatar, attachments);
+  bindUploads(view);
+
+  avatar.pick(new File(["a"], "a.png"));
+  attachments.pick(new File(["b"], "b.txt"));
+  avatar.pick(new File(["c"], "c.png"));
+
+  const [first, second, ...rest] = view.sent;
+  assert.deepEqual(
+    rest.map(({ t, r, f }) => ({ t, r, f })),
+    [
+      { t: "uc", r: first.r, f: undefined },
+      { t: "us", r: rest[1].r, f: "c.png" },
+    ],
+  );
+  assert.deepEqual(
+    Array.from(view.uploads.values(), ({ file }) => file.name),
+    ["b.txt", "c.png"],
+  );
+  assert.notEqual(second.r, first.r);
+});
@@ -10,10 +10,10 @@ use shipwright_liveview::{
     event_data::EventData,
     html, js_command,
     live_view::{self, Updated, ViewHandle},
+    pubsub::PubSub,
     Html, LiveView, LiveViewUpgrade,
 };
 use std::sync::{Arc, Mutex};
-use tokio::sync::broadcast;
 use tower::ServiceBuilder;
 
 #[tokio::main]
@@ -22,7 +22,7 @@ async fn main() {
 
     let messages: Messages = Default::default();
 
-    let (tx, _) = broadcast::channel::<NewMessagePing>(1024);
+    let pubsub = PubSub::new();
 
     let app = Router::new()
         .route("/", get(root))
@@ -31,7 +31,7 @@ async fn main() {
         .layer(
             ServiceBuilder::new()
                 .layer(Extension(messages))
-                .layer(Extension(tx)),
+                .layer(Extension(pubsub)),
         );
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
@@ -41,24 +41,26 @@ async fn main() {
 
 type Messages = Arc<Mutex<Vec<Message>>>;
 
-#[derive(Clone, Copy)]
+const NEW_MESSAGE_TOPIC: &str = "new-message";
+
+#[derive(Serialize, Deserialize)]
 struct NewMessagePing;
 
 async fn root(
     live: LiveViewUpgrade,
     Extension(messages): Extension<Messages>,
-    Extension(tx): Extension<broadcast::Sender<NewMessagePing>>,
+    Extension(pubsub): Extension<PubSub>,
 ) -> impl IntoResponse {
     let list = MessagesList {
         messages: messages.clone(),
-        tx: tx.clone(),
+        pubsub: pubsub.clone(),
     };
 
     let form = SendMessageForm {
         message: Default::default(),
         name: Default::default(),
         messages,
-        tx,
+        pubsub,
     };
 
     let combined = live_view::combine((list, form), |list, form| {
@@ -98,21 +100,15 @@ async fn root(
 
 struct MessagesList {
     messages: Messages,
-    tx: broadcast::Sender<NewMessagePing>,
+    pubsub: PubSub,
 }
 
 impl LiveView for MessagesList {
     type Message = ();
 
     fn mount(&mut self, _: Uri, _: &HeaderMap, handle: ViewHandle<Self::Message>) {
-        let mut rx = self.tx.subscribe();
-        tokio::spawn(async move {
-            while let Ok(NewMessagePing) = rx.recv().await {
-                if handle.send(()).await.is_err() {
-                    break;
-                }
-            }
-        });
+        self.pubsub
+            .subscribe(NEW_MESSAGE_TOPIC, &handle, |NewMessagePing| ());
     }
 
     fn update(self, _msg: (), _data: Option<EventData>) -> Updated<Self> {
@@ -144,7 +140,7 @@ struct SendMessageForm {
     message: String,
     name: String,
     messages: Messages,
-    tx: broadcast::Sender<NewMessagePing>,
+    pubsub: PubSub,
 }
 
 impl LiveView for SendMessageForm {
@@ -163,7 +159,7 @@ impl LiveView for SendMessageForm {
                     .unwrap();
 
                 self.messages.lock().unwrap().push(new_msg);
-                let _ = self.tx.send(NewMessagePing);
+                let _ = self.pubsub.publish(NEW_MESSAGE_TOPIC, &NewMessagePing);
 
                 self.message.clear();
                 js_commands.push(js_command::clear_value("#text-input"));
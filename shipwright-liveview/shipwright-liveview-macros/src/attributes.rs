@@ -165,7 +165,7 @@ impl AttributeProcessor {
             }
             ValidatedAttributeValue::Dynamic(block) => {
                 quote! {
-                    __dynamic.push_fragment(format!("{}", #block));
+                    __dynamic.push_attribute_value(#attr_name, #block);
                 }
             }
             ValidatedAttributeValue::DynamicBoolean(block) => {
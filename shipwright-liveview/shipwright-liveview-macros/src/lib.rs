@@ -307,8 +307,9 @@ impl Parse for AttrIdent {
                     "click" | "input" | "change" | "submit" | "focus" | "blur" | "keydown"
                     | "keyup" | "window-keydown" | "window-keyup" | "window-focus"
                     | "window-blur" | "mouseenter" | "mouseover" | "mouseleave" | "mouseout"
-                    | "mousemove" | "scroll" => Ok(Self::Axm(out)),
-                    "throttle" | "debounce" | "key" => Ok(Self::Lit(out)),
+                    | "mousemove" | "scroll" | "hook-event" => Ok(Self::Axm(out)),
+                    "throttle" | "debounce" | "key" | "patch" | "redirect" | "upload"
+                    | "hook" => Ok(Self::Lit(out)),
                     _ => Err(syn::Error::new(
                         idents_span,
                         format!("unknown `{out}` attribute"),
@@ -469,22 +470,36 @@ where
 struct For {
     pat: syn::Pat,
     expr: syn::Expr,
+    key: Option<syn::Expr>,
     tree: Tree,
 }
 
 impl Parse for For {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        syn::custom_keyword!(key);
+
         input.parse::<Token![for]>()?;
         let pat = syn::Pat::parse_single(input)?;
 
         input.parse::<Token![in]>()?;
         let expr = input.call(syn::Expr::parse_without_eager_brace)?;
 
+        let key = if input.parse::<key>().is_ok() {
+            Some(input.call(syn::Expr::parse_without_eager_brace)?)
+        } else {
+            None
+        };
+
         let content;
         syn::braced!(content in input);
         let tree = content.parse::<Tree>()?;
 
-        Ok(Self { pat, expr, tree })
+        Ok(Self {
+            pat,
+            expr,
+            key,
+            tree,
+        })
     }
 }
 
@@ -640,7 +655,9 @@ where
 
         out.extend(quote! {
             {
-                use shipwright_liveview::__private::DynamicFragmentVecExt;
+                use shipwright_liveview::__private::{
+                    DynamicFragmentVecExt, PushJsBinding, PushMessageBinding,
+                };
                 #inside_braces
                 shipwright_liveview::__private::HtmlBuilder {
                     dynamic: __dynamic,
@@ -722,11 +739,13 @@ impl NodeToTokens for Attr {
                 NormalAttrValue::Block(block) => {
                     fixed.append(" ");
                     ident.node_to_tokens(fixed, out);
-                    fixed.append("=");
+                    fixed.append("=\"");
                     fixed.start_new_part();
+                    fixed.append("\"");
+                    let name = ident.name();
                     out.extend(quote! {
                         #[allow(unused_braces)]
-                        __dynamic.push_fragment(format!("{}", #block));
+                        __dynamic.push_attribute_value(#name, #block);
                     });
                 }
                 NormalAttrValue::If(if_) => {
@@ -750,7 +769,8 @@ impl NodeToTokens for Attr {
                     fixed.start_new_part();
                     out.extend(quote! {
                         #[allow(unused_braces)]
-                        __dynamic.push_message(#block);
+                        (&shipwright_liveview::__private::BindingValue::new(#block))
+                            .push_binding(&mut __dynamic);
                     });
                 }
                 AxmAttrValue::If(if_) => {
@@ -765,6 +785,14 @@ impl NodeToTokens for Attr {
     }
 }
 
+impl AttrIdent {
+    fn name(&self) -> &str {
+        match self {
+            AttrIdent::Lit(ident) | AttrIdent::Axm(ident) => ident,
+        }
+    }
+}
+
 impl NodeToTokens for AttrIdent {
     fn node_to_tokens(&self, fixed: &mut FixedParts, _out: &mut TokenStream) {
         match self {
@@ -843,7 +871,12 @@ where
 
 impl NodeToTokens for For {
     fn node_to_tokens(&self, fixed: &mut FixedParts, out: &mut TokenStream) {
-        let Self { pat, expr, tree } = self;
+        let Self {
+            pat,
+            expr,
+            key,
+            tree,
+        } = self;
 
         fixed.start_new_part();
 
@@ -853,6 +886,26 @@ impl NodeToTokens for For {
         tree.node_to_tokens(&mut fixed, &mut inside);
         let FixedParts { parts } = fixed;
 
+        if let Some(key) = key {
+            out.extend(quote! {
+                let mut __dynamic_loop_parts = Vec::new();
+                for #pat in #expr {
+                    let __key = std::string::ToString::to_string(&#key);
+                    let __parts = {
+                        let mut __dynamic = std::vec::Vec::<shipwright_liveview::__private::DynamicFragment<_>>::new();
+                        #inside
+                        __dynamic
+                    };
+                    __dynamic_loop_parts.push((__key, __parts));
+                }
+                __dynamic.push_keyed_fragments(
+                    &[#(#parts),*],
+                    __dynamic_loop_parts,
+                );
+            });
+            return;
+        }
+
         out.extend(quote! {
             let mut __dynamic_loop_parts = Vec::new();
             for #pat in #expr {
@@ -0,0 +1,346 @@
+//! Encoding of the messages sent over the socket.
+//!
+//! Messages are encoded as JSON text frames by default. Clients can ask for MessagePack by
+//! connecting with the `encoding=msgpack` query parameter, for example
+//! `ws://example.com/?encoding=msgpack`. Messages are then sent as binary frames in both
+//! directions, with the same shape as the JSON messages. Maps are encoded with their field names.
+//!
+//! With MessagePack, binary frames from the client are told apart from upload chunks by their
+//! first byte. Client messages are always maps, so they start with `0x80..=0x8f`, `0xde` or
+//! `0xdf`, while upload chunks start with their id which is always below 2^63. Text frames are
+//! decoded as JSON regardless of the encoding.
+
+use crate::{
+    life_cycle::{MessageFromSocket, MessageToSocket},
+    upload::UploadChunk,
+};
+use axum::{extract::ws, http::Uri};
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Deserialize,
+};
+
+/// The wire encoding negotiated for a socket.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+impl Encoding {
+    /// Pick the encoding requested in the query of the WebSocket request.
+    ///
+    /// Unknown encodings fall back to JSON.
+    pub(crate) fn from_uri(uri: &Uri) -> Self {
+        let requested = uri
+            .query()
+            .into_iter()
+            .flat_map(|query| query.split('&'))
+            .find_map(|pair| pair.strip_prefix("encoding="));
+
+        match requested {
+            Some("msgpack") => Self::MessagePack,
+            _ => Self::Json,
+        }
+    }
+
+    pub(crate) fn encode(self, msg: &MessageToSocket) -> anyhow::Result<ws::Message> {
+        if let Some((code, reason)) = msg.close_frame() {
+            return Ok(ws::Message::Close(Some(ws::CloseFrame {
+                code,
+                reason: reason.into(),
+            })));
+        }
+
+        match self {
+            Self::Json => Ok(ws::Message::Text(serde_json::to_string(msg)?.into())),
+            Self::MessagePack => Ok(ws::Message::Binary(rmp_serde::to_vec_named(msg)?.into())),
+        }
+    }
+
+    pub(crate) fn decode<M>(
+        self,
+        msg: Result<ws::Message, axum::Error>,
+    ) -> anyhow::Result<MessageFromSocket<M>>
+    where
+        M: DeserializeOwned,
+    {
+        match msg? {
+            ws::Message::Text(text) => Ok(serde_json::from_str(&text)?),
+            ws::Message::Binary(data) => {
+                if self == Self::MessagePack && starts_with_map(&data) {
+                    return Ok(rmp_serde::from_slice(&data)?);
+                }
+
+                match UploadChunk::parse(data) {
+                    Some(chunk) => Ok(MessageFromSocket::UploadChunk(chunk)),
+                    None => anyhow::bail!("received binary message without an upload id"),
+                }
+            }
+            _ => anyhow::bail!("received message from socket that wasn't text or binary"),
+        }
+    }
+
+    /// Whether a message from the client nests arrays and maps deeper than `max_depth`.
+    ///
+    /// Upload chunks aren't structured so they never do.
+    pub(crate) fn exceeds_depth(self, msg: &ws::Message, max_depth: usize) -> bool {
+        match msg {
+            ws::Message::Text(text) => json_exceeds_depth(text.as_bytes(), max_depth),
+            ws::Message::Binary(data) if self == Self::MessagePack && starts_with_map(data) => {
+                let mut de = rmp_serde::Deserializer::from_read_ref(&data[..]);
+                // rmp_serde fails when the remaining depth reaches zero, not below it
+                de.set_max_depth(max_depth.saturating_add(1));
+                matches!(
+                    IgnoredAny::deserialize(&mut de),
+                    Err(rmp_serde::decode::Error::DepthLimitExceeded)
+                )
+            }
+            _ => false,
+        }
+    }
+}
+
+// serde_json doesn't have a configurable depth limit so the text is scanned before parsing it
+fn json_exceeds_depth(json: &[u8], max_depth: usize) -> bool {
+    let mut depth = 0_usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for &byte in json {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > max_depth {
+                    return true;
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    false
+}
+
+fn starts_with_map(data: &[u8]) -> bool {
+    matches!(data.first(), Some(0x80..=0x8f | 0xde | 0xdf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        html, js_command, life_cycle::EventMessageFromSocketData, util::assert_client_fixture, Html,
+    };
+    use serde::{Deserialize, Serialize};
+    use serde_json::{json, Value};
+    use std::time::Duration;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    enum Msg {
+        Incr,
+    }
+
+    #[test]
+    fn negotiate_from_query() {
+        let encoding = |uri: &str| Encoding::from_uri(&uri.parse().unwrap());
+
+        assert_eq!(encoding("/"), Encoding::Json);
+        assert_eq!(encoding("/?page=2"), Encoding::Json);
+        assert_eq!(encoding("/?encoding=json"), Encoding::Json);
+        assert_eq!(encoding("/?encoding=cbor"), Encoding::Json);
+        assert_eq!(encoding("/?encoding=msgpack"), Encoding::MessagePack);
+        assert_eq!(encoding("/?page=2&encoding=msgpack"), Encoding::MessagePack);
+    }
+
+    #[test]
+    fn encode_msgpack_as_binary() {
+        let msg = MessageToSocket::js_commands(Vec::from([js_command::navigate_to(
+            "/settings".parse().unwrap(),
+        )]));
+
+        let json = match Encoding::Json.encode(&msg).unwrap() {
+            ws::Message::Text(text) => serde_json::from_str::<serde_json::Value>(&text).unwrap(),
+            other => panic!("expected text frame, got {other:?}"),
+        };
+        let msgpack = match Encoding::MessagePack.encode(&msg).unwrap() {
+            ws::Message::Binary(data) => {
+                assert!(starts_with_map(&data));
+                rmp_serde::from_slice::<serde_json::Value>(&data).unwrap()
+            }
+            other => panic!("expected binary frame, got {other:?}"),
+        };
+
+        assert_eq!(json, msgpack);
+    }
+
+    #[test]
+    fn decode_msgpack_event() {
+        let event = json!({
+            "m": "\"Incr\"",
+            "t": "key",
+            "d": { "k": "a", "kc": "KeyA", "a": false, "c": true, "s": false, "me": false },
+        });
+        let data = rmp_serde::to_vec_named(&event).unwrap();
+
+        let msg = Encoding::MessagePack
+            .decode::<Msg>(Ok(ws::Message::Binary(data.into())))
+            .unwrap();
+        assert!(matches!(
+            msg,
+            MessageFromSocket::Event {
+                msg: Msg::Incr,
+                data: EventMessageFromSocketData::Key { ctrl: true, .. },
+            }
+        ));
+
+        // JSON text frames are still understood
+        let msg = Encoding::MessagePack
+            .decode::<Msg>(Ok(ws::Message::Text(event.to_string().into())))
+            .unwrap();
+        assert!(matches!(msg, MessageFromSocket::Event { .. }));
+    }
+
+    #[test]
+    fn binary_frames_without_a_map_are_upload_chunks() {
+        let mut data = 7u64.to_be_bytes().to_vec();
+        data.extend_from_slice(b"hello");
+
+        for encoding in [Encoding::Json, Encoding::MessagePack] {
+            let msg = encoding
+                .decode::<Msg>(Ok(ws::Message::Binary(data.clone().into())))
+                .unwrap();
+            assert!(matches!(msg, MessageFromSocket::UploadChunk(_)));
+        }
+    }
+
+    #[test]
+    fn nesting_depth() {
+        let event = json!({ "m": "\"Incr\"", "t": "hook", "d": { "e": "[{", "p": [[1]] } });
+        let text = ws::Message::Text(event.to_string().into());
+        let binary = ws::Message::Binary(rmp_serde::to_vec_named(&event).unwrap().into());
+
+        for (encoding, msg) in [(Encoding::Json, text), (Encoding::MessagePack, binary)] {
+            assert!(!encoding.exceeds_depth(&msg, 4));
+            assert!(encoding.exceeds_depth(&msg, 3));
+        }
+    }
+
+    /// The client's tests decode the messages from the server and encode the messages from the
+    /// client, and check they match these.
+    #[test]
+    fn msgpack_client_fixture() {
+        let render = |names: &[&str]| -> Html<()> {
+            html! {
+                <ul class="users">
+                    for name in names.iter() {
+                        <li>{ name }</li>
+                    }
+                </ul>
+            }
+        };
+        let old = render(&["alice", "bob"]);
+        let new = render(&["alice", "bob", "cindy", &"long name ".repeat(30), "zoë"]);
+
+        let cases = Vec::from([
+            // renders are sent as JSON values, which are encoded the same way
+            from_server("initial render", json!({ "t": "i", "d": old })),
+            from_server("diff", json!({ "t": "r", "d": old.diff(&new) })),
+            server_message(
+                "commands",
+                MessageToSocket::js_commands(Vec::from([
+                    js_command::navigate_to("/settings".parse().unwrap()),
+                    js_command::set_title("Settings"),
+                ])),
+            ),
+            server_message(
+                "going away",
+                MessageToSocket::going_away(Duration::from_secs(70)),
+            ),
+            from_client("click", json!({ "m": "\"Incr\"", "t": "click" })),
+            from_client("health", json!({ "t": "h" })),
+            from_client(
+                "key",
+                json!({
+                    "m": "\"Incr\"",
+                    "t": "key",
+                    "d": { "k": "ö", "kc": "KeyO", "a": false, "c": true, "s": false, "me": false },
+                }),
+            ),
+            from_client(
+                "mouse",
+                json!({
+                    "m": "\"Incr\"",
+                    "t": "mouse",
+                    "d": {
+                        "cx": 12, "cy": 300, "px": 70000, "py": 5000000000_u64, "ox": -1,
+                        "oy": -100, "mx": -1000, "my": -100000, "sx": 10.5, "sy": -0.25,
+                    },
+                }),
+            ),
+            from_client(
+                "form",
+                json!({ "m": "\"Incr\"", "t": "form", "d": { "q": "a=1&".repeat(80) } }),
+            ),
+            from_client(
+                "hook",
+                json!({
+                    "m": "\"Incr\"",
+                    "t": "hook",
+                    "d": { "e": "moved", "p": { "ids": (0..20).collect::<Vec<_>>(), "at": null } },
+                }),
+            ),
+        ]);
+
+        assert_client_fixture("msgpack.json", cases);
+    }
+
+    fn server_message(name: &str, msg: MessageToSocket) -> Value {
+        let json = match Encoding::Json.encode(&msg).unwrap() {
+            ws::Message::Text(text) => serde_json::from_str::<Value>(&text).unwrap(),
+            other => panic!("expected text frame, got {other:?}"),
+        };
+        let msgpack = match Encoding::MessagePack.encode(&msg).unwrap() {
+            ws::Message::Binary(data) => hex(&data),
+            other => panic!("expected binary frame, got {other:?}"),
+        };
+        json!({ "name": name, "from": "server", "json": json, "msgpack": msgpack })
+    }
+
+    fn from_server(name: &str, json: Value) -> Value {
+        let msgpack = hex(&rmp_serde::to_vec_named(&json).unwrap());
+        json!({ "name": name, "from": "server", "json": json, "msgpack": msgpack })
+    }
+
+    /// A message from the client, checking the crate decodes its MessagePack like its JSON.
+    fn from_client(name: &str, json: Value) -> Value {
+        let msgpack = rmp_serde::to_vec_named(&json).unwrap();
+
+        let decoded = Encoding::MessagePack
+            .decode::<Msg>(Ok(ws::Message::Binary(msgpack.clone().into())))
+            .unwrap();
+        let expected = Encoding::Json
+            .decode::<Msg>(Ok(ws::Message::Text(json.to_string().into())))
+            .unwrap();
+        assert_eq!(decoded, expected, "{name}");
+
+        json!({ "name": name, "from": "client", "json": json, "msgpack": hex(&msgpack) })
+    }
+
+    fn hex(data: &[u8]) -> String {
+        data.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
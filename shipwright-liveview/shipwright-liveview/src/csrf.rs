@@ -0,0 +1,422 @@
+//! Protection against cross-site WebSocket hijacking.
+//!
+//! Browsers send cookies with WebSocket upgrade requests from any site, and WebSockets aren't
+//! subject to CORS. Without additional checks a malicious page could open a socket to a live view
+//! and act on behalf of the logged-in user. [`LiveViewUpgrade`] guards against that in two ways:
+//!
+//! - The `Origin` header must be allowed by [`AllowedOrigins`]. By default only the origin the
+//!   request was sent to, as given by the `Host` header, is allowed. Requests without an `Origin`
+//!   header don't come from a browser and are let through.
+//! - The client must present the token embedded in the page by [`EmbedLiveView::embed`]. The
+//!   token is signed with the [`SigningKey`] from the request extensions and expires after a day.
+//!   Other sites can't read the page so they can't get a token.
+//!
+//! The token is also bound to an `HttpOnly` cookie set along with the page, so a token that
+//! leaks, for example through logs, can't be used from another browser. Browsers only send the
+//! cookie with requests from the same site. The cookie is `Secure` when the page is requested over
+//! HTTPS, directly or through a proxy that sets `X-Forwarded-Proto`.
+//!
+//! Sockets that fail either check are accepted and then closed right away with close code `4002`,
+//! since browsers don't tell scripts why a handshake failed. The reason is sent with the close
+//! frame. The client doesn't reconnect after that since it would be rejected again. Instead it
+//! reloads the page to get a fresh token, unless it has just done so.
+//!
+//! # Example
+//!
+//! Allowing the views to be embedded on another origin:
+//!
+//! ```
+//! use axum::{routing::get, Extension, Router};
+//! use shipwright_liveview::csrf::AllowedOrigins;
+//!
+//! let app = Router::new()
+//!     .route("/", get(|| async {}))
+//!     .layer(Extension(AllowedOrigins::list([
+//!         "https://example.com",
+//!         "https://admin.example.com",
+//!     ])));
+//! # let _: Router = app;
+//! ```
+//!
+//! # Protocol
+//!
+//! The token is rendered in the `data-csrf-token` attribute of the live view container. The
+//! client sends it back in the `_csrf` query parameter of the WebSocket URL, and the browser
+//! sends the `axm_csrf` cookie.
+//!
+//! [`LiveViewUpgrade`]: crate::LiveViewUpgrade
+//! [`EmbedLiveView::embed`]: crate::extract::EmbedLiveView::embed
+//! [`SigningKey`]: crate::signing::SigningKey
+
+use crate::signing::SigningKey;
+use axum::http::{header, HeaderMap, HeaderValue, Uri};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const QUERY_PARAM: &str = "_csrf";
+
+const PURPOSE: &str = "csrf";
+
+const COOKIE_NAME: &str = "axm_csrf";
+
+const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The origins allowed to connect to live views.
+///
+/// Add it to the request extensions to override the default, which only allows the origin the
+/// request was sent to. See the [module docs](self) for an example.
+#[derive(Clone)]
+pub struct AllowedOrigins {
+    kind: AllowedOriginsKind,
+}
+
+#[derive(Clone)]
+enum AllowedOriginsKind {
+    SameHost,
+    Any,
+    List(Arc<[String]>),
+}
+
+impl AllowedOrigins {
+    /// Only allow the origin whose host matches the `Host` header of the request.
+    pub fn same_host() -> Self {
+        Self {
+            kind: AllowedOriginsKind::SameHost,
+        }
+    }
+
+    /// Allow any origin.
+    ///
+    /// The CSRF token is still required.
+    pub fn any() -> Self {
+        Self {
+            kind: AllowedOriginsKind::Any,
+        }
+    }
+
+    /// Only allow the given origins, such as `https://example.com`.
+    pub fn list<I>(origins: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let origins = origins
+            .into_iter()
+            .map(|origin| origin.into().trim_end_matches('/').to_ascii_lowercase())
+            .collect();
+
+        Self {
+            kind: AllowedOriginsKind::List(origins),
+        }
+    }
+
+    fn allows(&self, headers: &HeaderMap) -> bool {
+        let Some(origin) = headers.get(header::ORIGIN) else {
+            return true;
+        };
+        let Ok(origin) = origin.to_str() else {
+            return false;
+        };
+
+        match &self.kind {
+            AllowedOriginsKind::Any => true,
+            AllowedOriginsKind::List(origins) => origins
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(origin)),
+            AllowedOriginsKind::SameHost => {
+                let origin_host = origin
+                    .parse::<Uri>()
+                    .ok()
+                    .and_then(|uri| uri.authority().cloned());
+                let host = headers
+                    .get(header::HOST)
+                    .and_then(|host| host.to_str().ok());
+
+                match (origin_host, host) {
+                    (Some(origin_host), Some(host)) => {
+                        origin_host.as_str().eq_ignore_ascii_case(host)
+                    }
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+impl Default for AllowedOrigins {
+    fn default() -> Self {
+        Self::same_host()
+    }
+}
+
+impl fmt::Debug for AllowedOrigins {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            AllowedOriginsKind::SameHost => f.write_str("AllowedOrigins::SameHost"),
+            AllowedOriginsKind::Any => f.write_str("AllowedOrigins::Any"),
+            AllowedOriginsKind::List(origins) => f
+                .debug_tuple("AllowedOrigins::List")
+                .field(origins)
+                .finish(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Token {
+    // makes each render's token unique
+    #[serde(rename = "n")]
+    nonce: u64,
+    #[serde(rename = "t")]
+    issued_at: u64,
+    // tag of the cookie the token is bound to
+    #[serde(rename = "c")]
+    cookie: String,
+}
+
+/// The cookie a page's tokens are bound to.
+///
+/// The browser keeps it for the session and it is shared by all tabs, so a new one is only set if
+/// the request doesn't have one yet.
+#[derive(Debug, Clone)]
+pub(crate) struct CsrfCookie {
+    value: String,
+    is_new: bool,
+    // only sent over HTTPS
+    secure: bool,
+}
+
+impl CsrfCookie {
+    /// The cookie sent with the request, or a new one.
+    pub(crate) fn from_request(uri: &Uri, headers: &HeaderMap) -> Self {
+        if let Some(value) = crate::util::cookie(headers, COOKIE_NAME) {
+            return Self {
+                value: value.to_owned(),
+                is_new: false,
+                secure: false,
+            };
+        }
+
+        let mut value = [0; 16];
+        getrandom::getrandom(&mut value).expect("failed to generate CSRF cookie");
+        Self {
+            value: format!("{:032x}", u128::from_le_bytes(value)),
+            is_new: true,
+            secure: is_https(uri, headers),
+        }
+    }
+
+    /// A `Set-Cookie` value if the cookie is new.
+    pub(crate) fn set_cookie(&self) -> Option<HeaderValue> {
+        if !self.is_new {
+            return None;
+        }
+        let mut cookie = format!(
+            "{COOKIE_NAME}={}; Path=/; SameSite=Lax; HttpOnly",
+            self.value
+        );
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+        Some(HeaderValue::from_str(&cookie).expect("invalid CSRF cookie"))
+    }
+}
+
+/// Whether the request was made over HTTPS, as far as the server can tell.
+fn is_https(uri: &Uri, headers: &HeaderMap) -> bool {
+    uri.scheme_str() == Some("https")
+        || headers
+            .get("x-forwarded-proto")
+            .and_then(|proto| proto.to_str().ok())
+            .is_some_and(|proto| proto.eq_ignore_ascii_case("https"))
+}
+
+/// Create a token for a page rendered over HTTP, bound to `cookie`.
+pub(crate) fn new_token(key: &SigningKey, cookie: &CsrfCookie) -> String {
+    let mut nonce = [0; 8];
+    getrandom::getrandom(&mut nonce).expect("failed to generate CSRF token");
+
+    let token = Token {
+        nonce: u64::from_le_bytes(nonce),
+        issued_at: unix_time(),
+        cookie: key.tag(COOKIE_NAME, cookie.value.as_bytes()),
+    };
+    let json = serde_json::to_vec(&token).expect("failed to serialize CSRF token");
+    key.sign(PURPOSE, &json)
+}
+
+/// Check that a WebSocket upgrade request may mount a view.
+///
+/// Returns the reason the socket is rejected, if it is.
+pub(crate) fn check_upgrade(
+    key: &SigningKey,
+    allowed_origins: &AllowedOrigins,
+    uri: &Uri,
+    headers: &HeaderMap,
+) -> Result<(), &'static str> {
+    if !allowed_origins.allows(headers) {
+        return Err("origin not allowed");
+    }
+
+    let token = uri
+        .query()
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .find_map(|pair| pair.strip_prefix(QUERY_PARAM)?.strip_prefix('='))
+        .ok_or("missing CSRF token")?;
+    let token = percent_encoding::percent_decode_str(token)
+        .decode_utf8()
+        .map_err(|_| "invalid CSRF token")?;
+
+    let token = key
+        .verify(PURPOSE, &token)
+        .and_then(|json| serde_json::from_slice::<Token>(&json).ok())
+        .ok_or("invalid CSRF token")?;
+
+    if unix_time().saturating_sub(token.issued_at) > MAX_AGE.as_secs() {
+        return Err("expired CSRF token");
+    }
+
+    let cookie = crate::util::cookie(headers, COOKIE_NAME).ok_or("missing CSRF cookie")?;
+    if !key.verify_tag(COOKIE_NAME, cookie.as_bytes(), &token.cookie) {
+        return Err("CSRF token doesn't match cookie");
+    }
+
+    Ok(())
+}
+
+/// A token and the `Cookie` header value it is bound to.
+#[cfg(test)]
+pub(crate) fn test_token(key: &SigningKey) -> (String, String) {
+    let cookie = CsrfCookie::from_request(&Uri::default(), &HeaderMap::new());
+    let token = new_token(key, &cookie);
+    (token, format!("{COOKIE_NAME}={}", cookie.value))
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(origin: Option<&'static str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, HeaderValue::from_static("example.com:3000"));
+        if let Some(origin) = origin {
+            headers.insert(header::ORIGIN, HeaderValue::from_static(origin));
+        }
+        headers
+    }
+
+    #[test]
+    fn same_host() {
+        let origins = AllowedOrigins::default();
+        assert!(origins.allows(&headers(Some("http://example.com:3000"))));
+        assert!(origins.allows(&headers(None)));
+        assert!(!origins.allows(&headers(Some("http://example.com"))));
+        assert!(!origins.allows(&headers(Some("https://evil.com"))));
+        assert!(!origins.allows(&headers(Some("null"))));
+    }
+
+    #[test]
+    fn list() {
+        let origins = AllowedOrigins::list(["https://Example.com/"]);
+        assert!(origins.allows(&headers(Some("https://example.com"))));
+        assert!(!origins.allows(&headers(Some("http://example.com"))));
+        assert!(!origins.allows(&headers(Some("http://example.com:3000"))));
+    }
+
+    #[test]
+    fn token() {
+        let key = SigningKey::generate();
+        let origins = AllowedOrigins::any();
+        let (token, cookie) = test_token(&key);
+        let mut headers = headers(None);
+        headers.insert(header::COOKIE, HeaderValue::from_str(&cookie).unwrap());
+
+        let uri = format!("/counter?encoding=msgpack&_csrf={token}")
+            .parse()
+            .unwrap();
+        assert_eq!(check_upgrade(&key, &origins, &uri, &headers), Ok(()));
+
+        assert_eq!(
+            check_upgrade(&key, &origins, &"/counter".parse().unwrap(), &headers),
+            Err("missing CSRF token")
+        );
+        assert_eq!(
+            check_upgrade(&SigningKey::generate(), &origins, &uri, &headers),
+            Err("invalid CSRF token")
+        );
+    }
+
+    #[test]
+    fn replayed_token() {
+        let key = SigningKey::generate();
+        let origins = AllowedOrigins::any();
+        let (token, _) = test_token(&key);
+        let uri = format!("/counter?_csrf={token}").parse().unwrap();
+
+        // a token leaked from a page can't be used without that page's cookie
+        assert_eq!(
+            check_upgrade(&key, &origins, &uri, &headers(None)),
+            Err("missing CSRF cookie")
+        );
+
+        let (_, other_cookie) = test_token(&key);
+        let mut headers = headers(None);
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_str(&other_cookie).unwrap(),
+        );
+        assert_eq!(
+            check_upgrade(&key, &origins, &uri, &headers),
+            Err("CSRF token doesn't match cookie")
+        );
+    }
+
+    #[test]
+    fn expired_token() {
+        let key = SigningKey::generate();
+        let (_, cookie) = test_token(&key);
+        let value = cookie.strip_prefix("axm_csrf=").unwrap();
+        let token = Token {
+            nonce: 0,
+            issued_at: unix_time() - MAX_AGE.as_secs() - 1,
+            cookie: key.tag(COOKIE_NAME, value.as_bytes()),
+        };
+        let token = key.sign(PURPOSE, &serde_json::to_vec(&token).unwrap());
+
+        let mut headers = headers(None);
+        headers.insert(header::COOKIE, HeaderValue::from_str(&cookie).unwrap());
+        let uri = format!("/counter?_csrf={token}").parse().unwrap();
+        assert_eq!(
+            check_upgrade(&key, &AllowedOrigins::any(), &uri, &headers),
+            Err("expired CSRF token")
+        );
+    }
+
+    #[test]
+    fn secure_cookie() {
+        fn set_cookie(uri: &str, headers: &HeaderMap) -> String {
+            let cookie = CsrfCookie::from_request(&uri.parse().unwrap(), headers);
+            cookie.set_cookie().unwrap().to_str().unwrap().to_owned()
+        }
+
+        assert!(!set_cookie("/", &headers(None)).contains("Secure"));
+        assert!(set_cookie("https://example.com/", &headers(None)).ends_with("; Secure"));
+
+        let mut headers = headers(None);
+        headers.insert("x-forwarded-proto", HeaderValue::from_static("https"));
+        assert!(set_cookie("/", &headers).ends_with("; Secure"));
+    }
+}
@@ -0,0 +1,242 @@
+//! A live dashboard of the views connected to the server.
+//!
+//! [`router`] returns a [`Router`] with a page that is itself a live view. It lists the views
+//! connected to this process with their type, URI, when they connected and how many messages
+//! they've handled, along with the sizes of their most recent diffs and the most recent errors.
+//! Each socket can be disconnected from the page, in which case its [`LiveView::terminate`] is
+//! called with [`ShutdownReason::Disconnected`] and the client doesn't reconnect.
+//!
+//! Views are only tracked once [`router`] has been called, so apps without the dashboard don't
+//! pay for the bookkeeping.
+//!
+//! The dashboard shows the URI of every connected client and can disconnect them, so make sure
+//! to protect it, for example with an authentication middleware added with
+//! [`Router::route_layer`].
+//!
+//! # Example
+//!
+//! ```
+//! use axum::Router;
+//!
+//! let app = Router::new().nest("/dashboard", shipwright_liveview::dashboard::router());
+//! # let _: Router = app;
+//! ```
+//!
+//! [`LiveView::terminate`]: crate::LiveView::terminate
+//! [`ShutdownReason::Disconnected`]: crate::live_view::ShutdownReason::Disconnected
+
+use crate::{
+    event_data::EventData,
+    html,
+    live_view::{Updated, ViewHandle},
+    registry::{self, RecentError, ViewSummary},
+    Html, LiveView, LiveViewUpgrade,
+};
+use axum::{
+    extract::{rejection::NestedPathRejection, NestedPath},
+    response::Response,
+    routing::get,
+    Router,
+};
+use http::{HeaderMap, Uri};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+
+/// How often the dashboard is refreshed.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A router with the dashboard.
+///
+/// The dashboard is served at `/` with the JavaScript it needs, so it is usually
+/// [nested](Router::nest) under a path of its own. See the [module docs](self) for more details.
+pub fn router<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    registry::enable();
+
+    Router::new()
+        .route("/", get(page))
+        .route("/live-view.js", crate::precompiled_js())
+}
+
+async fn page(
+    live: LiveViewUpgrade,
+    nested_path: Result<NestedPath, NestedPathRejection>,
+) -> Response {
+    // the page is also served without a trailing slash so the script path can't be relative
+    let script = match &nested_path {
+        Ok(nested_path) => format!(
+            "{}/live-view.js",
+            nested_path.as_str().trim_end_matches('/')
+        ),
+        Err(_) => "/live-view.js".to_owned(),
+    };
+
+    live.response(move |embed| {
+        html! {
+            <!DOCTYPE html>
+            <html>
+                <head>
+                    <title>"Live views"</title>
+                    <meta charset="utf-8" />
+                    <style>
+                        "body { font-family: system-ui, sans-serif; margin: 2rem; }"
+                        "table { border-collapse: collapse; margin-bottom: 2rem; }"
+                        "th, td { border-bottom: 1px solid #ddd; padding: 0.25rem 0.75rem; text-align: left; }"
+                        "code { font-size: 0.9em; }"
+                    </style>
+                </head>
+                <body>
+                    { embed.embed(Dashboard::new()) }
+                    <script src={ script }></script>
+                </body>
+            </html>
+        }
+    })
+}
+
+struct Dashboard {
+    views: Vec<ViewSummary>,
+    errors: Vec<RecentError>,
+}
+
+impl Dashboard {
+    fn new() -> Self {
+        let (views, errors) = registry::snapshot();
+        Self { views, errors }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+enum Msg {
+    Refresh,
+    Disconnect(u64),
+}
+
+impl LiveView for Dashboard {
+    type Message = Msg;
+
+    fn mount(&mut self, _uri: Uri, _request_headers: &HeaderMap, handle: ViewHandle<Msg>) {
+        crate::util::spawn_unit(async move {
+            let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+            loop {
+                interval.tick().await;
+                if handle.send(Msg::Refresh).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    fn update(self, msg: Msg, _data: Option<EventData>) -> Updated<Self> {
+        if let Msg::Disconnect(id) = msg {
+            if !registry::disconnect(id) {
+                tracing::debug!(id, "view to disconnect is already gone");
+            }
+        }
+
+        Updated::new(Self::new())
+    }
+
+    fn render(&self) -> Html<Msg> {
+        let now = SystemTime::now();
+
+        html! {
+            <h1>"Live views"</h1>
+
+            <h2>"Connected (" { self.views.len() } ")"</h2>
+            if self.views.is_empty() {
+                <p>"No views are connected."</p>
+            } else {
+                <table>
+                    <thead>
+                        <tr>
+                            <th>"View"</th>
+                            <th>"URI"</th>
+                            <th>"Connected"</th>
+                            <th>"Messages"</th>
+                            <th>"Recent diffs (bytes)"</th>
+                            <th></th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        for view in &self.views key view.id {
+                            <tr>
+                                <td><code>{ view.view }</code></td>
+                                <td>{ &view.uri }</td>
+                                <td>{ ago(now, view.connected_at) }</td>
+                                <td>{ view.messages }</td>
+                                <td>{ join(&view.recent_diffs) }</td>
+                                <td>
+                                    <button axm-click={ Msg::Disconnect(view.id) }>
+                                        "Disconnect"
+                                    </button>
+                                </td>
+                            </tr>
+                        }
+                    </tbody>
+                </table>
+            }
+
+            <h2>"Recent errors"</h2>
+            if self.errors.is_empty() {
+                <p>"No errors."</p>
+            } else {
+                <table>
+                    <thead>
+                        <tr>
+                            <th>"When"</th>
+                            <th>"View"</th>
+                            <th>"URI"</th>
+                            <th>"Error"</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        for error in &self.errors {
+                            <tr>
+                                <td>{ ago(now, error.at) }</td>
+                                <td><code>{ error.view }</code></td>
+                                <td>{ &error.uri }</td>
+                                <td>{ &error.message }</td>
+                            </tr>
+                        }
+                    </tbody>
+                </table>
+            }
+        }
+    }
+}
+
+/// How long ago `then` was, such as `3m 12s ago`.
+fn ago(now: SystemTime, then: SystemTime) -> String {
+    let secs = now.duration_since(then).unwrap_or_default().as_secs();
+    match secs {
+        0..=59 => format!("{secs}s ago"),
+        60..=3599 => format!("{}m {}s ago", secs / 60, secs % 60),
+        _ => format!("{}h {}m ago", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+fn join(sizes: &[usize]) -> String {
+    sizes
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_age() {
+        let now = SystemTime::now();
+        let ago = |secs| ago(now, now - Duration::from_secs(secs));
+
+        assert_eq!(ago(5), "5s ago");
+        assert_eq!(ago(192), "3m 12s ago");
+        assert_eq!(ago(7500), "2h 5m ago");
+    }
+}
@@ -33,6 +33,10 @@ mod inner {
         ///
         /// See [`Scroll`] for more details.
         Scroll(Scroll),
+        /// An event sent by a client hook.
+        ///
+        /// See [`Hook`] for more details.
+        Hook(Hook),
     }
 
     impl_from!(EventData::Form);
@@ -40,6 +44,7 @@ mod inner {
     impl_from!(EventData::Key);
     impl_from!(EventData::Mouse);
     impl_from!(EventData::Scroll);
+    impl_from!(EventData::Hook);
 
     impl EventData {
         /// Get the inner [`Form`] if any.
@@ -86,6 +91,15 @@ mod inner {
                 None
             }
         }
+
+        /// Get the inner [`Hook`] if any.
+        pub fn as_hook(&self) -> Option<&Hook> {
+            if let Self::Hook(inner) = self {
+                Some(inner)
+            } else {
+                None
+            }
+        }
     }
 
     impl From<EventMessageFromSocketData> for Option<EventData> {
@@ -94,7 +108,8 @@ mod inner {
                 EventMessageFromSocketData::Click
                 | EventMessageFromSocketData::WindowFocus
                 | EventMessageFromSocketData::WindowBlur
-                | EventMessageFromSocketData::None => None,
+                | EventMessageFromSocketData::None
+                | EventMessageFromSocketData::ViewHandle => None,
                 EventMessageFromSocketData::Form { query } => Some(EventData::Form(Form { query })),
                 EventMessageFromSocketData::Input { value } => {
                     let value = match value {
@@ -145,6 +160,9 @@ mod inner {
                 EventMessageFromSocketData::Scroll { scroll_x, scroll_y } => {
                     Some(EventData::Scroll(Scroll { scroll_x, scroll_y }))
                 }
+                EventMessageFromSocketData::Hook { event, payload } => {
+                    Some(EventData::Hook(Hook { event, payload }))
+                }
             }
         }
     }
@@ -473,12 +491,46 @@ mod inner {
             self.scroll_y
         }
     }
+
+    builder! {
+        #[builder_name = HookBuilder]
+        #[derive(Debug, Clone)]
+        /// An event sent by a client hook.
+        ///
+        /// This event type is sent when the hook of an element with `axm-hook` calls
+        /// `pushEvent`. The view receives the message from the element's `axm-hook-event`
+        /// attribute.
+        pub struct Hook {
+            event: String,
+            payload: serde_json::Value,
+        }
+    }
+
+    impl Hook {
+        /// The name of the event, as given to `pushEvent`.
+        pub fn event(&self) -> &str {
+            &self.event
+        }
+
+        /// The JSON payload of the event.
+        pub fn payload(&self) -> &serde_json::Value {
+            &self.payload
+        }
+
+        /// Deserialize the payload into some type.
+        pub fn deserialize<T>(&self) -> Result<T, serde_json::Error>
+        where
+            T: DeserializeOwned,
+        {
+            T::deserialize(&self.payload)
+        }
+    }
 }
 
-pub use self::inner::{EventData, Form, FormSerializationError, Input, Key, Mouse, Scroll};
+pub use self::inner::{EventData, Form, FormSerializationError, Hook, Input, Key, Mouse, Scroll};
 
 pub mod builders {
     //! Event data builder types.
 
-    pub use super::inner::{FormBuilder, KeyBuilder, MouseBuilder, ScrollBuilder};
+    pub use super::inner::{FormBuilder, HookBuilder, KeyBuilder, MouseBuilder, ScrollBuilder};
 }
@@ -1,33 +1,95 @@
 //! Extractor for embedding live views in HTML templates.
 
-use crate::{html::Html, life_cycle::run_view, LiveView};
+use crate::{
+    codec::Encoding,
+    csrf::{self, AllowedOrigins, CsrfCookie},
+    flash::{self, Flash},
+    html::Html,
+    life_cycle::{close_code, run_view, MessageFromSocket, MessageToSocket, ViewExit},
+    limits::{Limiter, SocketLimits, Verdict, Watchdog},
+    live_view::{error::default_error_policy, AsyncLiveView, ErrorPolicy, ShutdownReason},
+    message_handles::MessageHandles,
+    multiplex::{self, EmbedLiveViews, Mount},
+    navigation::{self, LiveNavigation, SocketHandoff},
+    on_mount::HooksOutsideNavigation,
+    shutdown::Shutdown,
+    signing::SigningKey,
+    socket::{Socket, Upgrade},
+};
 use axum::{
-    extract::{
-        ws::{self, WebSocket, WebSocketUpgrade},
-        FromRequestParts,
-    },
-    http::{HeaderMap, Uri},
+    extract::{ws, FromRequestParts},
+    http::{header, Extensions, HeaderMap, Uri},
     response::{IntoResponse, Response},
 };
 use futures_util::{
+    future::{self, FutureExt},
     sink::SinkExt,
-    stream::{StreamExt, TryStreamExt},
+    stream::StreamExt,
 };
 use http::request::Parts;
-use std::{convert::Infallible, fmt::Debug};
+use serde::de::DeserializeOwned;
+use std::{convert::Infallible, fmt, sync::Arc, task::Poll};
 
 pub use crate::life_cycle::EmbedLiveView;
 
 /// Extractor for embedding live views in HTML templates.
-#[derive(Debug)]
+///
+/// Messages are sent over the socket as JSON. Clients can connect with `?encoding=msgpack` to
+/// use MessagePack binary frames instead.
+///
+/// WebSocket upgrades are checked against the [`AllowedOrigins`] and must present the CSRF token
+/// embedded in the page. See the [`csrf`] module for details. Messages from the client are
+/// checked against the [`SocketLimits`].
+///
+/// Clients that can't open a WebSocket fall back to Server-Sent Events if the router has an
+/// [`SseLayer`](crate::sse::SseLayer).
+///
+/// Several views can share one socket with [`response_many`](Self::response_many).
 pub struct LiveViewUpgrade {
     inner: LiveViewUpgradeInner,
+    options: ViewOptions,
+}
+
+/// Configuration for running a view on a socket.
+#[derive(Clone)]
+pub(crate) struct ViewOptions {
+    pub(crate) error_policy: Arc<dyn ErrorPolicy>,
+    pub(crate) navigation: Option<LiveNavigation>,
+    pub(crate) flash: Flash,
+    pub(crate) protect_messages: bool,
+    pub(crate) limits: SocketLimits,
+    pub(crate) shutdown: Option<Shutdown>,
+    // passed to the view through its `ViewHandle`
+    pub(crate) extensions: Extensions,
+}
+
+impl fmt::Debug for LiveViewUpgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LiveViewUpgrade")
+            .field("inner", &self.inner)
+            .finish()
+    }
 }
 
 #[derive(Debug)]
 enum LiveViewUpgradeInner {
-    Http,
-    Ws(Box<(WebSocketUpgrade, Uri, HeaderMap)>),
+    // the key signs the CSRF token embedded in the page, which is bound to the cookie
+    Http(SigningKey, CsrfCookie),
+    Socket(Box<SocketUpgrade>),
+    // the request was made by `live_redirect` to mount a view on an existing socket
+    Handoff(Box<(SocketHandoff, Uri, HeaderMap)>),
+}
+
+#[derive(Debug)]
+struct SocketUpgrade {
+    upgrade: Upgrade,
+    encoding: Encoding,
+    uri: Uri,
+    headers: HeaderMap,
+    // the flash has been read so the cookie is removed once the view is connected
+    remove_flash_cookie: bool,
+    // why the socket failed the CSRF checks, if it did
+    csrf_check: Result<(), &'static str>,
 }
 
 impl<S> FromRequestParts<S> for LiveViewUpgrade
@@ -37,22 +99,211 @@ where
     type Rejection = Infallible;
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
-        if let Ok(ws) = WebSocketUpgrade::from_request_parts(parts, state).await {
+        if let Some(handoff) = parts.extensions.remove::<SocketHandoff>() {
             let uri = parts.uri.clone();
             let headers = parts.headers.clone();
+            let navigation = Some(handoff.navigation().clone());
+
+            return Ok(Self {
+                inner: LiveViewUpgradeInner::Handoff(Box::new((handoff, uri, headers))),
+                options: ViewOptions {
+                    error_policy: default_error_policy(),
+                    navigation,
+                    flash: Flash::from_parts(parts),
+                    protect_messages: false,
+                    limits: limits_from_extensions(parts),
+                    shutdown: parts.extensions.get::<Shutdown>().cloned(),
+                    extensions: parts.extensions.clone(),
+                },
+            });
+        }
+
+        // redirecting over the socket would skip the hooks, so the page is loaded instead
+        let navigation = parts
+            .extensions
+            .get::<LiveNavigation>()
+            .filter(|_| parts.extensions.get::<HooksOutsideNavigation>().is_none())
+            .cloned();
+
+        let options = ViewOptions {
+            error_policy: default_error_policy(),
+            navigation,
+            flash: Flash::from_parts(parts),
+            protect_messages: false,
+            limits: limits_from_extensions(parts),
+            shutdown: parts.extensions.get::<Shutdown>().cloned(),
+            extensions: Extensions::new(),
+        };
+
+        let key = SigningKey::from_extensions(&parts.extensions);
+
+        if let Some(upgrade) = Upgrade::from_request_parts(parts, state).await {
+            let options = ViewOptions {
+                extensions: parts.extensions.clone(),
+                ..options
+            };
+            let allowed_origins = parts
+                .extensions
+                .get::<AllowedOrigins>()
+                .cloned()
+                .unwrap_or_default();
+
+            let upgrade = SocketUpgrade {
+                encoding: upgrade.encoding(parts),
+                upgrade,
+                uri: parts.uri.clone(),
+                headers: parts.headers.clone(),
+                remove_flash_cookie: flash::has_cookie(&parts.headers),
+                csrf_check: csrf::check_upgrade(&key, &allowed_origins, &parts.uri, &parts.headers),
+            };
 
             Ok(Self {
-                inner: LiveViewUpgradeInner::Ws(Box::new((ws, uri, headers))),
+                inner: LiveViewUpgradeInner::Socket(Box::new(upgrade)),
+                options,
             })
         } else {
             Ok(Self {
-                inner: LiveViewUpgradeInner::Http,
+                inner: LiveViewUpgradeInner::Http(
+                    key,
+                    CsrfCookie::from_request(&parts.uri, &parts.headers),
+                ),
+                options,
             })
         }
     }
 }
 
+fn with_csrf_cookie(mut response: Response, cookie: &CsrfCookie) -> Response {
+    if let Some(set_cookie) = cookie.set_cookie() {
+        response
+            .headers_mut()
+            .append(header::SET_COOKIE, set_cookie);
+    }
+    response
+}
+
+fn limits_from_extensions(parts: &Parts) -> SocketLimits {
+    parts
+        .extensions
+        .get::<SocketLimits>()
+        .cloned()
+        .unwrap_or_default()
+}
+
 impl LiveViewUpgrade {
+    /// Set the [`ErrorPolicy`] that decides what happens when the view fails.
+    ///
+    /// Defaults to [`ErrorAction::Remount`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::response::IntoResponse;
+    /// use shipwright_liveview::{
+    ///     html,
+    ///     live_view::{ErrorAction, ViewError},
+    ///     LiveView, LiveViewUpgrade,
+    /// };
+    ///
+    /// async fn handler(live: LiveViewUpgrade) -> impl IntoResponse {
+    ///     live.error_policy(|_: &ViewError| ErrorAction::Close {
+    ///         message: "Something went wrong".to_owned(),
+    ///     })
+    ///     .response(|embed_live_view| {
+    ///         // ...
+    ///         # let view: MyView = todo!();
+    ///         html! {
+    ///           { embed_live_view.embed(view) }
+    ///         }
+    ///     })
+    /// }
+    /// # struct MyView;
+    /// # impl LiveView for MyView {
+    /// #     type Message = ();
+    /// #     fn update(
+    /// #         self,
+    /// #         msg: (),
+    /// #         data: Option<shipwright_liveview::event_data::EventData>,
+    /// #     ) -> shipwright_liveview::live_view::Updated<Self> {
+    /// #         todo!()
+    /// #     }
+    /// #     fn render(&self) -> shipwright_liveview::Html<()> {
+    /// #         todo!()
+    /// #     }
+    /// # }
+    /// ```
+    ///
+    /// [`ErrorAction::Remount`]: crate::live_view::ErrorAction::Remount
+    pub fn error_policy<P>(mut self, policy: P) -> Self
+    where
+        P: ErrorPolicy,
+    {
+        self.options.error_policy = Arc::new(policy);
+        self
+    }
+
+    /// Only accept messages the view has rendered.
+    ///
+    /// Messages in the HTML, such as `axm-click={ Msg::Delete(id) }`, are normally sent to the
+    /// client as JSON and whatever the client sends back is passed to [`LiveView::update`]. A
+    /// user can edit the page to send any message, for example `Msg::Delete` with an id they
+    /// were never shown, so `update` has to check every message it receives.
+    ///
+    /// With this enabled each message is replaced by an opaque handle and only the handles in the
+    /// latest render are accepted. Other messages are ignored. Messages in [`Stream`] rows stay
+    /// valid until the row is deleted or replaced, or the stream is reset, since rows are only
+    /// sent once.
+    ///
+    /// Messages sent with [`ViewHandle::send`] and [`Updated::spawn`] aren't affected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::response::IntoResponse;
+    /// use shipwright_liveview::{html, LiveView, LiveViewUpgrade};
+    ///
+    /// async fn handler(live: LiveViewUpgrade) -> impl IntoResponse {
+    ///     live.protect_messages().response(|embed_live_view| {
+    ///         // ...
+    ///         # let view: MyView = todo!();
+    ///         html! {
+    ///           { embed_live_view.embed(view) }
+    ///         }
+    ///     })
+    /// }
+    /// # struct MyView;
+    /// # impl LiveView for MyView {
+    /// #     type Message = ();
+    /// #     fn update(
+    /// #         self,
+    /// #         msg: (),
+    /// #         data: Option<shipwright_liveview::event_data::EventData>,
+    /// #     ) -> shipwright_liveview::live_view::Updated<Self> {
+    /// #         todo!()
+    /// #     }
+    /// #     fn render(&self) -> shipwright_liveview::Html<()> {
+    /// #         todo!()
+    /// #     }
+    /// # }
+    /// ```
+    ///
+    /// [`LiveView::update`]: crate::LiveView::update
+    /// [`Stream`]: crate::stream::Stream
+    /// [`ViewHandle::send`]: crate::live_view::ViewHandle::send
+    /// [`Updated::spawn`]: crate::live_view::Updated::spawn
+    pub fn protect_messages(mut self) -> Self {
+        self.options.protect_messages = true;
+        self
+    }
+
+    /// Set the [`SocketLimits`] for this view, overriding those in the request extensions.
+    ///
+    /// See the [`limits`](crate::limits) module for details.
+    pub fn limits(mut self, limits: SocketLimits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+
     /// Return a response that contains an embedded live view.
     ///
     /// # Example
@@ -104,16 +355,39 @@ impl LiveViewUpgrade {
     /// See the [root module docs](crate) for a more complete example.
     pub fn response<F, L>(self, gather_view: F) -> Response
     where
-        L: LiveView,
+        L: AsyncLiveView,
         F: FnOnce(EmbedLiveView<'_, L>) -> Html<L::Message>,
     {
         match self.inner {
-            LiveViewUpgradeInner::Http => {
-                let embed = EmbedLiveView::noop();
-                gather_view(embed).into_response()
+            LiveViewUpgradeInner::Http(key, cookie) => {
+                let embed = EmbedLiveView::http(
+                    csrf::new_token(&key, &cookie),
+                    self.options.protect_messages,
+                );
+                with_csrf_cookie(gather_view(embed).into_response(), &cookie)
             }
-            LiveViewUpgradeInner::Ws(data) => {
-                let (ws, uri, headers) = *data;
+            LiveViewUpgradeInner::Socket(upgrade) => {
+                let SocketUpgrade {
+                    upgrade,
+                    encoding,
+                    uri,
+                    headers,
+                    remove_flash_cookie,
+                    csrf_check,
+                } = *upgrade;
+
+                if let Err(reason) = csrf_check {
+                    tracing::debug!(%uri, reason, "rejecting live view socket");
+                    return upgrade.on_upgrade(move |socket| reject_socket(socket, reason));
+                }
+                let options = self.options;
+                let upgrade = upgrade.with_limits(&options.limits);
+
+                if let Some(shutdown) = shutting_down(options.shutdown.as_ref()) {
+                    return upgrade.on_upgrade(move |socket| async move {
+                        shutdown.reject_socket(socket, encoding).await;
+                    });
+                }
                 let mut view = None;
 
                 let embed = EmbedLiveView::new(&mut view);
@@ -121,40 +395,313 @@ impl LiveViewUpgrade {
                 gather_view(embed);
 
                 if let Some(view) = view {
-                    ws.on_upgrade(|socket| run_view_on_socket(socket, view, uri, headers))
-                        .into_response()
+                    let mut response = upgrade.on_upgrade(move |socket| {
+                        run_view_on_socket(socket, encoding, view, uri, headers, options)
+                    });
+                    if remove_flash_cookie {
+                        response
+                            .headers_mut()
+                            .append(header::SET_COOKIE, flash::removal_cookie());
+                    }
+                    response
                 } else {
-                    ws.on_upgrade(|_| async {}).into_response()
+                    upgrade.on_upgrade(|_| async {})
                 }
             }
+            LiveViewUpgradeInner::Handoff(data) => {
+                let (handoff, uri, headers) = *data;
+                let options = self.options;
+
+                if let Some(shutdown) = shutting_down(options.shutdown.as_ref()) {
+                    if let Some(socket) = handoff.take() {
+                        let encoding = handoff.encoding();
+                        crate::util::spawn_unit(async move {
+                            shutdown.reject_socket(socket, encoding).await;
+                        });
+                    }
+                    return ().into_response();
+                }
+                let mut view = None;
+
+                let embed = EmbedLiveView::new(&mut view);
+
+                gather_view(embed);
+
+                // if there is no view the socket is left in the handoff and the client is told to
+                // navigate to the page normally
+                if let Some(view) = view {
+                    if let Some(socket) = handoff.take() {
+                        crate::util::spawn_unit(run_view_on_socket(
+                            socket,
+                            handoff.encoding(),
+                            view,
+                            uri,
+                            headers,
+                            options,
+                        ));
+                    }
+                }
+
+                ().into_response()
+            }
+        }
+    }
+
+    /// Return a response that contains several independent live views sharing one socket.
+    ///
+    /// Each view is embedded with an id that is unique on the page. Unlike
+    /// [`response`](Self::response), `gather_views` is called again while the socket is open
+    /// when the client mounts a view that failed or was closed again, so it must be `Fn`.
+    ///
+    /// See the [`multiplex`] module for more details.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::response::IntoResponse;
+    /// use shipwright_liveview::{html, LiveView, LiveViewUpgrade};
+    ///
+    /// async fn handler(live: LiveViewUpgrade) -> impl IntoResponse {
+    ///     live.response_many(|views| {
+    ///         html! {
+    ///           { views.embed("first", MyView) }
+    ///           { views.embed("second", MyView) }
+    ///         }
+    ///     })
+    /// }
+    /// # struct MyView;
+    /// # impl LiveView for MyView {
+    /// #     type Message = ();
+    /// #     fn update(
+    /// #         self,
+    /// #         msg: (),
+    /// #         data: Option<shipwright_liveview::event_data::EventData>,
+    /// #     ) -> shipwright_liveview::live_view::Updated<Self> {
+    /// #         todo!()
+    /// #     }
+    /// #     fn render(&self) -> shipwright_liveview::Html<()> {
+    /// #         todo!()
+    /// #     }
+    /// # }
+    /// ```
+    pub fn response_many<F>(self, gather_views: F) -> Response
+    where
+        F: Fn(&mut EmbedLiveViews) -> Html<()> + Send + Sync + 'static,
+    {
+        match self.inner {
+            LiveViewUpgradeInner::Http(key, cookie) => {
+                let mut embed = EmbedLiveViews::http(
+                    csrf::new_token(&key, &cookie),
+                    self.options.protect_messages,
+                );
+                with_csrf_cookie(gather_views(&mut embed).into_response(), &cookie)
+            }
+            LiveViewUpgradeInner::Socket(upgrade) => {
+                let SocketUpgrade {
+                    upgrade,
+                    encoding,
+                    uri,
+                    headers,
+                    remove_flash_cookie,
+                    csrf_check,
+                } = *upgrade;
+
+                if let Err(reason) = csrf_check {
+                    tracing::debug!(%uri, reason, "rejecting live view socket");
+                    return upgrade.on_upgrade(move |socket| reject_socket(socket, reason));
+                }
+                let options = self.options;
+                let upgrade = upgrade.with_limits(&options.limits);
+
+                if let Some(shutdown) = shutting_down(options.shutdown.as_ref()) {
+                    return upgrade.on_upgrade(move |socket| async move {
+                        shutdown.reject_socket(socket, encoding).await;
+                    });
+                }
+                let mount = Mount {
+                    encoding,
+                    uri,
+                    headers,
+                    options,
+                };
+
+                let mut response =
+                    upgrade.on_upgrade(move |socket| multiplex::run(socket, mount, gather_views));
+                if remove_flash_cookie {
+                    response
+                        .headers_mut()
+                        .append(header::SET_COOKIE, flash::removal_cookie());
+                }
+                response
+            }
+            // views that share a socket can't be mounted on the socket of a single view, so the
+            // socket is left in the handoff and the client is told to navigate to the page
+            // normally
+            LiveViewUpgradeInner::Handoff(_) => ().into_response(),
         }
     }
 }
 
-async fn run_view_on_socket<L>(socket: WebSocket, view: L, uri: Uri, headers: HeaderMap)
-where
-    L: LiveView,
+/// The [`Shutdown`] if the server is shutting down, in which case no views are mounted.
+fn shutting_down(shutdown: Option<&Shutdown>) -> Option<Shutdown> {
+    shutdown
+        .filter(|shutdown| shutdown.is_shutting_down())
+        .cloned()
+}
+
+/// Close a socket that failed the CSRF checks or was halted by an [`OnMount`] hook.
+///
+/// [`OnMount`]: crate::on_mount::OnMount
+pub(crate) async fn reject_socket(mut socket: Socket, reason: &'static str) {
+    let frame = ws::CloseFrame {
+        code: close_code::FORBIDDEN,
+        reason: reason.into(),
+    };
+    let _ = socket.send(ws::Message::Close(Some(frame))).await;
+}
+
+pub(crate) async fn run_view_on_socket<L>(
+    socket: Socket,
+    encoding: Encoding,
+    view: L,
+    uri: Uri,
+    headers: HeaderMap,
+    options: ViewOptions,
+) where
+    L: AsyncLiveView,
 {
+    // shutdown waits for the view until this is dropped
+    let _view_guard = options.shutdown.as_ref().map(Shutdown::track_view);
+
     let (write, read) = socket.split();
 
-    let write = write.with(|msg| async move {
-        let encoded_msg = ws::Message::Text(serde_json::to_string(&msg)?.into());
-        Ok::<_, anyhow::Error>(encoded_msg)
+    let mut write = write.with(move |msg: MessageToSocket| future::ready(encoding.encode(&msg)));
+
+    let limiter = Arc::new(Limiter::new(options.limits.clone()));
+    let message_handles = options
+        .protect_messages
+        .then(|| Arc::new(MessageHandles::new()));
+    let read = read.filter_map({
+        let limiter = limiter.clone();
+        let message_handles = message_handles.clone();
+        move |msg| {
+            Box::pin(decode_message::<L::Message>(
+                msg,
+                encoding,
+                limiter.clone(),
+                message_handles.clone(),
+            ))
+        }
+    });
+    let mut read = Watchdog::new(read, &options.limits);
+    let shutdown_started = options.shutdown.as_ref().map(Shutdown::started);
+    let mut shutdown = Box::pin(
+        async move {
+            match shutdown_started {
+                Some(started) => started.await,
+                None => future::pending().await,
+            }
+        }
+        .fuse(),
+    );
+    // part of the socket's stream so the view still ends when the socket's stream does
+    let read_or_shutdown = futures_util::stream::poll_fn(|cx| {
+        if shutdown.poll_unpin(cx).is_ready() {
+            return Poll::Ready(Some(Ok(MessageFromSocket::Shutdown(
+                ShutdownReason::ServerShutdown,
+            ))));
+        }
+        read.poll_next_unpin(cx)
     });
-    futures_util::pin_mut!(write);
 
-    let read = read
-        .map_err(anyhow::Error::from)
-        .and_then(|msg| async move {
-            if let ws::Message::Text(text) = msg {
-                serde_json::from_str(&text).map_err(Into::into)
+    let exit = run_view(
+        &mut write,
+        read_or_shutdown,
+        view,
+        uri,
+        headers.clone(),
+        &options,
+        message_handles,
+    )
+    .await;
+
+    match exit {
+        Ok(ViewExit::Closed) => {
+            if let Some(reason) = limiter.violation() {
+                let _ = write
+                    .send(MessageToSocket::close(close_code::POLICY_VIOLATION, reason))
+                    .await;
+            } else if let Some(shutdown) = shutting_down(options.shutdown.as_ref()) {
+                for msg in shutdown.going_away() {
+                    if write.send(msg).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(ViewExit::LiveRedirect(uri)) => {
+            let socket = match read.into_inner().into_inner().reunite(write.into_inner()) {
+                Ok(socket) => socket,
+                Err(err) => {
+                    tracing::error!(%err, "failed to reunite socket");
+                    return;
+                }
+            };
+
+            if let Some(navigation) = options.navigation {
+                navigation
+                    .redirect(socket, encoding, uri, headers, options.flash)
+                    .await;
             } else {
-                anyhow::bail!("received message from socket that wasn't text")
+                tracing::debug!(%uri, "live redirect without `live_navigation`, reloading the page");
+                navigation::navigate_away(socket, encoding, &uri, &options.flash).await;
             }
-        });
-    futures_util::pin_mut!(read);
+        }
+        Err(err) => {
+            tracing::error!(%err, "encountered while processing socket");
+        }
+    }
+}
 
-    if let Err(err) = run_view(write, read, view, uri, headers).await {
-        tracing::error!(%err, "encountered while processing socket");
+/// Decode a message from the socket, applying the limits and resolving message handles.
+///
+/// Returns `None` if the message is dropped.
+async fn decode_message<M>(
+    msg: Result<ws::Message, axum::Error>,
+    encoding: Encoding,
+    limiter: Arc<Limiter>,
+    message_handles: Option<Arc<MessageHandles>>,
+) -> Option<anyhow::Result<MessageFromSocket<M>>>
+where
+    M: DeserializeOwned,
+{
+    let msg = match msg {
+        Ok(msg) => msg,
+        Err(err) => return Some(Err(err.into())),
+    };
+
+    match limiter.check_message(&msg, encoding) {
+        Verdict::Accept => {}
+        Verdict::Drop => return None,
+        Verdict::Close => return Some(Err(anyhow::anyhow!("socket limit exceeded"))),
+    }
+
+    let msg = match message_handles {
+        // handles are rendered as JSON strings
+        Some(message_handles) => match encoding.decode::<String>(Ok(msg)) {
+            Ok(msg) => message_handles.resolve(msg)?,
+            Err(err) => Err(err),
+        },
+        None => encoding.decode::<M>(Ok(msg)),
+    };
+
+    if let Ok(MessageFromSocket::Event { .. }) = &msg {
+        match limiter.check_event().await {
+            Verdict::Accept => {}
+            Verdict::Drop => return None,
+            Verdict::Close => return Some(Err(anyhow::anyhow!("socket limit exceeded"))),
+        }
     }
+
+    Some(msg)
 }
@@ -0,0 +1,487 @@
+//! Short messages, such as "Saved!", shown on the next page the user sees.
+//!
+//! A [`Flash`] holds one message per kind, for example `"info"` or `"error"`. Views set them with
+//! [`Updated::put_flash`] and plain axum handlers by extracting a [`Flash`] and returning it as
+//! part of the response. Messages survive:
+//!
+//! - re-renders of the current view, until they're cleared with [`Updated::clear_flash`].
+//! - [live redirects](crate::navigation) to another view.
+//! - [`js_command::navigate_to`] and HTTP redirects. The messages are stored in a cookie, signed
+//!   with the [`SigningKey`] from the request extensions, and removed again once a live view has
+//!   connected and read them.
+//!
+//! # Example
+//!
+//! A form handler that redirects to a live view which shows the message:
+//!
+//! ```
+//! use axum::response::{IntoResponse, Redirect};
+//! use serde::{Deserialize, Serialize};
+//! use shipwright_liveview::{
+//!     event_data::EventData, flash::Flash, html, live_view::Updated, Html, LiveView,
+//!     LiveViewUpgrade,
+//! };
+//!
+//! async fn create_post(flash: Flash) -> impl IntoResponse {
+//!     // ...
+//!     flash.put("info", "Post created!");
+//!     (flash, Redirect::to("/posts"))
+//! }
+//!
+//! async fn posts(live: LiveViewUpgrade, flash: Flash) -> impl IntoResponse {
+//!     live.response(|embed| html! { { embed.embed(Posts { flash }) } })
+//! }
+//!
+//! struct Posts {
+//!     flash: Flash,
+//! }
+//!
+//! impl LiveView for Posts {
+//!     type Message = Msg;
+//!
+//!     fn update(self, msg: Msg, _: Option<EventData>) -> Updated<Self> {
+//!         match msg {
+//!             Msg::Delete => Updated::new(self).put_flash("info", "Post deleted"),
+//!             Msg::CloseFlash(kind) => Updated::new(self).clear_flash(kind),
+//!         }
+//!     }
+//!
+//!     fn render(&self) -> Html<Msg> {
+//!         html! {
+//!             {
+//!                 self.flash.render(|kind, message| html! {
+//!                     <p class={ format!("alert alert-{kind}") } role="alert">
+//!                         { message }
+//!                         <button axm-click={ Msg::CloseFlash(kind.to_owned()) }>"×"</button>
+//!                     </p>
+//!                 })
+//!             }
+//!             <button axm-click={ Msg::Delete }>"Delete"</button>
+//!         }
+//!     }
+//! }
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! enum Msg {
+//!     Delete,
+//!     CloseFlash(String),
+//! }
+//! ```
+//!
+//! [`Updated::put_flash`]: crate::live_view::Updated::put_flash
+//! [`Updated::clear_flash`]: crate::live_view::Updated::clear_flash
+//! [`js_command::navigate_to`]: crate::js_command::navigate_to
+
+use crate::{
+    html::{DynamicFragment, DynamicFragmentVecExt, HtmlBuilder},
+    signing::SigningKey,
+    Html,
+};
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap, HeaderValue},
+    response::{IntoResponseParts, ResponseParts},
+};
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+pub(crate) const COOKIE_NAME: &str = "axm_flash";
+
+// flash cookies only have to survive a redirect
+const MAX_AGE_SECS: u32 = 60;
+
+/// Flash messages for the current request or view.
+///
+/// `Flash` is a handle, clones share the same messages. That way a view can keep the `Flash` it
+/// was created with and render the messages set with [`Updated::put_flash`].
+///
+/// Extracting a `Flash` reads the messages from the flash cookie. Returning it from a handler
+/// stores its messages in the cookie, or removes the cookie if there are none.
+///
+/// See the [module docs](self) for an example.
+///
+/// [`Updated::put_flash`]: crate::live_view::Updated::put_flash
+#[derive(Clone)]
+pub struct Flash {
+    messages: Arc<Mutex<BTreeMap<String, String>>>,
+    key: SigningKey,
+}
+
+impl Flash {
+    pub(crate) fn new(key: SigningKey) -> Self {
+        Self {
+            messages: Default::default(),
+            key,
+        }
+    }
+
+    /// The flash for a request, read from the flash cookie.
+    ///
+    /// The flash is stored in the request extensions so all extractors in the same request, and
+    /// the view they set up, share it.
+    pub(crate) fn from_parts(parts: &mut Parts) -> Self {
+        if let Some(flash) = parts.extensions.get::<Flash>() {
+            return flash.clone();
+        }
+
+        let flash = Self::new(SigningKey::from_extensions(&parts.extensions));
+        if let Some(messages) = flash.read_cookie(&parts.headers) {
+            *flash.messages.lock().unwrap() = messages;
+        }
+
+        parts.extensions.insert(flash.clone());
+        flash
+    }
+
+    /// Set the message for `kind`, replacing any previous message of that kind.
+    pub fn put(&self, kind: impl Into<String>, message: impl Into<String>) {
+        self.messages
+            .lock()
+            .unwrap()
+            .insert(kind.into(), message.into());
+    }
+
+    /// Get the message for `kind`.
+    pub fn get(&self, kind: &str) -> Option<String> {
+        self.messages.lock().unwrap().get(kind).cloned()
+    }
+
+    /// Remove and return the message for `kind`.
+    pub fn remove(&self, kind: &str) -> Option<String> {
+        self.messages.lock().unwrap().remove(kind)
+    }
+
+    /// Remove all messages.
+    pub fn clear(&self) {
+        self.messages.lock().unwrap().clear();
+    }
+
+    /// Whether there are no messages.
+    pub fn is_empty(&self) -> bool {
+        self.messages.lock().unwrap().is_empty()
+    }
+
+    /// Render each message with `render_message`, ordered by kind.
+    ///
+    /// `render_message` is called with the kind and the message.
+    pub fn render<M, F>(&self, render_message: F) -> Html<M>
+    where
+        F: Fn(&str, &str) -> Html<M>,
+    {
+        let messages = self.messages.lock().unwrap();
+
+        let mut dynamic = Vec::new();
+        dynamic.push_fragments(
+            &["", ""],
+            messages
+                .iter()
+                .map(|(kind, message)| {
+                    Vec::from([DynamicFragment::Html(render_message(kind, message))])
+                })
+                .collect(),
+        );
+
+        HtmlBuilder {
+            fixed: &["", ""],
+            dynamic,
+        }
+        .into_html()
+    }
+
+    pub(crate) fn apply(&self, changes: Vec<FlashChange>) {
+        let mut messages = self.messages.lock().unwrap();
+        for change in changes {
+            match change {
+                FlashChange::Put { kind, message } => {
+                    messages.insert(kind, message);
+                }
+                FlashChange::Clear { kind } => {
+                    messages.remove(&kind);
+                }
+            }
+        }
+    }
+
+    /// A `Set-Cookie` value that stores the messages, or removes the cookie if there are none.
+    ///
+    /// `http_only` has to be `false` for cookies set with `document.cookie`.
+    pub(crate) fn cookie(&self, http_only: bool) -> String {
+        let messages = self.messages.lock().unwrap();
+
+        let (value, max_age) = if messages.is_empty() {
+            (String::new(), 0)
+        } else {
+            let json = serde_json::to_vec(&*messages).expect("failed to serialize flash");
+            (self.key.sign(COOKIE_NAME, &json), MAX_AGE_SECS)
+        };
+
+        let mut cookie = format!("{COOKIE_NAME}={value}; Path=/; Max-Age={max_age}; SameSite=Lax");
+        if http_only {
+            cookie.push_str("; HttpOnly");
+        }
+        cookie
+    }
+
+    fn read_cookie(&self, headers: &HeaderMap) -> Option<BTreeMap<String, String>> {
+        let value = cookie_value(headers)?;
+        let json = self.key.verify(COOKIE_NAME, value)?;
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+/// A `Set-Cookie` value that removes the flash cookie.
+pub(crate) fn removal_cookie() -> HeaderValue {
+    HeaderValue::from_static("axm_flash=; Path=/; Max-Age=0; SameSite=Lax; HttpOnly")
+}
+
+/// Whether the request has a flash cookie.
+pub(crate) fn has_cookie(headers: &HeaderMap) -> bool {
+    cookie_value(headers).is_some()
+}
+
+fn cookie_value(headers: &HeaderMap) -> Option<&str> {
+    crate::util::cookie(headers, COOKIE_NAME)
+}
+
+/// A change made with [`Updated::put_flash`] or [`Updated::clear_flash`].
+///
+/// [`Updated::put_flash`]: crate::live_view::Updated::put_flash
+/// [`Updated::clear_flash`]: crate::live_view::Updated::clear_flash
+#[derive(Debug)]
+pub(crate) enum FlashChange {
+    Put { kind: String, message: String },
+    Clear { kind: String },
+}
+
+impl<S> FromRequestParts<S> for Flash
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_parts(parts))
+    }
+}
+
+impl IntoResponseParts for Flash {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        let cookie = HeaderValue::try_from(self.cookie(true)).expect("invalid flash cookie");
+        res.headers_mut().append(header::SET_COOKIE, cookie);
+        Ok(res)
+    }
+}
+
+impl fmt::Debug for Flash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Flash")
+            .field("messages", &*self.messages.lock().unwrap())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event_data::EventData,
+        html, js_command,
+        live_view::Updated,
+        navigation::live_navigation,
+        sse::{tests as sse, SseLayer},
+        LiveView, LiveViewUpgrade,
+    };
+    use axum::{body::Body, http::StatusCode, routing::get, Router};
+    use http::Request;
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    fn parts_with_cookie(cookie: &str) -> Parts {
+        Request::builder()
+            .header(header::COOKIE, cookie)
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0
+    }
+
+    #[test]
+    fn cookie_round_trip() {
+        let flash = Flash::new(SigningKey::generate());
+        flash.put("info", "Saved!");
+
+        let set_cookie = flash.cookie(true);
+        let cookie = set_cookie.split(';').next().unwrap();
+
+        let mut parts = parts_with_cookie(&format!("theme=dark; {cookie}"));
+        parts.extensions.insert(flash.key.clone());
+        let read = Flash::from_parts(&mut parts);
+        assert_eq!(read.get("info").unwrap(), "Saved!");
+
+        // a different key rejects the cookie
+        let mut parts = parts_with_cookie(cookie);
+        parts.extensions.insert(SigningKey::generate());
+        assert!(Flash::from_parts(&mut parts).is_empty());
+    }
+
+    #[test]
+    fn extractors_share_the_flash() {
+        let mut parts = parts_with_cookie("theme=dark");
+        let first = Flash::from_parts(&mut parts);
+        let second = Flash::from_parts(&mut parts);
+
+        first.put("error", "Oops");
+        assert_eq!(second.get("error").unwrap(), "Oops");
+    }
+
+    #[test]
+    fn empty_flash_removes_cookie() {
+        let flash = Flash::new(SigningKey::generate());
+        assert_eq!(
+            flash.cookie(true),
+            "axm_flash=; Path=/; Max-Age=0; SameSite=Lax; HttpOnly"
+        );
+    }
+
+    #[test]
+    fn render() {
+        let flash = Flash::new(SigningKey::generate());
+        flash.put("info", "Saved!");
+        flash.put("error", "Oops");
+
+        let html: Html<()> = html! {
+            <div>
+                { flash.render(|kind, message| html! { <p class={ kind }>{ message }</p> }) }
+            </div>
+        };
+        assert_eq!(
+            html.render(),
+            "<div><p class=\"error\">Oops</p><p class=\"info\">Saved!</p></div>"
+        );
+
+        flash.apply(Vec::from([FlashChange::Clear {
+            kind: "error".to_owned(),
+        }]));
+        let html: Html<()> = html! {
+            <div>
+                { flash.render(|kind, message| html! { <p class={ kind }>{ message }</p> }) }
+            </div>
+        };
+        assert_eq!(html.render(), "<div><p class=\"info\">Saved!</p></div>");
+    }
+
+    /// Shows the flash, and puts a message before navigating away.
+    struct Posts {
+        flash: Flash,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Msg {
+        NavigateTo,
+        LiveRedirect,
+    }
+
+    impl LiveView for Posts {
+        type Message = Msg;
+
+        fn update(self, msg: Msg, _data: Option<EventData>) -> Updated<Self> {
+            let uri = "/posts".parse().unwrap();
+            let command = match msg {
+                Msg::NavigateTo => js_command::navigate_to(uri),
+                Msg::LiveRedirect => js_command::live_redirect(uri),
+            };
+            Updated::new(self).put_flash("info", "Saved!").with(command)
+        }
+
+        fn render(&self) -> Html<Msg> {
+            html! {
+                { self.flash.render(|_, message| html! { <p>{ message }</p> }) }
+            }
+        }
+    }
+
+    fn app() -> Router {
+        let posts = get(|live: LiveViewUpgrade, flash: Flash| async move {
+            live.response(|embed| embed.embed(Posts { flash }))
+        });
+        live_navigation(
+            Router::new()
+                .route("/", posts.clone())
+                .route("/posts", posts),
+        )
+        .layer(SseLayer::new())
+    }
+
+    /// Connect to the view at `/`, send it `msg` and return the JS commands it responds with.
+    async fn navigate(app: Router, msg: &str) -> (sse::Events, String, Value) {
+        let mut events = sse::connect(app.clone()).await;
+        let (_, session) = events.next().await;
+        let (_, data) = events.next().await;
+        assert_eq!(serde_json::from_str::<Value>(&data).unwrap()["t"], "i");
+
+        let click = serde_json::json!({ "m": format!("%22{msg}%22"), "t": "click" }).to_string();
+        assert_eq!(sse::post(app, &session, &click).await, StatusCode::ACCEPTED);
+
+        loop {
+            let (_, data) = events.next().await;
+            let msg = serde_json::from_str::<Value>(&data).unwrap();
+            if msg["t"] == "j" {
+                return (events, session, msg["d"].clone());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn survives_navigate_to() {
+        let app = app();
+        let (_events, _, commands) = navigate(app.clone(), "NavigateTo").await;
+
+        assert_eq!(commands[0]["kind"]["t"], "set_cookie");
+        assert_eq!(commands[1]["kind"]["t"], "navigate_to");
+        let set_cookie = commands[0]["kind"]["cookie"].as_str().unwrap();
+        assert!(!set_cookie.contains("HttpOnly"));
+
+        // the client sets the cookie and loads the next page
+        let cookie = set_cookie.split(';').next().unwrap();
+        let request = Request::builder()
+            .uri("/posts")
+            .header(header::COOKIE, cookie)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(std::str::from_utf8(&body)
+            .unwrap()
+            .contains("<p>Saved!</p>"));
+    }
+
+    #[tokio::test]
+    async fn survives_live_redirects() {
+        let app = app();
+        let (mut events, session, commands) = navigate(app.clone(), "LiveRedirect").await;
+        assert_eq!(commands[0]["kind"]["t"], "live_redirect");
+
+        // the client changes the URL and asks for the view there
+        let redirect = r#"{"t":"lr","u":"/posts"}"#;
+        assert_eq!(
+            sse::post(app, &session, redirect).await,
+            StatusCode::ACCEPTED
+        );
+
+        let (_, data) = events.next().await;
+        let msg = serde_json::from_str::<Value>(&data).unwrap();
+        assert_eq!(msg["t"], "i");
+        assert!(data.contains("Saved!"), "{data}");
+    }
+}
@@ -1,6 +1,6 @@
-use super::{empty_slice, serialize_msg, DynamicFragment, Html, IndexMap};
+use super::{empty_slice, serialize_msg, DynamicFragment, Html, IndexMap, StreamFragment};
 use serde::Serialize;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 #[derive(Serialize)]
 pub(crate) struct HtmlDiff<'a, T> {
@@ -23,6 +23,45 @@ pub(crate) enum DynamicFragmentDiff<'a, T> {
         #[serde(rename = "b", skip_serializing_if = "BTreeMap::is_empty")]
         dynamic: IndexMap<Option<IndexMap<DynamicFragmentDiff<'a, T>>>>,
     },
+    KeyedLoop {
+        #[serde(rename = "f")]
+        fixed: &'static [&'static str],
+        #[serde(rename = "k")]
+        keys: &'a [String],
+        #[serde(rename = "b", skip_serializing_if = "BTreeMap::is_empty")]
+        dynamic: IndexMap<IndexMap<DynamicFragmentDiff<'a, T>>>,
+    },
+    KeyedLoopDiff {
+        #[serde(rename = "r", skip_serializing_if = "Vec::is_empty")]
+        removed: Vec<String>,
+        #[serde(rename = "o", skip_serializing_if = "Vec::is_empty")]
+        ops: Vec<KeyedLoopOp<'a, T>>,
+        #[serde(rename = "u", skip_serializing_if = "BTreeMap::is_empty")]
+        updated: BTreeMap<&'a str, IndexMap<DynamicFragmentDiff<'a, T>>>,
+    },
+    Stream(&'a StreamFragment<T>),
+}
+
+/// Operation that puts a row of a keyed loop at its new position.
+///
+/// Operations are applied in order, after the removed rows have been dropped.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub(crate) enum KeyedLoopOp<'a, T> {
+    Insert {
+        #[serde(rename = "i")]
+        index: usize,
+        #[serde(rename = "k")]
+        key: &'a str,
+        #[serde(rename = "b")]
+        dynamic: IndexMap<DynamicFragmentDiff<'a, T>>,
+    },
+    Move {
+        #[serde(rename = "m")]
+        index: usize,
+        #[serde(rename = "k")]
+        key: &'a str,
+    },
 }
 
 impl<'a, T> From<&'a Html<T>> for HtmlDiff<'a, T> {
@@ -60,10 +99,30 @@ impl<'a, T> From<&'a DynamicFragment<T>> for DynamicFragmentDiff<'a, T> {
                     })
                     .collect(),
             },
+            DynamicFragment::KeyedLoop {
+                fixed,
+                keys,
+                dynamic,
+            } => Self::KeyedLoop {
+                fixed,
+                keys,
+                dynamic: dynamic
+                    .iter()
+                    .map(|(idx, map)| (*idx, full_parts(map)))
+                    .collect(),
+            },
+            DynamicFragment::Stream(stream) => Self::Stream(stream),
         }
     }
 }
 
+fn full_parts<T>(parts: &IndexMap<DynamicFragment<T>>) -> IndexMap<DynamicFragmentDiff<'_, T>> {
+    parts
+        .iter()
+        .map(|(idx, dynamic)| (*idx, dynamic.into()))
+        .collect()
+}
+
 impl<T> Html<T> {
     pub(crate) fn diff<'a>(&self, other: &'a Self) -> Option<HtmlDiff<'a, T>>
     where
@@ -196,11 +255,124 @@ impl<T> DynamicFragment<T> {
                     Some(DynamicFragmentDiff::Loop { fixed, dynamic })
                 }
             }
+            (
+                Self::KeyedLoop {
+                    fixed: self_fixed,
+                    keys: self_keys,
+                    dynamic: self_dynamic,
+                },
+                Self::KeyedLoop {
+                    fixed: other_fixed,
+                    keys: other_keys,
+                    dynamic: other_dynamic,
+                },
+            ) if self_fixed == other_fixed
+                && has_unique_keys(self_keys)
+                && has_unique_keys(other_keys) =>
+            {
+                diff_keyed(self_keys, self_dynamic, other_keys, other_dynamic)
+            }
+            // the changes in a batch are only sent the first time it is rendered
+            (Self::Stream(self_stream), Self::Stream(other_stream)) => {
+                if self_stream.batch == other_stream.batch {
+                    None
+                } else {
+                    Some(DynamicFragmentDiff::Stream(other_stream))
+                }
+            }
             (_, other) => Some(other.into()),
         }
     }
 }
 
+/// Diff two keyed loops that share the same template.
+///
+/// The rows that remain are reordered by walking the new keys and inserting or moving every row
+/// that isn't already at its new index. Rows that stay in place are only diffed.
+fn diff_keyed<'a, T>(
+    self_keys: &[String],
+    self_dynamic: &IndexMap<IndexMap<DynamicFragment<T>>>,
+    other_keys: &'a [String],
+    other_dynamic: &'a IndexMap<IndexMap<DynamicFragment<T>>>,
+) -> Option<DynamicFragmentDiff<'a, T>>
+where
+    T: PartialEq + Serialize,
+{
+    let self_rows = self_keys
+        .iter()
+        .map(String::as_str)
+        .zip(self_dynamic.values())
+        .collect::<HashMap<_, _>>();
+    let other_key_set = other_keys
+        .iter()
+        .map(String::as_str)
+        .collect::<HashSet<_>>();
+
+    let removed = self_keys
+        .iter()
+        .filter(|key| !other_key_set.contains(key.as_str()))
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut current = self_keys
+        .iter()
+        .map(String::as_str)
+        .filter(|key| other_key_set.contains(key))
+        .collect::<Vec<_>>();
+
+    let mut ops = Vec::new();
+    let mut updated = BTreeMap::new();
+
+    for (index, (key, other_parts)) in other_keys.iter().zip(other_dynamic.values()).enumerate() {
+        let key = key.as_str();
+
+        let Some(self_parts) = self_rows.get(key) else {
+            current.insert(index, key);
+            ops.push(KeyedLoopOp::Insert {
+                index,
+                key,
+                dynamic: full_parts(other_parts),
+            });
+            continue;
+        };
+
+        if current.get(index) != Some(&key) {
+            if let Some(position) = current.iter().position(|current| *current == key) {
+                current.remove(position);
+            }
+            current.insert(index, key);
+            ops.push(KeyedLoopOp::Move { index, key });
+        }
+
+        let parts = zip(self_parts.iter(), other_parts.iter())
+            .filter_map(|pair| match pair {
+                Zipped::Both((idx, self_value), (_, other_value)) => {
+                    self_value.diff(other_value).map(|diff| (*idx, diff))
+                }
+                Zipped::Right((idx, other_value)) => Some((*idx, other_value.into())),
+                Zipped::Left(_) => None,
+            })
+            .collect::<IndexMap<_>>();
+        if !parts.is_empty() {
+            updated.insert(key, parts);
+        }
+    }
+
+    if removed.is_empty() && ops.is_empty() && updated.is_empty() {
+        None
+    } else {
+        Some(DynamicFragmentDiff::KeyedLoopDiff {
+            removed,
+            ops,
+            updated,
+        })
+    }
+}
+
+fn has_unique_keys(keys: &[String]) -> bool {
+    let mut seen = HashSet::with_capacity(keys.len());
+    keys.iter().all(|key| seen.insert(key.as_str()))
+}
+
 fn diff_fixed(a: &'static [&'static str], b: &'static [&'static str]) -> &'static [&'static str] {
     if a.len() == b.len() && a == b {
         &[]
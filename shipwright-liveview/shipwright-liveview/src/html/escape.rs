@@ -0,0 +1,202 @@
+use std::fmt;
+
+/// Content that has already been escaped and is trusted to be safe to include in HTML as is.
+///
+/// Values interpolated into [`html!`] templates are escaped by default. Text nodes have `&`, `<`
+/// and `>` escaped, attribute values additionally have quotes escaped, and URL attributes such as
+/// `href` or `src` have unsafe schemes like `javascript:` neutralized. Wrapping a value in
+/// `PreEscaped` opts out of all of that.
+///
+/// Only use this for content you control. Wrapping user supplied data in `PreEscaped` opens you
+/// up to [cross-site scripting][xss].
+///
+/// # Example
+///
+/// ```
+/// use shipwright_liveview::{html, Html, PreEscaped};
+///
+/// let markdown_output = "<strong>rendered on the server</strong>";
+///
+/// let view: Html<()> = html! {
+///     <div>{ PreEscaped(markdown_output) }</div>
+/// };
+/// ```
+///
+/// [`html!`]: crate::html!
+/// [xss]: https://owasp.org/www-community/attacks/xss/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PreEscaped<T>(pub T);
+
+/// Attributes whose values are interpreted as URLs by browsers.
+const URL_ATTRIBUTES: &[&str] = &[
+    "action",
+    "background",
+    "cite",
+    "formaction",
+    "href",
+    "icon",
+    "longdesc",
+    "manifest",
+    "poster",
+    "src",
+    "xlink:href",
+];
+
+/// Schemes that are allowed in URL attributes. Relative URLs are always allowed.
+const SAFE_URL_SCHEMES: &[&str] = &["http", "https", "mailto", "tel"];
+
+/// What an unsafe URL is replaced with.
+const INVALID_URL: &str = "about:invalid";
+
+pub(crate) fn escape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+pub(crate) fn escape_attribute(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+pub(crate) fn escape_attribute_value(name: &str, value: &str) -> String {
+    if is_url_attribute(name) && !is_safe_url(value) {
+        INVALID_URL.to_owned()
+    } else {
+        escape_attribute(value)
+    }
+}
+
+fn is_url_attribute(name: &str) -> bool {
+    URL_ATTRIBUTES
+        .iter()
+        .any(|attr| attr.eq_ignore_ascii_case(name))
+}
+
+fn is_safe_url(url: &str) -> bool {
+    // browsers ignore leading whitespace and control characters, and strip tabs and newlines
+    // anywhere in the scheme, so `java\tscript:` is still `javascript:`
+    let url = url
+        .trim_start_matches(|c: char| c.is_ascii_whitespace() || c.is_ascii_control())
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'));
+
+    let mut scheme = String::new();
+    for c in url {
+        match c {
+            ':' => {
+                return SAFE_URL_SCHEMES
+                    .iter()
+                    .any(|safe| safe.eq_ignore_ascii_case(&scheme));
+            }
+            // a `/`, `?` or `#` before any `:` means the URL is relative
+            '/' | '?' | '#' => return true,
+            _ => scheme.push(c),
+        }
+    }
+
+    true
+}
+
+/// Values that can be interpolated into attribute values in [`html!`] templates.
+///
+/// Implemented for everything that implements [`Display`](fmt::Display) and for [`PreEscaped`].
+///
+/// [`html!`]: crate::html!
+pub trait AttributeValue {
+    /// Convert the value into an escaped attribute value for the attribute called `name`.
+    fn to_attribute_value(&self, name: &str) -> String;
+}
+
+impl<T> AttributeValue for T
+where
+    T: fmt::Display,
+{
+    fn to_attribute_value(&self, name: &str) -> String {
+        escape_attribute_value(name, &self.to_string())
+    }
+}
+
+impl<T> AttributeValue for PreEscaped<T>
+where
+    T: fmt::Display,
+{
+    fn to_attribute_value(&self, _name: &str) -> String {
+        self.0.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text() {
+        assert_eq!(
+            escape_text("<script>alert('hi') && \"bye\"</script>"),
+            "&lt;script&gt;alert('hi') &amp;&amp; \"bye\"&lt;/script&gt;"
+        );
+    }
+
+    #[test]
+    fn attribute() {
+        assert_eq!(
+            escape_attribute("\" onmouseover='alert(1)'"),
+            "&quot; onmouseover=&#39;alert(1)&#39;"
+        );
+    }
+
+    #[test]
+    fn url_attribute() {
+        assert_eq!(
+            escape_attribute_value("href", "/users?id=1&x=2"),
+            "/users?id=1&amp;x=2"
+        );
+        assert_eq!(
+            escape_attribute_value("href", "https://example.com"),
+            "https://example.com"
+        );
+        assert_eq!(
+            escape_attribute_value("href", "mailto:bob@example.com"),
+            "mailto:bob@example.com"
+        );
+        assert_eq!(escape_attribute_value("href", "foo/bar:baz"), "foo/bar:baz");
+        assert_eq!(
+            escape_attribute_value("href", "javascript:alert(1)"),
+            INVALID_URL
+        );
+        assert_eq!(
+            escape_attribute_value("HREF", " JavaScript:alert(1)"),
+            INVALID_URL
+        );
+        assert_eq!(
+            escape_attribute_value("src", "java\tscript:alert(1)"),
+            INVALID_URL
+        );
+        assert_eq!(
+            escape_attribute_value("src", "data:text/html,<script>"),
+            INVALID_URL
+        );
+        assert_eq!(
+            escape_attribute_value("title", "javascript:alert(1)"),
+            "javascript:alert(1)"
+        );
+    }
+}
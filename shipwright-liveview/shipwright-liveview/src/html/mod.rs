@@ -4,7 +4,10 @@ use std::{collections::BTreeMap, fmt};
 
 pub(crate) use self::private::*;
 
+pub use self::escape::PreEscaped;
+
 mod diff;
+mod escape;
 pub(crate) mod private;
 mod render;
 
@@ -50,6 +53,21 @@ impl<T> std::fmt::Debug for DynamicFragment<T> {
                 .field("fixed", &fixed)
                 .field("dynamic", &dynamic)
                 .finish(),
+            Self::KeyedLoop {
+                fixed,
+                keys,
+                dynamic,
+            } => f
+                .debug_struct("KeyedLoop")
+                .field("fixed", &fixed)
+                .field("keys", &keys)
+                .field("dynamic", &dynamic)
+                .finish(),
+            Self::Stream(stream) => f
+                .debug_struct("Stream")
+                .field("reset", &stream.reset)
+                .field("ops", &stream.ops.len())
+                .finish(),
         }
     }
 }
@@ -59,7 +77,16 @@ where
     S: fmt::Display,
 {
     fn from(x: S) -> Self {
-        DynamicFragment::String(x.to_string())
+        DynamicFragment::String(escape::escape_text(&x.to_string()))
+    }
+}
+
+impl<S, T> From<PreEscaped<S>> for DynamicFragment<T>
+where
+    S: fmt::Display,
+{
+    fn from(x: PreEscaped<S>) -> Self {
+        DynamicFragment::String(x.0.to_string())
     }
 }
 
@@ -82,15 +109,45 @@ where
     serializer.serialize_str(&encoded)
 }
 
+/// The row of a [`Stream`](crate::stream::Stream) a message is rendered in.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct StreamRow<'a> {
+    pub(crate) stream: u64,
+    pub(crate) key: &'a str,
+}
+
 impl<T> DynamicFragment<T> {
-    fn map_with_mut<F, K>(self, f: &mut F) -> DynamicFragment<K>
+    fn for_each_stream<F>(&self, f: &mut F)
     where
-        F: FnMut(T) -> K,
+        F: FnMut(&StreamFragment<T>),
+    {
+        match self {
+            DynamicFragment::String(_) | DynamicFragment::Message(_) => {}
+            DynamicFragment::Html(inner) => inner.for_each_stream(f),
+            DynamicFragment::Loop { dynamic, .. } | DynamicFragment::KeyedLoop { dynamic, .. } => {
+                for value in dynamic.values().flat_map(|row| row.values()) {
+                    value.for_each_stream(f);
+                }
+            }
+            DynamicFragment::Stream(stream) => {
+                f(stream);
+                for op in &stream.ops {
+                    if let StreamOp::Insert { html, .. } = op {
+                        html.for_each_stream(f);
+                    }
+                }
+            }
+        }
+    }
+
+    fn map_with_mut<F, K>(self, f: &mut F, row: Option<StreamRow<'_>>) -> DynamicFragment<K>
+    where
+        F: FnMut(T, Option<StreamRow<'_>>) -> DynamicFragment<K>,
     {
         match self {
             DynamicFragment::String(s) => DynamicFragment::String(s),
-            DynamicFragment::Message(msg) => DynamicFragment::Message(f(msg)),
-            DynamicFragment::Html(inner) => DynamicFragment::Html(inner.map_with_mut(f)),
+            DynamicFragment::Message(msg) => f(msg, row),
+            DynamicFragment::Html(inner) => DynamicFragment::Html(inner.map_with_mut(f, row)),
             DynamicFragment::Loop { fixed, dynamic } => DynamicFragment::Loop {
                 fixed,
                 dynamic: dynamic
@@ -99,33 +156,117 @@ impl<T> DynamicFragment<T> {
                         (
                             idx,
                             map.into_iter()
-                                .map(|(idx, value)| (idx, value.map_with_mut(f)))
+                                .map(|(idx, value)| (idx, value.map_with_mut(f, row)))
+                                .collect(),
+                        )
+                    })
+                    .collect(),
+            },
+            DynamicFragment::KeyedLoop {
+                fixed,
+                keys,
+                dynamic,
+            } => DynamicFragment::KeyedLoop {
+                fixed,
+                keys,
+                dynamic: dynamic
+                    .into_iter()
+                    .map(move |(idx, map)| {
+                        (
+                            idx,
+                            map.into_iter()
+                                .map(|(idx, value)| (idx, value.map_with_mut(f, row)))
                                 .collect(),
                         )
                     })
                     .collect(),
             },
+            DynamicFragment::Stream(StreamFragment {
+                stream,
+                batch,
+                reset,
+                ops,
+            }) => DynamicFragment::Stream(StreamFragment {
+                stream,
+                batch,
+                reset,
+                ops: ops
+                    .into_iter()
+                    .map(|op| match op {
+                        StreamOp::Insert { key, at, html } => {
+                            let html = html.map_with_mut(f, Some(StreamRow { stream, key: &key }));
+                            StreamOp::Insert { key, at, html }
+                        }
+                        StreamOp::Delete { key } => StreamOp::Delete { key },
+                    })
+                    .collect(),
+            }),
         }
     }
 }
 
 impl<T> Html<T> {
+    /// Create an `Html` from a string of markup that is trusted to be safe.
+    ///
+    /// The markup is included as is, without any escaping. Only use this for content you
+    /// control, never for user supplied data. See [`PreEscaped`] for more details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use shipwright_liveview::{html, Html};
+    ///
+    /// let icon: Html<()> = Html::raw("<svg><use href=\"#star\"/></svg>");
+    ///
+    /// let view: Html<()> = html! {
+    ///     <button>{ icon } "Favorite"</button>
+    /// };
+    /// ```
+    pub fn raw(markup: impl Into<String>) -> Self {
+        Self {
+            fixed: &["", ""],
+            dynamic: BTreeMap::from([(0, DynamicFragment::String(markup.into()))]),
+        }
+    }
+
     /// Map the messages to a different type.
     pub fn map<F, K>(self, mut f: F) -> Html<K>
     where
         F: FnMut(T) -> K,
     {
-        self.map_with_mut(&mut f)
+        self.map_with_mut(&mut |msg, _| DynamicFragment::Message(f(msg)), None)
     }
 
-    fn map_with_mut<F, K>(self, f: &mut F) -> Html<K>
+    /// Replace each message with a string rendered as is.
+    ///
+    /// `f` is also told the row of the [`Stream`](crate::stream::Stream) the message is part of,
+    /// if any.
+    pub(crate) fn replace_messages<F>(self, mut f: F) -> Self
     where
-        F: FnMut(T) -> K,
+        F: FnMut(T, Option<StreamRow<'_>>) -> String,
+    {
+        self.map_with_mut(&mut |msg, row| DynamicFragment::String(f(msg, row)), None)
+    }
+
+    /// Call `f` with each [`Stream`](crate::stream::Stream) in the HTML, including those in the
+    /// rows of other streams.
+    pub(crate) fn for_each_stream<F>(&self, f: &mut F)
+    where
+        F: FnMut(&StreamFragment<T>),
+    {
+        for fragment in self.dynamic.values() {
+            fragment.for_each_stream(f);
+        }
+    }
+
+    fn map_with_mut<F, K>(self, f: &mut F, row: Option<StreamRow<'_>>) -> Html<K>
+    where
+        F: FnMut(T, Option<StreamRow<'_>>) -> DynamicFragment<K>,
     {
         let dynamic = self
             .dynamic
             .into_iter()
-            .map(move |(idx, d)| (idx, d.map_with_mut(f)))
+            .map(move |(idx, d)| (idx, d.map_with_mut(f, row)))
             .collect();
         Html {
             fixed: self.fixed,
@@ -3,6 +3,10 @@
 #![allow(missing_docs)]
 
 use super::*;
+use crate::js_command::JsCommand;
+use std::cell::Cell;
+
+pub use super::escape::AttributeValue;
 
 #[derive(Clone, Serialize, PartialEq)]
 #[serde(untagged)]
@@ -17,6 +21,72 @@ pub enum DynamicFragment<T> {
         #[serde(rename = "b", skip_serializing_if = "BTreeMap::is_empty")]
         dynamic: IndexMap<IndexMap<DynamicFragment<T>>>,
     },
+    KeyedLoop {
+        #[serde(rename = "f")]
+        fixed: &'static [&'static str],
+        #[serde(rename = "k")]
+        keys: Vec<String>,
+        #[serde(rename = "b", skip_serializing_if = "BTreeMap::is_empty")]
+        dynamic: IndexMap<IndexMap<DynamicFragment<T>>>,
+    },
+    Stream(StreamFragment<T>),
+}
+
+/// The changes made to a [`Stream`] since it was last rendered.
+///
+/// [`Stream`]: crate::stream::Stream
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct StreamFragment<T> {
+    // identifies the stream across batches
+    #[serde(skip)]
+    pub stream: u64,
+    // identifies the batch of changes so they are only sent once
+    #[serde(skip)]
+    pub batch: u64,
+    #[serde(rename = "r", skip_serializing_if = "std::ops::Not::not")]
+    pub reset: bool,
+    #[serde(rename = "s")]
+    pub ops: Vec<StreamOp<T>>,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum StreamOp<T> {
+    Insert {
+        #[serde(rename = "i")]
+        key: String,
+        #[serde(rename = "a")]
+        at: i64,
+        #[serde(rename = "h")]
+        html: Html<T>,
+    },
+    Delete {
+        #[serde(rename = "d")]
+        key: String,
+    },
+}
+
+impl<T> StreamFragment<T> {
+    /// The rows of the stream after applying the changes to an empty container.
+    pub(crate) fn rows(&self) -> Vec<(&str, &Html<T>)> {
+        let mut rows = Vec::<(&str, &Html<T>)>::new();
+        for op in &self.ops {
+            match op {
+                StreamOp::Insert { key, at, html } => {
+                    if let Some(row) = rows.iter_mut().find(|(row_key, _)| row_key == key) {
+                        row.1 = html;
+                    } else if *at < 0 {
+                        rows.push((key, html));
+                    } else {
+                        let at = (*at as usize).min(rows.len());
+                        rows.insert(at, (key, html));
+                    }
+                }
+                StreamOp::Delete { key } => rows.retain(|(row_key, _)| row_key != key),
+            }
+        }
+        rows
+    }
 }
 
 pub trait DynamicFragmentVecExt<T> {
@@ -28,7 +98,15 @@ pub trait DynamicFragmentVecExt<T> {
         dynamic: Vec<Vec<DynamicFragment<T>>>,
     );
 
+    fn push_keyed_fragments(
+        &mut self,
+        fixed: &'static [&'static str],
+        dynamic: Vec<(String, Vec<DynamicFragment<T>>)>,
+    );
+
     fn push_message(&mut self, msg: T);
+
+    fn push_attribute_value(&mut self, name: &'static str, value: impl AttributeValue);
 }
 
 impl<T> DynamicFragmentVecExt<T> for Vec<DynamicFragment<T>> {
@@ -51,10 +129,90 @@ impl<T> DynamicFragmentVecExt<T> for Vec<DynamicFragment<T>> {
         self.push(DynamicFragment::Loop { fixed, dynamic })
     }
 
+    #[inline]
+    fn push_keyed_fragments(
+        &mut self,
+        fixed: &'static [&'static str],
+        dynamic: Vec<(String, Vec<DynamicFragment<T>>)>,
+    ) {
+        let (keys, dynamic): (Vec<_>, Vec<_>) = dynamic.into_iter().unzip();
+        let dynamic = dynamic
+            .into_iter()
+            .enumerate()
+            .map(|(idx, inner)| (idx, inner.into_iter().enumerate().collect()))
+            .collect();
+        self.push(DynamicFragment::KeyedLoop {
+            fixed,
+            keys,
+            dynamic,
+        })
+    }
+
     #[inline]
     fn push_message(&mut self, msg: T) {
         self.push(DynamicFragment::Message(msg))
     }
+
+    #[inline]
+    fn push_attribute_value(&mut self, name: &'static str, value: impl AttributeValue) {
+        self.push(DynamicFragment::String(value.to_attribute_value(name)))
+    }
+}
+
+/// The value of an `axm-*` attribute, either a message or [`JsCommand`]s.
+///
+/// Which of the two it is is decided by method resolution: `(&value).push_binding(..)` picks
+/// [`PushJsBinding`] if the value is a command, and otherwise autorefs to [`PushMessageBinding`].
+pub struct BindingValue<V>(Cell<Option<V>>);
+
+impl<V> BindingValue<V> {
+    pub fn new(value: V) -> Self {
+        Self(Cell::new(Some(value)))
+    }
+
+    fn take(&self) -> V {
+        self.0.take().expect("binding value used twice")
+    }
+}
+
+impl<V> fmt::Debug for BindingValue<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BindingValue").finish()
+    }
+}
+
+pub trait PushJsBinding<T> {
+    fn push_binding(&self, dynamic: &mut Vec<DynamicFragment<T>>);
+}
+
+impl<T> PushJsBinding<T> for BindingValue<JsCommand> {
+    fn push_binding(&self, dynamic: &mut Vec<DynamicFragment<T>>) {
+        dynamic.push(js_binding(&[self.take()]));
+    }
+}
+
+impl<T> PushJsBinding<T> for BindingValue<Vec<JsCommand>> {
+    fn push_binding(&self, dynamic: &mut Vec<DynamicFragment<T>>) {
+        dynamic.push(js_binding(&self.take()));
+    }
+}
+
+pub trait PushMessageBinding<T> {
+    fn push_binding(&self, dynamic: &mut Vec<DynamicFragment<T>>);
+}
+
+impl<T> PushMessageBinding<T> for &BindingValue<T> {
+    fn push_binding(&self, dynamic: &mut Vec<DynamicFragment<T>>) {
+        dynamic.push_message(self.take());
+    }
+}
+
+/// Commands bound to an event run in the browser, so they are prefixed with `js:` to tell them
+/// apart from messages which are always JSON.
+fn js_binding<T>(commands: &[JsCommand]) -> DynamicFragment<T> {
+    let encoded = serde_json::to_string(commands).expect("failed to serialize JS commands");
+    let encoded = percent_encoding::utf8_percent_encode(&encoded, ENCODE_FRAGMENT);
+    DynamicFragment::String(format!("js:{}", encoded))
 }
 
 #[derive(Debug, Clone)]
@@ -45,12 +45,22 @@ where
                 DynamicFragment::Loop {
                     fixed: loop_fixed,
                     dynamic,
+                }
+                | DynamicFragment::KeyedLoop {
+                    fixed: loop_fixed,
+                    dynamic,
+                    ..
                 },
             )) => {
                 for d in dynamic.values() {
                     let _ = render_to(loop_fixed, d, out);
                 }
             }
+            Some((_, DynamicFragment::Stream(stream))) => {
+                for (_, html) in stream.rows() {
+                    let _ = render_to(html.fixed, &html.dynamic, out);
+                }
+            }
             None => {}
         }
     }
@@ -1,5 +1,5 @@
 use super::*;
-use crate::html;
+use crate::{html, stream::Stream, util::assert_client_fixture};
 use serde_json::json;
 
 fn pretty_print<T>(t: T) -> T
@@ -131,7 +131,73 @@ fn interpolate_class() {
     let view: Html<String> = html! {
         <div class={ format!("col-{}", size) }>"foo"</div>
     };
-    assert_eq!(view.render(), "<div class=col-8>foo</div>");
+    assert_eq!(view.render(), "<div class=\"col-8\">foo</div>");
+}
+
+#[test]
+fn interpolate_escapes_text() {
+    let name = "<script>alert(1)</script>";
+    let view: Html<()> = html! { <div>{ name }</div> };
+    assert_eq!(
+        view.render(),
+        "<div>&lt;script&gt;alert(1)&lt;/script&gt;</div>"
+    );
+}
+
+#[test]
+fn interpolate_escapes_attribute() {
+    let class = "\" onmouseover=\"alert(1)";
+    let view: Html<()> = html! { <div class={ class }></div> };
+    assert_eq!(
+        view.render(),
+        "<div class=\"&quot; onmouseover=&quot;alert(1)\"></div>"
+    );
+}
+
+#[test]
+fn interpolate_url_attribute() {
+    let href = "javascript:alert(1)";
+    let view: Html<()> = html! { <a href={ href }>"click"</a> };
+    assert_eq!(view.render(), "<a href=\"about:invalid\">click</a>");
+
+    let href = "/users?page=2&sort=name";
+    let view: Html<()> = html! { <a href={ href }>"next"</a> };
+    assert_eq!(
+        view.render(),
+        "<a href=\"/users?page=2&amp;sort=name\">next</a>"
+    );
+}
+
+#[test]
+fn pre_escaped() {
+    let markup = "<strong>hi</strong>";
+    let view: Html<()> = html! { <div>{ PreEscaped(markup) }</div> };
+    assert_eq!(view.render(), "<div><strong>hi</strong></div>");
+
+    let view: Html<()> = html! { <a href={ PreEscaped("javascript:void(0)") }></a> };
+    assert_eq!(view.render(), "<a href=\"javascript:void(0)\"></a>");
+
+    let icon: Html<()> = Html::raw("<svg></svg>");
+    let view: Html<()> = html! { <button>{ icon }</button> };
+    assert_eq!(view.render(), "<button><svg></svg></button>");
+}
+
+#[test]
+fn diffing_escapes() {
+    fn render(name: &str) -> Html<()> {
+        html! { <div>{ name }</div> }
+    }
+
+    let a = render("bob");
+    let b = render("<b>bob</b>");
+    assert_json_diff::assert_json_eq!(
+        a.diff(&b),
+        json!({
+            "d": {
+                "0": "&lt;b&gt;bob&lt;/b&gt;"
+            }
+        })
+    );
 }
 
 #[test]
@@ -445,6 +511,33 @@ fn axm_attribute() {
     );
 }
 
+#[test]
+fn axm_js_command_attribute() {
+    use crate::js_command;
+
+    let view: Html<()> = html! { <button axm-click={ js_command::show("#menu") }></button> };
+    assert_eq!(
+        view.render(),
+        concat!(
+            "<button axm-click=js:[{%22kind%22:{%22t%22:%22show%22,%22selector%22:%22#menu%22,",
+            "%22display%22:null},%22delay_ms%22:null}]></button>",
+        ),
+    );
+
+    let view: Html<()> = html! {
+        <button axm-click={ Vec::from([js_command::hide("#menu"), js_command::blur("#open")]) }>
+        </button>
+    };
+    assert!(view.render().starts_with("<button axm-click=js:[{%22kind%22:{%22t%22:%22hide%22"));
+
+    // messages and commands can be mixed in the same template
+    let view: Html<&str> = html! {
+        <button axm-click={ "foo" }></button>
+        <button axm-click={ js_command::focus("#search") }></button>
+    };
+    assert!(view.render().starts_with("<button axm-click=%22foo%22></button>"));
+}
+
 #[test]
 fn axm_enum_update_attribute() {
     #[derive(Serialize)]
@@ -879,3 +972,320 @@ fn if_toggle() {
         })
     );
 }
+
+fn render_keyed(rows: &[(u32, &str)]) -> Html<()> {
+    html! {
+        <ul>
+            for (id, name) in rows key id {
+                <li>{ name }</li>
+            }
+        </ul>
+    }
+}
+
+#[test]
+fn keyed_loop() {
+    let view = render_keyed(&[(1, "alice"), (2, "bob")]);
+    assert_eq!(view.render(), "<ul><li>alice</li><li>bob</li></ul>");
+
+    assert_json_diff::assert_json_eq!(
+        pretty_print(&view),
+        json!({
+            "f": ["<ul>", "</ul>"],
+            "d": {
+                "0": {
+                    "f": ["<li>", "</li>"],
+                    "k": ["1", "2"],
+                    "b": {
+                        "0": { "0": "alice" },
+                        "1": { "0": "bob" },
+                    },
+                },
+            },
+        })
+    );
+}
+
+#[test]
+fn diffing_keyed_loop_unchanged() {
+    let a = render_keyed(&[(1, "alice"), (2, "bob")]);
+    let b = render_keyed(&[(1, "alice"), (2, "bob")]);
+    assert_json_diff::assert_json_eq!(pretty_print(a.diff(&b)), json!(null));
+}
+
+#[test]
+fn diffing_keyed_loop_prepend() {
+    let a = render_keyed(&[(1, "alice"), (2, "bob")]);
+    let b = render_keyed(&[(3, "cindy"), (1, "alice"), (2, "bob")]);
+    assert_json_diff::assert_json_eq!(
+        pretty_print(a.diff(&b)),
+        json!({
+            "d": {
+                "0": {
+                    "o": [
+                        { "i": 0, "k": "3", "b": { "0": "cindy" } },
+                    ],
+                },
+            },
+        })
+    );
+}
+
+#[test]
+fn diffing_keyed_loop_remove_and_update() {
+    let a = render_keyed(&[(1, "alice"), (2, "bob"), (3, "cindy")]);
+    let b = render_keyed(&[(1, "alice"), (3, "cynthia")]);
+    assert_json_diff::assert_json_eq!(
+        pretty_print(a.diff(&b)),
+        json!({
+            "d": {
+                "0": {
+                    "r": ["2"],
+                    "u": {
+                        "3": { "0": "cynthia" },
+                    },
+                },
+            },
+        })
+    );
+}
+
+#[test]
+fn diffing_keyed_loop_move() {
+    let a = render_keyed(&[(1, "alice"), (2, "bob"), (3, "cindy")]);
+    let b = render_keyed(&[(3, "cindy"), (1, "alice"), (2, "bob")]);
+    assert_json_diff::assert_json_eq!(
+        pretty_print(a.diff(&b)),
+        json!({
+            "d": {
+                "0": {
+                    "o": [
+                        { "m": 0, "k": "3" },
+                    ],
+                },
+            },
+        })
+    );
+
+    let a = render_keyed(&[(1, "alice"), (2, "bob")]);
+    let b = render_keyed(&[(2, "bob"), (4, "dan"), (1, "alice")]);
+    assert_json_diff::assert_json_eq!(
+        pretty_print(a.diff(&b)),
+        json!({
+            "d": {
+                "0": {
+                    "o": [
+                        { "m": 0, "k": "2" },
+                        { "i": 1, "k": "4", "b": { "0": "dan" } },
+                    ],
+                },
+            },
+        })
+    );
+}
+
+#[test]
+fn diffing_keyed_loop_with_duplicate_keys() {
+    let a = render_keyed(&[(1, "alice"), (1, "bob")]);
+    let b = render_keyed(&[(1, "bob"), (1, "alice")]);
+    assert_json_diff::assert_json_eq!(
+        pretty_print(a.diff(&b)),
+        json!({
+            "d": {
+                "0": {
+                    "f": ["<li>", "</li>"],
+                    "k": ["1", "1"],
+                    "b": {
+                        "0": { "0": "bob" },
+                        "1": { "0": "alice" },
+                    },
+                },
+            },
+        })
+    );
+}
+
+fn render_keyed_groups(groups: &[(u32, &[(u32, &str)])]) -> Html<()> {
+    html! {
+        for (id, rows) in groups key id {
+            <section>
+                for (row, name) in rows.iter() key row {
+                    <p>{ name }</p>
+                }
+            </section>
+        }
+    }
+}
+
+/// The client's tests apply these diffs and check that they render the new HTML.
+#[test]
+fn keyed_loop_client_fixture() {
+    assert_client_fixture(
+        "keyed-loops.json",
+        Vec::from([
+            diff_case(
+                "prepend",
+                render_keyed(&[(1, "alice"), (2, "bob")]),
+                render_keyed(&[(3, "cindy"), (1, "alice"), (2, "bob")]),
+            ),
+            diff_case(
+                "remove and update",
+                render_keyed(&[(1, "alice"), (2, "bob"), (3, "cindy")]),
+                render_keyed(&[(1, "alice"), (3, "cynthia")]),
+            ),
+            diff_case(
+                "move",
+                render_keyed(&[(1, "alice"), (2, "bob"), (3, "cindy")]),
+                render_keyed(&[(3, "cindy"), (1, "alice"), (2, "bob")]),
+            ),
+            diff_case(
+                "move and insert",
+                render_keyed(&[(1, "alice"), (2, "bob")]),
+                render_keyed(&[(2, "bob"), (4, "dan"), (1, "alice")]),
+            ),
+            diff_case(
+                "reverse and update",
+                render_keyed(&[(1, "alice"), (2, "bob"), (3, "cindy"), (4, "dan")]),
+                render_keyed(&[(4, "dan"), (3, "cindy"), (2, "robert"), (1, "alice")]),
+            ),
+            diff_case(
+                "from empty",
+                render_keyed(&[]),
+                render_keyed(&[(1, "alice"), (2, "bob")]),
+            ),
+            diff_case(
+                "to empty",
+                render_keyed(&[(1, "alice"), (2, "bob")]),
+                render_keyed(&[]),
+            ),
+            diff_case(
+                "duplicate keys",
+                render_keyed(&[(1, "alice"), (1, "bob")]),
+                render_keyed(&[(1, "bob"), (1, "alice")]),
+            ),
+            diff_case(
+                "nested",
+                render_keyed_groups(&[(1, &[(1, "alice"), (2, "bob")]), (2, &[(3, "cindy")])]),
+                render_keyed_groups(&[(2, &[(4, "dan"), (3, "cindy")]), (1, &[(2, "bobby")])]),
+            ),
+        ]),
+    );
+}
+
+type StreamChange = dyn Fn(&mut Stream<u32, &'static str>);
+
+fn render_stream(title: &str, stream: &Stream<u32, &'static str>) -> Html<()> {
+    html! {
+        <h1>{ title }</h1>
+        <ul>
+            { stream.render(|item| html! { <li>{ item }</li> }) }
+        </ul>
+    }
+}
+
+/// Streams only send their changes, so the client applies them to the rows it already has.
+#[test]
+fn stream_client_fixture() {
+    assert_client_fixture(
+        "streams.json",
+        Vec::from([
+            stream_case(
+                "append and delete",
+                &[
+                    &|stream| {
+                        stream.insert(1, "a");
+                        stream.insert(2, "b");
+                    },
+                    &|stream| {
+                        stream.insert(3, "c");
+                        stream.delete(&1);
+                    },
+                ],
+                "<h1>step 1</h1><ul><li>b</li><li>c</li></ul>",
+            ),
+            stream_case(
+                "insert at and replace",
+                &[
+                    &|stream| {
+                        stream.insert(1, "a");
+                        stream.insert(2, "b");
+                        stream.insert(3, "c");
+                    },
+                    &|stream| {
+                        stream.insert_at(1, 4, "d");
+                        stream.insert(2, "B");
+                    },
+                ],
+                "<h1>step 1</h1><ul><li>a</li><li>d</li><li>B</li><li>c</li></ul>",
+            ),
+            stream_case(
+                "prepend",
+                &[
+                    &|stream| stream.insert(1, "a"),
+                    &|stream| stream.prepend(2, "b"),
+                    &|stream| stream.prepend(3, "c"),
+                ],
+                "<h1>step 2</h1><ul><li>c</li><li>b</li><li>a</li></ul>",
+            ),
+            stream_case(
+                "reset",
+                &[
+                    &|stream| {
+                        stream.insert(1, "a");
+                        stream.insert(2, "b");
+                    },
+                    &|stream| {
+                        stream.reset();
+                        stream.insert(3, "c");
+                    },
+                ],
+                "<h1>step 1</h1><ul><li>c</li></ul>",
+            ),
+            stream_case(
+                "unchanged",
+                &[
+                    &|stream| stream.insert(1, "a"),
+                    // only the title changes
+                    &|_| (),
+                    &|stream| stream.insert(2, "b"),
+                ],
+                "<h1>step 2</h1><ul><li>a</li><li>b</li></ul>",
+            ),
+        ]),
+    );
+}
+
+/// Render the stream after each change, and diff the renders.
+fn stream_case(name: &str, changes: &[&StreamChange], html: &str) -> serde_json::Value {
+    let mut stream = Stream::new();
+    let renders = changes
+        .iter()
+        .enumerate()
+        .map(|(step, change)| {
+            change(&mut stream);
+            render_stream(&format!("step {step}"), &stream)
+        })
+        .collect::<Vec<_>>();
+    let diffs = renders
+        .windows(2)
+        .map(|pair| pair[0].diff(&pair[1]))
+        .collect::<Vec<_>>();
+
+    json!({
+        "name": name,
+        "old": renders[0],
+        "diffs": diffs,
+        "html": html,
+    })
+}
+
+/// A case of a client fixture: the `old` HTML, the `diffs` the crate sends for it and the `html`
+/// the client should render after applying them.
+fn diff_case(name: &str, old: Html<()>, new: Html<()>) -> serde_json::Value {
+    json!({
+        "name": name,
+        "old": old,
+        "diffs": [old.diff(&new)],
+        "html": new.render(),
+    })
+}
@@ -1,7 +1,30 @@
 //! JavaScript commands for performing additional kinds of actions directly in the browser.
+//!
+//! Commands can be sent from [`LiveView::update`] with [`Updated::with`], or bound to events in
+//! [`html!`] to run them in the browser without a round-trip to the server:
+//!
+//! ```
+//! use shipwright_liveview::{html, js_command, Html};
+//!
+//! let view: Html<()> = html! {
+//!     <button axm-click={ js_command::show("#menu") }>"Open menu"</button>
+//!     <nav id="menu" style="display: none">
+//!         <button axm-click={ Vec::from([js_command::hide("#menu"), js_command::focus("#open")]) }>
+//!             "Close"
+//!         </button>
+//!     </nav>
+//! };
+//! ```
+//!
+//! An event is bound either to a message or to commands, not both.
+//!
+//! [`LiveView::update`]: crate::LiveView::update
+//! [`Updated::with`]: crate::live_view::Updated::with
+//! [`html!`]: crate::html!
 
 use axum::http::Uri;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::time::Duration;
 
 /// A JavaScript command that can be sent along with view updates to perform actions
@@ -34,19 +57,90 @@ impl JsCommand {
         self.delay_ms = Some(duration.as_millis() as _);
         self
     }
+
+    pub(crate) fn is_navigate_to(&self) -> bool {
+        matches!(self.kind, JsCommandKind::NavigateTo { .. })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "t")]
 pub(crate) enum JsCommandKind {
-    NavigateTo { uri: String },
-    AddClass { selector: String, klass: String },
-    RemoveClass { selector: String, klass: String },
-    ToggleClass { selector: String, klass: String },
-    ClearValue { selector: String },
-    SetTitle { title: String },
-    HistoryPushState { uri: String },
+    NavigateTo {
+        uri: String,
+    },
+    AddClass {
+        selector: String,
+        klass: String,
+    },
+    RemoveClass {
+        selector: String,
+        klass: String,
+    },
+    ToggleClass {
+        selector: String,
+        klass: String,
+    },
+    ClearValue {
+        selector: String,
+    },
+    SetTitle {
+        title: String,
+    },
+    HistoryPushState {
+        uri: String,
+    },
+    LivePatch {
+        uri: String,
+    },
+    LiveRedirect {
+        uri: String,
+    },
+    Show {
+        selector: String,
+        display: Option<String>,
+    },
+    Hide {
+        selector: String,
+    },
+    Transition {
+        selector: String,
+        classes: String,
+        start: String,
+        end: String,
+        duration_ms: u64,
+    },
+    Focus {
+        selector: String,
+    },
+    FocusFirst {
+        selector: String,
+    },
+    Blur {
+        selector: String,
+    },
+    SetAttribute {
+        selector: String,
+        name: String,
+        value: String,
+    },
+    RemoveAttribute {
+        selector: String,
+        name: String,
+    },
+    Dispatch {
+        selector: String,
+        event: String,
+        detail: Value,
+    },
+    PushEvent {
+        event: String,
+        payload: Value,
+    },
+    SetCookie {
+        cookie: String,
+    },
 }
 
 impl From<JsCommandKind> for JsCommand {
@@ -166,3 +260,320 @@ pub fn history_push_state(uri: Uri) -> JsCommand {
     }
     .into()
 }
+
+/// Change the location without reloading the page and let the current view react to it.
+///
+/// This calls [`History.pushState`] and then [`LiveView::handle_params`] with the new URI. The
+/// view keeps its state.
+///
+/// Links can do the same by setting the `axm-patch` attribute:
+///
+/// ```text
+/// <a href="/users?page=2" axm-patch>"Next page"</a>
+/// ```
+///
+/// # Example
+///
+/// ```
+/// shipwright_liveview::js_command::live_patch("/users?page=2".parse().unwrap());
+/// ```
+///
+/// [`History.pushState`]: https://developer.mozilla.org/en-US/docs/Web/API/History/pushState
+/// [`LiveView::handle_params`]: crate::LiveView::handle_params
+pub fn live_patch(uri: Uri) -> JsCommand {
+    JsCommandKind::LivePatch {
+        uri: uri.to_string(),
+    }
+    .into()
+}
+
+/// Navigate to another live view without reloading the page.
+///
+/// This calls [`History.pushState`] and then replaces the current view with the view of the route
+/// `uri` points to, reusing the same WebSocket connection. Requires [`live_navigation`] to be set
+/// up. If the route doesn't render a live view the browser navigates to it normally.
+///
+/// Links can do the same by setting the `axm-redirect` attribute:
+///
+/// ```text
+/// <a href="/settings" axm-redirect>"Settings"</a>
+/// ```
+///
+/// # Example
+///
+/// ```
+/// shipwright_liveview::js_command::live_redirect("/settings".parse().unwrap());
+/// ```
+///
+/// [`History.pushState`]: https://developer.mozilla.org/en-US/docs/Web/API/History/pushState
+/// [`live_navigation`]: crate::navigation::live_navigation
+pub fn live_redirect(uri: Uri) -> JsCommand {
+    JsCommandKind::LiveRedirect {
+        uri: uri.to_string(),
+    }
+    .into()
+}
+
+/// Show elements matching a CSS selector.
+///
+/// This removes the inline `display` style so the element's default display, or the one from its
+/// stylesheet, applies. Use [`show_as`] to set a specific display.
+///
+/// # Example
+///
+/// ```
+/// shipwright_liveview::js_command::show("#modal");
+/// ```
+pub fn show(selector: impl Into<String>) -> JsCommand {
+    JsCommandKind::Show {
+        selector: selector.into(),
+        display: None,
+    }
+    .into()
+}
+
+/// Show elements matching a CSS selector with a specific `display`, such as `"flex"`.
+///
+/// # Example
+///
+/// ```
+/// shipwright_liveview::js_command::show_as("#toolbar", "flex");
+/// ```
+pub fn show_as(selector: impl Into<String>, display: impl Into<String>) -> JsCommand {
+    JsCommandKind::Show {
+        selector: selector.into(),
+        display: Some(display.into()),
+    }
+    .into()
+}
+
+/// Hide elements matching a CSS selector.
+///
+/// This sets `display: none`.
+///
+/// # Example
+///
+/// ```
+/// shipwright_liveview::js_command::hide("#modal");
+/// ```
+pub fn hide(selector: impl Into<String>) -> JsCommand {
+    JsCommandKind::Hide {
+        selector: selector.into(),
+    }
+    .into()
+}
+
+/// Run a CSS transition on elements matching a CSS selector.
+///
+/// See [`Transition`] for how the classes are applied.
+///
+/// # Example
+///
+/// ```
+/// use shipwright_liveview::js_command::{self, Transition};
+/// use std::time::Duration;
+///
+/// js_command::transition(
+///     "#modal",
+///     Transition::new("transition-opacity")
+///         .start("opacity-0")
+///         .end("opacity-100")
+///         .duration(Duration::from_millis(300)),
+/// );
+/// ```
+pub fn transition(selector: impl Into<String>, transition: Transition) -> JsCommand {
+    let Transition {
+        classes,
+        start,
+        end,
+        duration,
+    } = transition;
+
+    JsCommandKind::Transition {
+        selector: selector.into(),
+        classes,
+        start,
+        end,
+        duration_ms: duration.as_millis() as _,
+    }
+    .into()
+}
+
+/// A CSS transition used with [`transition`].
+///
+/// The transition classes and start classes are added first. On the next frame the start classes
+/// are replaced by the end classes, and when the duration has passed the transition classes are
+/// removed. The end classes are kept.
+///
+/// Classes are separated by spaces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    classes: String,
+    start: String,
+    end: String,
+    duration: Duration,
+}
+
+impl Transition {
+    /// Create a new transition that applies `classes` while it runs.
+    ///
+    /// The duration defaults to 200 milliseconds.
+    pub fn new(classes: impl Into<String>) -> Self {
+        Self {
+            classes: classes.into(),
+            start: String::new(),
+            end: String::new(),
+            duration: Duration::from_millis(200),
+        }
+    }
+
+    /// Set the classes applied when the transition starts.
+    pub fn start(mut self, classes: impl Into<String>) -> Self {
+        self.start = classes.into();
+        self
+    }
+
+    /// Set the classes applied when the transition ends.
+    pub fn end(mut self, classes: impl Into<String>) -> Self {
+        self.end = classes.into();
+        self
+    }
+
+    /// Set how long the transition runs.
+    ///
+    /// The duration will be rounded the nearest millisecond.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+}
+
+/// Focus the first element matching a CSS selector.
+///
+/// # Example
+///
+/// ```
+/// shipwright_liveview::js_command::focus("#search");
+/// ```
+pub fn focus(selector: impl Into<String>) -> JsCommand {
+    JsCommandKind::Focus {
+        selector: selector.into(),
+    }
+    .into()
+}
+
+/// Focus the first focusable element inside the element matching a CSS selector.
+///
+/// Useful for moving focus into a modal or menu when it opens.
+///
+/// # Example
+///
+/// ```
+/// shipwright_liveview::js_command::focus_first("#modal");
+/// ```
+pub fn focus_first(selector: impl Into<String>) -> JsCommand {
+    JsCommandKind::FocusFirst {
+        selector: selector.into(),
+    }
+    .into()
+}
+
+/// Remove focus from elements matching a CSS selector.
+///
+/// # Example
+///
+/// ```
+/// shipwright_liveview::js_command::blur("#search");
+/// ```
+pub fn blur(selector: impl Into<String>) -> JsCommand {
+    JsCommandKind::Blur {
+        selector: selector.into(),
+    }
+    .into()
+}
+
+/// Set an attribute on elements matching a CSS selector.
+///
+/// # Example
+///
+/// ```
+/// shipwright_liveview::js_command::set_attribute("#menu-button", "aria-expanded", "true");
+/// ```
+pub fn set_attribute(
+    selector: impl Into<String>,
+    name: impl Into<String>,
+    value: impl Into<String>,
+) -> JsCommand {
+    JsCommandKind::SetAttribute {
+        selector: selector.into(),
+        name: name.into(),
+        value: value.into(),
+    }
+    .into()
+}
+
+/// Remove an attribute from elements matching a CSS selector.
+///
+/// # Example
+///
+/// ```
+/// shipwright_liveview::js_command::remove_attribute("#submit", "disabled");
+/// ```
+pub fn remove_attribute(selector: impl Into<String>, name: impl Into<String>) -> JsCommand {
+    JsCommandKind::RemoveAttribute {
+        selector: selector.into(),
+        name: name.into(),
+    }
+    .into()
+}
+
+/// Dispatch a [`CustomEvent`] on elements matching a CSS selector.
+///
+/// `detail` is available as `event.detail` in event listeners.
+///
+/// # Example
+///
+/// ```
+/// use serde_json::json;
+///
+/// shipwright_liveview::js_command::dispatch("#chart", "chart:zoom", json!({ "level": 2 }));
+/// ```
+///
+/// [`CustomEvent`]: https://developer.mozilla.org/en-US/docs/Web/API/CustomEvent
+pub fn dispatch(selector: impl Into<String>, event: impl Into<String>, detail: Value) -> JsCommand {
+    JsCommandKind::Dispatch {
+        selector: selector.into(),
+        event: event.into(),
+        detail,
+    }
+    .into()
+}
+
+/// Send an event to the client hooks listening for it.
+///
+/// Hooks receive the payload in callbacks registered with `handleEvent`. Usually sent with
+/// [`Updated::push_event`].
+///
+/// # Example
+///
+/// ```
+/// use serde_json::json;
+///
+/// shipwright_liveview::js_command::push_event("points", json!([[0, 1], [1, 3]]));
+/// ```
+///
+/// [`Updated::push_event`]: crate::live_view::Updated::push_event
+pub fn push_event(event: impl Into<String>, payload: Value) -> JsCommand {
+    JsCommandKind::PushEvent {
+        event: event.into(),
+        payload,
+    }
+    .into()
+}
+
+/// Set a cookie with `document.cookie`.
+///
+/// Used to carry state, such as [flash messages](crate::flash), over to the next page load.
+pub(crate) fn set_cookie(cookie: String) -> JsCommand {
+    JsCommandKind::SetCookie { cookie }.into()
+}
@@ -174,6 +174,12 @@
 //!    [`LiveViewUpgrade::response`] will be called and a new fresh instance of your view is
 //!    created.
 //!
+//! If the WebSocket can't be opened, for example because a proxy blocks it, the client falls back
+//! to Server-Sent Events for routes wrapped with [`sse::SseLayer`]. The view runs the same way.
+//!
+//! A page can also embed several independent views with [`LiveViewUpgrade::response_many`]. They
+//! share one socket but are otherwise mounted and run separately, see the [`multiplex`] module.
+//!
 //! Take note that your endpoint is called twice. Once with a regular `GET` request and again to
 //! upgrade to a stateful WebSocket connection. You can use [`EmbedLiveView::connected`] to check
 //! whether the handler is responding to the initial `GET` request or the WebSocket upgrade
@@ -200,6 +206,109 @@
 //!
 //! See [`html`](macro.html.html) for details on all the support bindings.
 //!
+//! # Escaping
+//!
+//! Values interpolated into [`html!`](macro.html.html) templates are escaped based on where they
+//! appear. Text is HTML escaped, attribute values are additionally quote escaped, and URL
+//! attributes such as `href` and `src` only accept relative URLs and `http`, `https`, `mailto`
+//! and `tel` URLs. Use [`PreEscaped`] or [`Html::raw`] to include markup you trust as is.
+//!
+//! # Keyed loops
+//!
+//! By default the rows of a `for` loop are identified by their index, so inserting a row at the
+//! top of a list changes every row after it. Add a `key` to identify rows by a stable value
+//! instead:
+//!
+//! ```rust
+//! # use shipwright_liveview::{html, Html};
+//! struct Todo {
+//!     id: u64,
+//!     title: String,
+//! }
+//!
+//! fn render(todos: &[Todo]) -> Html<()> {
+//!     html! {
+//!         <ul>
+//!             for todo in todos key todo.id {
+//!                 <li>{ &todo.title }</li>
+//!             }
+//!         </ul>
+//!     }
+//! }
+//! ```
+//!
+//! The key can be any expression that implements [`Display`](std::fmt::Display) and must be unique
+//! within the loop. Inserting, removing and reordering rows is then sent to the client as
+//! insertions, removals and moves of the rows with those keys, and the DOM nodes of the other rows
+//! are kept. If a loop contains duplicate keys it is re-rendered entirely.
+//!
+//! # Hooks
+//!
+//! Hooks connect JavaScript widgets such as charts, maps or editors to a view. Give an element an
+//! `axm-hook` attribute naming a hook registered in the client, and an `axm-hook-event` message
+//! for the events the hook sends:
+//!
+//! ```rust
+//! # use shipwright_liveview::html;
+//! # #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+//! # enum Msg { Chart }
+//! #
+//! html! {
+//!     <div id="chart" axm-hook="Chart" axm-hook-event={ Msg::Chart }></div>
+//! };
+//! ```
+//!
+//! Hooks are registered with the client:
+//!
+//! ```javascript
+//! window.liveView.registerHook("Chart", {
+//!   mounted() {
+//!     this.chart = createChart(this.el);
+//!     this.chart.on("zoom", (level) => this.pushEvent("zoom", { level }));
+//!     this.handleEvent("points", (points) => this.chart.setData(points));
+//!   },
+//!   updated() {},
+//!   destroyed() {},
+//! });
+//! ```
+//!
+//! `mounted` is called when an element with the hook is added to the page, `updated` after each
+//! render that keeps it and `destroyed` once it is removed.
+//!
+//! When the hook calls `this.pushEvent("zoom", { level: 2 })` the view's `update` receives
+//! `Msg::Chart` along with [`EventData::Hook`] that contains the event name and payload. Going
+//! the other way, [`Updated::push_event`] sends an event to the hooks that listen for it with
+//! `this.handleEvent("points", callback)`.
+//!
+//! Hook events are sent as `{"m": <message>, "t": "hook", "d": {"e": <event>, "p": <payload>}}`
+//! and pushed events as a `push_event` [JS command](js_command).
+//!
+//! [`EventData::Hook`]: event_data::EventData::Hook
+//! [`Updated::push_event`]: live_view::Updated::push_event
+//!
+//! # Tracing and metrics
+//!
+//! Each socket runs inside a `live_view` span with the `view` type and `uri` it was mounted at.
+//! Inside it are `debug` spans for:
+//!
+//! - `mount`.
+//! - `update`, with the `msg` variant name.
+//! - `handle_params`, with the new `uri`.
+//!
+//! `update` and `handle_params` also record `update_time`, `render_time` and the size of the diff
+//! sent to the client in `diff_bytes`.
+//!
+//! With the `metrics` feature enabled the following are recorded through the [`metrics`] crate,
+//! labeled with the `view` type:
+//!
+//! - `shipwright_liveview_views_connected`: gauge of connected views.
+//! - `shipwright_liveview_messages_total`: counter of messages handled.
+//! - `shipwright_liveview_update_seconds`: histogram of update durations.
+//! - `shipwright_liveview_render_seconds`: histogram of render durations.
+//! - `shipwright_liveview_diff_bytes`: histogram of diff sizes.
+//!
+//! [`metrics`]: https://docs.rs/metrics
+//!
 //! # Pros and cons
 //!
 //! Some pros and cons to keep in mind when deciding whether axum-live-view is right for your use
@@ -263,22 +372,52 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![cfg_attr(test, allow(clippy::float_cmp))]
 
+// lets the `html!` macro be used inside the crate
+#[cfg(any(test, feature = "dashboard"))]
+extern crate self as shipwright_liveview;
+
 #[macro_use]
 mod macros;
 
+pub mod csrf;
+#[cfg(feature = "dashboard")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dashboard")))]
+pub mod dashboard;
 pub mod event_data;
 pub mod extract;
+pub mod flash;
 pub mod js_command;
+pub mod limits;
 pub mod live_view;
+pub mod multiplex;
+pub mod navigation;
+pub mod on_mount;
+pub mod presence;
+pub mod pubsub;
+pub mod shutdown;
+pub mod signing;
+pub mod sse;
+pub mod stream;
 pub mod test;
+pub mod transport;
+pub mod upload;
 
 pub mod diff;
+mod codec;
 mod html;
 mod life_cycle;
+mod message_handles;
+mod registry;
+mod socket;
+mod telemetry;
 mod util;
 
 #[doc(inline)]
-pub use self::{extract::LiveViewUpgrade, html::Html, live_view::LiveView};
+pub use self::{
+    extract::LiveViewUpgrade,
+    html::{Html, PreEscaped},
+    live_view::LiveView,
+};
 
 #[doc = include_str!("docs/html.md")]
 pub use shipwright_liveview_macros::html;
@@ -1,14 +1,24 @@
 use crate::{
     event_data::EventData,
+    extract::ViewOptions,
+    flash::Flash,
     html::Html,
-    js_command::JsCommand,
-    live_view::{Updated, ViewHandle},
+    js_command::{self, JsCommand},
+    live_view::{
+        AsyncLiveView, ErrorAction, ErrorPolicy, PendingMessages, ShutdownReason, Updated,
+        ViewError, ViewHandle,
+    },
+    message_handles::MessageHandles,
+    registry,
+    telemetry::{self, ConnectedView},
+    upload::{ActiveUploads, UploadChunk, UploadError, UploadStart},
     util::ReceiverStream,
-    LiveView,
 };
 use futures_util::{
+    future::{Either, FutureExt},
+    ready,
     sink::{Sink, SinkExt},
-    stream::StreamExt,
+    stream::{FusedStream, Stream, StreamExt},
     TryStream, TryStreamExt,
 };
 use http::{HeaderMap, Uri};
@@ -17,29 +27,58 @@ use serde::{
     Deserialize, Serialize,
 };
 use serde_json::Value;
-use std::{fmt, marker::PhantomData};
-use tokio::sync::{mpsc, oneshot};
+use std::{
+    collections::VecDeque, fmt, marker::PhantomData, panic::AssertUnwindSafe, sync::Arc,
+    task::Poll, time::Duration,
+};
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::Instant,
+};
+use tracing::{field, Instrument};
 
 /// Type used to embed live views in HTML templates.
 pub struct EmbedLiveView<'a, L> {
     view: Option<&'a mut Option<L>>,
+    csrf_token: Option<String>,
+    protect_messages: bool,
 }
 
 impl<'a, L> EmbedLiveView<'a, L> {
-    pub(crate) fn noop() -> Self {
-        Self { view: None }
+    pub(crate) fn http(csrf_token: String, protect_messages: bool) -> Self {
+        Self {
+            view: None,
+            csrf_token: Some(csrf_token),
+            protect_messages,
+        }
     }
 
     pub(crate) fn new(view: &'a mut Option<L>) -> Self {
-        Self { view: Some(view) }
+        Self {
+            view: Some(view),
+            csrf_token: None,
+            protect_messages: false,
+        }
     }
 
     /// Embed a live view in a HTML template.
+    ///
+    /// When rendering over HTTP this also embeds the [CSRF token](crate::csrf) the client needs
+    /// to connect.
     pub fn embed(self, view: L) -> Html<L::Message>
     where
-        L: LiveView,
+        L: AsyncLiveView,
     {
-        let html = wrap_in_live_view_container(view.render());
+        let mut markup = view.render();
+        if self.protect_messages {
+            // the handles are only used until the socket connects and renders the view again
+            markup = MessageHandles::new().protect(markup);
+        }
+
+        let html = match self.csrf_token {
+            Some(csrf_token) => wrap_in_live_view_container_with_csrf_token(markup, csrf_token),
+            None => wrap_in_live_view_container(markup),
+        };
 
         if let Some(view_handle) = self.view {
             *view_handle = Some(view);
@@ -64,27 +103,86 @@ impl<'a, M> fmt::Debug for EmbedLiveView<'a, M> {
     }
 }
 
+/// Why [`run_view`] stopped.
+#[derive(Debug)]
+pub(crate) enum ViewExit {
+    /// The socket was closed or the view shut down.
+    Closed,
+    /// The client asked to navigate to another live view over the same socket.
+    LiveRedirect(Uri),
+}
+
+#[tracing::instrument(
+    name = "live_view",
+    skip_all,
+    fields(view = telemetry::view_name::<L>(), uri = %uri),
+)]
 pub(crate) async fn run_view<W, R, L>(
     mut write: W,
     read: R,
     view: L,
     uri: Uri,
     headers: HeaderMap,
-) -> Result<(), String>
+    options: &ViewOptions,
+    message_handles: Option<Arc<MessageHandles>>,
+) -> Result<ViewExit, String>
 where
-    L: LiveView,
+    L: AsyncLiveView,
     W: Sink<MessageToSocket> + Unpin,
     W::Error: fmt::Display + Send + Sync + 'static,
     R: TryStream<Ok = MessageFromSocket<L::Message>> + Unpin,
     R::Error: fmt::Display + Send + Sync + 'static,
 {
     let (handle, rx) = ViewHandle::new();
+    let handle = handle.with_extensions(options.extensions.clone());
+    let mut uploads = ActiveUploads::new(handle.uploads().clone());
 
-    let view = spawn_view(view, Some(handle.clone()));
-
-    view.mount(uri, headers, handle)
-        .await
-        .map_err(|err| err.to_string())?;
+    let rx_stream = ReceiverStream::new(rx).map(|msg| {
+        Ok(MessageFromSocket::Event {
+            msg,
+            data: EventMessageFromSocketData::ViewHandle,
+        })
+    });
+    let registration = registry::register(telemetry::view_name::<L>(), &uri);
+    // part of the socket's stream so it still ends when the socket is closed
+    let mut read = read.into_stream().fuse();
+    let mut disconnected = Box::pin(registration.disconnected().fuse());
+    let read = futures_util::stream::poll_fn(move |cx| {
+        if disconnected.poll_unpin(cx).is_ready() {
+            return Poll::Ready(Some(Ok(MessageFromSocket::Shutdown(
+                ShutdownReason::Disconnected,
+            ))));
+        }
+        if read.is_terminated() {
+            return Poll::Ready(None);
+        }
+        // the view's own messages keep the merged stream open, so the end of the socket's stream
+        // has to stop the view explicitly
+        match ready!(read.poll_next_unpin(cx)) {
+            Some(msg) => Poll::Ready(Some(msg)),
+            None => Poll::Ready(Some(Ok(MessageFromSocket::Shutdown(
+                ShutdownReason::Closed,
+            )))),
+        }
+    });
+    let mut stream = crate::util::StreamExt::merge(read, rx_stream);
+
+    let view = spawn_view(
+        view,
+        Some(handle.clone()),
+        options.flash.clone(),
+        message_handles,
+    );
+
+    match view.mount(uri, headers, handle).await {
+        Ok(()) => {}
+        Err(ViewRequestError::View(err)) => {
+            registration.record_error(&err);
+            return handle_view_error(&mut write, &mut stream, &*options.error_policy, err).await;
+        }
+        Err(err) => return Err(err.to_string()),
+    }
+    let _connected = ConnectedView::new(telemetry::view_name::<L>());
 
     let markup = view.render().await.map_err(|err| err.to_string())?;
 
@@ -92,148 +190,627 @@ where
         .await
         .map_err(|err| err.to_string())?;
 
-    let rx_stream = ReceiverStream::new(rx).map(|msg| {
-        Ok(MessageFromSocket::Event {
-            msg,
-            data: EventMessageFromSocketData::None,
-        })
-    });
-    let mut stream = crate::util::StreamExt::merge(read.into_stream(), rx_stream);
-
-    loop {
-        let msg = match stream.next().await {
-            Some(Ok(msg)) => msg,
-            Some(Err(err)) => {
-                let err = err.to_string();
-                tracing::trace!(%err, "error from socket");
-                break;
-            }
-            None => {
-                tracing::trace!("no more messages on socket");
-                break;
+    let mut pending = PendingQueue::new(L::PENDING_MESSAGES);
+
+    let reason = loop {
+        let msg = if let Some(msg) = pending.pop() {
+            msg
+        } else {
+            match stream.next().await {
+                Some(Ok(msg)) => msg,
+                Some(Err(err)) => {
+                    let err = err.to_string();
+                    tracing::trace!(%err, "error from socket");
+                    break ShutdownReason::Closed;
+                }
+                None => {
+                    tracing::trace!("no more messages on socket");
+                    break ShutdownReason::Closed;
+                }
             }
         };
 
-        match msg {
+        let update = match msg {
             MessageFromSocket::Event {
                 msg: msg_for_view,
                 data,
             } => {
                 let data = Option::<EventData>::from(data);
-
-                match view
-                    .update(msg_for_view, data)
+                registration.record_message();
+                Either::Left(view.update(msg_for_view, data))
+            }
+            MessageFromSocket::Internal {
+                data: InternalMessageFromSocketData::Health,
+            } => {
+                write_message(&mut write, MessageToSocketData::Health)
                     .await
-                    .map_err(|err| err.to_string())?
-                {
-                    UpdateResponse::Diff(diff) => {
-                        write_message(&mut write, MessageToSocketData::Render(diff))
-                            .await
-                            .map_err(|err| err.to_string())?;
+                    .map_err(|err| err.to_string())?;
+                continue;
+            }
+            MessageFromSocket::UploadChunk(chunk) => {
+                uploads.write(chunk).await;
+                continue;
+            }
+            MessageFromSocket::Internal {
+                data: InternalMessageFromSocketData::UploadStart(start),
+            } => {
+                start_upload(&mut write, &mut uploads, start).await?;
+                continue;
+            }
+            MessageFromSocket::Internal {
+                data: InternalMessageFromSocketData::UploadCancel { id },
+            } => {
+                uploads.cancel(id);
+                continue;
+            }
+            MessageFromSocket::Internal {
+                data: InternalMessageFromSocketData::LivePatch { uri },
+            } => match uri.parse() {
+                Ok(uri) => Either::Right(view.handle_params(uri)),
+                Err(err) => {
+                    tracing::debug!(%err, %uri, "invalid URI in live patch");
+                    continue;
+                }
+            },
+            MessageFromSocket::Internal {
+                data: InternalMessageFromSocketData::LiveRedirect { uri },
+            } => match uri.parse() {
+                Ok(uri) => {
+                    view.terminate(ShutdownReason::LiveRedirect).await;
+                    return Ok(ViewExit::LiveRedirect(uri));
+                }
+                Err(err) => {
+                    tracing::debug!(%err, %uri, "invalid URI in live redirect");
+                    continue;
+                }
+            },
+            MessageFromSocket::Shutdown(reason) => break reason,
+        };
+        futures_util::pin_mut!(update);
+
+        // keep reading from the socket while the update is running so health checks are
+        // answered and `PendingMessages` can be applied to new messages
+        let mut closed = None;
+        let response = loop {
+            tokio::select! {
+                response = &mut update => break response,
+                msg = stream.next(), if closed.is_none() => match msg {
+                    Some(Ok(MessageFromSocket::Event { msg, data })) => {
+                        pending.push(msg, data);
                     }
-                    UpdateResponse::JsCommands(commands) => {
-                        write_message(&mut write, MessageToSocketData::JsCommands(commands))
+                    Some(Ok(MessageFromSocket::Internal {
+                        data: InternalMessageFromSocketData::Health,
+                    })) => {
+                        write_message(&mut write, MessageToSocketData::Health)
                             .await
                             .map_err(|err| err.to_string())?;
                     }
-                    UpdateResponse::DiffAndJsCommands(diff, commands) => {
-                        write_message(&mut write, MessageToSocketData::Render(diff))
-                            .await
-                            .map_err(|err| err.to_string())?;
-                        write_message(&mut write, MessageToSocketData::JsCommands(commands))
+                    // uploads don't touch the view so they're handled right away
+                    Some(Ok(MessageFromSocket::UploadChunk(chunk))) => {
+                        uploads.write(chunk).await;
+                    }
+                    Some(Ok(MessageFromSocket::Internal {
+                        data: InternalMessageFromSocketData::UploadStart(start),
+                    })) => {
+                        start_upload(&mut write, &mut uploads, start).await?;
+                    }
+                    Some(Ok(MessageFromSocket::Internal {
+                        data: InternalMessageFromSocketData::UploadCancel { id },
+                    })) => {
+                        uploads.cancel(id);
+                    }
+                    Some(Ok(MessageFromSocket::Internal { data })) => {
+                        pending.push_internal(data);
+                    }
+                    Some(Ok(MessageFromSocket::Shutdown(reason))) => {
+                        closed = Some(reason);
+                    }
+                    Some(Err(err)) => {
+                        let err = err.to_string();
+                        tracing::trace!(%err, "error from socket");
+                        closed = Some(ShutdownReason::Closed);
+                    }
+                    None => {
+                        tracing::trace!("no more messages on socket");
+                        closed = Some(ShutdownReason::Closed);
+                    }
+                },
+            }
+        };
+
+        if closed == Some(ShutdownReason::Closed) {
+            break ShutdownReason::Closed;
+        }
+
+        let response = match response {
+            Ok(response) => response,
+            Err(ViewRequestError::View(err)) => {
+                registration.record_error(&err);
+                match closed {
+                    // the socket is closed next anyway so the error policy doesn't apply
+                    Some(reason) => {
+                        tracing::error!(%err, "live view failed");
+                        break reason;
+                    }
+                    None => {
+                        return handle_view_error(
+                            &mut write,
+                            &mut stream,
+                            &*options.error_policy,
+                            err,
+                        )
+                        .await;
+                    }
+                }
+            }
+            Err(err) => return Err(err.to_string()),
+        };
+        if let UpdateResponse::Diff(diff) | UpdateResponse::DiffAndJsCommands(diff, _) = &response {
+            registration.record_diff(diff);
+        }
+
+        // the socket is still open if the server is shutting the view down, so the client gets
+        // the result of the update
+        write_update_response(&mut write, response)
+            .await
+            .map_err(|err| err.to_string())?;
+
+        if let Some(reason) = closed {
+            break reason;
+        }
+    };
+
+    view.terminate(reason).await;
+    close_for_reason(&mut write, reason).await?;
+
+    Ok(ViewExit::Closed)
+}
+
+/// Tell the client why the socket is closed if it timed out or was disconnected.
+async fn close_for_reason<W>(write: &mut W, reason: ShutdownReason) -> Result<(), String>
+where
+    W: Sink<MessageToSocket> + Unpin,
+    W::Error: fmt::Display,
+{
+    let frame = match reason {
+        ShutdownReason::HeartbeatTimeout => CloseFrame {
+            code: close_code::HEARTBEAT_TIMEOUT,
+            reason: "heartbeat timeout",
+        },
+        ShutdownReason::IdleTimeout => CloseFrame {
+            code: close_code::IDLE_TIMEOUT,
+            reason: "idle timeout",
+        },
+        ShutdownReason::Disconnected => CloseFrame {
+            code: close_code::DISCONNECTED,
+            reason: "disconnected",
+        },
+        // the going away frame depends on the `Shutdown` so it is sent by the caller
+        ShutdownReason::Closed | ShutdownReason::LiveRedirect | ShutdownReason::ServerShutdown => {
+            return Ok(())
+        }
+    };
+
+    write_message(write, MessageToSocketData::Close(frame))
+        .await
+        .map_err(|err| err.to_string())
+}
+
+/// Start an upload and tell the client whether it was accepted.
+async fn start_upload<W>(
+    write: &mut W,
+    uploads: &mut ActiveUploads,
+    start: UploadStart,
+) -> Result<(), String>
+where
+    W: Sink<MessageToSocket> + Unpin,
+    W::Error: fmt::Display,
+{
+    let id = start.id();
+    let error = uploads.start(start).await.err();
+    write_message(
+        write,
+        MessageToSocketData::Upload(UploadReply { id, error }),
+    )
+    .await
+    .map_err(|err| err.to_string())
+}
+
+/// Handle a failed view according to the error policy.
+///
+/// The view task has shut down by the time this is called.
+async fn handle_view_error<W, S, M, E>(
+    write: &mut W,
+    stream: &mut S,
+    error_policy: &dyn ErrorPolicy,
+    err: ViewError,
+) -> Result<ViewExit, String>
+where
+    W: Sink<MessageToSocket> + Unpin,
+    W::Error: fmt::Display,
+    S: Stream<Item = Result<MessageFromSocket<M>, E>> + Unpin,
+    M: DeserializeOwned,
+    E: fmt::Display,
+{
+    tracing::error!(%err, "live view failed");
+
+    match error_policy.on_error(&err) {
+        ErrorAction::Render(html) => {
+            let markup = serde_json::to_value(wrap_in_live_view_container(html))
+                .expect("failed to serialize HTML");
+            write_message(write, MessageToSocketData::InitialRender(markup))
+                .await
+                .map_err(|err| err.to_string())?;
+
+            // keep the socket open so the client doesn't reconnect and replace the error boundary
+            // with a fresh view
+            while let Some(msg) = stream.next().await {
+                match msg {
+                    Ok(MessageFromSocket::Internal {
+                        data: InternalMessageFromSocketData::Health,
+                    }) => {
+                        write_message(write, MessageToSocketData::Health)
                             .await
                             .map_err(|err| err.to_string())?;
                     }
-                    UpdateResponse::Empty => {}
+                    Ok(MessageFromSocket::Internal {
+                        data: InternalMessageFromSocketData::LiveRedirect { uri },
+                    }) => {
+                        if let Ok(uri) = uri.parse() {
+                            return Ok(ViewExit::LiveRedirect(uri));
+                        }
+                    }
+                    Ok(MessageFromSocket::Shutdown(reason)) => {
+                        close_for_reason(write, reason).await?;
+                        break;
+                    }
+                    Ok(
+                        MessageFromSocket::Event { .. }
+                        | MessageFromSocket::Internal { .. }
+                        | MessageFromSocket::UploadChunk(_),
+                    ) => {
+                        tracing::trace!("ignoring message for failed view");
+                    }
+                    Err(err) => {
+                        let err = err.to_string();
+                        tracing::trace!(%err, "error from socket");
+                        break;
+                    }
                 }
             }
-            MessageFromSocket::Internal {
-                data: InternalMessageFromSocketData::Health,
-            } => {
-                write_message(&mut write, MessageToSocketData::Health)
-                    .await
-                    .map_err(|err| err.to_string())?;
+        }
+        ErrorAction::Remount => {
+            write_message(
+                write,
+                MessageToSocketData::Close(CloseFrame {
+                    code: close_code::REMOUNT,
+                    reason: "remount",
+                }),
+            )
+            .await
+            .map_err(|err| err.to_string())?;
+        }
+        ErrorAction::Close { message } => {
+            write_message(
+                write,
+                MessageToSocketData::Error(ErrorFrame {
+                    code: close_code::VIEW_ERROR,
+                    message,
+                }),
+            )
+            .await
+            .map_err(|err| err.to_string())?;
+            write_message(
+                write,
+                MessageToSocketData::Close(CloseFrame {
+                    code: close_code::VIEW_ERROR,
+                    reason: "view error",
+                }),
+            )
+            .await
+            .map_err(|err| err.to_string())?;
+        }
+    }
+
+    Ok(ViewExit::Closed)
+}
+
+/// Messages that arrived while an update was running.
+struct PendingQueue<M>
+where
+    M: DeserializeOwned,
+{
+    policy: PendingMessages,
+    queue: VecDeque<MessageFromSocket<M>>,
+}
+
+impl<M> PendingQueue<M>
+where
+    M: DeserializeOwned + PartialEq,
+{
+    fn new(policy: PendingMessages) -> Self {
+        Self {
+            policy,
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Queue an event according to the policy. Messages sent through a [`ViewHandle`] are always
+    /// queued, the policy only applies to events from the client.
+    fn push(&mut self, msg: M, data: EventMessageFromSocketData) {
+        if matches!(data, EventMessageFromSocketData::ViewHandle) {
+            self.queue.push_back(MessageFromSocket::Event { msg, data });
+            return;
+        }
+
+        match self.policy {
+            PendingMessages::Queue => self.queue.push_back(MessageFromSocket::Event { msg, data }),
+            PendingMessages::Drop => {
+                tracing::trace!("dropping message received while update was pending");
+            }
+            PendingMessages::Coalesce => {
+                // only coalesced with other events from the client
+                let already_queued = self.queue.iter().any(|queued| {
+                    matches!(
+                        queued,
+                        MessageFromSocket::Event { msg: queued, data }
+                            if *queued == msg
+                                && !matches!(data, EventMessageFromSocketData::ViewHandle)
+                    )
+                });
+
+                if already_queued {
+                    tracing::trace!("coalescing message received while update was pending");
+                } else {
+                    self.queue.push_back(MessageFromSocket::Event { msg, data });
+                }
             }
         }
     }
 
+    /// Queue an internal message. These are never dropped.
+    fn push_internal(&mut self, data: InternalMessageFromSocketData) {
+        self.queue.push_back(MessageFromSocket::Internal { data });
+    }
+
+    fn pop(&mut self) -> Option<MessageFromSocket<M>> {
+        self.queue.pop_front()
+    }
+}
+
+async fn write_update_response<W>(write: &mut W, response: UpdateResponse) -> Result<(), W::Error>
+where
+    W: Sink<MessageToSocket> + Unpin,
+{
+    match response {
+        UpdateResponse::Diff(diff) => {
+            write_message(write, MessageToSocketData::Render(diff)).await?;
+        }
+        UpdateResponse::JsCommands(commands) => {
+            write_message(write, MessageToSocketData::JsCommands(commands)).await?;
+        }
+        UpdateResponse::DiffAndJsCommands(diff, commands) => {
+            write_message(write, MessageToSocketData::Render(diff)).await?;
+            write_message(write, MessageToSocketData::JsCommands(commands)).await?;
+        }
+        UpdateResponse::Empty => {}
+    }
     Ok(())
 }
 
 pub(crate) fn spawn_view<L>(
     mut view: L,
     view_handle: Option<ViewHandle<L::Message>>,
+    flash: Flash,
+    message_handles: Option<Arc<MessageHandles>>,
 ) -> ViewTaskHandle<L::Message>
 where
-    L: LiveView,
+    L: AsyncLiveView,
 {
-    let (tx, mut rx) = mpsc::channel(1024);
-
-    crate::util::spawn_unit(async move {
-        let mut markup = wrap_in_live_view_container(view.render());
+    let (tx, mut rx) = mpsc::channel::<ViewRequest<L::Message>>(1024);
+    let view_name = telemetry::view_name::<L>();
+
+    let task = async move {
+        let mut markup = match render_view(&view, message_handles.as_deref()) {
+            Ok(markup) => markup,
+            Err(err) => {
+                if let Some(request) = rx.recv().await {
+                    request.fail(err);
+                }
+                return;
+            }
+        };
 
         while let Some(request) = rx.recv().await {
-            match request {
+            let started = Instant::now();
+
+            // updates and param changes share everything after the call into the view
+            let (result, reply_tx, span) = match request {
                 ViewRequest::Mount {
                     uri,
                     headers,
                     handle,
                     reply_tx,
                 } => {
-                    let _ = reply_tx.send(view.mount(uri, &headers, handle));
+                    let span = tracing::debug_span!("mount");
+                    let result = AssertUnwindSafe(view.mount(uri, &headers, handle))
+                        .catch_unwind()
+                        .instrument(span)
+                        .await
+                        .unwrap_or_else(|panic| Err(ViewError::from_panic(panic)))
+                        // mounting may have changed the view, which the initial render must show
+                        .and_then(|()| {
+                            markup = render_view(&view, message_handles.as_deref())?;
+                            Ok(())
+                        });
+
+                    let failed = result.is_err();
+                    let _ = reply_tx.send(result);
+                    if failed {
+                        break;
+                    }
+                    continue;
                 }
                 ViewRequest::Render { reply_tx } => {
                     let _ = reply_tx
                         .send(serde_json::to_value(&markup).expect("failed to serialize HTML"));
+                    continue;
                 }
                 ViewRequest::RenderToString { reply_tx } => {
                     let _ = reply_tx.send(markup.render());
+                    continue;
+                }
+                ViewRequest::Terminate { reason, reply_tx } => {
+                    if let Err(panic) = AssertUnwindSafe(view.terminate(reason))
+                        .catch_unwind()
+                        .await
+                    {
+                        let err = ViewError::from_panic(panic);
+                        tracing::error!(%err, "live view panicked while terminating");
+                    }
+                    let _ = reply_tx.send(());
+                    break;
                 }
                 ViewRequest::Update {
                     msg,
                     reply_tx,
                     event_data,
                 } => {
-                    let Updated {
-                        live_view: new_view,
-                        js_commands,
-                        spawns,
-                    } = view.update(msg, event_data);
-
-                    if let Some(view_handle) = &view_handle {
-                        for future in spawns {
-                            let view_handle = view_handle.clone();
-                            crate::util::spawn_unit(async move {
-                                let msg = future.await;
-                                let _ = view_handle.send(msg).await;
-                            });
-                        }
-                    }
-
-                    view = new_view;
-
-                    let new_markup = wrap_in_live_view_container(view.render());
-                    let diff = markup.diff(&new_markup).map(|diff| {
-                        serde_json::to_value(&diff).expect("failed to serialize HTML diff")
+                    let span = tracing::debug_span!(
+                        "update",
+                        msg = telemetry::message_name(&msg),
+                        update_time = field::Empty,
+                        render_time = field::Empty,
+                        diff_bytes = field::Empty,
+                    );
+                    let result = AssertUnwindSafe(view.update(msg, event_data))
+                        .catch_unwind()
+                        .instrument(span.clone())
+                        .await
+                        .unwrap_or_else(|panic| Err(ViewError::from_panic(panic)));
+                    (result, reply_tx, span)
+                }
+                ViewRequest::HandleParams { uri, reply_tx } => {
+                    let span = tracing::debug_span!(
+                        "handle_params",
+                        uri = %uri,
+                        update_time = field::Empty,
+                        render_time = field::Empty,
+                        diff_bytes = field::Empty,
+                    );
+                    let result = AssertUnwindSafe(view.handle_params(uri))
+                        .catch_unwind()
+                        .instrument(span.clone())
+                        .await
+                        .unwrap_or_else(|panic| Err(ViewError::from_panic(panic)));
+                    (result, reply_tx, span)
+                }
+            };
+
+            let update_time = started.elapsed();
+            span.record("update_time", field::debug(update_time));
+            telemetry::record_message(view_name);
+            let _enter = span.enter();
+
+            let Updated {
+                live_view: new_view,
+                mut js_commands,
+                spawns,
+                flash: flash_changes,
+            } = match result {
+                Ok(updated) => updated,
+                Err(err) => {
+                    let _ = reply_tx.send(Err(err));
+                    break;
+                }
+            };
+
+            if let Some(view_handle) = &view_handle {
+                for future in spawns {
+                    let view_handle = view_handle.clone();
+                    crate::util::spawn_unit(async move {
+                        let msg = future.await;
+                        let _ = view_handle.send(msg).await;
                     });
-                    markup = new_markup;
+                }
+            }
 
-                    let response = match (diff, js_commands.is_empty()) {
-                        (None, true) => UpdateResponse::Empty,
-                        (None, false) => UpdateResponse::JsCommands(js_commands),
-                        (Some(diff), true) => UpdateResponse::Diff(diff),
-                        (Some(diff), false) => UpdateResponse::DiffAndJsCommands(diff, js_commands),
-                    };
+            view = new_view;
 
-                    let _ = reply_tx.send(response);
+            flash.apply(flash_changes);
+            carry_flash_over_navigation(&flash, &mut js_commands);
+
+            let started = Instant::now();
+            let new_markup = match render_view(&view, message_handles.as_deref()) {
+                Ok(markup) => markup,
+                Err(err) => {
+                    let _ = reply_tx.send(Err(err));
+                    break;
                 }
+            };
+            let diff = markup
+                .diff(&new_markup)
+                .map(|diff| serde_json::to_value(&diff).expect("failed to serialize HTML diff"));
+            markup = new_markup;
+            let render_time = started.elapsed();
+            span.record("render_time", field::debug(render_time));
+
+            // only measured when someone is looking since it serializes the diff again
+            let diff_bytes = diff
+                .as_ref()
+                .filter(|_| cfg!(feature = "metrics") || !span.is_disabled())
+                .map(telemetry::diff_bytes);
+            if let Some(bytes) = diff_bytes {
+                span.record("diff_bytes", bytes);
             }
+            telemetry::record_update(view_name, update_time, render_time, diff_bytes);
+
+            let response = match (diff, js_commands.is_empty()) {
+                (None, true) => UpdateResponse::Empty,
+                (None, false) => UpdateResponse::JsCommands(js_commands),
+                (Some(diff), true) => UpdateResponse::Diff(diff),
+                (Some(diff), false) => UpdateResponse::DiffAndJsCommands(diff, js_commands),
+            };
+
+            let _ = reply_tx.send(Ok(response));
         }
-    });
+    };
+
+    // the view's spans are nested in the span of the socket it runs on
+    crate::util::spawn_unit(task.in_current_span());
 
     ViewTaskHandle { tx }
 }
 
+/// Store the flash in a cookie before the client navigates away, so the next page can show it.
+fn carry_flash_over_navigation(flash: &Flash, js_commands: &mut Vec<JsCommand>) {
+    if flash.is_empty() {
+        return;
+    }
+
+    if let Some(idx) = js_commands.iter().position(JsCommand::is_navigate_to) {
+        js_commands.insert(idx, js_command::set_cookie(flash.cookie(false)));
+    }
+}
+
+fn render_view<L>(
+    view: &L,
+    message_handles: Option<&MessageHandles>,
+) -> Result<Html<L::Message>, ViewError>
+where
+    L: AsyncLiveView,
+{
+    std::panic::catch_unwind(AssertUnwindSafe(|| {
+        let markup = view.render();
+        let markup = match message_handles {
+            Some(message_handles) => message_handles.protect(markup),
+            None => markup,
+        };
+        wrap_in_live_view_container(markup)
+    }))
+    .map_err(ViewError::from_panic)
+}
+
 pub(crate) struct ViewTaskHandle<M> {
     tx: mpsc::Sender<ViewRequest<M>>,
 }
@@ -276,7 +853,7 @@ impl<M> ViewTaskHandle<M> {
             .map_err(|_| ViewRequestError::ChannelClosed(ChannelClosed))?;
 
         match reply_rx.await {
-            Ok(()) => Ok(()),
+            Ok(result) => result.map_err(ViewRequestError::View),
             Err(_) => Err(ViewRequestError::ChannelClosed(ChannelClosed)),
         }
     }
@@ -320,7 +897,36 @@ impl<M> ViewTaskHandle<M> {
             .map_err(|_| ViewRequestError::ChannelClosed(ChannelClosed))?;
 
         match reply_rx.await {
-            Ok(updated) => Ok(updated),
+            Ok(result) => result.map_err(ViewRequestError::View),
+            Err(_) => Err(ViewRequestError::ChannelClosed(ChannelClosed)),
+        }
+    }
+
+    /// Call [`AsyncLiveView::terminate`] and shut the view down.
+    ///
+    /// Does nothing if the view has already shut down.
+    pub(crate) async fn terminate(&self, reason: ShutdownReason) {
+        let (reply_tx, reply_rx) = oneshot::channel();
+
+        let request = ViewRequest::Terminate { reason, reply_tx };
+
+        if self.tx.send(request).await.is_ok() {
+            let _ = reply_rx.await;
+        }
+    }
+
+    pub(crate) async fn handle_params(&self, uri: Uri) -> Result<UpdateResponse, ViewRequestError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+
+        let request = ViewRequest::HandleParams { uri, reply_tx };
+
+        self.tx
+            .send(request)
+            .await
+            .map_err(|_| ViewRequestError::ChannelClosed(ChannelClosed))?;
+
+        match reply_rx.await {
+            Ok(result) => result.map_err(ViewRequestError::View),
             Err(_) => Err(ViewRequestError::ChannelClosed(ChannelClosed)),
         }
     }
@@ -331,7 +937,7 @@ enum ViewRequest<M> {
         uri: Uri,
         headers: HeaderMap,
         handle: ViewHandle<M>,
-        reply_tx: oneshot::Sender<()>,
+        reply_tx: oneshot::Sender<Result<(), ViewError>>,
     },
     Render {
         reply_tx: oneshot::Sender<Value>,
@@ -342,19 +948,44 @@ enum ViewRequest<M> {
     Update {
         msg: M,
         event_data: Option<EventData>,
-        reply_tx: oneshot::Sender<UpdateResponse>,
+        reply_tx: oneshot::Sender<Result<UpdateResponse, ViewError>>,
     },
+    HandleParams {
+        uri: Uri,
+        reply_tx: oneshot::Sender<Result<UpdateResponse, ViewError>>,
+    },
+    Terminate {
+        reason: ShutdownReason,
+        reply_tx: oneshot::Sender<()>,
+    },
+}
+
+impl<M> ViewRequest<M> {
+    /// Reply to the request with an error, if it can fail.
+    fn fail(self, err: ViewError) {
+        match self {
+            Self::Mount { reply_tx, .. } => {
+                let _ = reply_tx.send(Err(err));
+            }
+            Self::Update { reply_tx, .. } | Self::HandleParams { reply_tx, .. } => {
+                let _ = reply_tx.send(Err(err));
+            }
+            Self::Render { .. } | Self::RenderToString { .. } | Self::Terminate { .. } => {}
+        }
+    }
 }
 
 #[derive(Debug)]
 pub(crate) enum ViewRequestError {
     ChannelClosed(ChannelClosed),
+    View(ViewError),
 }
 
 impl fmt::Display for ViewRequestError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::ChannelClosed(err) => err.fmt(f),
+            Self::View(err) => err.fmt(f),
         }
     }
 }
@@ -363,6 +994,7 @@ impl std::error::Error for ViewRequestError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Self::ChannelClosed(_) => None,
+            Self::View(err) => err.source().map(|err| err as _),
         }
     }
 }
@@ -391,6 +1023,65 @@ pub(crate) struct MessageToSocket {
     data: MessageToSocketData,
 }
 
+impl MessageToSocket {
+    pub(crate) fn js_commands(commands: Vec<JsCommand>) -> Self {
+        Self {
+            data: MessageToSocketData::JsCommands(commands),
+        }
+    }
+
+    pub(crate) fn going_away(reconnect_after: Duration) -> Self {
+        Self {
+            data: MessageToSocketData::GoingAway(GoingAwayFrame {
+                reconnect_after: reconnect_after.as_millis() as u64,
+            }),
+        }
+    }
+
+    pub(crate) fn close(code: u16, reason: &'static str) -> Self {
+        Self {
+            data: MessageToSocketData::Close(CloseFrame { code, reason }),
+        }
+    }
+
+    /// If this message means the socket should be closed, get the close code and reason.
+    pub(crate) fn close_frame(&self) -> Option<(u16, &'static str)> {
+        match &self.data {
+            MessageToSocketData::Close(CloseFrame { code, reason }) => Some((*code, reason)),
+            _ => None,
+        }
+    }
+}
+
+/// Close codes used when the server closes the socket.
+pub(crate) mod close_code {
+    /// The view failed and was closed by the error policy.
+    pub(crate) const VIEW_ERROR: u16 = 4000;
+    /// The view failed and the client should reconnect to mount a fresh instance.
+    pub(crate) const REMOUNT: u16 = 4001;
+    /// The socket failed the [CSRF checks](crate::csrf) and the client should reload the page.
+    pub(crate) const FORBIDDEN: u16 = 4002;
+    /// The client exceeded the [socket limits](crate::limits). This is the standard WebSocket
+    /// policy violation code.
+    pub(crate) const POLICY_VIOLATION: u16 = 1008;
+    /// Nothing was received within the [heartbeat timeout]. If the client is still there it
+    /// reconnects.
+    ///
+    /// [heartbeat timeout]: crate::limits::SocketLimits::heartbeat_timeout
+    pub(crate) const HEARTBEAT_TIMEOUT: u16 = 4003;
+    /// The user was idle for the [idle timeout]. The client reconnects once they interact with
+    /// the page again.
+    ///
+    /// [idle timeout]: crate::limits::SocketLimits::idle_timeout
+    pub(crate) const IDLE_TIMEOUT: u16 = 4004;
+    /// The socket was disconnected from the [dashboard](crate::dashboard). The client doesn't
+    /// reconnect.
+    pub(crate) const DISCONNECTED: u16 = 4005;
+    /// The [server is shutting down](crate::shutdown). This is the standard WebSocket going away
+    /// code.
+    pub(crate) const GOING_AWAY: u16 = 1001;
+}
+
 #[derive(Serialize)]
 #[serde(tag = "t", content = "d")]
 enum MessageToSocketData {
@@ -402,6 +1093,42 @@ enum MessageToSocketData {
     JsCommands(Vec<JsCommand>),
     #[serde(rename = "h")]
     Health,
+    #[serde(rename = "e")]
+    Error(ErrorFrame),
+    #[serde(rename = "u")]
+    Upload(UploadReply),
+    #[serde(rename = "g")]
+    GoingAway(GoingAwayFrame),
+    #[serde(skip)]
+    Close(CloseFrame),
+}
+
+#[derive(Serialize)]
+struct ErrorFrame {
+    #[serde(rename = "c")]
+    code: u16,
+    #[serde(rename = "m")]
+    message: String,
+}
+
+#[derive(Serialize)]
+struct UploadReply {
+    #[serde(rename = "r")]
+    id: u64,
+    #[serde(rename = "e")]
+    error: Option<UploadError>,
+}
+
+#[derive(Serialize)]
+struct GoingAwayFrame {
+    // milliseconds
+    #[serde(rename = "r")]
+    reconnect_after: u64,
+}
+
+struct CloseFrame {
+    code: u16,
+    reason: &'static str,
 }
 
 async fn write_message<W>(write: &mut W, data: MessageToSocketData) -> Result<(), W::Error>
@@ -428,6 +1155,14 @@ where
         #[serde(flatten)]
         data: InternalMessageFromSocketData,
     },
+    /// Part of an uploaded file, sent as a binary message.
+    #[serde(skip)]
+    UploadChunk(UploadChunk),
+    /// The server is shutting the view down, because the socket was quiet for longer than the
+    /// [socket limits](crate::limits) allow or the [server is shutting down](crate::shutdown).
+    /// Never sent by the client.
+    #[serde(skip)]
+    Shutdown(ShutdownReason),
 }
 
 fn deserialize_msg<'de, D, M>(d: D) -> Result<M, D::Error>
@@ -467,6 +1202,9 @@ where
 #[serde(rename_all = "snake_case")]
 pub(crate) enum EventMessageFromSocketData {
     None,
+    /// The message was sent through a [`ViewHandle`], never sent by the client.
+    #[serde(skip)]
+    ViewHandle,
     Click,
     WindowFocus,
     WindowBlur,
@@ -520,6 +1258,12 @@ pub(crate) enum EventMessageFromSocketData {
         #[serde(rename = "sy")]
         scroll_y: f64,
     },
+    Hook {
+        #[serde(rename = "e")]
+        event: String,
+        #[serde(rename = "p", default)]
+        payload: Value,
+    },
 }
 
 #[derive(Deserialize, PartialEq, Debug)]
@@ -535,6 +1279,26 @@ pub(crate) enum InputValue {
 pub(crate) enum InternalMessageFromSocketData {
     #[serde(rename = "h")]
     Health,
+    /// The client changed the URL with `pushState`, or the user went back or forward between two
+    /// URLs of the same view.
+    #[serde(rename = "lp")]
+    LivePatch {
+        #[serde(rename = "u")]
+        uri: String,
+    },
+    /// The client wants to mount the live view at another URL over the same socket.
+    #[serde(rename = "lr")]
+    LiveRedirect {
+        #[serde(rename = "u")]
+        uri: String,
+    },
+    #[serde(rename = "us")]
+    UploadStart(UploadStart),
+    #[serde(rename = "uc")]
+    UploadCancel {
+        #[serde(rename = "r")]
+        id: u64,
+    },
 }
 
 fn wrap_in_live_view_container<T>(markup: Html<T>) -> Html<T> {
@@ -545,12 +1309,28 @@ fn wrap_in_live_view_container<T>(markup: Html<T>) -> Html<T> {
     .into_html()
 }
 
+fn wrap_in_live_view_container_with_csrf_token<T>(markup: Html<T>, csrf_token: String) -> Html<T> {
+    crate::html::private::HtmlBuilder {
+        dynamic: Vec::from([
+            crate::html::DynamicFragment::String(csrf_token),
+            crate::html::DynamicFragment::Html(markup),
+        ]),
+        fixed: &[
+            "<div id=\"live-view-container\" data-csrf-token=\"",
+            "\">",
+            "</div>",
+        ],
+    }
+    .into_html()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{signing::SigningKey, LiveView};
+    use http::Extensions;
     use serde::{Deserialize, Serialize};
     use serde_json::json;
-    use std::time::Duration;
 
     #[test]
     fn serialize_js_commands() {
@@ -574,6 +1354,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn serialize_client_side_js_commands() {
+        use crate::js_command::{self, Transition};
+
+        let commands = Vec::from([
+            js_command::show_as("#menu", "flex"),
+            js_command::transition(
+                "#menu",
+                Transition::new("fade")
+                    .start("opacity-0")
+                    .end("opacity-100")
+                    .duration(Duration::from_millis(300)),
+            ),
+            js_command::set_attribute("#open", "aria-expanded", "true"),
+            js_command::dispatch("#menu", "menu:open", json!({ "items": 3 })),
+        ]);
+        let msg = json!(MessageToSocketData::JsCommands(commands));
+
+        assert_eq!(
+            msg["d"],
+            json!([
+                {
+                    "delay_ms": null,
+                    "kind": { "t": "show", "selector": "#menu", "display": "flex" },
+                },
+                {
+                    "delay_ms": null,
+                    "kind": {
+                        "t": "transition",
+                        "selector": "#menu",
+                        "classes": "fade",
+                        "start": "opacity-0",
+                        "end": "opacity-100",
+                        "duration_ms": 300,
+                    },
+                },
+                {
+                    "delay_ms": null,
+                    "kind": {
+                        "t": "set_attribute",
+                        "selector": "#open",
+                        "name": "aria-expanded",
+                        "value": "true",
+                    },
+                },
+                {
+                    "delay_ms": null,
+                    "kind": {
+                        "t": "dispatch",
+                        "selector": "#menu",
+                        "event": "menu:open",
+                        "detail": { "items": 3 },
+                    },
+                },
+            ])
+        );
+    }
+
     #[test]
     fn deserialize_message_from_socket_mount() {
         let msg = serde_json::from_value::<MessageFromSocket<Msg>>(
@@ -603,7 +1441,638 @@ mod tests {
         );
     }
 
-    #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+    #[test]
+    fn deserialize_hook_event() {
+        let msg = serde_json::from_value::<MessageFromSocket<Msg>>(json!({
+            "m": "%22Incr%22",
+            "t": "hook",
+            "d": { "e": "zoom", "p": { "level": 2 } },
+        }))
+        .unwrap();
+        assert_eq!(
+            msg,
+            MessageFromSocket::Event {
+                msg: Msg::Incr,
+                data: EventMessageFromSocketData::Hook {
+                    event: "zoom".to_owned(),
+                    payload: json!({ "level": 2 }),
+                }
+            }
+        );
+
+        let MessageFromSocket::Event { data, .. } = msg else {
+            unreachable!()
+        };
+        let data = Option::<crate::event_data::EventData>::from(data).unwrap();
+        let hook = data.as_hook().unwrap();
+        assert_eq!(hook.event(), "zoom");
+        assert_eq!(hook.deserialize::<serde_json::Value>().unwrap()["level"], 2);
+
+        // the payload is optional
+        let msg = serde_json::from_value::<MessageFromSocket<Msg>>(json!({
+            "m": "%22Incr%22",
+            "t": "hook",
+            "d": { "e": "ready" },
+        }))
+        .unwrap();
+        assert!(matches!(
+            msg,
+            MessageFromSocket::Event {
+                data: EventMessageFromSocketData::Hook {
+                    payload: Value::Null,
+                    ..
+                },
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn deserialize_upload_messages() {
+        let msg = serde_json::from_value::<MessageFromSocket<Msg>>(json!({
+            "t": "us",
+            "n": "avatar",
+            "r": 7,
+            "f": "me.png",
+            "ct": "image/png",
+            "s": 1024,
+        }))
+        .unwrap();
+        assert!(matches!(
+            msg,
+            MessageFromSocket::Internal {
+                data: InternalMessageFromSocketData::UploadStart(start),
+            } if start.id() == 7
+        ));
+
+        let msg =
+            serde_json::from_value::<MessageFromSocket<Msg>>(json!({ "t": "uc", "r": 7 })).unwrap();
+        assert_eq!(
+            msg,
+            MessageFromSocket::Internal {
+                data: InternalMessageFromSocketData::UploadCancel { id: 7 },
+            }
+        );
+    }
+
+    #[test]
+    fn pending_messages_policy() {
+        fn drain(queue: &mut PendingQueue<Msg>) -> Vec<Msg> {
+            std::iter::from_fn(|| queue.pop())
+                .map(|msg| match msg {
+                    MessageFromSocket::Event { msg, .. } => msg,
+                    MessageFromSocket::Internal { .. }
+                    | MessageFromSocket::UploadChunk(_)
+                    | MessageFromSocket::Shutdown(_) => unreachable!(),
+                })
+                .collect()
+        }
+
+        let msgs = [Msg::Incr, Msg::Incr, Msg::Decr, Msg::Incr];
+
+        let mut queue = PendingQueue::new(PendingMessages::Queue);
+        for msg in msgs {
+            queue.push(msg, EventMessageFromSocketData::None);
+        }
+        assert_eq!(
+            drain(&mut queue),
+            [Msg::Incr, Msg::Incr, Msg::Decr, Msg::Incr]
+        );
+
+        let mut queue = PendingQueue::new(PendingMessages::Drop);
+        for msg in msgs {
+            queue.push(msg, EventMessageFromSocketData::None);
+        }
+        assert_eq!(drain(&mut queue), []);
+
+        let mut queue = PendingQueue::new(PendingMessages::Coalesce);
+        for msg in msgs {
+            queue.push(msg, EventMessageFromSocketData::None);
+        }
+        assert_eq!(drain(&mut queue), [Msg::Incr, Msg::Decr]);
+    }
+
+    #[tokio::test]
+    async fn view_handle_messages_are_not_dropped() {
+        let mut written = Vec::new();
+        // the second click arrives while the first one is being handled
+        let read = futures_util::stream::iter([
+            Ok::<_, std::convert::Infallible>(MessageFromSocket::Event {
+                msg: Msg::Incr,
+                data: EventMessageFromSocketData::Click,
+            }),
+            Ok(MessageFromSocket::Event {
+                msg: Msg::Incr,
+                data: EventMessageFromSocketData::Click,
+            }),
+        ])
+        .chain(
+            futures_util::stream::once(tokio::time::sleep(Duration::from_millis(100)))
+                .filter_map(|()| async { None }),
+        );
+        futures_util::pin_mut!(read);
+
+        run_view(
+            &mut written,
+            read,
+            SelfSendingView {
+                handle: None,
+                received: Vec::new(),
+            },
+            "/".parse().unwrap(),
+            HeaderMap::new(),
+            &options(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        // the click is dropped, the message sent during the update isn't
+        assert_eq!(written.len(), 3);
+        assert_eq!(
+            json!(written[1]),
+            json!({ "t": "r", "d": { "d": { "0": { "d": { "0": "[Incr]" } } } } })
+        );
+        assert_eq!(
+            json!(written[2]),
+            json!({ "t": "r", "d": { "d": { "0": { "d": { "0": "[Incr, Decr]" } } } } })
+        );
+    }
+
+    #[tokio::test]
+    async fn initial_render_shows_mounted_view() {
+        let mut written = Vec::new();
+        let read = futures_util::stream::empty::<
+            Result<MessageFromSocket<Msg>, std::convert::Infallible>,
+        >();
+        futures_util::pin_mut!(read);
+
+        run_view(
+            &mut written,
+            read,
+            LoadingView { greeting: None },
+            "/".parse().unwrap(),
+            HeaderMap::new(),
+            &options(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            json!(written[0]),
+            json!({
+                "t": "i",
+                "d": {
+                    "f": ["<div id=\"live-view-container\">", "</div>"],
+                    "d": { "0": { "f": ["", ""], "d": { "0": "Hello from mount" } } },
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn error_policy() {
+        async fn run(
+            view: FailingView,
+            msg: Msg,
+            error_policy: impl ErrorPolicy,
+        ) -> Vec<MessageToSocket> {
+            let mut written = Vec::new();
+            // keep the socket open for a bit so the view has time to fail
+            let read = futures_util::stream::iter([Ok::<_, std::convert::Infallible>(
+                MessageFromSocket::Event {
+                    msg,
+                    data: EventMessageFromSocketData::Click,
+                },
+            )])
+            .chain(
+                futures_util::stream::once(tokio::time::sleep(Duration::from_millis(100)))
+                    .filter_map(|()| async { None }),
+            );
+            futures_util::pin_mut!(read);
+            run_view(
+                &mut written,
+                read,
+                view,
+                "/".parse().unwrap(),
+                HeaderMap::new(),
+                &ViewOptions {
+                    error_policy: Arc::new(error_policy),
+                    ..options()
+                },
+                None,
+            )
+            .await
+            .unwrap();
+            written
+        }
+
+        fn close(message: &str) -> impl Fn(&ViewError) -> ErrorAction {
+            let message = message.to_owned();
+            move |_: &ViewError| ErrorAction::Close {
+                message: message.clone(),
+            }
+        }
+
+        // `update` returning an error
+        let written = run(FailingView { fail_mount: false }, Msg::Incr, close("oops")).await;
+        assert_eq!(written.len(), 3);
+        assert_eq!(json!(written[0])["t"], "i");
+        assert_eq!(
+            json!(written[1]),
+            json!({ "t": "e", "d": { "c": close_code::VIEW_ERROR, "m": "oops" } })
+        );
+        assert_eq!(
+            written[2].close_frame(),
+            Some((close_code::VIEW_ERROR, "view error"))
+        );
+
+        // `update` panicking
+        let written = run(
+            FailingView { fail_mount: false },
+            Msg::Decr,
+            |err: &ViewError| {
+                assert!(err.is_panic());
+                ErrorAction::Render(Html::raw("<p>error</p>"))
+            },
+        )
+        .await;
+        assert_eq!(written.len(), 2);
+        assert_eq!(json!(written[0])["t"], "i");
+        assert_eq!(
+            json!(written[1]),
+            json!({
+                "t": "i",
+                "d": {
+                    "f": ["<div id=\"live-view-container\">", "</div>"],
+                    "d": { "0": { "f": ["", ""], "d": { "0": "<p>error</p>" } } },
+                }
+            })
+        );
+
+        // `mount` returning an error with the default policy
+        let written = run(
+            FailingView { fail_mount: true },
+            Msg::Incr,
+            crate::live_view::error::DefaultErrorPolicy,
+        )
+        .await;
+        assert_eq!(written.len(), 1);
+        assert_eq!(
+            written[0].close_frame(),
+            Some((close_code::REMOUNT, "remount"))
+        );
+    }
+
+    #[tokio::test]
+    async fn live_navigation() {
+        let mut written = Vec::new();
+        let read = futures_util::stream::iter(
+            [
+                InternalMessageFromSocketData::LivePatch {
+                    uri: "/users?page=2".to_owned(),
+                },
+                InternalMessageFromSocketData::LiveRedirect {
+                    uri: "/settings".to_owned(),
+                },
+            ]
+            .map(|data| Ok::<_, std::convert::Infallible>(MessageFromSocket::Internal { data })),
+        )
+        // the socket stays open, otherwise the view stops before handling the redirect
+        .chain(futures_util::stream::pending());
+        futures_util::pin_mut!(read);
+
+        let exit = run_view(
+            &mut written,
+            read,
+            PagedView {
+                uri: "/users".parse().unwrap(),
+            },
+            "/users".parse().unwrap(),
+            HeaderMap::new(),
+            &options(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(exit, ViewExit::LiveRedirect(uri) if uri == "/settings"));
+        assert_eq!(written.len(), 2);
+        assert_eq!(json!(written[0])["t"], "i");
+        assert_eq!(
+            json!(written[1]),
+            json!({ "t": "r", "d": { "d": { "0": { "d": { "0": "/users?page=2" } } } } })
+        );
+    }
+
+    #[tokio::test]
+    async fn terminate_on_timeout() {
+        let terminated = Arc::new(std::sync::Mutex::new(None));
+        let mut written = Vec::new();
+        let read = futures_util::stream::iter([Ok::<_, std::convert::Infallible>(
+            MessageFromSocket::Shutdown(ShutdownReason::IdleTimeout),
+        )]);
+        futures_util::pin_mut!(read);
+
+        let exit = run_view(
+            &mut written,
+            read,
+            PersistingView {
+                count: 0,
+                terminated: terminated.clone(),
+            },
+            "/".parse().unwrap(),
+            HeaderMap::new(),
+            &options(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(exit, ViewExit::Closed));
+        assert_eq!(
+            *terminated.lock().unwrap(),
+            Some(ShutdownReason::IdleTimeout)
+        );
+        assert_eq!(written.len(), 2);
+        assert_eq!(json!(written[0])["t"], "i");
+        assert_eq!(
+            written[1].close_frame(),
+            Some((close_code::IDLE_TIMEOUT, "idle timeout"))
+        );
+    }
+
+    #[tokio::test]
+    async fn server_shutdown_lets_update_finish() {
+        let terminated = Arc::new(std::sync::Mutex::new(None));
+        let mut written = Vec::new();
+        let read = futures_util::stream::iter([
+            Ok::<_, std::convert::Infallible>(MessageFromSocket::Event {
+                msg: Msg::Incr,
+                data: EventMessageFromSocketData::Click,
+            }),
+            Ok(MessageFromSocket::Shutdown(ShutdownReason::ServerShutdown)),
+        ]);
+        futures_util::pin_mut!(read);
+
+        run_view(
+            &mut written,
+            read,
+            PersistingView {
+                count: 0,
+                terminated: terminated.clone(),
+            },
+            "/".parse().unwrap(),
+            HeaderMap::new(),
+            &options(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            *terminated.lock().unwrap(),
+            Some(ShutdownReason::ServerShutdown)
+        );
+        // the going away frame is sent by the caller
+        assert_eq!(written.len(), 2);
+        assert_eq!(json!(written[0])["t"], "i");
+        assert_eq!(
+            json!(written[1]),
+            json!({ "t": "r", "d": { "d": { "0": { "d": { "0": "1" } } } } })
+        );
+    }
+
+    #[tokio::test]
+    async fn disconnect_from_registry() {
+        crate::registry::enable();
+        let terminated = Arc::new(std::sync::Mutex::new(None));
+        let read = futures_util::stream::iter([Ok::<_, std::convert::Infallible>(
+            MessageFromSocket::Event {
+                msg: Msg::Incr,
+                data: EventMessageFromSocketData::Click,
+            },
+        )])
+        .chain(futures_util::stream::pending());
+
+        let task = tokio::spawn({
+            let terminated = terminated.clone();
+            async move {
+                let mut written = Vec::new();
+                futures_util::pin_mut!(read);
+                run_view(
+                    &mut written,
+                    read,
+                    PersistingView {
+                        count: 0,
+                        terminated,
+                    },
+                    "/disconnect".parse().unwrap(),
+                    HeaderMap::new(),
+                    &options(),
+                    None,
+                )
+                .await
+                .unwrap();
+                written
+            }
+        });
+
+        // wait for the update so the view is mounted and its diff recorded
+        let view = loop {
+            let (views, _) = crate::registry::snapshot();
+            match views.into_iter().find(|view| view.uri == "/disconnect") {
+                Some(view) if !view.recent_diffs.is_empty() => break view,
+                _ => tokio::time::sleep(Duration::from_millis(5)).await,
+            }
+        };
+        assert_eq!(view.messages, 1);
+        assert!(crate::registry::disconnect(view.id));
+
+        let written = task.await.unwrap();
+        assert_eq!(
+            *terminated.lock().unwrap(),
+            Some(ShutdownReason::Disconnected)
+        );
+        assert_eq!(
+            written.last().unwrap().close_frame(),
+            Some((close_code::DISCONNECTED, "disconnected"))
+        );
+    }
+
+    fn options() -> ViewOptions {
+        ViewOptions {
+            error_policy: crate::live_view::error::default_error_policy(),
+            navigation: None,
+            flash: Flash::new(SigningKey::generate()),
+            protect_messages: false,
+            limits: Default::default(),
+            shutdown: None,
+            extensions: Extensions::new(),
+        }
+    }
+
+    struct PersistingView {
+        count: u32,
+        terminated: Arc<std::sync::Mutex<Option<ShutdownReason>>>,
+    }
+
+    #[crate::live_view::async_trait]
+    impl AsyncLiveView for PersistingView {
+        type Message = Msg;
+
+        async fn update(
+            mut self,
+            _msg: Msg,
+            _data: Option<EventData>,
+        ) -> Result<Updated<Self>, ViewError> {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.count += 1;
+            Ok(Updated::new(self))
+        }
+
+        fn render(&self) -> Html<Msg> {
+            Html::raw(self.count.to_string())
+        }
+
+        async fn terminate(&mut self, reason: ShutdownReason) {
+            *self.terminated.lock().unwrap() = Some(reason);
+        }
+    }
+
+    /// Drops pending messages, and sends itself `Decr` while handling `Incr`.
+    struct SelfSendingView {
+        handle: Option<ViewHandle<Msg>>,
+        received: Vec<Msg>,
+    }
+
+    #[crate::live_view::async_trait]
+    impl AsyncLiveView for SelfSendingView {
+        type Message = Msg;
+
+        const PENDING_MESSAGES: PendingMessages = PendingMessages::Drop;
+
+        async fn mount(
+            &mut self,
+            _uri: Uri,
+            _request_headers: &HeaderMap,
+            handle: ViewHandle<Msg>,
+        ) -> Result<(), ViewError> {
+            self.handle = Some(handle);
+            Ok(())
+        }
+
+        async fn update(
+            mut self,
+            msg: Msg,
+            _data: Option<EventData>,
+        ) -> Result<Updated<Self>, ViewError> {
+            if msg == Msg::Incr {
+                self.handle.as_ref().unwrap().send(Msg::Decr).await.unwrap();
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            self.received.push(msg);
+            Ok(Updated::new(self))
+        }
+
+        fn render(&self) -> Html<Msg> {
+            Html::raw(format!("{:?}", self.received))
+        }
+    }
+
+    struct PagedView {
+        uri: Uri,
+    }
+
+    impl LiveView for PagedView {
+        type Message = Msg;
+
+        fn update(self, _msg: Msg, _data: Option<EventData>) -> Updated<Self> {
+            Updated::new(self)
+        }
+
+        fn handle_params(mut self, uri: Uri) -> Updated<Self> {
+            self.uri = uri;
+            Updated::new(self)
+        }
+
+        fn render(&self) -> Html<Msg> {
+            Html::raw(self.uri.to_string())
+        }
+    }
+
+    /// Loads what it renders in `mount`.
+    struct LoadingView {
+        greeting: Option<String>,
+    }
+
+    #[crate::live_view::async_trait]
+    impl AsyncLiveView for LoadingView {
+        type Message = Msg;
+
+        async fn mount(
+            &mut self,
+            _uri: Uri,
+            _request_headers: &HeaderMap,
+            _handle: ViewHandle<Msg>,
+        ) -> Result<(), ViewError> {
+            tokio::task::yield_now().await;
+            self.greeting = Some("Hello from mount".to_owned());
+            Ok(())
+        }
+
+        async fn update(
+            self,
+            _msg: Msg,
+            _data: Option<EventData>,
+        ) -> Result<Updated<Self>, ViewError> {
+            Ok(Updated::new(self))
+        }
+
+        fn render(&self) -> Html<Msg> {
+            Html::raw(self.greeting.as_deref().unwrap_or("Loading"))
+        }
+    }
+
+    struct FailingView {
+        fail_mount: bool,
+    }
+
+    #[crate::live_view::async_trait]
+    impl AsyncLiveView for FailingView {
+        type Message = Msg;
+
+        async fn mount(
+            &mut self,
+            _uri: Uri,
+            _request_headers: &HeaderMap,
+            _handle: ViewHandle<Msg>,
+        ) -> Result<(), ViewError> {
+            if self.fail_mount {
+                Err(ViewError::msg("mount failed"))
+            } else {
+                Ok(())
+            }
+        }
+
+        async fn update(
+            self,
+            msg: Msg,
+            _data: Option<EventData>,
+        ) -> Result<Updated<Self>, ViewError> {
+            match msg {
+                Msg::Incr => Err(ViewError::msg("update failed")),
+                Msg::Decr => panic!("update panicked"),
+            }
+        }
+
+        fn render(&self) -> Html<Msg> {
+            Html::raw("view")
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
     enum Msg {
         Incr,
         Decr,
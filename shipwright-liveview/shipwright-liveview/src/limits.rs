@@ -0,0 +1,474 @@
+//! Limits on what clients can send over a live view socket, and how long they may stay quiet.
+//!
+//! Every message from the client is deserialized and most are passed to the view, so a buggy or
+//! malicious client could otherwise keep a view busy or make the server parse huge messages.
+//! [`SocketLimits`] bounds:
+//!
+//! - the size of each WebSocket message, including upload chunks. Defaults to 1 MiB. The
+//!   WebSocket itself also refuses larger messages, which closes the socket whatever the
+//!   [`LimitAction`], so they are never buffered in full.
+//! - how deeply arrays and maps may be nested in a message. Defaults to 32.
+//! - how many events per second are passed to the view, using a token bucket. Unlimited by
+//!   default.
+//!
+//! What happens to messages over a limit is decided by a [`LimitAction`].
+//!
+//! A client whose connection silently dropped, for example because the laptop went to sleep,
+//! never closes its socket. The client sends a heartbeat every 30 seconds, so the view is shut
+//! down if nothing arrives within the [heartbeat timeout](SocketLimits::heartbeat_timeout),
+//! which defaults to 90 seconds. Views can also be shut down after an
+//! [idle timeout](SocketLimits::idle_timeout) without user activity. Either way
+//! [`LiveView::terminate`] is called first.
+//!
+//! Limits are configured for all live views by adding [`SocketLimits`] to the request
+//! extensions, or for a single handler with [`LiveViewUpgrade::limits`].
+//!
+//! # Example
+//!
+//! ```
+//! use axum::{routing::get, Extension, Router};
+//! use shipwright_liveview::limits::{LimitAction, SocketLimits};
+//! use std::time::Duration;
+//!
+//! let limits = SocketLimits::new()
+//!     .max_message_size(64 * 1024)
+//!     .events_per_second(20, 40)
+//!     .on_violation(LimitAction::Throttle)
+//!     .idle_timeout(Duration::from_secs(30 * 60));
+//!
+//! let app = Router::new()
+//!     .route("/", get(|| async {}))
+//!     .layer(Extension(limits));
+//! # let _: Router = app;
+//! ```
+//!
+//! [`LiveViewUpgrade::limits`]: crate::LiveViewUpgrade::limits
+//! [`LiveView::terminate`]: crate::LiveView::terminate
+
+use crate::{
+    codec::Encoding,
+    life_cycle::{InternalMessageFromSocketData, MessageFromSocket},
+    live_view::ShutdownReason,
+};
+use axum::extract::ws;
+use futures_util::Stream;
+use pin_project_lite::pin_project;
+use serde::de::DeserializeOwned;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Mutex,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::{Instant, Sleep};
+
+/// Limits on what clients can send over a live view socket.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct SocketLimits {
+    max_message_size: usize,
+    max_depth: usize,
+    events: Option<EventRate>,
+    on_violation: LimitAction,
+    heartbeat_timeout: Duration,
+    idle_timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct EventRate {
+    per_second: u32,
+    burst: u32,
+}
+
+impl SocketLimits {
+    /// Create the default limits.
+    pub fn new() -> Self {
+        Self {
+            max_message_size: 1024 * 1024,
+            max_depth: 32,
+            events: None,
+            on_violation: LimitAction::default(),
+            heartbeat_timeout: Duration::from_secs(90),
+            idle_timeout: None,
+        }
+    }
+
+    /// Set the maximum size of a message in bytes.
+    pub fn max_message_size(mut self, bytes: usize) -> Self {
+        self.max_message_size = bytes;
+        self
+    }
+
+    pub(crate) fn message_size_limit(&self) -> usize {
+        self.max_message_size
+    }
+
+    /// Set how deeply arrays and maps may be nested in a message.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Allow `per_second` events per second on average, and up to `burst` in a row.
+    ///
+    /// Only events are counted, not health checks, navigation or uploads.
+    pub fn events_per_second(mut self, per_second: u32, burst: u32) -> Self {
+        self.events = Some(EventRate {
+            per_second: per_second.max(1),
+            burst: burst.max(1),
+        });
+        self
+    }
+
+    /// Set what happens to messages over a limit.
+    ///
+    /// Defaults to [`LimitAction::Close`].
+    pub fn on_violation(mut self, action: LimitAction) -> Self {
+        self.on_violation = action;
+        self
+    }
+
+    /// Shut the view down if nothing is received from the client for this long.
+    ///
+    /// The client sends a heartbeat every 30 seconds, and browsers may delay it for tabs in the
+    /// background, so this should be well above that. Defaults to 90 seconds.
+    ///
+    /// The view is shut down with [`ShutdownReason::HeartbeatTimeout`] and the socket is closed
+    /// with close code `4003`. If the client is still there it reconnects and mounts a new view.
+    pub fn heartbeat_timeout(mut self, timeout: Duration) -> Self {
+        self.heartbeat_timeout = timeout;
+        self
+    }
+
+    /// Shut the view down if the client sends nothing but heartbeats for this long.
+    ///
+    /// The view is shut down with [`ShutdownReason::IdleTimeout`] and the socket is closed with
+    /// close code `4004`. The client reconnects once the user interacts with the page again.
+    /// Disabled by default.
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+}
+
+impl Default for SocketLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What happens to messages over a [`SocketLimits`] limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LimitAction {
+    /// Ignore the message.
+    Drop,
+    /// Wait until the event rate allows the event. Messages that are too large or nested too
+    /// deeply are dropped.
+    ///
+    /// The socket isn't read while waiting so the client is slowed down as well.
+    Throttle,
+    /// Close the socket with close code `1008` and the limit as the reason.
+    #[default]
+    Close,
+}
+
+/// Applies [`SocketLimits`] to the messages of one socket.
+pub(crate) struct Limiter {
+    limits: SocketLimits,
+    bucket: Mutex<Bucket>,
+    violation: Mutex<Option<&'static str>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// What to do with a message that was checked against the limits.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Verdict {
+    Accept,
+    Drop,
+    Close,
+}
+
+impl Limiter {
+    pub(crate) fn new(limits: SocketLimits) -> Self {
+        let burst = limits.events.map_or(0, |rate| rate.burst);
+        Self {
+            limits,
+            bucket: Mutex::new(Bucket {
+                tokens: f64::from(burst),
+                refilled_at: Instant::now(),
+            }),
+            violation: Mutex::new(None),
+        }
+    }
+
+    /// Check a message before it is decoded.
+    pub(crate) fn check_message(&self, msg: &ws::Message, encoding: Encoding) -> Verdict {
+        let size = match msg {
+            ws::Message::Text(text) => text.len(),
+            ws::Message::Binary(data) => data.len(),
+            _ => 0,
+        };
+
+        if size > self.limits.max_message_size {
+            self.violated("message too large")
+        } else if encoding.exceeds_depth(msg, self.limits.max_depth) {
+            self.violated("message nested too deeply")
+        } else {
+            Verdict::Accept
+        }
+    }
+
+    /// Take a token for an event, waiting for one if the action is [`LimitAction::Throttle`].
+    pub(crate) async fn check_event(&self) -> Verdict {
+        let Some(rate) = self.limits.events else {
+            return Verdict::Accept;
+        };
+
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+                let now = Instant::now();
+                let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+                bucket.tokens = elapsed
+                    .mul_add(f64::from(rate.per_second), bucket.tokens)
+                    .min(f64::from(rate.burst));
+                bucket.refilled_at = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return Verdict::Accept;
+                }
+
+                Duration::from_secs_f64((1.0 - bucket.tokens) / f64::from(rate.per_second))
+            };
+
+            if self.limits.on_violation != LimitAction::Throttle {
+                return self.violated("too many events");
+            }
+
+            tracing::trace!(?wait, "throttling events");
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// The limit that made the socket close, if any.
+    pub(crate) fn violation(&self) -> Option<&'static str> {
+        *self.violation.lock().unwrap()
+    }
+
+    fn violated(&self, reason: &'static str) -> Verdict {
+        match self.limits.on_violation {
+            LimitAction::Drop | LimitAction::Throttle => {
+                tracing::debug!(reason, "dropping message from socket");
+                Verdict::Drop
+            }
+            LimitAction::Close => {
+                tracing::debug!(reason, "closing socket");
+                *self.violation.lock().unwrap() = Some(reason);
+                Verdict::Close
+            }
+        }
+    }
+}
+
+pin_project! {
+    /// Ends a stream of messages from the socket with [`MessageFromSocket::Shutdown`] once the
+    /// heartbeat or idle timeout expires.
+    pub(crate) struct Watchdog<S> {
+        #[pin]
+        inner: S,
+        heartbeat: Timer,
+        idle: Option<Timer>,
+        timed_out: bool,
+    }
+}
+
+struct Timer {
+    timeout: Duration,
+    sleep: Pin<Box<Sleep>>,
+}
+
+impl Timer {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            sleep: Box::pin(tokio::time::sleep(timeout)),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.sleep.as_mut().reset(Instant::now() + self.timeout);
+    }
+
+    fn expired(&mut self, cx: &mut Context<'_>) -> bool {
+        self.sleep.as_mut().poll(cx).is_ready()
+    }
+}
+
+impl<S> Watchdog<S> {
+    pub(crate) fn new(inner: S, limits: &SocketLimits) -> Self {
+        Self {
+            inner,
+            heartbeat: Timer::new(limits.heartbeat_timeout),
+            idle: limits.idle_timeout.map(Timer::new),
+            timed_out: false,
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, M> Stream for Watchdog<S>
+where
+    S: Stream<Item = anyhow::Result<MessageFromSocket<M>>>,
+    M: DeserializeOwned,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        if *this.timed_out {
+            return Poll::Ready(None);
+        }
+
+        // messages that are already waiting count even if a timer expired in the meantime
+        if let Poll::Ready(msg) = this.inner.poll_next(cx) {
+            if let Some(msg) = &msg {
+                this.heartbeat.reset();
+                let heartbeat = matches!(
+                    msg,
+                    Ok(MessageFromSocket::Internal {
+                        data: InternalMessageFromSocketData::Health
+                    })
+                );
+                if let Some(idle) = this.idle.as_mut().filter(|_| !heartbeat) {
+                    idle.reset();
+                }
+            }
+            return Poll::Ready(msg);
+        }
+
+        let reason = if this.heartbeat.expired(cx) {
+            ShutdownReason::HeartbeatTimeout
+        } else if this.idle.as_mut().is_some_and(|idle| idle.expired(cx)) {
+            ShutdownReason::IdleTimeout
+        } else {
+            return Poll::Pending;
+        };
+
+        tracing::debug!(?reason, "socket timed out");
+        *this.timed_out = true;
+        Poll::Ready(Some(Ok(MessageFromSocket::Shutdown(reason))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_size() {
+        let limiter = Limiter::new(SocketLimits::new().max_message_size(8));
+
+        let small = ws::Message::Text("{}".into());
+        assert_eq!(
+            limiter.check_message(&small, Encoding::Json),
+            Verdict::Accept
+        );
+        assert_eq!(limiter.violation(), None);
+
+        let large = ws::Message::Binary(vec![0; 9].into());
+        assert_eq!(
+            limiter.check_message(&large, Encoding::Json),
+            Verdict::Close
+        );
+        assert_eq!(limiter.violation(), Some("message too large"));
+    }
+
+    #[tokio::test]
+    async fn event_rate() {
+        let limiter = Limiter::new(
+            SocketLimits::new()
+                .events_per_second(10, 2)
+                .on_violation(LimitAction::Drop),
+        );
+
+        assert_eq!(limiter.check_event().await, Verdict::Accept);
+        assert_eq!(limiter.check_event().await, Verdict::Accept);
+        assert_eq!(limiter.check_event().await, Verdict::Drop);
+    }
+
+    #[tokio::test]
+    async fn throttle_waits_for_a_token() {
+        let limiter = Limiter::new(
+            SocketLimits::new()
+                .events_per_second(50, 1)
+                .on_violation(LimitAction::Throttle),
+        );
+
+        assert_eq!(limiter.check_event().await, Verdict::Accept);
+
+        let start = Instant::now();
+        assert_eq!(limiter.check_event().await, Verdict::Accept);
+        assert!(start.elapsed() >= Duration::from_millis(15));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timeouts() {
+        use crate::util::ReceiverStream;
+        use futures_util::{FutureExt, StreamExt};
+        use tokio::{sync::mpsc, time::advance};
+
+        fn health() -> anyhow::Result<MessageFromSocket<()>> {
+            Ok(MessageFromSocket::Internal {
+                data: InternalMessageFromSocketData::Health,
+            })
+        }
+
+        let limits = SocketLimits::new()
+            .heartbeat_timeout(Duration::from_millis(100))
+            .idle_timeout(Duration::from_millis(250));
+        let (tx, rx) = mpsc::channel(8);
+        let mut watchdog = Watchdog::new(ReceiverStream::new(rx), &limits);
+
+        // heartbeats keep the socket open but don't count as activity
+        for _ in 0..4 {
+            advance(Duration::from_millis(60)).await;
+            tx.send(health()).await.unwrap();
+            assert!(matches!(
+                watchdog.next().await,
+                Some(Ok(MessageFromSocket::Internal { .. }))
+            ));
+        }
+        assert!(watchdog.next().now_or_never().is_none());
+        advance(Duration::from_millis(10)).await;
+        assert!(matches!(
+            watchdog.next().await,
+            Some(Ok(MessageFromSocket::Shutdown(ShutdownReason::IdleTimeout)))
+        ));
+        assert!(watchdog.next().await.is_none());
+
+        let (_tx, rx) = mpsc::channel::<anyhow::Result<MessageFromSocket<()>>>(8);
+        let limits = SocketLimits::new().heartbeat_timeout(Duration::from_millis(50));
+        let mut watchdog = Watchdog::new(ReceiverStream::new(rx), &limits);
+        advance(Duration::from_millis(49)).await;
+        assert!(watchdog.next().now_or_never().is_none());
+        advance(Duration::from_millis(1)).await;
+        assert!(matches!(
+            watchdog.next().await,
+            Some(Ok(MessageFromSocket::Shutdown(
+                ShutdownReason::HeartbeatTimeout
+            )))
+        ));
+    }
+}
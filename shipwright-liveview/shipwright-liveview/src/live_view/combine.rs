@@ -1,7 +1,7 @@
 use crate::{
     event_data::EventData,
     html::Html,
-    live_view::{Updated, ViewHandle},
+    live_view::{ShutdownReason, Updated, ViewHandle},
     LiveView,
 };
 use axum::http::{HeaderMap, Uri};
@@ -105,6 +105,7 @@ where
                     live_view: T1,
                     js_commands,
                     spawns,
+                    flash,
                 } = T1.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -117,10 +118,39 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
         }
     }
+    fn handle_params(self, uri: Uri) -> Updated<Self> {
+        let Self {
+            views: (T1,),
+            render,
+        } = self;
+        let Updated {
+            live_view: T1,
+            js_commands: t1_js_commands,
+            spawns: t1_spawns,
+            flash: t1_flash,
+        } = T1.handle_params(uri.clone());
+        Updated {
+            live_view: Self {
+                views: (T1,),
+                render,
+            },
+            js_commands: t1_js_commands.into_iter().collect(),
+            flash: t1_flash.into_iter().collect(),
+            spawns: t1_spawns
+                .into_iter()
+                .map(|future| Box::pin(async move { Either1::T1(future.await) }) as _)
+                .collect(),
+        }
+    }
+    fn terminate(&mut self, reason: ShutdownReason) {
+        let Self { views: (T1,), .. } = self;
+        T1.terminate(reason);
+    }
     fn render(&self) -> Html<Self::Message> {
         let Self {
             views: (T1,),
@@ -169,6 +199,7 @@ where
                     live_view: T1,
                     js_commands,
                     spawns,
+                    flash,
                 } = T1.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -181,6 +212,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either2::T2(msg) => {
@@ -192,6 +224,7 @@ where
                     live_view: T2,
                     js_commands,
                     spawns,
+                    flash,
                 } = T2.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -204,10 +237,53 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
         }
     }
+    fn handle_params(self, uri: Uri) -> Updated<Self> {
+        let Self {
+            views: (T1, T2),
+            render,
+        } = self;
+        let Updated {
+            live_view: T1,
+            js_commands: t1_js_commands,
+            spawns: t1_spawns,
+            flash: t1_flash,
+        } = T1.handle_params(uri.clone());
+        let Updated {
+            live_view: T2,
+            js_commands: t2_js_commands,
+            spawns: t2_spawns,
+            flash: t2_flash,
+        } = T2.handle_params(uri.clone());
+        Updated {
+            live_view: Self {
+                views: (T1, T2),
+                render,
+            },
+            js_commands: t1_js_commands.into_iter().chain(t2_js_commands).collect(),
+            flash: t1_flash.into_iter().chain(t2_flash).collect(),
+            spawns: t1_spawns
+                .into_iter()
+                .map(|future| Box::pin(async move { Either2::T1(future.await) }) as _)
+                .chain(
+                    t2_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either2::T2(future.await) }) as _),
+                )
+                .collect(),
+        }
+    }
+    fn terminate(&mut self, reason: ShutdownReason) {
+        let Self {
+            views: (T1, T2), ..
+        } = self;
+        T1.terminate(reason);
+        T2.terminate(reason);
+    }
     fn render(&self) -> Html<Self::Message> {
         let Self {
             views: (T1, T2),
@@ -264,6 +340,7 @@ where
                     live_view: T1,
                     js_commands,
                     spawns,
+                    flash,
                 } = T1.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -276,6 +353,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either3::T2(msg) => {
@@ -287,6 +365,7 @@ where
                     live_view: T2,
                     js_commands,
                     spawns,
+                    flash,
                 } = T2.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -299,6 +378,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either3::T3(msg) => {
@@ -310,6 +390,7 @@ where
                     live_view: T3,
                     js_commands,
                     spawns,
+                    flash,
                 } = T3.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -322,10 +403,74 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
         }
     }
+    fn handle_params(self, uri: Uri) -> Updated<Self> {
+        let Self {
+            views: (T1, T2, T3),
+            render,
+        } = self;
+        let Updated {
+            live_view: T1,
+            js_commands: t1_js_commands,
+            spawns: t1_spawns,
+            flash: t1_flash,
+        } = T1.handle_params(uri.clone());
+        let Updated {
+            live_view: T2,
+            js_commands: t2_js_commands,
+            spawns: t2_spawns,
+            flash: t2_flash,
+        } = T2.handle_params(uri.clone());
+        let Updated {
+            live_view: T3,
+            js_commands: t3_js_commands,
+            spawns: t3_spawns,
+            flash: t3_flash,
+        } = T3.handle_params(uri.clone());
+        Updated {
+            live_view: Self {
+                views: (T1, T2, T3),
+                render,
+            },
+            js_commands: t1_js_commands
+                .into_iter()
+                .chain(t2_js_commands)
+                .chain(t3_js_commands)
+                .collect(),
+            flash: t1_flash
+                .into_iter()
+                .chain(t2_flash)
+                .chain(t3_flash)
+                .collect(),
+            spawns: t1_spawns
+                .into_iter()
+                .map(|future| Box::pin(async move { Either3::T1(future.await) }) as _)
+                .chain(
+                    t2_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either3::T2(future.await) }) as _),
+                )
+                .chain(
+                    t3_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either3::T3(future.await) }) as _),
+                )
+                .collect(),
+        }
+    }
+    fn terminate(&mut self, reason: ShutdownReason) {
+        let Self {
+            views: (T1, T2, T3),
+            ..
+        } = self;
+        T1.terminate(reason);
+        T2.terminate(reason);
+        T3.terminate(reason);
+    }
     fn render(&self) -> Html<Self::Message> {
         let Self {
             views: (T1, T2, T3),
@@ -393,6 +538,7 @@ where
                     live_view: T1,
                     js_commands,
                     spawns,
+                    flash,
                 } = T1.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -405,6 +551,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either4::T2(msg) => {
@@ -416,6 +563,7 @@ where
                     live_view: T2,
                     js_commands,
                     spawns,
+                    flash,
                 } = T2.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -428,6 +576,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either4::T3(msg) => {
@@ -439,6 +588,7 @@ where
                     live_view: T3,
                     js_commands,
                     spawns,
+                    flash,
                 } = T3.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -451,6 +601,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either4::T4(msg) => {
@@ -462,6 +613,7 @@ where
                     live_view: T4,
                     js_commands,
                     spawns,
+                    flash,
                 } = T4.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -474,10 +626,88 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
         }
     }
+    fn handle_params(self, uri: Uri) -> Updated<Self> {
+        let Self {
+            views: (T1, T2, T3, T4),
+            render,
+        } = self;
+        let Updated {
+            live_view: T1,
+            js_commands: t1_js_commands,
+            spawns: t1_spawns,
+            flash: t1_flash,
+        } = T1.handle_params(uri.clone());
+        let Updated {
+            live_view: T2,
+            js_commands: t2_js_commands,
+            spawns: t2_spawns,
+            flash: t2_flash,
+        } = T2.handle_params(uri.clone());
+        let Updated {
+            live_view: T3,
+            js_commands: t3_js_commands,
+            spawns: t3_spawns,
+            flash: t3_flash,
+        } = T3.handle_params(uri.clone());
+        let Updated {
+            live_view: T4,
+            js_commands: t4_js_commands,
+            spawns: t4_spawns,
+            flash: t4_flash,
+        } = T4.handle_params(uri.clone());
+        Updated {
+            live_view: Self {
+                views: (T1, T2, T3, T4),
+                render,
+            },
+            js_commands: t1_js_commands
+                .into_iter()
+                .chain(t2_js_commands)
+                .chain(t3_js_commands)
+                .chain(t4_js_commands)
+                .collect(),
+            flash: t1_flash
+                .into_iter()
+                .chain(t2_flash)
+                .chain(t3_flash)
+                .chain(t4_flash)
+                .collect(),
+            spawns: t1_spawns
+                .into_iter()
+                .map(|future| Box::pin(async move { Either4::T1(future.await) }) as _)
+                .chain(
+                    t2_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either4::T2(future.await) }) as _),
+                )
+                .chain(
+                    t3_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either4::T3(future.await) }) as _),
+                )
+                .chain(
+                    t4_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either4::T4(future.await) }) as _),
+                )
+                .collect(),
+        }
+    }
+    fn terminate(&mut self, reason: ShutdownReason) {
+        let Self {
+            views: (T1, T2, T3, T4),
+            ..
+        } = self;
+        T1.terminate(reason);
+        T2.terminate(reason);
+        T3.terminate(reason);
+        T4.terminate(reason);
+    }
     fn render(&self) -> Html<Self::Message> {
         let Self {
             views: (T1, T2, T3, T4),
@@ -554,6 +784,7 @@ where
                     live_view: T1,
                     js_commands,
                     spawns,
+                    flash,
                 } = T1.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -566,6 +797,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either5::T2(msg) => {
@@ -577,6 +809,7 @@ where
                     live_view: T2,
                     js_commands,
                     spawns,
+                    flash,
                 } = T2.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -589,6 +822,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either5::T3(msg) => {
@@ -600,6 +834,7 @@ where
                     live_view: T3,
                     js_commands,
                     spawns,
+                    flash,
                 } = T3.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -612,6 +847,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either5::T4(msg) => {
@@ -623,6 +859,7 @@ where
                     live_view: T4,
                     js_commands,
                     spawns,
+                    flash,
                 } = T4.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -635,6 +872,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either5::T5(msg) => {
@@ -646,6 +884,7 @@ where
                     live_view: T5,
                     js_commands,
                     spawns,
+                    flash,
                 } = T5.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -658,10 +897,102 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
         }
     }
+    fn handle_params(self, uri: Uri) -> Updated<Self> {
+        let Self {
+            views: (T1, T2, T3, T4, T5),
+            render,
+        } = self;
+        let Updated {
+            live_view: T1,
+            js_commands: t1_js_commands,
+            spawns: t1_spawns,
+            flash: t1_flash,
+        } = T1.handle_params(uri.clone());
+        let Updated {
+            live_view: T2,
+            js_commands: t2_js_commands,
+            spawns: t2_spawns,
+            flash: t2_flash,
+        } = T2.handle_params(uri.clone());
+        let Updated {
+            live_view: T3,
+            js_commands: t3_js_commands,
+            spawns: t3_spawns,
+            flash: t3_flash,
+        } = T3.handle_params(uri.clone());
+        let Updated {
+            live_view: T4,
+            js_commands: t4_js_commands,
+            spawns: t4_spawns,
+            flash: t4_flash,
+        } = T4.handle_params(uri.clone());
+        let Updated {
+            live_view: T5,
+            js_commands: t5_js_commands,
+            spawns: t5_spawns,
+            flash: t5_flash,
+        } = T5.handle_params(uri.clone());
+        Updated {
+            live_view: Self {
+                views: (T1, T2, T3, T4, T5),
+                render,
+            },
+            js_commands: t1_js_commands
+                .into_iter()
+                .chain(t2_js_commands)
+                .chain(t3_js_commands)
+                .chain(t4_js_commands)
+                .chain(t5_js_commands)
+                .collect(),
+            flash: t1_flash
+                .into_iter()
+                .chain(t2_flash)
+                .chain(t3_flash)
+                .chain(t4_flash)
+                .chain(t5_flash)
+                .collect(),
+            spawns: t1_spawns
+                .into_iter()
+                .map(|future| Box::pin(async move { Either5::T1(future.await) }) as _)
+                .chain(
+                    t2_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either5::T2(future.await) }) as _),
+                )
+                .chain(
+                    t3_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either5::T3(future.await) }) as _),
+                )
+                .chain(
+                    t4_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either5::T4(future.await) }) as _),
+                )
+                .chain(
+                    t5_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either5::T5(future.await) }) as _),
+                )
+                .collect(),
+        }
+    }
+    fn terminate(&mut self, reason: ShutdownReason) {
+        let Self {
+            views: (T1, T2, T3, T4, T5),
+            ..
+        } = self;
+        T1.terminate(reason);
+        T2.terminate(reason);
+        T3.terminate(reason);
+        T4.terminate(reason);
+        T5.terminate(reason);
+    }
     fn render(&self) -> Html<Self::Message> {
         let Self {
             views: (T1, T2, T3, T4, T5),
@@ -801,6 +1132,7 @@ where
                     live_view: T1,
                     js_commands,
                     spawns,
+                    flash,
                 } = T1.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -813,6 +1145,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either6::T2(msg) => {
@@ -824,6 +1157,7 @@ where
                     live_view: T2,
                     js_commands,
                     spawns,
+                    flash,
                 } = T2.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -836,6 +1170,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either6::T3(msg) => {
@@ -847,6 +1182,7 @@ where
                     live_view: T3,
                     js_commands,
                     spawns,
+                    flash,
                 } = T3.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -859,6 +1195,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either6::T4(msg) => {
@@ -870,6 +1207,7 @@ where
                     live_view: T4,
                     js_commands,
                     spawns,
+                    flash,
                 } = T4.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -882,6 +1220,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either6::T5(msg) => {
@@ -893,6 +1232,7 @@ where
                     live_view: T5,
                     js_commands,
                     spawns,
+                    flash,
                 } = T5.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -905,6 +1245,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either6::T6(msg) => {
@@ -916,6 +1257,7 @@ where
                     live_view: T6,
                     js_commands,
                     spawns,
+                    flash,
                 } = T6.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -928,10 +1270,116 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
         }
     }
+    fn handle_params(self, uri: Uri) -> Updated<Self> {
+        let Self {
+            views: (T1, T2, T3, T4, T5, T6),
+            render,
+        } = self;
+        let Updated {
+            live_view: T1,
+            js_commands: t1_js_commands,
+            spawns: t1_spawns,
+            flash: t1_flash,
+        } = T1.handle_params(uri.clone());
+        let Updated {
+            live_view: T2,
+            js_commands: t2_js_commands,
+            spawns: t2_spawns,
+            flash: t2_flash,
+        } = T2.handle_params(uri.clone());
+        let Updated {
+            live_view: T3,
+            js_commands: t3_js_commands,
+            spawns: t3_spawns,
+            flash: t3_flash,
+        } = T3.handle_params(uri.clone());
+        let Updated {
+            live_view: T4,
+            js_commands: t4_js_commands,
+            spawns: t4_spawns,
+            flash: t4_flash,
+        } = T4.handle_params(uri.clone());
+        let Updated {
+            live_view: T5,
+            js_commands: t5_js_commands,
+            spawns: t5_spawns,
+            flash: t5_flash,
+        } = T5.handle_params(uri.clone());
+        let Updated {
+            live_view: T6,
+            js_commands: t6_js_commands,
+            spawns: t6_spawns,
+            flash: t6_flash,
+        } = T6.handle_params(uri.clone());
+        Updated {
+            live_view: Self {
+                views: (T1, T2, T3, T4, T5, T6),
+                render,
+            },
+            js_commands: t1_js_commands
+                .into_iter()
+                .chain(t2_js_commands)
+                .chain(t3_js_commands)
+                .chain(t4_js_commands)
+                .chain(t5_js_commands)
+                .chain(t6_js_commands)
+                .collect(),
+            flash: t1_flash
+                .into_iter()
+                .chain(t2_flash)
+                .chain(t3_flash)
+                .chain(t4_flash)
+                .chain(t5_flash)
+                .chain(t6_flash)
+                .collect(),
+            spawns: t1_spawns
+                .into_iter()
+                .map(|future| Box::pin(async move { Either6::T1(future.await) }) as _)
+                .chain(
+                    t2_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either6::T2(future.await) }) as _),
+                )
+                .chain(
+                    t3_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either6::T3(future.await) }) as _),
+                )
+                .chain(
+                    t4_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either6::T4(future.await) }) as _),
+                )
+                .chain(
+                    t5_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either6::T5(future.await) }) as _),
+                )
+                .chain(
+                    t6_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either6::T6(future.await) }) as _),
+                )
+                .collect(),
+        }
+    }
+    fn terminate(&mut self, reason: ShutdownReason) {
+        let Self {
+            views: (T1, T2, T3, T4, T5, T6),
+            ..
+        } = self;
+        T1.terminate(reason);
+        T2.terminate(reason);
+        T3.terminate(reason);
+        T4.terminate(reason);
+        T5.terminate(reason);
+        T6.terminate(reason);
+    }
     fn render(&self) -> Html<Self::Message> {
         let Self {
             views: (T1, T2, T3, T4, T5, T6),
@@ -1110,6 +1558,7 @@ where
                     live_view: T1,
                     js_commands,
                     spawns,
+                    flash,
                 } = T1.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -1122,6 +1571,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either7::T2(msg) => {
@@ -1133,6 +1583,7 @@ where
                     live_view: T2,
                     js_commands,
                     spawns,
+                    flash,
                 } = T2.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -1145,6 +1596,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either7::T3(msg) => {
@@ -1156,6 +1608,7 @@ where
                     live_view: T3,
                     js_commands,
                     spawns,
+                    flash,
                 } = T3.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -1168,6 +1621,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either7::T4(msg) => {
@@ -1179,6 +1633,7 @@ where
                     live_view: T4,
                     js_commands,
                     spawns,
+                    flash,
                 } = T4.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -1191,6 +1646,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either7::T5(msg) => {
@@ -1202,6 +1658,7 @@ where
                     live_view: T5,
                     js_commands,
                     spawns,
+                    flash,
                 } = T5.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -1214,6 +1671,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either7::T6(msg) => {
@@ -1225,6 +1683,7 @@ where
                     live_view: T6,
                     js_commands,
                     spawns,
+                    flash,
                 } = T6.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -1237,6 +1696,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either7::T7(msg) => {
@@ -1248,6 +1708,7 @@ where
                     live_view: T7,
                     js_commands,
                     spawns,
+                    flash,
                 } = T7.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -1260,10 +1721,130 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
         }
     }
+    fn handle_params(self, uri: Uri) -> Updated<Self> {
+        let Self {
+            views: (T1, T2, T3, T4, T5, T6, T7),
+            render,
+        } = self;
+        let Updated {
+            live_view: T1,
+            js_commands: t1_js_commands,
+            spawns: t1_spawns,
+            flash: t1_flash,
+        } = T1.handle_params(uri.clone());
+        let Updated {
+            live_view: T2,
+            js_commands: t2_js_commands,
+            spawns: t2_spawns,
+            flash: t2_flash,
+        } = T2.handle_params(uri.clone());
+        let Updated {
+            live_view: T3,
+            js_commands: t3_js_commands,
+            spawns: t3_spawns,
+            flash: t3_flash,
+        } = T3.handle_params(uri.clone());
+        let Updated {
+            live_view: T4,
+            js_commands: t4_js_commands,
+            spawns: t4_spawns,
+            flash: t4_flash,
+        } = T4.handle_params(uri.clone());
+        let Updated {
+            live_view: T5,
+            js_commands: t5_js_commands,
+            spawns: t5_spawns,
+            flash: t5_flash,
+        } = T5.handle_params(uri.clone());
+        let Updated {
+            live_view: T6,
+            js_commands: t6_js_commands,
+            spawns: t6_spawns,
+            flash: t6_flash,
+        } = T6.handle_params(uri.clone());
+        let Updated {
+            live_view: T7,
+            js_commands: t7_js_commands,
+            spawns: t7_spawns,
+            flash: t7_flash,
+        } = T7.handle_params(uri.clone());
+        Updated {
+            live_view: Self {
+                views: (T1, T2, T3, T4, T5, T6, T7),
+                render,
+            },
+            js_commands: t1_js_commands
+                .into_iter()
+                .chain(t2_js_commands)
+                .chain(t3_js_commands)
+                .chain(t4_js_commands)
+                .chain(t5_js_commands)
+                .chain(t6_js_commands)
+                .chain(t7_js_commands)
+                .collect(),
+            flash: t1_flash
+                .into_iter()
+                .chain(t2_flash)
+                .chain(t3_flash)
+                .chain(t4_flash)
+                .chain(t5_flash)
+                .chain(t6_flash)
+                .chain(t7_flash)
+                .collect(),
+            spawns: t1_spawns
+                .into_iter()
+                .map(|future| Box::pin(async move { Either7::T1(future.await) }) as _)
+                .chain(
+                    t2_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either7::T2(future.await) }) as _),
+                )
+                .chain(
+                    t3_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either7::T3(future.await) }) as _),
+                )
+                .chain(
+                    t4_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either7::T4(future.await) }) as _),
+                )
+                .chain(
+                    t5_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either7::T5(future.await) }) as _),
+                )
+                .chain(
+                    t6_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either7::T6(future.await) }) as _),
+                )
+                .chain(
+                    t7_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either7::T7(future.await) }) as _),
+                )
+                .collect(),
+        }
+    }
+    fn terminate(&mut self, reason: ShutdownReason) {
+        let Self {
+            views: (T1, T2, T3, T4, T5, T6, T7),
+            ..
+        } = self;
+        T1.terminate(reason);
+        T2.terminate(reason);
+        T3.terminate(reason);
+        T4.terminate(reason);
+        T5.terminate(reason);
+        T6.terminate(reason);
+        T7.terminate(reason);
+    }
     fn render(&self) -> Html<Self::Message> {
         let Self {
             views: (T1, T2, T3, T4, T5, T6, T7),
@@ -1470,6 +2051,7 @@ where
                     live_view: T1,
                     js_commands,
                     spawns,
+                    flash,
                 } = T1.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -1482,6 +2064,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either8::T2(msg) => {
@@ -1493,6 +2076,7 @@ where
                     live_view: T2,
                     js_commands,
                     spawns,
+                    flash,
                 } = T2.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -1505,6 +2089,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either8::T3(msg) => {
@@ -1516,6 +2101,7 @@ where
                     live_view: T3,
                     js_commands,
                     spawns,
+                    flash,
                 } = T3.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -1528,6 +2114,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either8::T4(msg) => {
@@ -1539,6 +2126,7 @@ where
                     live_view: T4,
                     js_commands,
                     spawns,
+                    flash,
                 } = T4.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -1551,6 +2139,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either8::T5(msg) => {
@@ -1562,6 +2151,7 @@ where
                     live_view: T5,
                     js_commands,
                     spawns,
+                    flash,
                 } = T5.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -1574,6 +2164,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either8::T6(msg) => {
@@ -1585,6 +2176,7 @@ where
                     live_view: T6,
                     js_commands,
                     spawns,
+                    flash,
                 } = T6.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -1597,6 +2189,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either8::T7(msg) => {
@@ -1608,6 +2201,7 @@ where
                     live_view: T7,
                     js_commands,
                     spawns,
+                    flash,
                 } = T7.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -1620,6 +2214,7 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
             Either8::T8(msg) => {
@@ -1631,6 +2226,7 @@ where
                     live_view: T8,
                     js_commands,
                     spawns,
+                    flash,
                 } = T8.update(msg, data);
                 let spawns = spawns
                     .into_iter()
@@ -1643,10 +2239,144 @@ where
                     },
                     js_commands,
                     spawns,
+                    flash,
                 }
             }
         }
     }
+    fn handle_params(self, uri: Uri) -> Updated<Self> {
+        let Self {
+            views: (T1, T2, T3, T4, T5, T6, T7, T8),
+            render,
+        } = self;
+        let Updated {
+            live_view: T1,
+            js_commands: t1_js_commands,
+            spawns: t1_spawns,
+            flash: t1_flash,
+        } = T1.handle_params(uri.clone());
+        let Updated {
+            live_view: T2,
+            js_commands: t2_js_commands,
+            spawns: t2_spawns,
+            flash: t2_flash,
+        } = T2.handle_params(uri.clone());
+        let Updated {
+            live_view: T3,
+            js_commands: t3_js_commands,
+            spawns: t3_spawns,
+            flash: t3_flash,
+        } = T3.handle_params(uri.clone());
+        let Updated {
+            live_view: T4,
+            js_commands: t4_js_commands,
+            spawns: t4_spawns,
+            flash: t4_flash,
+        } = T4.handle_params(uri.clone());
+        let Updated {
+            live_view: T5,
+            js_commands: t5_js_commands,
+            spawns: t5_spawns,
+            flash: t5_flash,
+        } = T5.handle_params(uri.clone());
+        let Updated {
+            live_view: T6,
+            js_commands: t6_js_commands,
+            spawns: t6_spawns,
+            flash: t6_flash,
+        } = T6.handle_params(uri.clone());
+        let Updated {
+            live_view: T7,
+            js_commands: t7_js_commands,
+            spawns: t7_spawns,
+            flash: t7_flash,
+        } = T7.handle_params(uri.clone());
+        let Updated {
+            live_view: T8,
+            js_commands: t8_js_commands,
+            spawns: t8_spawns,
+            flash: t8_flash,
+        } = T8.handle_params(uri.clone());
+        Updated {
+            live_view: Self {
+                views: (T1, T2, T3, T4, T5, T6, T7, T8),
+                render,
+            },
+            js_commands: t1_js_commands
+                .into_iter()
+                .chain(t2_js_commands)
+                .chain(t3_js_commands)
+                .chain(t4_js_commands)
+                .chain(t5_js_commands)
+                .chain(t6_js_commands)
+                .chain(t7_js_commands)
+                .chain(t8_js_commands)
+                .collect(),
+            flash: t1_flash
+                .into_iter()
+                .chain(t2_flash)
+                .chain(t3_flash)
+                .chain(t4_flash)
+                .chain(t5_flash)
+                .chain(t6_flash)
+                .chain(t7_flash)
+                .chain(t8_flash)
+                .collect(),
+            spawns: t1_spawns
+                .into_iter()
+                .map(|future| Box::pin(async move { Either8::T1(future.await) }) as _)
+                .chain(
+                    t2_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either8::T2(future.await) }) as _),
+                )
+                .chain(
+                    t3_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either8::T3(future.await) }) as _),
+                )
+                .chain(
+                    t4_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either8::T4(future.await) }) as _),
+                )
+                .chain(
+                    t5_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either8::T5(future.await) }) as _),
+                )
+                .chain(
+                    t6_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either8::T6(future.await) }) as _),
+                )
+                .chain(
+                    t7_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either8::T7(future.await) }) as _),
+                )
+                .chain(
+                    t8_spawns
+                        .into_iter()
+                        .map(|future| Box::pin(async move { Either8::T8(future.await) }) as _),
+                )
+                .collect(),
+        }
+    }
+    fn terminate(&mut self, reason: ShutdownReason) {
+        let Self {
+            views: (T1, T2, T3, T4, T5, T6, T7, T8),
+            ..
+        } = self;
+        T1.terminate(reason);
+        T2.terminate(reason);
+        T3.terminate(reason);
+        T4.terminate(reason);
+        T5.terminate(reason);
+        T6.terminate(reason);
+        T7.terminate(reason);
+        T8.terminate(reason);
+    }
     fn render(&self) -> Html<Self::Message> {
         let Self {
             views: (T1, T2, T3, T4, T5, T6, T7, T8),
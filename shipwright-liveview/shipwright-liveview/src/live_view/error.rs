@@ -0,0 +1,174 @@
+use crate::html::Html;
+use std::{any::Any, fmt, sync::Arc};
+
+/// An error returned from [`AsyncLiveView::mount`] or [`AsyncLiveView::update`], or caused by
+/// a view panicking.
+///
+/// Any [`std::error::Error`] can be converted into a `ViewError` so `?` can be used in `mount` and
+/// `update`.
+///
+/// What happens to the view and its socket after an error is decided by an [`ErrorPolicy`].
+///
+/// [`AsyncLiveView::mount`]: super::AsyncLiveView::mount
+/// [`AsyncLiveView::update`]: super::AsyncLiveView::update
+pub struct ViewError {
+    kind: ViewErrorKind,
+}
+
+enum ViewErrorKind {
+    Error(Box<dyn std::error::Error + Send + Sync>),
+    Panic(String),
+}
+
+impl ViewError {
+    /// Create a new `ViewError` from an error.
+    pub fn new<E>(error: E) -> Self
+    where
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        Self {
+            kind: ViewErrorKind::Error(error.into()),
+        }
+    }
+
+    /// Create a new `ViewError` from an error message.
+    pub fn msg(message: impl fmt::Display) -> Self {
+        Self::new(message.to_string())
+    }
+
+    pub(crate) fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            (*message).to_owned()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "view panicked".to_owned()
+        };
+
+        Self {
+            kind: ViewErrorKind::Panic(message),
+        }
+    }
+
+    /// Whether the error was caused by the view panicking.
+    pub fn is_panic(&self) -> bool {
+        matches!(self.kind, ViewErrorKind::Panic(_))
+    }
+
+    /// Get the underlying error, if the error wasn't caused by a panic.
+    pub fn source(&self) -> Option<&(dyn std::error::Error + Send + Sync + 'static)> {
+        match &self.kind {
+            ViewErrorKind::Error(err) => Some(&**err),
+            ViewErrorKind::Panic(_) => None,
+        }
+    }
+}
+
+impl<E> From<E> for ViewError
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    fn from(error: E) -> Self {
+        Self::new(error)
+    }
+}
+
+impl fmt::Debug for ViewError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ViewErrorKind::Error(err) => f.debug_tuple("ViewError").field(err).finish(),
+            ViewErrorKind::Panic(message) => {
+                f.debug_tuple("ViewError::Panic").field(message).finish()
+            }
+        }
+    }
+}
+
+impl fmt::Display for ViewError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ViewErrorKind::Error(err) => err.fmt(f),
+            ViewErrorKind::Panic(message) => write!(f, "view panicked: {message}"),
+        }
+    }
+}
+
+/// Decides what happens when a live view fails.
+///
+/// The policy is called whenever [`AsyncLiveView::mount`] or [`AsyncLiveView::update`] return an
+/// error, or when the view panics while mounting, updating, or rendering. In all cases the failed
+/// view is shut down.
+///
+/// It is implemented for closures so a policy can be created from a function:
+///
+/// ```
+/// use shipwright_liveview::{html, live_view::{ErrorAction, ViewError}};
+///
+/// let policy = |err: &ViewError| {
+///     if err.is_panic() {
+///         ErrorAction::Remount
+///     } else {
+///         ErrorAction::Render(html! {
+///             <div class="error">"Something went wrong. Please reload the page."</div>
+///         })
+///     }
+/// };
+/// # let _ = policy;
+/// ```
+///
+/// Use [`LiveViewUpgrade::error_policy`] to set the policy for a view. The default policy is
+/// [`ErrorAction::Remount`].
+///
+/// [`AsyncLiveView::mount`]: super::AsyncLiveView::mount
+/// [`AsyncLiveView::update`]: super::AsyncLiveView::update
+/// [`LiveViewUpgrade::error_policy`]: crate::LiveViewUpgrade::error_policy
+pub trait ErrorPolicy: Send + Sync + 'static {
+    /// Decide what to do about a failed view.
+    fn on_error(&self, error: &ViewError) -> ErrorAction;
+}
+
+impl<F> ErrorPolicy for F
+where
+    F: Fn(&ViewError) -> ErrorAction + Send + Sync + 'static,
+{
+    fn on_error(&self, error: &ViewError) -> ErrorAction {
+        self(error)
+    }
+}
+
+/// What to do about a failed view, as decided by an [`ErrorPolicy`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ErrorAction {
+    /// Replace the view with an error boundary.
+    ///
+    /// The socket is kept open so the HTML stays on the page until the user navigates away or
+    /// reloads.
+    Render(Html<()>),
+    /// Close the socket so the client reconnects and a fresh instance of the view is mounted.
+    ///
+    /// The client waits longer after each failed attempt and gives up after five of them.
+    Remount,
+    /// Send the client an error frame containing `message` and close the socket.
+    ///
+    /// The client shows the message in place of the view, in a `<div class="axm-error">`, and
+    /// doesn't reconnect.
+    Close {
+        /// The message included in the error frame. This is shown to users so it shouldn't
+        /// contain internal details.
+        message: String,
+    },
+}
+
+#[derive(Debug)]
+pub(crate) struct DefaultErrorPolicy;
+
+impl ErrorPolicy for DefaultErrorPolicy {
+    fn on_error(&self, _error: &ViewError) -> ErrorAction {
+        ErrorAction::Remount
+    }
+}
+
+pub(crate) fn default_error_policy() -> Arc<dyn ErrorPolicy> {
+    Arc::new(DefaultErrorPolicy)
+}
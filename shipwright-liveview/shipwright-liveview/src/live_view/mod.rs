@@ -1,13 +1,24 @@
 //! Server-rendered live views.
 
-use crate::{event_data::EventData, html::Html, js_command::JsCommand};
-use axum::http::{HeaderMap, Uri};
+use crate::{event_data::EventData, flash::FlashChange, html::Html, js_command::JsCommand, upload};
+use axum::http::{Extensions, HeaderMap, Uri};
 use futures_util::Stream;
 use serde::{de::DeserializeOwned, Serialize};
-use std::{fmt, future::Future, pin::Pin};
-use tokio::sync::mpsc;
+use std::{fmt, future::Future, pin::Pin, sync::Arc};
+use tokio::{sync::mpsc, task::JoinHandle};
 
 mod combine;
+pub(crate) mod error;
+mod view_list;
+
+pub use self::{
+    error::{ErrorAction, ErrorPolicy, ViewError},
+    view_list::{ViewList, ViewListMsg},
+};
+
+/// Re-export of [`async_trait`](https://docs.rs/async-trait) for implementing [`AsyncLiveView`].
+#[doc(no_inline)]
+pub use async_trait::async_trait;
 
 /// A server-rendered live view.
 ///
@@ -86,59 +97,291 @@ pub trait LiveView: Sized + Send + Sync + 'static {
     ///
     /// This can be used to load additional data that isn't necessary for the initial render.
     ///
-    /// If an error is returned the view will be shutdown, the JavaScript client will reconnect,
-    /// and a fresh instance of the view will be created.
+    /// If this method panics the view is shut down and the [`ErrorPolicy`] decides what happens
+    /// next. Use [`AsyncLiveView`] if mounting can fail and you'd rather return an error.
     ///
     /// The provided [`ViewHandle`] can be used to send messages to the view that don't come from
-    /// the client. See the documentation for [`ViewHandle`] for examples.
+    /// the client. See the documentation for [`ViewHandle`] for examples. It also gives access to
+    /// the request extensions, such as those inserted by [`on_mount`](crate::on_mount) hooks.
     #[allow(unused_variables)]
     fn mount(&mut self, uri: Uri, request_headers: &HeaderMap, handle: ViewHandle<Self::Message>) {}
 
     /// React to a message and asynchronously update the view.
     ///
-    /// If an error is returned the view will be shutdown, the JavaScript client will reconnect,
-    /// and a fresh instance of the view will be created. Ideally you should handle errors
-    /// gracefully and present them to the end user.
+    /// If this method panics the view is shut down and the [`ErrorPolicy`] decides what happens
+    /// next. Ideally you should handle errors gracefully and present them to the end user.
     fn update(self, msg: Self::Message, data: Option<EventData>) -> Updated<Self>;
 
+    /// React to the URL changing through live navigation.
+    ///
+    /// This is called when the client follows an `axm-patch` link or receives a
+    /// [`live_patch`] command. The page isn't reloaded and the view keeps its state, so this can
+    /// be used to keep things like the current page or filters in the URL.
+    ///
+    /// The default implementation does nothing.
+    ///
+    /// [`live_patch`]: crate::js_command::live_patch
+    #[allow(unused_variables)]
+    fn handle_params(self, uri: Uri) -> Updated<Self> {
+        Updated::new(self)
+    }
+
     /// Render the views HTML.
     ///
     /// This method will be called after [`update`](LiveView::update) and the changes will be
     /// effeciently sent to the client.
     fn render(&self) -> Html<Self::Message>;
+
+    /// Called right before the view is shut down, for example to persist state.
+    ///
+    /// This isn't called if the view failed, and can't be relied on if the process exits. The
+    /// socket is closed once this returns.
+    ///
+    /// The default implementation does nothing.
+    #[allow(unused_variables)]
+    fn terminate(&mut self, reason: ShutdownReason) {}
+}
+
+/// A server-rendered live view whose [`mount`](AsyncLiveView::mount) and
+/// [`update`](AsyncLiveView::update) are async and fallible.
+///
+/// This is useful when handling a message requires I/O, such as loading data from a database,
+/// since the result can be awaited directly rather than going through [`Updated::spawn`] and a
+/// second message.
+///
+/// If `mount` or `update` return an error, or the view panics, the view is shut down and the
+/// [`ErrorPolicy`] decides what happens next.
+///
+/// All [`LiveView`]s are also `AsyncLiveView`s so everything that accepts an `AsyncLiveView`,
+/// such as [`EmbedLiveView::embed`], also accepts regular live views.
+///
+/// While an update is running, messages from the client are handled according to
+/// [`AsyncLiveView::PENDING_MESSAGES`]. Messages sent through [`ViewHandle`]s are always queued.
+///
+/// # Example
+///
+/// ```
+/// use shipwright_liveview::{
+///     Html,
+///     html,
+///     event_data::EventData,
+///     live_view::{async_trait, AsyncLiveView, PendingMessages, Updated, ViewError},
+/// };
+/// use serde::{Deserialize, Serialize};
+///
+/// struct UserProfile {
+///     user_id: u64,
+///     name: Option<String>,
+/// }
+///
+/// #[async_trait]
+/// impl AsyncLiveView for UserProfile {
+///     type Message = Msg;
+///
+///     // Repeated clicks on "reload" while a load is in flight only trigger one more load.
+///     const PENDING_MESSAGES: PendingMessages = PendingMessages::Coalesce;
+///
+///     async fn update(
+///         mut self,
+///         msg: Msg,
+///         data: Option<EventData>,
+///     ) -> Result<Updated<Self>, ViewError> {
+///         match msg {
+///             Msg::Reload => {
+///                 self.name = Some(load_name(self.user_id).await?);
+///             }
+///         }
+///
+///         Ok(Updated::new(self))
+///     }
+///
+///     fn render(&self) -> Html<Self::Message> {
+///         html! {
+///             if let Some(name) = &self.name {
+///                 <p>{ name }</p>
+///             }
+///             <button axm-click={ Msg::Reload }>"reload"</button>
+///         }
+///     }
+/// }
+///
+/// async fn load_name(user_id: u64) -> Result<String, std::io::Error> {
+///     tokio::fs::read_to_string(format!("users/{user_id}/name")).await
+/// }
+///
+/// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// enum Msg {
+///     Reload,
+/// }
+/// ```
+///
+/// [`EmbedLiveView::embed`]: crate::extract::EmbedLiveView::embed
+#[async_trait]
+pub trait AsyncLiveView: Sized + Send + Sync + 'static {
+    /// The message type this view receives.
+    type Message: Serialize + DeserializeOwned + PartialEq + Send + Sync + 'static;
+
+    /// What to do with messages from the client that arrive while
+    /// [`update`](AsyncLiveView::update) is running.
+    ///
+    /// Defaults to [`PendingMessages::Queue`].
+    const PENDING_MESSAGES: PendingMessages = PendingMessages::Queue;
+
+    /// Perform additional setup of the view once its fully connected to the WebSocket.
+    ///
+    /// See [`LiveView::mount`] for more details.
+    #[allow(unused_variables)]
+    async fn mount(
+        &mut self,
+        uri: Uri,
+        request_headers: &HeaderMap,
+        handle: ViewHandle<Self::Message>,
+    ) -> Result<(), ViewError> {
+        Ok(())
+    }
+
+    /// React to a message and asynchronously update the view.
+    ///
+    /// See [`LiveView::update`] for more details.
+    async fn update(
+        self,
+        msg: Self::Message,
+        data: Option<EventData>,
+    ) -> Result<Updated<Self>, ViewError>;
+
+    /// React to the URL changing through live navigation.
+    ///
+    /// See [`LiveView::handle_params`] for more details.
+    #[allow(unused_variables)]
+    async fn handle_params(self, uri: Uri) -> Result<Updated<Self>, ViewError> {
+        Ok(Updated::new(self))
+    }
+
+    /// Render the views HTML.
+    ///
+    /// See [`LiveView::render`] for more details.
+    fn render(&self) -> Html<Self::Message>;
+
+    /// Called right before the view is shut down.
+    ///
+    /// See [`LiveView::terminate`] for more details.
+    #[allow(unused_variables)]
+    async fn terminate(&mut self, reason: ShutdownReason) {}
+}
+
+#[async_trait]
+impl<T> AsyncLiveView for T
+where
+    T: LiveView,
+{
+    type Message = T::Message;
+
+    async fn mount(
+        &mut self,
+        uri: Uri,
+        request_headers: &HeaderMap,
+        handle: ViewHandle<Self::Message>,
+    ) -> Result<(), ViewError> {
+        LiveView::mount(self, uri, request_headers, handle);
+        Ok(())
+    }
+
+    async fn update(
+        self,
+        msg: Self::Message,
+        data: Option<EventData>,
+    ) -> Result<Updated<Self>, ViewError> {
+        Ok(LiveView::update(self, msg, data))
+    }
+
+    async fn handle_params(self, uri: Uri) -> Result<Updated<Self>, ViewError> {
+        Ok(LiveView::handle_params(self, uri))
+    }
+
+    fn render(&self) -> Html<Self::Message> {
+        LiveView::render(self)
+    }
+
+    async fn terminate(&mut self, reason: ShutdownReason) {
+        LiveView::terminate(self, reason);
+    }
+}
+
+/// What to do with messages from the client that arrive while [`AsyncLiveView::update`] is
+/// running.
+///
+/// Messages are never processed concurrently. This only controls which of the messages that
+/// arrive in the meantime are processed afterwards. Messages sent through a [`ViewHandle`] are
+/// always queued, since the server relies on them being delivered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PendingMessages {
+    /// Queue all messages and process them in order once the update has finished.
+    #[default]
+    Queue,
+    /// Drop messages that arrive while an update is running.
+    Drop,
+    /// Queue messages but drop those that are equal to a message already in the queue.
+    Coalesce,
+}
+
+/// Why a live view is shut down, passed to [`LiveView::terminate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ShutdownReason {
+    /// The socket was closed, usually because the user left the page.
+    Closed,
+    /// The client navigated to another live view over the same socket.
+    LiveRedirect,
+    /// Nothing was received from the client within the [heartbeat timeout].
+    ///
+    /// [heartbeat timeout]: crate::limits::SocketLimits::heartbeat_timeout
+    HeartbeatTimeout,
+    /// The client sent no events within the [idle timeout].
+    ///
+    /// [idle timeout]: crate::limits::SocketLimits::idle_timeout
+    IdleTimeout,
+    /// The [server is shutting down](crate::shutdown). The client reconnects to another server.
+    ServerShutdown,
+    /// The socket was disconnected from the [dashboard](crate::dashboard). The client doesn't
+    /// reconnect.
+    Disconnected,
 }
 
 /// An updated live view as returned by [`LiveView::update`].
 pub struct Updated<T>
 where
-    T: LiveView,
+    T: AsyncLiveView,
 {
     pub(crate) live_view: T,
     pub(crate) js_commands: Vec<JsCommand>,
     pub(crate) spawns: Vec<Pin<Box<dyn Future<Output = T::Message> + Send + 'static>>>,
+    pub(crate) flash: Vec<FlashChange>,
 }
 
 impl<T> fmt::Debug for Updated<T>
 where
-    T: LiveView + fmt::Debug,
+    T: AsyncLiveView + fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let Self {
             live_view,
             js_commands,
             spawns: _,
+            flash,
         } = self;
 
         f.debug_struct("Updated")
             .field("live_view", &live_view)
             .field("js_commands", &js_commands)
+            .field("flash", &flash)
             .finish()
     }
 }
 
 impl<T> Updated<T>
 where
-    T: LiveView,
+    T: AsyncLiveView,
 {
     /// Create a new `Updated` from the given view.
     pub fn new(live_view: T) -> Self {
@@ -146,6 +389,7 @@ where
             live_view,
             js_commands: Default::default(),
             spawns: Default::default(),
+            flash: Default::default(),
         }
     }
 
@@ -174,6 +418,36 @@ where
         self
     }
 
+    /// Send an event to the client hooks listening for it.
+    ///
+    /// Hooks are attached to elements with the `axm-hook` attribute and receive the payload in
+    /// callbacks registered with `handleEvent`. See the [root module docs](crate#hooks) for
+    /// more details.
+    ///
+    /// Calling this method multiple times will not override previous values.
+    pub fn push_event(self, event: impl Into<String>, payload: serde_json::Value) -> Self {
+        self.with(crate::js_command::push_event(event, payload))
+    }
+
+    /// Set the [flash](crate::flash) message for `kind`, replacing any previous message of that
+    /// kind.
+    ///
+    /// The message is kept when the view navigates away, either with a live redirect or
+    /// [`navigate_to`](crate::js_command::navigate_to).
+    pub fn put_flash(mut self, kind: impl Into<String>, message: impl Into<String>) -> Self {
+        self.flash.push(FlashChange::Put {
+            kind: kind.into(),
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Remove the [flash](crate::flash) message for `kind`.
+    pub fn clear_flash(mut self, kind: impl Into<String>) -> Self {
+        self.flash.push(FlashChange::Clear { kind: kind.into() });
+        self
+    }
+
     /// Spawn a future to run when this `Updated` is passed back to axum-live-view.
     ///
     /// The future must yield a message which is trigger [`LiveView::update`] to be called.
@@ -197,11 +471,49 @@ where
         self.spawns.push(Box::pin(future));
         self
     }
+
+    /// Turn the update of a view embedded in another view into an update of the outer view.
+    ///
+    /// `view` builds the outer view from the updated inner view and `msg` converts the messages
+    /// of futures added with [`Updated::spawn`]. JS commands are kept as is.
+    ///
+    /// See [`ViewList`] for an example.
+    pub fn map<P, F, G>(self, view: F, msg: G) -> Updated<P>
+    where
+        P: AsyncLiveView,
+        F: FnOnce(T) -> P,
+        G: Fn(T::Message) -> P::Message + Clone + Send + 'static,
+    {
+        let Self {
+            live_view,
+            js_commands,
+            spawns,
+            flash,
+        } = self;
+
+        let spawns = spawns
+            .into_iter()
+            .map(|future| {
+                let msg = msg.clone();
+                Box::pin(async move {
+                    let value = future.await;
+                    msg(value)
+                }) as _
+            })
+            .collect();
+
+        Updated {
+            live_view: view(live_view),
+            js_commands,
+            spawns,
+            flash,
+        }
+    }
 }
 
 impl<T> Extend<JsCommand> for Updated<T>
 where
-    T: LiveView,
+    T: AsyncLiveView,
 {
     fn extend<I>(&mut self, iter: I)
     where
@@ -217,12 +529,36 @@ where
 /// A [`ViewHandle`] is passed to [`LiveView::mount`].
 pub struct ViewHandle<M> {
     tx: mpsc::Sender<M>,
+    uploads: upload::Registry,
+    extensions: Arc<Extensions>,
 }
 
 impl<M> ViewHandle<M> {
     pub(crate) fn new() -> (Self, mpsc::Receiver<M>) {
         let (tx, rx) = mpsc::channel(1024);
-        (Self { tx }, rx)
+        let handle = Self {
+            tx,
+            uploads: Default::default(),
+            extensions: Default::default(),
+        };
+        (handle, rx)
+    }
+
+    pub(crate) fn with_extensions(mut self, extensions: Extensions) -> Self {
+        self.extensions = Arc::new(extensions);
+        self
+    }
+
+    pub(crate) fn uploads(&self) -> &upload::Registry {
+        &self.uploads
+    }
+
+    /// The extensions of the request the view was mounted from.
+    ///
+    /// This includes the extensions inserted by [`on_mount`](crate::on_mount) hooks, such as the
+    /// current user.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
     }
 
     /// Send a message to the view.
@@ -310,28 +646,119 @@ impl<M> ViewHandle<M> {
         }
     }
 
-    pub(crate) fn with<F, M2>(self, f: F) -> ViewHandle<M2>
+    /// Create a handle for a view embedded in this view.
+    ///
+    /// Messages sent through the returned handle are converted with `f` and then sent to this
+    /// view. This is used to pass a handle to [`LiveView::mount`] of a child view, together with
+    /// [`Updated::map`].
+    pub fn with<F, M2>(self, f: F) -> ViewHandle<M2>
+    where
+        F: Fn(M2) -> M + Send + Sync + 'static,
+        M2: Send + 'static,
+        M: Send + 'static,
+    {
+        self.with_task(f).0
+    }
+
+    /// Like [`ViewHandle::with`] but also returns the forwarding task. Aborting the task closes
+    /// the returned handle.
+    pub(crate) fn with_task<F, M2>(self, f: F) -> (ViewHandle<M2>, JoinHandle<()>)
     where
         F: Fn(M2) -> M + Send + Sync + 'static,
         M2: Send + 'static,
         M: Send + 'static,
     {
         let (tx, mut rx) = mpsc::channel::<M2>(1024);
+        let uploads = self.uploads;
+        let extensions = self.extensions;
         let old_tx = self.tx;
 
         // probably not the most effecient thing to spawn here
         // might be worth moving to using a `Sink` and using `SinkExt::with`
         // will probably require boxing since `ViewHandle` should only
         // be generic over the message
+        let task = crate::util::spawn_unit(async move {
+            loop {
+                tokio::select! {
+                    msg = rx.recv() => match msg {
+                        Some(msg) => {
+                            if old_tx.send(f(msg)).await.is_err() {
+                                break;
+                            }
+                        }
+                        None => break,
+                    },
+                    // stop as soon as the view is gone so `closed` works on mapped handles
+                    () = old_tx.closed() => break,
+                }
+            }
+        });
+
+        let handle = ViewHandle {
+            tx,
+            uploads,
+            extensions,
+        };
+        (handle, task)
+    }
+
+    /// Wait until the view has been shut down.
+    ///
+    /// A view is shut down when its connection ends, including when the client disconnects. This
+    /// can be used to clean up resources associated with the view.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use shipwright_liveview::live_view::ViewHandle;
+    ///
+    /// fn on_mount(handle: ViewHandle<()>) {
+    ///     tokio::spawn(async move {
+    ///         handle.closed().await;
+    ///         println!("view disconnected");
+    ///     });
+    /// }
+    /// ```
+    pub async fn closed(&self) {
+        self.tx.closed().await
+    }
+
+    /// Allow the client to upload files to the view.
+    ///
+    /// File inputs with `axm-upload="{name}"` upload their files to the view. Each
+    /// [`UploadEvent`] is passed to `f` and the message it returns is sent to the view. Use the
+    /// returned [`Upload`] to consume files once they've been uploaded.
+    ///
+    /// Calling this again with the same name replaces the configuration. See the
+    /// [`upload`](crate::upload) module for an example.
+    ///
+    /// [`UploadEvent`]: crate::upload::UploadEvent
+    /// [`Upload`]: crate::upload::Upload
+    pub fn allow_upload<F>(&self, name: &str, config: upload::UploadConfig, f: F) -> upload::Upload
+    where
+        F: Fn(upload::UploadEvent) -> M + Send + 'static,
+        M: Send + 'static,
+    {
+        let mut events = self.uploads.allow(name, config);
+
+        let handle = self.clone();
         crate::util::spawn_unit(async move {
-            while let Some(msg) = rx.recv().await {
-                if old_tx.send(f(msg)).await.is_err() {
+            loop {
+                let event = tokio::select! {
+                    event = events.recv() => match event {
+                        Some(event) => event,
+                        None => break,
+                    },
+                    () = handle.closed() => break,
+                };
+
+                if handle.send(f(event)).await.is_err() {
                     break;
                 }
             }
         });
 
-        ViewHandle { tx }
+        upload::Upload::new(name.to_owned(), self.uploads.clone())
     }
 }
 
@@ -339,6 +766,8 @@ impl<M> Clone for ViewHandle<M> {
     fn clone(&self) -> Self {
         Self {
             tx: self.tx.clone(),
+            uploads: self.uploads.clone(),
+            extensions: self.extensions.clone(),
         }
     }
 }
@@ -452,3 +881,62 @@ impl std::error::Error for ViewHandleSendError {}
 pub fn combine<V, F>(views: V, render: F) -> combine::Combine<V, F> {
     combine::Combine { views, render }
 }
+
+/// Combine a keyed collection of live views of the same type into one.
+///
+/// `render` is called with the key and HTML of each view whenever that view changes and can be
+/// used to wrap each view in additional markup. The views are rendered in order.
+///
+/// See [`ViewList`] for more details.
+///
+/// # Example
+///
+/// ```
+/// use axum::response::IntoResponse;
+/// use shipwright_liveview::{html, live_view::combine_many, LiveView, LiveViewUpgrade};
+///
+/// async fn handler(live: LiveViewUpgrade) -> impl IntoResponse {
+///     live.response(|embed_live_view| {
+///         let rows = combine_many(
+///             [(1, Row::new()), (2, Row::new())],
+///             |id, row| html! { <li id={ format!("row-{id}") }>{ row }</li> },
+///         );
+///
+///         html! {
+///             <ul>{ embed_live_view.embed(rows) }</ul>
+///         }
+///     })
+/// }
+/// # struct Row;
+/// # impl Row { fn new() -> Self { Row } }
+/// # impl LiveView for Row {
+/// #     type Message = ();
+/// #     fn update(
+/// #         self,
+/// #         msg: (),
+/// #         data: Option<shipwright_liveview::event_data::EventData>,
+/// #     ) -> shipwright_liveview::live_view::Updated<Self> {
+/// #         todo!()
+/// #     }
+/// #     fn render(&self) -> shipwright_liveview::Html<()> {
+/// #         todo!()
+/// #     }
+/// # }
+/// ```
+pub fn combine_many<K, L, I, F>(views: I, render: F) -> ViewList<K, L>
+where
+    K: Clone + PartialEq + Serialize + DeserializeOwned + Send + Sync + 'static,
+    L: LiveView,
+    L::Message: Clone,
+    I: IntoIterator<Item = (K, L)>,
+    F: Fn(&K, Html<ViewListMsg<K, L::Message>>) -> Html<ViewListMsg<K, L::Message>>
+        + Send
+        + Sync
+        + 'static,
+{
+    let mut list = ViewList::with_render(Box::new(render));
+    for (key, view) in views {
+        list.insert(key, view);
+    }
+    list
+}
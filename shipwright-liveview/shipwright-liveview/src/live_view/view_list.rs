@@ -0,0 +1,539 @@
+use crate::{
+    event_data::EventData,
+    html::{
+        private::{DynamicFragment, DynamicFragmentVecExt, HtmlBuilder},
+        Html,
+    },
+    live_view::{ShutdownReason, Updated, ViewHandle},
+    LiveView,
+};
+use axum::http::{HeaderMap, Uri};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt;
+use tokio::task::JoinHandle;
+
+/// A keyed list of live views of the same type.
+///
+/// Unlike [`combine`] the number of views isn't fixed, so views can be added and removed while the
+/// list is running, for example one stateful widget per row in a table. Messages are routed to
+/// the right view by key.
+///
+/// Each view is rendered when it changes and the result is kept until the next change. Updating
+/// one view therefore only renders that view and the resulting diff only contains that view.
+///
+/// Created with [`ViewList::new`] or [`combine_many`].
+///
+/// # Example
+///
+/// A `ViewList` can be used as a live view of its own, or be part of another view that adds and
+/// removes rows:
+///
+/// ```
+/// use axum::http::{HeaderMap, Uri};
+/// use serde::{Deserialize, Serialize};
+/// use shipwright_liveview::{
+///     event_data::EventData,
+///     html,
+///     live_view::{Updated, ViewHandle, ViewList, ViewListMsg},
+///     Html, LiveView,
+/// };
+///
+/// struct Counters {
+///     counters: ViewList<u32, Counter>,
+///     next_id: u32,
+/// }
+///
+/// impl LiveView for Counters {
+///     type Message = Msg;
+///
+///     fn mount(&mut self, uri: Uri, request_headers: &HeaderMap, handle: ViewHandle<Msg>) {
+///         self.counters.mount(uri, request_headers, handle.with(Msg::Counter));
+///     }
+///
+///     fn update(mut self, msg: Msg, data: Option<EventData>) -> Updated<Self> {
+///         match msg {
+///             Msg::Add => {
+///                 self.counters.insert(self.next_id, Counter { count: 0 });
+///                 self.next_id += 1;
+///             }
+///             Msg::Remove(id) => {
+///                 self.counters.remove(&id);
+///             }
+///             Msg::Counter(msg) => {
+///                 let Self { counters, next_id } = self;
+///                 // forward the message to the list and put the updated list back
+///                 return counters.update(msg, data).map(
+///                     |counters| Self { counters, next_id },
+///                     Msg::Counter,
+///                 );
+///             }
+///         }
+///         Updated::new(self)
+///     }
+///
+///     fn render(&self) -> Html<Msg> {
+///         html! {
+///             <button axm-click={ Msg::Add }>"Add counter"</button>
+///             { self.counters.render().map(Msg::Counter) }
+///         }
+///     }
+/// }
+///
+/// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// enum Msg {
+///     Add,
+///     Remove(u32),
+///     Counter(ViewListMsg<u32, CounterMsg>),
+/// }
+///
+/// struct Counter {
+///     count: u64,
+/// }
+///
+/// impl LiveView for Counter {
+///     type Message = CounterMsg;
+///
+///     fn update(mut self, msg: CounterMsg, _: Option<EventData>) -> Updated<Self> {
+///         match msg {
+///             CounterMsg::Incr => self.count += 1,
+///         }
+///         Updated::new(self)
+///     }
+///
+///     fn render(&self) -> Html<CounterMsg> {
+///         html! {
+///             <div>
+///                 { self.count }
+///                 <button axm-click={ CounterMsg::Incr }>"+"</button>
+///             </div>
+///         }
+///     }
+/// }
+///
+/// #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// enum CounterMsg {
+///     Incr,
+/// }
+/// ```
+///
+/// [`combine`]: super::combine
+/// [`combine_many`]: super::combine_many
+pub struct ViewList<K, L>
+where
+    L: LiveView,
+{
+    children: Vec<Child<K, L>>,
+    render_child: Box<RenderChild<K, L::Message>>,
+    mounted: Option<Mounted<K, L::Message>>,
+}
+
+type RenderChild<K, M> =
+    dyn Fn(&K, Html<ViewListMsg<K, M>>) -> Html<ViewListMsg<K, M>> + Send + Sync + 'static;
+
+struct Child<K, L>
+where
+    L: LiveView,
+{
+    key: K,
+    view: L,
+    html: Html<ViewListMsg<K, L::Message>>,
+    // forwards messages from the view's handle, aborted when the view is removed
+    handle_task: Option<JoinHandle<()>>,
+}
+
+struct Mounted<K, M> {
+    uri: Uri,
+    headers: HeaderMap,
+    handle: ViewHandle<ViewListMsg<K, M>>,
+}
+
+/// A message for one of the views in a [`ViewList`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewListMsg<K, M> {
+    /// The key of the view.
+    pub key: K,
+    /// The message for the view.
+    pub msg: M,
+}
+
+impl<K, L> ViewList<K, L>
+where
+    K: Clone + PartialEq + Serialize + DeserializeOwned + Send + Sync + 'static,
+    L: LiveView,
+    L::Message: Clone,
+{
+    /// Create an empty `ViewList`.
+    ///
+    /// The views are rendered one after another. Use [`combine_many`] to customize how each view
+    /// is rendered.
+    ///
+    /// [`combine_many`]: super::combine_many
+    pub fn new() -> Self {
+        Self::with_render(Box::new(|_, html| html))
+    }
+
+    pub(super) fn with_render(render_child: Box<RenderChild<K, L::Message>>) -> Self {
+        Self {
+            children: Vec::new(),
+            render_child,
+            mounted: None,
+        }
+    }
+
+    /// Add a view with the given key to the end of the list.
+    ///
+    /// If the list has already been mounted the view is mounted immediately. If the key is
+    /// already in the list the view is replaced, keeping its position, and the old view is
+    /// returned.
+    pub fn insert(&mut self, key: K, mut view: L) -> Option<L> {
+        let handle_task = self
+            .mounted
+            .as_ref()
+            .map(|mounted| mounted.mount_child(&key, &mut view));
+        let child = self.child(key, view, handle_task);
+
+        if let Some(idx) = self.position(&child.key) {
+            let old = std::mem::replace(&mut self.children[idx], child);
+            Some(old.into_view())
+        } else {
+            self.children.push(child);
+            None
+        }
+    }
+
+    /// Remove the view with the given key from the list.
+    ///
+    /// The view's [`ViewHandle`] is closed so anything started from [`LiveView::mount`], such as
+    /// [`PubSub`] subscriptions, is cleaned up.
+    ///
+    /// [`PubSub`]: crate::pubsub::PubSub
+    pub fn remove(&mut self, key: &K) -> Option<L> {
+        let idx = self.position(key)?;
+        Some(self.children.remove(idx).into_view())
+    }
+
+    /// Get the view with the given key.
+    pub fn get(&self, key: &K) -> Option<&L> {
+        let idx = self.position(key)?;
+        Some(&self.children[idx].view)
+    }
+
+    /// Iterate over the keys in the list, in order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.children.iter().map(|child| &child.key)
+    }
+
+    /// The number of views in the list.
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Whether the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    fn position(&self, key: &K) -> Option<usize> {
+        self.children.iter().position(|child| child.key == *key)
+    }
+
+    fn child(&self, key: K, view: L, handle_task: Option<JoinHandle<()>>) -> Child<K, L> {
+        let html = render_child(&*self.render_child, &key, &view);
+        Child {
+            key,
+            view,
+            html,
+            handle_task,
+        }
+    }
+}
+
+fn render_child<K, L>(
+    render: &RenderChild<K, L::Message>,
+    key: &K,
+    view: &L,
+) -> Html<ViewListMsg<K, L::Message>>
+where
+    K: Clone,
+    L: LiveView,
+{
+    let html = view.render().map(|msg| ViewListMsg {
+        key: key.clone(),
+        msg,
+    });
+    render(key, html)
+}
+
+impl<K, M> Mounted<K, M>
+where
+    K: Clone + Send + Sync + 'static,
+    M: Send + 'static,
+{
+    fn mount_child<L>(&self, key: &K, view: &mut L) -> JoinHandle<()>
+    where
+        L: LiveView<Message = M>,
+    {
+        let key = key.clone();
+        let (handle, task) = self.handle.clone().with_task(move |msg| ViewListMsg {
+            key: key.clone(),
+            msg,
+        });
+        view.mount(self.uri.clone(), &self.headers, handle);
+        task
+    }
+}
+
+impl<K, L> Child<K, L>
+where
+    L: LiveView,
+{
+    fn into_view(self) -> L {
+        if let Some(task) = self.handle_task {
+            task.abort();
+        }
+        self.view
+    }
+}
+
+impl<K, L> LiveView for ViewList<K, L>
+where
+    K: Clone + PartialEq + Serialize + DeserializeOwned + Send + Sync + 'static,
+    L: LiveView,
+    L::Message: Clone,
+{
+    type Message = ViewListMsg<K, L::Message>;
+
+    fn mount(&mut self, uri: Uri, request_headers: &HeaderMap, handle: ViewHandle<Self::Message>) {
+        let mounted = Mounted {
+            uri,
+            headers: request_headers.clone(),
+            handle,
+        };
+
+        for child in &mut self.children {
+            child.handle_task = Some(mounted.mount_child(&child.key, &mut child.view));
+            child.html = render_child(&*self.render_child, &child.key, &child.view);
+        }
+
+        self.mounted = Some(mounted);
+    }
+
+    fn update(mut self, msg: Self::Message, data: Option<EventData>) -> Updated<Self> {
+        let ViewListMsg { key, msg } = msg;
+
+        let idx = match self.position(&key) {
+            Some(idx) => idx,
+            None => {
+                tracing::trace!("dropping message for view that was removed from the list");
+                return Updated::new(self);
+            }
+        };
+
+        let Child {
+            key,
+            view,
+            html: _,
+            handle_task,
+        } = self.children.remove(idx);
+
+        let msg_key = key.clone();
+        view.update(msg, data).map(
+            move |view| {
+                let child = self.child(key, view, handle_task);
+                self.children.insert(idx, child);
+                self
+            },
+            move |msg| ViewListMsg {
+                key: msg_key.clone(),
+                msg,
+            },
+        )
+    }
+
+    fn handle_params(mut self, uri: Uri) -> Updated<Self> {
+        let mut js_commands = Vec::new();
+        let mut spawns = Vec::new();
+        let mut flash = Vec::new();
+
+        for child in std::mem::take(&mut self.children) {
+            let Child {
+                key,
+                view,
+                html: _,
+                handle_task,
+            } = child;
+
+            let Updated {
+                live_view: view,
+                js_commands: child_js_commands,
+                spawns: child_spawns,
+                flash: child_flash,
+            } = view.handle_params(uri.clone());
+            js_commands.extend(child_js_commands);
+            flash.extend(child_flash);
+            spawns.extend(child_spawns.into_iter().map(|future| {
+                let key = key.clone();
+                Box::pin(async move {
+                    ViewListMsg {
+                        key,
+                        msg: future.await,
+                    }
+                }) as _
+            }));
+
+            let child = self.child(key, view, handle_task);
+            self.children.push(child);
+        }
+
+        Updated {
+            live_view: self,
+            js_commands,
+            spawns,
+            flash,
+        }
+    }
+
+    fn terminate(&mut self, reason: ShutdownReason) {
+        for child in &mut self.children {
+            child.view.terminate(reason);
+        }
+    }
+
+    fn render(&self) -> Html<Self::Message> {
+        // keyed by the views' keys so adding and removing views doesn't change the other rows
+        let mut dynamic = Vec::new();
+        dynamic.push_keyed_fragments(
+            &["", ""],
+            self.children
+                .iter()
+                .map(|child| {
+                    let key = serde_json::to_string(&child.key).expect("failed to serialize key");
+                    (key, Vec::from([DynamicFragment::Html(child.html.clone())]))
+                })
+                .collect(),
+        );
+
+        HtmlBuilder {
+            fixed: &["", ""],
+            dynamic,
+        }
+        .into_html()
+    }
+}
+
+impl<K, L> Default for ViewList<K, L>
+where
+    K: Clone + PartialEq + Serialize + DeserializeOwned + Send + Sync + 'static,
+    L: LiveView,
+    L::Message: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, L> fmt::Debug for ViewList<K, L>
+where
+    K: fmt::Debug,
+    L: LiveView,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ViewList")
+            .field(
+                "keys",
+                &self
+                    .children
+                    .iter()
+                    .map(|child| &child.key)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{live_view::combine_many, test::run_live_view};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn routes_messages_by_key() {
+        let list = combine_many([(1, Counter(0)), (2, Counter(0))], |_, html| html);
+        let view = run_live_view(list).mount().await;
+
+        let (html, _) = view.send(ViewListMsg { key: 2, msg: Incr }, None).await;
+        assert!(html.contains("01"));
+
+        // messages for unknown keys are ignored
+        let (html, _) = view.send(ViewListMsg { key: 3, msg: Incr }, None).await;
+        assert!(html.contains("01"));
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut list = ViewList::new();
+        assert!(list.insert(1, Counter(0)).is_none());
+        assert!(list.insert(2, Counter(0)).is_none());
+        assert!(list.insert(3, Counter(0)).is_none());
+
+        assert_eq!(list.insert(1, Counter(5)).map(|counter| counter.0), Some(0));
+        assert_eq!(list.remove(&2).map(|counter| counter.0), Some(0));
+        assert!(list.remove(&2).is_none());
+
+        assert_eq!(list.keys().copied().collect::<Vec<_>>(), [1, 3]);
+        assert_eq!(list.get(&1).map(|counter| counter.0), Some(5));
+        assert_eq!(list.render().render(), "50");
+    }
+
+    #[test]
+    fn only_changed_view_is_diffed() {
+        let list = combine_many([(1, Counter(0)), (2, Counter(0))], |_, html| html);
+        let before = list.render();
+
+        let list = list
+            .update(ViewListMsg { key: 2, msg: Incr }, None)
+            .live_view;
+        let after = list.render();
+
+        let diff = json!(before.diff(&after));
+        assert_eq!(
+            diff,
+            json!({ "d": { "0": { "u": { "2": { "0": { "d": { "0": "1" } } } } } } })
+        );
+    }
+
+    #[test]
+    fn removing_a_view_keeps_the_others() {
+        let mut list = combine_many(
+            [(1, Counter(0)), (2, Counter(0)), (3, Counter(0))],
+            |_, html| html,
+        );
+        let before = list.render();
+
+        list.remove(&1);
+        let after = list.render();
+
+        let diff = json!(before.diff(&after));
+        // only the removed row is sent
+        assert_eq!(diff, json!({ "d": { "0": { "r": ["1"] } } }));
+    }
+
+    struct Counter(u64);
+
+    impl LiveView for Counter {
+        type Message = Incr;
+
+        fn update(mut self, _msg: Incr, _data: Option<EventData>) -> Updated<Self> {
+            self.0 += 1;
+            Updated::new(self)
+        }
+
+        fn render(&self) -> Html<Incr> {
+            Html::raw(self.0.to_string())
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Incr;
+}
@@ -0,0 +1,343 @@
+//! Opaque handles for the messages in rendered HTML.
+//!
+//! Used by [`LiveViewUpgrade::protect_messages`].
+//!
+//! [`LiveViewUpgrade::protect_messages`]: crate::LiveViewUpgrade::protect_messages
+
+use crate::{
+    html::{Html, StreamOp},
+    life_cycle::MessageFromSocket,
+    signing::SigningKey,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::Mutex,
+};
+
+const PURPOSE: &str = "message handle";
+
+/// The handles offered to the client by a view, and the messages they stand for.
+pub(crate) struct MessageHandles {
+    // only used to derive handles so each view gets its own
+    key: SigningKey,
+    offered: Mutex<Offered>,
+}
+
+#[derive(Default)]
+struct Offered {
+    // handle -> message serialized as JSON
+    rendered: HashMap<String, String>,
+    // stream rows are only sent once, so their handles are kept until the row is deleted, replaced
+    // or the stream is reset
+    // handle -> message serialized as JSON, and the number of rows it is in
+    streamed: HashMap<String, (String, usize)>,
+    // (stream, row key) -> handles in the row
+    rows: HashMap<(u64, String), Vec<String>>,
+}
+
+impl Offered {
+    fn insert_streamed(&mut self, row: (u64, String), handle: String, json: String) {
+        let handles = self.rows.entry(row).or_default();
+        if handles.contains(&handle) {
+            return;
+        }
+        self.streamed.entry(handle.clone()).or_insert((json, 0)).1 += 1;
+        handles.push(handle);
+    }
+
+    fn remove_row(&mut self, row: &(u64, String)) {
+        for handle in self.rows.remove(row).unwrap_or_default() {
+            if let Some((_, count)) = self.streamed.get_mut(&handle) {
+                *count -= 1;
+                if *count == 0 {
+                    self.streamed.remove(&handle);
+                }
+            }
+        }
+    }
+
+    fn remove_stream(&mut self, stream: u64) {
+        let rows = self
+            .rows
+            .keys()
+            .filter(|(row_stream, _)| *row_stream == stream)
+            .cloned()
+            .collect::<Vec<_>>();
+        for row in rows {
+            self.remove_row(&row);
+        }
+    }
+}
+
+impl MessageHandles {
+    pub(crate) fn new() -> Self {
+        Self {
+            key: SigningKey::generate(),
+            offered: Default::default(),
+        }
+    }
+
+    /// Replace the messages in `html` with handles.
+    ///
+    /// Afterwards only the handles in `html`, and those of the stream rows the client still
+    /// shows, are accepted.
+    pub(crate) fn protect<M>(&self, html: Html<M>) -> Html<M>
+    where
+        M: Serialize,
+    {
+        let mut offered = self.offered.lock().unwrap();
+        let mut rendered = HashMap::new();
+
+        // the rows changed by the streams' operations are forgotten, and those still shown
+        // afterwards offered again
+        let mut shown = HashSet::new();
+        html.for_each_stream(&mut |stream| {
+            if stream.reset {
+                offered.remove_stream(stream.stream);
+            }
+            for op in &stream.ops {
+                let (StreamOp::Insert { key, .. } | StreamOp::Delete { key }) = op;
+                offered.remove_row(&(stream.stream, key.clone()));
+            }
+            shown.extend(
+                stream
+                    .rows()
+                    .into_iter()
+                    .map(|(key, _)| (stream.stream, key.to_owned())),
+            );
+        });
+
+        let html = html.replace_messages(|msg, row| {
+            let json = serde_json::to_string(&msg).expect("failed to serialize message");
+            let handle = self.key.tag(PURPOSE, json.as_bytes());
+            // rendered the same way as a message that is a JSON string, so the client doesn't
+            // have to know about handles
+            let rendered_handle = format!("%22{handle}%22");
+
+            match row {
+                Some(row) => {
+                    // rows deleted again in the same batch never reach the client
+                    let row = (row.stream, row.key.to_owned());
+                    if shown.contains(&row) {
+                        offered.insert_streamed(row, handle, json);
+                    }
+                }
+                None => {
+                    rendered.insert(handle, json);
+                }
+            }
+
+            rendered_handle
+        });
+
+        offered.rendered = rendered;
+        html
+    }
+
+    /// Replace the handle in an event from the client with the message it stands for.
+    ///
+    /// Returns `None` if the handle wasn't offered.
+    pub(crate) fn resolve<M>(
+        &self,
+        msg: MessageFromSocket<String>,
+    ) -> Option<anyhow::Result<MessageFromSocket<M>>>
+    where
+        M: DeserializeOwned,
+    {
+        let msg = match msg {
+            MessageFromSocket::Event { msg: handle, data } => {
+                let offered = self.offered.lock().unwrap();
+                let Some(json) = offered
+                    .rendered
+                    .get(&handle)
+                    .or_else(|| offered.streamed.get(&handle).map(|(json, _)| json))
+                else {
+                    tracing::debug!(%handle, "ignoring message that wasn't rendered");
+                    return None;
+                };
+
+                match serde_json::from_str(json) {
+                    Ok(msg) => MessageFromSocket::Event { msg, data },
+                    Err(err) => return Some(Err(err.into())),
+                }
+            }
+            MessageFromSocket::Internal { data } => MessageFromSocket::Internal { data },
+            MessageFromSocket::UploadChunk(chunk) => MessageFromSocket::UploadChunk(chunk),
+            MessageFromSocket::Shutdown(reason) => MessageFromSocket::Shutdown(reason),
+        };
+
+        Some(Ok(msg))
+    }
+}
+
+impl fmt::Debug for MessageHandles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let offered = self.offered.lock().unwrap();
+        f.debug_struct("MessageHandles")
+            .field("rendered", &offered.rendered.len())
+            .field("streamed", &offered.streamed.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{html, life_cycle::EventMessageFromSocketData, stream::Stream};
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Msg {
+        Delete(u64),
+        Clear,
+    }
+
+    fn event(rendered_handle: &str) -> MessageFromSocket<String> {
+        let event = serde_json::json!({ "m": rendered_handle, "t": "click" });
+        serde_json::from_value(event).unwrap()
+    }
+
+    fn handles(html: &str) -> Vec<&str> {
+        html.split("axm-click=")
+            .skip(1)
+            .map(|rest| rest.split('>').next().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn only_current_render_is_accepted() {
+        let handles_table = MessageHandles::new();
+
+        let render = |ids: &[u64]| -> Html<Msg> {
+            html! {
+                for id in ids {
+                    <button axm-click={ Msg::Delete(*id) }>"Delete"</button>
+                }
+            }
+        };
+
+        let first = handles_table.protect(render(&[1, 2])).render();
+        assert!(!first.contains("Delete%28"));
+        let first_handles = handles(&first);
+        assert_eq!(first_handles.len(), 2);
+
+        let resolved = handles_table
+            .resolve::<Msg>(event(first_handles[1]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            resolved,
+            MessageFromSocket::Event {
+                msg: Msg::Delete(2),
+                data: EventMessageFromSocketData::Click,
+            }
+        );
+
+        // equal messages get equal handles so diffs stay small
+        let second = handles_table.protect(render(&[1])).render();
+        assert_eq!(handles(&second), [first_handles[0]]);
+        assert!(handles_table
+            .resolve::<Msg>(event(first_handles[1]))
+            .is_none());
+
+        // messages the view never rendered are rejected
+        let json = serde_json::to_string(&Msg::Clear).unwrap();
+        let forged =
+            percent_encoding::utf8_percent_encode(&json, percent_encoding::NON_ALPHANUMERIC);
+        assert!(handles_table
+            .resolve::<Msg>(event(&forged.to_string()))
+            .is_none());
+    }
+
+    #[test]
+    fn stream_rows_stay_accepted() {
+        let handles_table = MessageHandles::new();
+        let mut entries = Stream::<u64, u64>::new();
+        entries.insert(1, 1);
+
+        let render = |entries: &Stream<u64, u64>| -> Html<Msg> {
+            html! {
+                {
+                    entries.render(|id| html! {
+                        <button axm-click={ Msg::Delete(*id) }>"Delete"</button>
+                    })
+                }
+                <button axm-click={ Msg::Clear }>"Clear"</button>
+            }
+        };
+
+        let first = handles_table.protect(render(&entries)).render();
+        let first_handles = handles(&first);
+
+        entries.insert(2, 2);
+        handles_table.protect(render(&entries));
+
+        let resolved = handles_table
+            .resolve::<Msg>(event(first_handles[0]))
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            resolved,
+            MessageFromSocket::Event {
+                msg: Msg::Delete(1),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn removed_stream_rows_are_forgotten() {
+        let handles_table = MessageHandles::new();
+        let mut entries = Stream::<u64, u64>::new();
+
+        let render = |entries: &Stream<u64, u64>| -> Html<Msg> {
+            html! {
+                {
+                    entries.render(|id| html! {
+                        <button axm-click={ Msg::Delete(*id) }>"Delete"</button>
+                    })
+                }
+            }
+        };
+        let accepted = |handle: &str| handles_table.resolve::<Msg>(event(handle)).is_some();
+
+        entries.insert(1, 1);
+        entries.insert(2, 2);
+        entries.insert(3, 3);
+        let first = handles_table.protect(render(&entries)).render();
+        let first_handles = handles(&first);
+
+        // deleted rows
+        entries.delete(&1);
+        handles_table.protect(render(&entries));
+        assert!(!accepted(first_handles[0]));
+        assert!(accepted(first_handles[1]));
+
+        // rows replaced with a different message
+        entries.insert(2, 4);
+        handles_table.protect(render(&entries));
+        assert!(!accepted(first_handles[1]));
+        assert!(accepted(first_handles[2]));
+
+        // rows inserted and deleted before they are rendered
+        entries.insert(5, 5);
+        entries.delete(&5);
+        handles_table.protect(render(&entries));
+        let json = serde_json::to_string(&Msg::Delete(5)).unwrap();
+        let handle = handles_table.key.tag(PURPOSE, json.as_bytes());
+        assert!(!accepted(&format!("%22{handle}%22")));
+
+        // rendering the same changes again doesn't forget anything
+        handles_table.protect(render(&entries));
+        assert!(accepted(first_handles[2]));
+
+        // all rows after a reset
+        entries.reset();
+        handles_table.protect(render(&entries));
+        assert!(!accepted(first_handles[2]));
+        assert!(handles_table.offered.lock().unwrap().rows.is_empty());
+        assert!(handles_table.offered.lock().unwrap().streamed.is_empty());
+    }
+}
@@ -0,0 +1,761 @@
+//! Several independent live views sharing one socket.
+//!
+//! A page can embed any number of live views with [`LiveViewUpgrade::response_many`]. Each view
+//! is embedded with an id that is unique on the page, and the JavaScript client connects all of
+//! them over a single socket instead of opening one per view.
+//!
+//! Every view has its own life cycle. It's mounted, limited by the [`SocketLimits`] and handled
+//! by the [`ErrorPolicy`] on its own, so a view that fails or is closed doesn't affect the others
+//! on the socket. The client mounts a closed view again by rejoining it, for which the closure
+//! passed to `response_many` is called again and only the view with the rejoined id is kept.
+//!
+//! Views can read their id from the [`ViewId`] extension, for example to use it as the topic of
+//! their [pubsub](crate::pubsub) subscriptions.
+//!
+//! # Example
+//!
+//! ```rust
+//! use axum::response::IntoResponse;
+//! use shipwright_liveview::{html, LiveViewUpgrade};
+//!
+//! async fn handler(live: LiveViewUpgrade) -> impl IntoResponse {
+//!     live.response_many(|views| {
+//!         html! {
+//!             <aside>{ views.embed("cart", Cart::default()) }</aside>
+//!             <main>{ views.embed("catalog", Catalog::default()) }</main>
+//!
+//!             <script src="/assets/live-view.js"></script>
+//!         }
+//!     })
+//! }
+//! # #[derive(Default)]
+//! # struct Cart;
+//! # impl shipwright_liveview::LiveView for Cart {
+//! #     type Message = ();
+//! #     fn update(
+//! #         self,
+//! #         msg: (),
+//! #         data: Option<shipwright_liveview::event_data::EventData>,
+//! #     ) -> shipwright_liveview::live_view::Updated<Self> {
+//! #         todo!()
+//! #     }
+//! #     fn render(&self) -> shipwright_liveview::Html<()> {
+//! #         todo!()
+//! #     }
+//! # }
+//! # #[derive(Default)]
+//! # struct Catalog;
+//! # impl shipwright_liveview::LiveView for Catalog {
+//! #     type Message = ();
+//! #     fn update(
+//! #         self,
+//! #         msg: (),
+//! #         data: Option<shipwright_liveview::event_data::EventData>,
+//! #     ) -> shipwright_liveview::live_view::Updated<Self> {
+//! #         todo!()
+//! #     }
+//! #     fn render(&self) -> shipwright_liveview::Html<()> {
+//! #         todo!()
+//! #     }
+//! # }
+//! ```
+//!
+//! # Protocol
+//!
+//! - Each view is rendered in a `<div data-live-view-id="...">` container instead of
+//!   `<div id="live-view-container">`. The client connects to the same URL as for a single view.
+//! - The messages are those of a single view, tagged with the id of their view. JSON messages have
+//!   a `"v"` field with the id. Binary messages start with the length of the id as one byte,
+//!   followed by the id. Client messages without an id, or with an empty id, such as heartbeats,
+//!   are sent to every view.
+//! - Instead of a close frame, a view that is closed sends
+//!   `{"v": <id>, "t": "c", "d": {"c": <code>, "r": <reason>}}` with the usual close code. The
+//!   socket stays open.
+//! - The client mounts a closed view again by sending `{"v": <id>, "t": "join"}` as a text
+//!   message.
+//! - When the server [shuts down](crate::shutdown) each view is told that it's going away and the
+//!   socket is closed once all of them have stopped.
+//!
+//! [`LiveViewUpgrade::response_many`]: crate::LiveViewUpgrade::response_many
+//! [`SocketLimits`]: crate::limits::SocketLimits
+//! [`ErrorPolicy`]: crate::live_view::ErrorPolicy
+
+use crate::{
+    codec::Encoding,
+    extract::{self, ViewOptions},
+    html::Html,
+    life_cycle::{close_code, MessageToSocket},
+    live_view::AsyncLiveView,
+    message_handles::MessageHandles,
+    shutdown::Shutdown,
+    socket::Socket,
+    util::ReceiverStream,
+    PreEscaped,
+};
+use axum::{
+    extract::ws,
+    http::{HeaderMap, Uri},
+};
+use futures_util::{future::BoxFuture, Sink, SinkExt, Stream, StreamExt};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, future,
+    pin::{pin, Pin},
+    task::{Context, Poll},
+};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio_util::sync::PollSender;
+
+/// How many messages can be buffered for each view, and from all views to the socket.
+const CHANNEL_CAPACITY: usize = 32;
+
+/// The longest allowed view id, in bytes.
+const MAX_ID_LEN: usize = 64;
+
+/// Type used to embed several live views in HTML templates.
+///
+/// See [`LiveViewUpgrade::response_many`] for more details.
+///
+/// [`LiveViewUpgrade::response_many`]: crate::LiveViewUpgrade::response_many
+pub struct EmbedLiveViews {
+    ids: HashSet<String>,
+    kind: EmbedKind,
+}
+
+enum EmbedKind {
+    Http {
+        csrf_token: String,
+        protect_messages: bool,
+    },
+    Socket {
+        views: Vec<(String, MountView)>,
+    },
+}
+
+/// Runs a view on its channel of the socket.
+type MountView = Box<dyn FnOnce(Socket, Mount) -> BoxFuture<'static, ()> + Send>;
+
+impl EmbedLiveViews {
+    pub(crate) fn http(csrf_token: String, protect_messages: bool) -> Self {
+        Self {
+            ids: HashSet::new(),
+            kind: EmbedKind::Http {
+                csrf_token,
+                protect_messages,
+            },
+        }
+    }
+
+    fn socket() -> Self {
+        Self {
+            ids: HashSet::new(),
+            kind: EmbedKind::Socket { views: Vec::new() },
+        }
+    }
+
+    /// Embed a live view with the given id in a HTML template.
+    ///
+    /// When rendering over HTTP this also embeds the [CSRF token](crate::csrf) the client needs
+    /// to connect.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id is used for another view, or if it isn't 1 to 64 ASCII letters, digits,
+    /// `-`, `_`, `:` or `.`.
+    pub fn embed<L>(&mut self, id: impl Into<String>, view: L) -> PreEscaped<String>
+    where
+        L: AsyncLiveView,
+    {
+        let id = id.into();
+        assert!(
+            is_valid_id(&id),
+            "invalid live view id `{id}`, ids must be 1 to {MAX_ID_LEN} ASCII letters, digits, \
+             `-`, `_`, `:` or `.`"
+        );
+        assert!(
+            self.ids.insert(id.clone()),
+            "live view id `{id}` is embedded more than once"
+        );
+
+        match &mut self.kind {
+            EmbedKind::Http {
+                csrf_token,
+                protect_messages,
+            } => {
+                let mut markup = view.render();
+                if *protect_messages {
+                    // the handles are only used until the socket connects and renders the view again
+                    markup = MessageHandles::new().protect(markup);
+                }
+
+                PreEscaped(format!(
+                    "<div data-live-view-id=\"{id}\" data-csrf-token=\"{csrf_token}\">{}</div>",
+                    markup.render()
+                ))
+            }
+            EmbedKind::Socket { views } => {
+                let mount_view: MountView = Box::new(move |socket, mount| {
+                    Box::pin(extract::run_view_on_socket(
+                        socket,
+                        mount.encoding,
+                        view,
+                        mount.uri,
+                        mount.headers,
+                        mount.options,
+                    ))
+                });
+                views.push((id, mount_view));
+                PreEscaped(String::new())
+            }
+        }
+    }
+
+    /// Check if the views are being embedded for a connected socket, rather than the initial
+    /// HTTP request.
+    pub fn connected(&self) -> bool {
+        matches!(self.kind, EmbedKind::Socket { .. })
+    }
+
+    fn into_views(self) -> Vec<(String, MountView)> {
+        match self.kind {
+            EmbedKind::Http { .. } => Vec::new(),
+            EmbedKind::Socket { views } => views,
+        }
+    }
+}
+
+impl fmt::Debug for EmbedLiveViews {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmbedLiveViews")
+            .field("ids", &self.ids)
+            .finish()
+    }
+}
+
+fn is_valid_id(id: &str) -> bool {
+    (1..=MAX_ID_LEN).contains(&id.len())
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b':' | b'.'))
+}
+
+/// The id a view was embedded with by [`EmbedLiveViews::embed`].
+///
+/// Available from [`ViewHandle::extensions`] for views that share a socket.
+///
+/// [`ViewHandle::extensions`]: crate::live_view::ViewHandle::extensions
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ViewId(String);
+
+impl ViewId {
+    /// The id as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ViewId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// What every view on the socket is mounted with.
+#[derive(Clone)]
+pub(crate) struct Mount {
+    pub(crate) encoding: Encoding,
+    pub(crate) uri: Uri,
+    pub(crate) headers: HeaderMap,
+    pub(crate) options: ViewOptions,
+}
+
+/// Run the views gathered by `gather_views` on one socket until it's closed.
+pub(crate) async fn run<F>(socket: Socket, mount: Mount, gather_views: F)
+where
+    F: Fn(&mut EmbedLiveViews) -> Html<()> + Send + Sync + 'static,
+{
+    let (mut write, mut read) = socket.split();
+    let (outgoing_tx, mut outgoing_rx) = mpsc::channel(CHANNEL_CAPACITY);
+    let shutdown_started = mount.options.shutdown.as_ref().map(Shutdown::started);
+
+    let mut mux = Mux {
+        views: HashMap::new(),
+        outgoing: Some(outgoing_tx),
+        mount,
+    };
+    let mut embed = EmbedLiveViews::socket();
+    gather_views(&mut embed);
+    for (id, mount_view) in embed.into_views() {
+        mux.mount(id, mount_view);
+    }
+
+    let mut shutdown = pin!(async move {
+        match shutdown_started {
+            Some(started) => started.await,
+            None => future::pending().await,
+        }
+    });
+
+    loop {
+        tokio::select! {
+            msg = read.next() => {
+                let Some(Ok(msg)) = msg else {
+                    // dropping the channels ends the views
+                    break;
+                };
+                for msg in mux.route(msg, &gather_views) {
+                    if write.send(msg).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            msg = outgoing_rx.recv() => {
+                let Some(msg) = msg else {
+                    // the server is shutting down and every view has stopped
+                    let close = MessageToSocket::close(close_code::GOING_AWAY, "server shutting down");
+                    if let Ok(msg) = mux.mount.encoding.encode(&close) {
+                        let _ = write.send(msg).await;
+                    }
+                    break;
+                };
+                if write.send(msg).await.is_err() {
+                    break;
+                }
+            }
+            () = &mut shutdown, if mux.outgoing.is_some() => {
+                // the views close themselves, and no more are mounted
+                mux.outgoing = None;
+            }
+        }
+    }
+}
+
+struct Mux {
+    // each view's stream of messages from the socket
+    views: HashMap<String, mpsc::Sender<ws::Message>>,
+    // `None` once the server starts shutting down
+    outgoing: Option<mpsc::Sender<ws::Message>>,
+    mount: Mount,
+}
+
+impl Mux {
+    fn mount(&mut self, id: String, mount_view: MountView) {
+        let Some(outgoing) = &self.outgoing else {
+            return;
+        };
+
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let socket = ChannelSocket {
+            id: id.clone(),
+            encoding: self.mount.encoding,
+            incoming: ReceiverStream::new(rx),
+            outgoing: PollSender::new(outgoing.clone()),
+        };
+        let mut mount = self.mount.clone();
+        mount.options.extensions.insert(ViewId(id.clone()));
+
+        crate::util::spawn_unit(mount_view(Socket::Channel(socket), mount));
+        self.views.insert(id, tx);
+    }
+
+    /// Pass a message from the socket to its view.
+    ///
+    /// Returns the messages to send back to the client.
+    fn route<F>(&mut self, msg: ws::Message, gather_views: &F) -> Vec<ws::Message>
+    where
+        F: Fn(&mut EmbedLiveViews) -> Html<()>,
+    {
+        match Frame::parse(msg) {
+            Some(Frame::Join(id)) => {
+                self.join(id, gather_views);
+                Vec::new()
+            }
+            Some(Frame::View(id, msg)) => self.forward(&id, msg).into_iter().collect(),
+            Some(Frame::All(msg)) => {
+                let ids = self.views.keys().cloned().collect::<Vec<_>>();
+                ids.iter()
+                    .filter_map(|id| self.forward(id, msg.clone()))
+                    .collect()
+            }
+            None => {
+                tracing::trace!("ignoring message without a valid view id");
+                Vec::new()
+            }
+        }
+    }
+
+    fn join<F>(&mut self, id: String, gather_views: &F)
+    where
+        F: Fn(&mut EmbedLiveViews) -> Html<()>,
+    {
+        if self.outgoing.is_none() {
+            return;
+        }
+        if self.views.get(&id).is_some_and(|tx| !tx.is_closed()) {
+            tracing::trace!(id, "ignoring join for a view that is running");
+            return;
+        }
+
+        let mut embed = EmbedLiveViews::socket();
+        gather_views(&mut embed);
+        match embed
+            .into_views()
+            .into_iter()
+            .find(|(view_id, _)| *view_id == id)
+        {
+            Some((id, mount_view)) => self.mount(id, mount_view),
+            None => tracing::debug!(id, "ignoring join for an unknown view"),
+        }
+    }
+
+    /// Returns the message closing the view if it can't keep up.
+    fn forward(&mut self, id: &str, msg: ws::Message) -> Option<ws::Message> {
+        let Some(tx) = self.views.get(id) else {
+            tracing::trace!(id, "ignoring message for an unknown view");
+            return None;
+        };
+
+        match tx.try_send(msg) {
+            Ok(()) => None,
+            Err(TrySendError::Closed(_)) => {
+                self.views.remove(id);
+                None
+            }
+            Err(TrySendError::Full(_)) => {
+                // waiting would hold up the other views, so this one is ended as if its socket
+                // was closed
+                tracing::debug!(
+                    id,
+                    "closing live view that isn't keeping up with its messages"
+                );
+                self.views.remove(id);
+                closed_message(
+                    id,
+                    self.mount.encoding,
+                    close_code::POLICY_VIOLATION,
+                    "too many messages",
+                )
+            }
+        }
+    }
+}
+
+/// A message from the client.
+#[derive(Debug)]
+enum Frame {
+    Join(String),
+    View(String, ws::Message),
+    All(ws::Message),
+}
+
+impl Frame {
+    fn parse(msg: ws::Message) -> Option<Self> {
+        #[derive(Deserialize)]
+        struct Envelope {
+            v: Option<String>,
+            t: Option<Value>,
+        }
+
+        match msg {
+            ws::Message::Text(text) => {
+                let envelope = serde_json::from_str::<Envelope>(text.as_str()).ok()?;
+                match envelope.v.filter(|id| !id.is_empty()) {
+                    Some(id) if envelope.t.as_ref().and_then(Value::as_str) == Some("join") => {
+                        Some(Self::Join(id))
+                    }
+                    Some(id) => Some(Self::View(id, ws::Message::Text(text))),
+                    None => Some(Self::All(ws::Message::Text(text))),
+                }
+            }
+            ws::Message::Binary(data) => {
+                let len = usize::from(*data.first()?);
+                let id = data.get(1..1 + len)?;
+                let id = std::str::from_utf8(id).ok()?.to_owned();
+                let msg = ws::Message::Binary(data.slice(1 + len..));
+                if id.is_empty() {
+                    Some(Self::All(msg))
+                } else {
+                    Some(Self::View(id, msg))
+                }
+            }
+            ws::Message::Ping(_) | ws::Message::Pong(_) | ws::Message::Close(_) => None,
+        }
+    }
+}
+
+/// Tag a message from a view with its id.
+///
+/// Close frames are replaced by a message saying the view was closed. Returns `None` for messages
+/// that aren't sent to the client.
+fn tag(id: &str, encoding: Encoding, msg: ws::Message) -> Option<ws::Message> {
+    match msg {
+        // messages are JSON objects so the id is added as their first field
+        ws::Message::Text(text) => match text.as_str().strip_prefix('{') {
+            Some(rest) => Some(ws::Message::Text(format!("{{\"v\":\"{id}\",{rest}").into())),
+            None => Some(ws::Message::Text(text)),
+        },
+        ws::Message::Binary(data) => {
+            let mut tagged = Vec::with_capacity(1 + id.len() + data.len());
+            tagged.push(id.len() as u8);
+            tagged.extend_from_slice(id.as_bytes());
+            tagged.extend_from_slice(&data);
+            Some(ws::Message::Binary(tagged.into()))
+        }
+        ws::Message::Close(frame) => {
+            let (code, reason) = frame
+                .map(|frame| (frame.code, frame.reason.to_string()))
+                .unwrap_or((ws::close_code::NORMAL, String::new()));
+            closed_message(id, encoding, code, &reason)
+        }
+        ws::Message::Ping(_) | ws::Message::Pong(_) => None,
+    }
+}
+
+/// The message telling the client a view was closed.
+fn closed_message(id: &str, encoding: Encoding, code: u16, reason: &str) -> Option<ws::Message> {
+    let msg = serde_json::json!({ "t": "c", "d": { "c": code, "r": reason } });
+    let msg = match encoding {
+        Encoding::Json => ws::Message::Text(msg.to_string().into()),
+        Encoding::MessagePack => ws::Message::Binary(rmp_serde::to_vec_named(&msg).ok()?.into()),
+    };
+    tag(id, encoding, msg)
+}
+
+/// A view's channel of a shared socket.
+///
+/// Looks like a socket of its own to the view.
+pub(crate) struct ChannelSocket {
+    id: String,
+    encoding: Encoding,
+    incoming: ReceiverStream<ws::Message>,
+    outgoing: PollSender<ws::Message>,
+}
+
+impl Stream for ChannelSocket {
+    type Item = Result<ws::Message, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.incoming)
+            .poll_next(cx)
+            .map(|msg| msg.map(Ok))
+    }
+}
+
+impl Sink<ws::Message> for ChannelSocket {
+    type Error = axum::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.outgoing)
+            .poll_ready(cx)
+            .map_err(axum::Error::new)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: ws::Message) -> Result<(), Self::Error> {
+        let Some(item) = tag(&self.id, self.encoding, item) else {
+            // the slot reserved by `poll_ready` stays reserved for the next message
+            return Ok(());
+        };
+        Pin::new(&mut self.outgoing)
+            .start_send(item)
+            .map_err(axum::Error::new)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.outgoing)
+            .poll_flush(cx)
+            .map_err(axum::Error::new)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.outgoing)
+            .poll_close(cx)
+            .map_err(axum::Error::new)
+    }
+}
+
+impl fmt::Debug for ChannelSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChannelSocket")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        html,
+        sse::tests::{connect, post, Events},
+        test::fixtures::{app, Counter},
+        LiveViewUpgrade,
+    };
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        response::Response,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    /// Embed two counters in the page.
+    async fn counters(live: LiveViewUpgrade) -> Response {
+        live.response_many(|views| {
+            html! {
+                { views.embed("first", Counter::default()) }
+                { views.embed("second", Counter::default()) }
+            }
+        })
+    }
+
+    async fn next_message(events: &mut Events) -> Value {
+        let (event, data) = events.next().await;
+        assert_eq!(event, "message");
+        serde_json::from_str(&data).unwrap()
+    }
+
+    /// Connect and wait for the initial render of both views.
+    async fn connect_views(app: Router) -> (Events, String) {
+        let mut events = connect(app).await;
+        let (_, session) = events.next().await;
+
+        let mut mounted = Vec::new();
+        for _ in 0..2 {
+            let msg = next_message(&mut events).await;
+            assert_eq!(msg["t"], "i");
+            mounted.push(msg["v"].as_str().unwrap().to_owned());
+        }
+        mounted.sort();
+        assert_eq!(mounted, ["first", "second"]);
+
+        (events, session)
+    }
+
+    #[tokio::test]
+    async fn embeds_views_in_their_own_containers() {
+        let response = app(counters)
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = std::str::from_utf8(&body).unwrap();
+
+        assert!(body.contains("<div data-live-view-id=\"first\" data-csrf-token=\""));
+        assert!(body.contains("<div data-live-view-id=\"second\" data-csrf-token=\""));
+        assert!(!body.contains("live-view-container"));
+    }
+
+    #[tokio::test]
+    async fn runs_views_over_one_socket() {
+        let app = app(counters);
+        let (mut events, session) = connect_views(app.clone()).await;
+
+        let click = r#"{"v":"second","m":"%22Incr%22","t":"click"}"#;
+        assert_eq!(
+            post(app.clone(), &session, click).await,
+            StatusCode::ACCEPTED
+        );
+
+        let msg = next_message(&mut events).await;
+        assert_eq!(msg["v"], "second");
+        assert_eq!(msg["t"], "r");
+
+        // heartbeats without an id are answered by every view
+        assert_eq!(
+            post(app, &session, r#"{"t":"h"}"#).await,
+            StatusCode::ACCEPTED
+        );
+        let mut answered = Vec::new();
+        for _ in 0..2 {
+            let msg = next_message(&mut events).await;
+            assert_eq!(msg["t"], "h");
+            answered.push(msg["v"].as_str().unwrap().to_owned());
+        }
+        answered.sort();
+        assert_eq!(answered, ["first", "second"]);
+    }
+
+    #[tokio::test]
+    async fn views_fail_on_their_own() {
+        let app = app(counters);
+        let (mut events, session) = connect_views(app.clone()).await;
+
+        let fail = r#"{"v":"first","m":"%22Fail%22","t":"click"}"#;
+        post(app.clone(), &session, fail).await;
+
+        let msg = next_message(&mut events).await;
+        assert_eq!(
+            msg,
+            serde_json::json!({
+                "v": "first",
+                "t": "c",
+                "d": { "c": close_code::REMOUNT, "r": "remount" },
+            })
+        );
+
+        // the other view keeps running
+        let click = r#"{"v":"second","m":"%22Incr%22","t":"click"}"#;
+        post(app.clone(), &session, click).await;
+        let msg = next_message(&mut events).await;
+        assert_eq!(msg["v"], "second");
+        assert_eq!(msg["t"], "r");
+
+        // and the failed one is mounted again when the client rejoins it
+        post(app, &session, r#"{"v":"first","t":"join"}"#).await;
+        let msg = next_message(&mut events).await;
+        assert_eq!(msg["v"], "first");
+        assert_eq!(msg["t"], "i");
+    }
+
+    #[test]
+    fn parses_text_messages() {
+        let parse = |text: &str| Frame::parse(ws::Message::Text(text.to_owned().into())).unwrap();
+
+        assert!(matches!(
+            parse(r#"{"v":"cart","t":"click","m":"%22Incr%22"}"#),
+            Frame::View(id, _) if id == "cart"
+        ));
+        assert!(matches!(
+            parse(r#"{"v":"cart","t":"join"}"#),
+            Frame::Join(id) if id == "cart"
+        ));
+        assert!(matches!(parse(r#"{"t":"h"}"#), Frame::All(_)));
+        assert!(matches!(parse(r#"{"v":"","t":"h"}"#), Frame::All(_)));
+    }
+
+    #[test]
+    fn tags_binary_messages() {
+        let msg = ws::Message::Binary(vec![0x81, 0xa1, b't'].into());
+        let tagged = tag("cart", Encoding::MessagePack, msg.clone()).unwrap();
+        assert_eq!(
+            tagged,
+            ws::Message::Binary(b"\x04cart\x81\xa1t".to_vec().into())
+        );
+
+        match Frame::parse(tagged).unwrap() {
+            Frame::View(id, parsed) => {
+                assert_eq!(id, "cart");
+                assert_eq!(parsed, msg);
+            }
+            frame => panic!("unexpected frame {frame:?}"),
+        }
+
+        assert!(matches!(
+            Frame::parse(ws::Message::Binary(b"\x00\x81".to_vec().into())),
+            Some(Frame::All(_))
+        ));
+        assert!(Frame::parse(ws::Message::Binary(b"\x09cart".to_vec().into())).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "live view id `cart` is embedded more than once")]
+    fn rejects_duplicate_ids() {
+        let mut views = EmbedLiveViews::socket();
+        views.embed("cart", Counter::default());
+        views.embed("cart", Counter::default());
+    }
+}
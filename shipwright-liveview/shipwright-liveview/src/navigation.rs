@@ -0,0 +1,190 @@
+//! Navigate between live views without reloading the page.
+//!
+//! Live navigation comes in two flavors:
+//!
+//! - **Patching** changes the URL of the current page and calls [`LiveView::handle_params`] with
+//!   the new URI. The view keeps its state. Use it for things like pagination or filters that
+//!   should be reflected in the URL.
+//! - **Redirecting** changes the URL and replaces the current view with the view of the route the
+//!   new URL points to. The WebSocket connection is reused so there is no full page load.
+//!
+//! Both can be triggered from the client with links:
+//!
+//! ```text
+//! <a href="/users?page=2" axm-patch>"Next page"</a>
+//! <a href="/settings" axm-redirect>"Settings"</a>
+//! ```
+//!
+//! or from the server with [`js_command::live_patch`] and [`js_command::live_redirect`]. Going
+//! back and forward through the browser history patches the view if the URL is handled by the
+//! same route, otherwise it redirects.
+//!
+//! Patching works out of the box. Redirecting requires the router to be wrapped with
+//! [`live_navigation`] so the new route can be found. Without it, if the route doesn't render
+//! a live view, or if the view is behind [`OnMount`] hooks added outside of it, the browser
+//! navigates to the new URL normally.
+//!
+//! # Example
+//!
+//! ```
+//! use axum::{routing::get, Router};
+//! use shipwright_liveview::navigation::live_navigation;
+//!
+//! let app = live_navigation(
+//!     Router::new()
+//!         .route("/", get(index))
+//!         .route("/settings", get(settings)),
+//! );
+//! # let _: Router = app;
+//!
+//! async fn index() { /* ... */ }
+//! async fn settings() { /* ... */ }
+//! ```
+//!
+//! # Protocol
+//!
+//! The client notifies the server about navigation with these messages:
+//!
+//! - `{"t": "lp", "u": "<uri>"}` after it has changed the URL with `history.pushState`, or after
+//!   a `popstate` event for the same route. The server calls [`LiveView::handle_params`] and
+//!   replies with a regular render.
+//! - `{"t": "lr", "u": "<uri>"}` after it has changed the URL for a redirect. The server unmounts
+//!   the current view and replies with an initial render of the new view on the same socket, or
+//!   with a `navigate_to` command if the route isn't a live view.
+//!
+//! [`LiveView::handle_params`]: crate::LiveView::handle_params
+//! [`OnMount`]: crate::on_mount::OnMount
+//! [`js_command::live_patch`]: crate::js_command::live_patch
+//! [`js_command::live_redirect`]: crate::js_command::live_redirect
+
+use crate::{
+    codec::Encoding, flash::Flash, js_command, life_cycle::MessageToSocket, socket::Socket,
+};
+use axum::{
+    body::Body,
+    http::{HeaderMap, Request, Uri},
+    Extension, Router,
+};
+use futures_util::sink::SinkExt;
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+use tower::ServiceExt;
+
+/// Enable [live redirects](self) between the live views in `router`.
+///
+/// When a view is redirected to a new URL, a `GET` request for that URL is routed through
+/// `router`, with the headers of the original WebSocket request. If the route responds with a
+/// live view through [`LiveViewUpgrade`], that view is mounted on the existing socket.
+///
+/// Because the request is sent to `router` directly, middleware applied outside of
+/// `live_navigation` doesn't run for redirects. [`OnMountLayer`] hooks applied outside of it turn
+/// live redirects into full page loads, so they still run.
+///
+/// [`LiveViewUpgrade`]: crate::LiveViewUpgrade
+/// [`OnMountLayer`]: crate::on_mount::OnMountLayer
+pub fn live_navigation(router: Router) -> Router {
+    let navigation = LiveNavigation {
+        router: router.clone(),
+    };
+    router.layer(Extension(navigation))
+}
+
+#[derive(Clone)]
+pub(crate) struct LiveNavigation {
+    router: Router,
+}
+
+impl LiveNavigation {
+    /// Mount the view at `uri` on `socket`, passing along the flash of the previous view.
+    pub(crate) async fn redirect(
+        &self,
+        socket: Socket,
+        encoding: Encoding,
+        uri: Uri,
+        headers: HeaderMap,
+        flash: Flash,
+    ) {
+        let handoff = SocketHandoff {
+            socket: Arc::new(Mutex::new(Some(socket))),
+            encoding,
+            navigation: self.clone(),
+        };
+
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = uri.clone();
+        *request.headers_mut() = headers;
+        request.extensions_mut().insert(handoff.clone());
+        request.extensions_mut().insert(flash.clone());
+
+        // the view, if any, is spawned by `LiveViewUpgrade` so the response doesn't matter
+        let _ = self.router.clone().oneshot(request).await;
+
+        if let Some(socket) = handoff.take() {
+            tracing::debug!(%uri, "redirected to a route without a live view");
+            navigate_away(socket, encoding, &uri, &flash).await;
+        }
+    }
+}
+
+impl fmt::Debug for LiveNavigation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LiveNavigation").finish()
+    }
+}
+
+/// Request extension used to pass a socket to the view of the route being redirected to.
+#[derive(Clone)]
+pub(crate) struct SocketHandoff {
+    socket: Arc<Mutex<Option<Socket>>>,
+    encoding: Encoding,
+    navigation: LiveNavigation,
+}
+
+impl SocketHandoff {
+    pub(crate) fn take(&self) -> Option<Socket> {
+        self.socket.lock().unwrap().take()
+    }
+
+    pub(crate) fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub(crate) fn navigation(&self) -> &LiveNavigation {
+        &self.navigation
+    }
+}
+
+impl fmt::Debug for SocketHandoff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SocketHandoff").finish()
+    }
+}
+
+/// Tell the client to load `uri` with a full page load and close the socket.
+///
+/// The flash is stored in a cookie so the next page can show it.
+pub(crate) async fn navigate_away(
+    mut socket: Socket,
+    encoding: Encoding,
+    uri: &Uri,
+    flash: &Flash,
+) {
+    let mut commands = Vec::new();
+    if !flash.is_empty() {
+        commands.push(js_command::set_cookie(flash.cookie(false)));
+    }
+    commands.push(js_command::navigate_to(uri.clone()));
+
+    let msg = MessageToSocket::js_commands(commands);
+    let msg = encoding
+        .encode(&msg)
+        .expect("failed to serialize JS commands");
+
+    if let Err(err) = socket.send(msg).await {
+        tracing::trace!(%err, "failed to send navigation to socket");
+        return;
+    }
+    let _ = socket.close().await;
+}
@@ -0,0 +1,409 @@
+//! Hooks that run before live views are mounted.
+//!
+//! Views often need the same setup, such as looking up the current user from a session cookie
+//! and redirecting to a login page if there isn't one. Rather than doing that in every
+//! [`LiveView::mount`], implement [`OnMount`] and wrap the routes with [`OnMountLayer`].
+//!
+//! A hook receives the request parts and either inserts extensions, which handlers can extract
+//! with [`Extension`] and views read with [`ViewHandle::extensions`], or halts with a response.
+//! Hooks run for the HTTP request that renders the page, for the WebSocket request that mounts
+//! the view and for [live redirects](crate::navigation), so a view is never mounted without
+//! them.
+//!
+//! Live redirects are routed through the router passed to [`live_navigation`], so only hooks
+//! added inside of it run for them. A view mounted behind hooks added outside of it is redirected
+//! with a full page load instead, which runs every hook again.
+//!
+//! When a hook halts a WebSocket request the response can't be sent to the browser. If it is a
+//! redirect the client is told to navigate to its location, otherwise the socket is closed with
+//! close code `4002`.
+//!
+//! # Example
+//!
+//! ```
+//! use axum::{
+//!     http::request::Parts,
+//!     response::{IntoResponse, Redirect, Response},
+//!     routing::get,
+//!     Extension, Router,
+//! };
+//! use shipwright_liveview::{
+//!     live_view::async_trait,
+//!     on_mount::{OnMount, OnMountLayer},
+//! };
+//!
+//! #[derive(Clone)]
+//! struct CurrentUser {
+//!     name: String,
+//! }
+//!
+//! struct RequireUser;
+//!
+//! #[async_trait]
+//! impl OnMount for RequireUser {
+//!     async fn on_mount(&self, parts: &mut Parts) -> Result<(), Response> {
+//!         match find_user(parts).await {
+//!             Some(user) => {
+//!                 parts.extensions.insert(user);
+//!                 Ok(())
+//!             }
+//!             None => Err(Redirect::to("/login").into_response()),
+//!         }
+//!     }
+//! }
+//!
+//! async fn find_user(parts: &Parts) -> Option<CurrentUser> {
+//!     // look up the session cookie...
+//!     # None
+//! }
+//!
+//! let app = Router::new()
+//!     .route("/", get(|Extension(user): Extension<CurrentUser>| async move { user.name }))
+//!     .layer(OnMountLayer::new(RequireUser))
+//!     .route("/login", get(|| async { "Log in" }));
+//! # let _: Router = app;
+//! ```
+//!
+//! Hooks are chained by adding more layers. Layers added to the router run before those added to
+//! a single route with [`MethodRouter::layer`], so a route can add hooks that depend on the ones
+//! shared by the whole router.
+//!
+//! [`LiveView::mount`]: crate::LiveView::mount
+//! [`Extension`]: axum::Extension
+//! [`ViewHandle::extensions`]: crate::live_view::ViewHandle::extensions
+//! [`MethodRouter::layer`]: axum::routing::MethodRouter::layer
+//! [`live_navigation`]: crate::navigation::live_navigation
+
+use crate::{
+    extract::reject_socket,
+    flash::Flash,
+    navigation::{self, LiveNavigation, SocketHandoff},
+    socket::Upgrade,
+};
+use async_trait::async_trait;
+use axum::{
+    extract::Request,
+    http::{header, request::Parts, Uri},
+    response::Response,
+};
+use futures_util::future::BoxFuture;
+use std::{
+    fmt,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// A hook that runs before a live view is mounted.
+///
+/// See the [module docs](self) for an example.
+#[async_trait]
+pub trait OnMount: Send + Sync + 'static {
+    /// Inspect the request and insert extensions for the handler and view.
+    ///
+    /// Returning `Err` halts the request with the response. The handler and any hooks after this
+    /// one don't run.
+    async fn on_mount(&self, parts: &mut Parts) -> Result<(), Response>;
+}
+
+/// [`Layer`] that runs an [`OnMount`] hook before the wrapped routes.
+///
+/// See the [module docs](self) for an example.
+#[derive(Clone)]
+pub struct OnMountLayer {
+    hook: Arc<dyn OnMount>,
+}
+
+impl OnMountLayer {
+    /// Create a layer that runs `hook`.
+    pub fn new<H>(hook: H) -> Self
+    where
+        H: OnMount,
+    {
+        Self {
+            hook: Arc::new(hook),
+        }
+    }
+}
+
+impl fmt::Debug for OnMountLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OnMountLayer").finish_non_exhaustive()
+    }
+}
+
+impl<S> Layer<S> for OnMountLayer {
+    type Service = OnMountService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        OnMountService {
+            inner,
+            hook: self.hook.clone(),
+        }
+    }
+}
+
+/// Service that runs an [`OnMount`] hook before the inner service.
+///
+/// Created with [`OnMountLayer`].
+#[derive(Clone)]
+pub struct OnMountService<S> {
+    inner: S,
+    hook: Arc<dyn OnMount>,
+}
+
+impl<S> fmt::Debug for OnMountService<S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OnMountService")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> Service<Request> for OnMountService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        // the clone might not be ready, so call the instance `poll_ready` was called on
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let hook = self.hook.clone();
+
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+
+            // `live_navigation` inserts its extension, so a hook added outside of it runs first
+            let outside_navigation = parts.extensions.get::<LiveNavigation>().is_none()
+                && parts.extensions.get::<SocketHandoff>().is_none();
+
+            match hook.on_mount(&mut parts).await {
+                Ok(()) => {
+                    if outside_navigation {
+                        parts.extensions.insert(HooksOutsideNavigation);
+                    }
+                    inner.call(Request::from_parts(parts, body)).await
+                }
+                Err(response) => Ok(halt(parts, response).await),
+            }
+        })
+    }
+}
+
+/// Request extension marking that a hook ran outside of [`live_navigation`], which live redirects
+/// would skip.
+///
+/// [`live_navigation`]: crate::navigation::live_navigation
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct HooksOutsideNavigation;
+
+/// Send the response of a halted request, or its equivalent if the request is for a socket.
+async fn halt(mut parts: Parts, response: Response) -> Response {
+    let location = response
+        .status()
+        .is_redirection()
+        .then(|| response.headers().get(header::LOCATION))
+        .flatten()
+        .and_then(|location| location.to_str().ok())
+        .and_then(|location| location.parse::<Uri>().ok());
+    let flash = Flash::from_parts(&mut parts);
+
+    if let Some(handoff) = parts.extensions.get::<SocketHandoff>() {
+        // without a redirect the socket is left in the handoff, which makes the client load the
+        // page normally and get the response
+        if let Some(location) = location {
+            if let Some(socket) = handoff.take() {
+                navigation::navigate_away(socket, handoff.encoding(), &location, &flash).await;
+            }
+        }
+        return response;
+    }
+
+    let Some(upgrade) = Upgrade::from_request_parts(&mut parts, &()).await else {
+        return response;
+    };
+
+    let encoding = upgrade.encoding(&parts);
+    upgrade.on_upgrade(move |socket| async move {
+        match location {
+            Some(location) => navigation::navigate_away(socket, encoding, &location, &flash).await,
+            None => reject_socket(socket, "mount halted").await,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event_data::EventData,
+        html,
+        live_view::Updated,
+        navigation::live_navigation,
+        sse::{tests as sse, SseLayer},
+        Html, LiveView, LiveViewUpgrade,
+    };
+    use axum::{
+        body::Body,
+        http::StatusCode,
+        response::{IntoResponse, Redirect},
+        routing::get,
+        Extension, Router,
+    };
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    #[derive(Clone)]
+    struct Locale(&'static str);
+
+    struct SetLocale;
+
+    #[async_trait]
+    impl OnMount for SetLocale {
+        async fn on_mount(&self, parts: &mut Parts) -> Result<(), Response> {
+            parts.extensions.insert(Locale("nl"));
+            Ok(())
+        }
+    }
+
+    struct RequireLocale;
+
+    #[async_trait]
+    impl OnMount for RequireLocale {
+        async fn on_mount(&self, parts: &mut Parts) -> Result<(), Response> {
+            if parts.extensions.get::<Locale>().is_some() {
+                Ok(())
+            } else {
+                Err(Redirect::to("/pick-locale").into_response())
+            }
+        }
+    }
+
+    struct RequirePublic;
+
+    #[async_trait]
+    impl OnMount for RequirePublic {
+        async fn on_mount(&self, parts: &mut Parts) -> Result<(), Response> {
+            if parts.uri.path().starts_with("/secret") {
+                Err(Redirect::to("/login").into_response())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    struct Page;
+
+    impl LiveView for Page {
+        type Message = ();
+
+        fn update(self, _msg: (), _data: Option<EventData>) -> Updated<Self> {
+            Updated::new(self)
+        }
+
+        fn render(&self) -> Html<()> {
+            html! { <p>"page"</p> }
+        }
+    }
+
+    fn page() -> axum::routing::MethodRouter {
+        get(|live: LiveViewUpgrade| async move { live.response(|embed| embed.embed(Page)) })
+    }
+
+    /// Connect to the view at `/`, live redirect it to `uri` and return the JS commands sent in
+    /// response.
+    async fn live_redirect(app: Router, uri: &str) -> Value {
+        let mut events = sse::connect(app.clone()).await;
+        let (_, session) = events.next().await;
+        let (_, data) = events.next().await;
+        assert_eq!(serde_json::from_str::<Value>(&data).unwrap()["t"], "i");
+
+        let redirect = serde_json::json!({ "t": "lr", "u": uri }).to_string();
+        assert_eq!(
+            sse::post(app, &session, &redirect).await,
+            StatusCode::ACCEPTED
+        );
+
+        let (_, data) = events.next().await;
+        let msg = serde_json::from_str::<Value>(&data).unwrap();
+        assert_eq!(msg["t"], "j");
+        msg["d"].clone()
+    }
+
+    async fn get_path(app: Router, path: &str) -> Response {
+        let request = Request::builder().uri(path).body(Body::empty()).unwrap();
+        app.oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn hooks_insert_extensions() {
+        let app = Router::new()
+            .route(
+                "/",
+                get(|Extension(Locale(locale)): Extension<Locale>| async move { locale })
+                    .layer(OnMountLayer::new(RequireLocale)),
+            )
+            .layer(OnMountLayer::new(SetLocale));
+
+        let response = get_path(app, "/").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, "nl");
+    }
+
+    #[tokio::test]
+    async fn hooks_halt() {
+        let app = Router::new()
+            .route("/", get(|| async { "home" }))
+            .layer(OnMountLayer::new(RequireLocale));
+
+        let response = get_path(app, "/").await;
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()[header::LOCATION], "/pick-locale");
+    }
+
+    #[tokio::test]
+    async fn hooks_halt_live_redirects() {
+        let app = live_navigation(
+            Router::new()
+                .route("/", page())
+                .route("/secret", page().layer(OnMountLayer::new(RequireLocale))),
+        )
+        .layer(SseLayer::new());
+
+        let commands = live_redirect(app, "/secret").await;
+        assert_eq!(
+            commands[0]["kind"],
+            serde_json::json!({ "t": "navigate_to", "uri": "/pick-locale" })
+        );
+    }
+
+    #[tokio::test]
+    async fn hooks_outside_live_navigation_reload_the_page() {
+        let app = live_navigation(Router::new().route("/", page()).route("/secret", page()))
+            .layer(OnMountLayer::new(RequirePublic))
+            .layer(SseLayer::new());
+
+        // the hook would be skipped by a live redirect, so the page is loaded to run it
+        let commands = live_redirect(app, "/secret").await;
+        assert_eq!(
+            commands[0]["kind"],
+            serde_json::json!({ "t": "navigate_to", "uri": "/secret" })
+        );
+    }
+}
@@ -0,0 +1,369 @@
+//! Track which users are connected to live views.
+//!
+//! [`Presence`] keeps a list of metadata per topic and key, for example "users viewing document
+//! 42" keyed by user id. Views register themselves with [`Presence::track`] and are removed
+//! automatically when their connection ends. Other views can [`Presence::subscribe`] to a topic to
+//! receive [`PresenceDiff`]s whenever someone joins or leaves.
+//!
+//! # Example
+//!
+//! ```
+//! use axum::http::{HeaderMap, Uri};
+//! use serde::{Deserialize, Serialize};
+//! use shipwright_liveview::{
+//!     event_data::EventData,
+//!     html,
+//!     live_view::{Updated, ViewHandle},
+//!     presence::{Presence, PresenceDiff},
+//!     Html, LiveView,
+//! };
+//! use std::collections::BTreeMap;
+//!
+//! #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! struct Document {
+//!     presence: Presence<User>,
+//!     user_id: String,
+//!     name: String,
+//!     online: BTreeMap<String, Vec<User>>,
+//! }
+//!
+//! impl LiveView for Document {
+//!     type Message = Msg;
+//!
+//!     fn mount(&mut self, _: Uri, _: &HeaderMap, handle: ViewHandle<Msg>) {
+//!         let user = User { name: self.name.clone() };
+//!         self.presence.track("document:42", &self.user_id, user, &handle);
+//!         self.online = self.presence.subscribe("document:42", &handle, Msg::Presence);
+//!     }
+//!
+//!     fn update(mut self, msg: Msg, _: Option<EventData>) -> Updated<Self> {
+//!         match msg {
+//!             Msg::Presence(diff) => diff.apply(&mut self.online),
+//!         }
+//!         Updated::new(self)
+//!     }
+//!
+//!     fn render(&self) -> Html<Msg> {
+//!         html! {
+//!             <ul>
+//!                 for (_, users) in &self.online {
+//!                     <li>{ &users[0].name }</li>
+//!                 }
+//!             </ul>
+//!         }
+//!     }
+//! }
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! enum Msg {
+//!     Presence(PresenceDiff<User>),
+//! }
+//! ```
+
+use crate::live_view::ViewHandle;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::mpsc;
+
+/// Tracks metadata per topic and key for connected live views.
+///
+/// Cloning a `Presence` is cheap and all clones share the same state.
+///
+/// Presence is tracked within the current process only.
+///
+/// See the [module docs](self) for an example.
+pub struct Presence<T> {
+    state: Arc<Mutex<State<T>>>,
+}
+
+struct State<T> {
+    topics: HashMap<String, Topic<T>>,
+    next_id: u64,
+}
+
+struct Topic<T> {
+    // each key can be tracked several times, for example a user with multiple tabs open
+    entries: BTreeMap<String, Vec<(u64, T)>>,
+    subscribers: Vec<mpsc::UnboundedSender<PresenceDiff<T>>>,
+}
+
+impl<T> Default for Topic<T> {
+    fn default() -> Self {
+        Self {
+            entries: Default::default(),
+            subscribers: Default::default(),
+        }
+    }
+}
+
+impl<T> Topic<T>
+where
+    T: Clone,
+{
+    fn broadcast(&mut self, diff: PresenceDiff<T>) {
+        self.subscribers.retain(|tx| tx.send(diff.clone()).is_ok());
+    }
+
+    fn list(&self) -> BTreeMap<String, Vec<T>> {
+        self.entries
+            .iter()
+            .map(|(key, metas)| {
+                let metas = metas.iter().map(|(_, meta)| meta.clone()).collect();
+                (key.clone(), metas)
+            })
+            .collect()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.subscribers.is_empty()
+    }
+}
+
+impl<T> Presence<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    /// Create a new `Presence`.
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                topics: HashMap::new(),
+                next_id: 0,
+            })),
+        }
+    }
+
+    /// Track `key` with `meta` in `topic` for as long as the view behind `handle` is running.
+    ///
+    /// This is usually called from [`LiveView::mount`]. The entry is removed when the view shuts
+    /// down, which happens when its connection ends. Subscribers of the topic receive a
+    /// [`PresenceDiff`] in both cases.
+    ///
+    /// [`LiveView::mount`]: crate::LiveView::mount
+    pub fn track<M>(&self, topic: &str, key: impl Into<String>, meta: T, handle: &ViewHandle<M>)
+    where
+        M: Send + 'static,
+    {
+        let key = key.into();
+
+        let id = {
+            let mut state = self.state.lock().unwrap();
+            let id = state.next_id;
+            state.next_id += 1;
+
+            let topic = state.topics.entry(topic.to_owned()).or_default();
+            topic
+                .entries
+                .entry(key.clone())
+                .or_default()
+                .push((id, meta.clone()));
+            topic.broadcast(PresenceDiff {
+                joins: BTreeMap::from([(key.clone(), Vec::from([meta]))]),
+                leaves: BTreeMap::new(),
+            });
+
+            id
+        };
+
+        let presence = self.clone();
+        let handle = handle.clone();
+        let topic = topic.to_owned();
+        crate::util::spawn_unit(async move {
+            handle.closed().await;
+            presence.untrack(&topic, &key, id);
+        });
+    }
+
+    fn untrack(&self, topic_name: &str, key: &str, id: u64) {
+        let mut state = self.state.lock().unwrap();
+
+        let topic = match state.topics.get_mut(topic_name) {
+            Some(topic) => topic,
+            None => return,
+        };
+
+        let metas = match topic.entries.get_mut(key) {
+            Some(metas) => metas,
+            None => return,
+        };
+
+        let meta = match metas.iter().position(|(entry_id, _)| *entry_id == id) {
+            Some(idx) => metas.remove(idx).1,
+            None => return,
+        };
+
+        if metas.is_empty() {
+            topic.entries.remove(key);
+        }
+
+        topic.broadcast(PresenceDiff {
+            joins: BTreeMap::new(),
+            leaves: BTreeMap::from([(key.to_owned(), Vec::from([meta]))]),
+        });
+
+        if topic.is_empty() {
+            state.topics.remove(topic_name);
+        }
+    }
+
+    /// Send the view a message whenever someone joins or leaves `topic`.
+    ///
+    /// Each [`PresenceDiff`] is passed to `f` and the message it returns is sent to the view, as
+    /// if by [`ViewHandle::send`]. The subscription ends when the view shuts down.
+    ///
+    /// Returns everyone currently tracked in `topic`. Only changes that happen after that are sent
+    /// to the view, so applying each diff to the returned list keeps it up to date.
+    pub fn subscribe<M, F>(
+        &self,
+        topic: &str,
+        handle: &ViewHandle<M>,
+        f: F,
+    ) -> BTreeMap<String, Vec<T>>
+    where
+        M: Send + 'static,
+        F: Fn(PresenceDiff<T>) -> M + Send + 'static,
+    {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let list = {
+            let mut state = self.state.lock().unwrap();
+            let topic = state.topics.entry(topic.to_owned()).or_default();
+            topic.subscribers.push(tx);
+            topic.list()
+        };
+
+        let handle = handle.clone();
+        crate::util::spawn_unit(async move {
+            loop {
+                let diff = tokio::select! {
+                    diff = rx.recv() => match diff {
+                        Some(diff) => diff,
+                        None => break,
+                    },
+                    () = handle.closed() => break,
+                };
+
+                if handle.send(f(diff)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        list
+    }
+
+    /// Get everyone currently tracked in `topic`, grouped by key.
+    pub fn list(&self, topic: &str) -> BTreeMap<String, Vec<T>> {
+        self.state
+            .lock()
+            .unwrap()
+            .topics
+            .get(topic)
+            .map(Topic::list)
+            .unwrap_or_default()
+    }
+}
+
+impl<T> Clone for Presence<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<T> Default for Presence<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for Presence<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Presence").finish()
+    }
+}
+
+/// Changes to a topic tracked by [`Presence`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresenceDiff<T> {
+    /// Entries that joined, grouped by key.
+    pub joins: BTreeMap<String, Vec<T>>,
+    /// Entries that left, grouped by key.
+    pub leaves: BTreeMap<String, Vec<T>>,
+}
+
+impl<T> PresenceDiff<T>
+where
+    T: PartialEq,
+{
+    /// Apply the diff to a list previously returned by [`Presence::list`].
+    pub fn apply(self, list: &mut BTreeMap<String, Vec<T>>) {
+        for (key, metas) in self.joins {
+            list.entry(key).or_default().extend(metas);
+        }
+
+        for (key, metas) in self.leaves {
+            if let Some(current) = list.get_mut(&key) {
+                for meta in metas {
+                    if let Some(idx) = current.iter().position(|current| *current == meta) {
+                        current.remove(idx);
+                    }
+                }
+
+                if current.is_empty() {
+                    list.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn join_and_leave() {
+        let presence = Presence::<&'static str>::new();
+
+        let (alice, alice_rx) = ViewHandle::<()>::new();
+        presence.track("room", "alice", "laptop", &alice);
+
+        let (watcher, mut diffs) = ViewHandle::new();
+        let mut list = presence.subscribe("room", &watcher, |diff| diff);
+        assert_eq!(list["alice"], ["laptop"]);
+
+        let (alice_phone, alice_phone_rx) = ViewHandle::<()>::new();
+        presence.track("room", "alice", "phone", &alice_phone);
+
+        diffs.recv().await.unwrap().apply(&mut list);
+        assert_eq!(list, presence.list("room"));
+        assert_eq!(list["alice"], ["laptop", "phone"]);
+
+        drop(alice_rx);
+        let diff = diffs.recv().await.unwrap();
+        assert_eq!(
+            diff.leaves,
+            BTreeMap::from([("alice".to_owned(), vec!["laptop"])])
+        );
+        diff.apply(&mut list);
+        assert_eq!(list["alice"], ["phone"]);
+
+        drop(alice_phone_rx);
+        diffs.recv().await.unwrap().apply(&mut list);
+        assert!(list.is_empty());
+        assert!(presence.list("room").is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+use super::PubSubBackend;
+use bytes::Bytes;
+use futures_util::stream::{BoxStream, StreamExt};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::broadcast;
+
+/// A [`PubSubBackend`] that delivers events within the current process.
+///
+/// Each topic buffers up to 1024 events per subscriber. Subscribers that fall further behind miss
+/// the oldest events. Topics are forgotten once their last subscriber is gone.
+#[derive(Clone, Default)]
+pub struct InProcessBackend {
+    topics: Topics,
+}
+
+type Topics = Arc<Mutex<HashMap<String, broadcast::Sender<Bytes>>>>;
+
+impl InProcessBackend {
+    /// Create a new `InProcessBackend`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn subscriber_count(&self, topic: &str) -> usize {
+        self.topics
+            .lock()
+            .unwrap()
+            .get(topic)
+            .map_or(0, |tx| tx.receiver_count())
+    }
+
+    #[cfg(test)]
+    pub(crate) fn has_topic(&self, topic: &str) -> bool {
+        self.topics.lock().unwrap().contains_key(topic)
+    }
+}
+
+impl PubSubBackend for InProcessBackend {
+    fn publish(&self, topic: &str, payload: Bytes) {
+        if let Some(tx) = self.topics.lock().unwrap().get(topic) {
+            let _ = tx.send(payload);
+        }
+    }
+
+    fn subscribe(&self, topic: &str) -> BoxStream<'static, Bytes> {
+        let rx = self
+            .topics
+            .lock()
+            .unwrap()
+            .entry(topic.to_owned())
+            .or_insert_with(|| broadcast::channel(1024).0)
+            .subscribe();
+        let mut subscription = Subscription {
+            rx,
+            topics: self.topics.clone(),
+            topic: topic.to_owned(),
+        };
+
+        async_stream::stream! {
+            loop {
+                match subscription.rx.recv().await {
+                    Ok(payload) => yield payload,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "subscriber lagged behind and missed events");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }
+        .boxed()
+    }
+}
+
+/// A subscriber's receiver, which removes its topic when it is the last one.
+struct Subscription {
+    rx: broadcast::Receiver<Bytes>,
+    topics: Topics,
+    topic: String,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let mut topics = self.topics.lock().unwrap();
+        // `rx` is only dropped after this, so it still counts
+        if topics
+            .get(&self.topic)
+            .is_some_and(|tx| tx.receiver_count() == 1)
+        {
+            topics.remove(&self.topic);
+        }
+    }
+}
+
+impl fmt::Debug for InProcessBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InProcessBackend").finish()
+    }
+}
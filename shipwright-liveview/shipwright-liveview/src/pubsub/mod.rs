@@ -0,0 +1,282 @@
+//! Topic based publish/subscribe for live views.
+//!
+//! [`PubSub`] lets views hear about things that happen elsewhere in the application, such as
+//! another user posting a chat message. Views subscribe their [`ViewHandle`] to a topic and each
+//! event published to that topic is turned into a message for the view.
+//!
+//! # Example
+//!
+//! ```
+//! use axum::{http::{HeaderMap, Uri}, response::IntoResponse, Extension};
+//! use serde::{Deserialize, Serialize};
+//! use shipwright_liveview::{
+//!     event_data::EventData,
+//!     html,
+//!     live_view::{Updated, ViewHandle},
+//!     pubsub::PubSub,
+//!     Html, LiveView, LiveViewUpgrade,
+//! };
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct NewMessage {
+//!     text: String,
+//! }
+//!
+//! struct Messages {
+//!     pubsub: PubSub,
+//!     messages: Vec<String>,
+//! }
+//!
+//! impl LiveView for Messages {
+//!     type Message = Msg;
+//!
+//!     fn mount(&mut self, _: Uri, _: &HeaderMap, handle: ViewHandle<Msg>) {
+//!         // the subscription ends when the view is shut down
+//!         self.pubsub
+//!             .subscribe("messages", &handle, |msg: NewMessage| Msg::Received(msg.text));
+//!     }
+//!
+//!     fn update(mut self, msg: Msg, _: Option<EventData>) -> Updated<Self> {
+//!         match msg {
+//!             Msg::Received(text) => self.messages.push(text),
+//!             Msg::Send => {
+//!                 let _ = self.pubsub.publish(
+//!                     "messages",
+//!                     &NewMessage { text: "hi".to_owned() },
+//!                 );
+//!             }
+//!         }
+//!         Updated::new(self)
+//!     }
+//!
+//!     fn render(&self) -> Html<Msg> {
+//!         html! {
+//!             for msg in &self.messages {
+//!                 <p>{ msg }</p>
+//!             }
+//!             <button axm-click={ Msg::Send }>"Say hi"</button>
+//!         }
+//!     }
+//! }
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! enum Msg {
+//!     Received(String),
+//!     Send,
+//! }
+//!
+//! async fn handler(live: LiveViewUpgrade, Extension(pubsub): Extension<PubSub>) -> impl IntoResponse {
+//!     let view = Messages { pubsub, messages: Vec::new() };
+//!     live.response(|embed| embed.embed(view))
+//! }
+//! ```
+
+use crate::live_view::ViewHandle;
+use bytes::Bytes;
+use futures_util::stream::{BoxStream, StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fmt, sync::Arc};
+
+mod in_process;
+
+pub use self::in_process::InProcessBackend;
+
+/// A handle to a publish/subscribe system.
+///
+/// Cloning a `PubSub` is cheap and all clones share the same subscriptions. Usually a `PubSub` is
+/// created once and then shared with handlers through [`Extension`] or application state.
+///
+/// Events are serialized to JSON before being handed to the [`PubSubBackend`], so they can be
+/// sent between processes by backends that support that.
+///
+/// See the [module docs](self) for an example.
+///
+/// [`Extension`]: axum::Extension
+#[derive(Clone)]
+pub struct PubSub {
+    backend: Arc<dyn PubSubBackend>,
+}
+
+impl PubSub {
+    /// Create a new `PubSub` that only delivers events within the current process.
+    pub fn new() -> Self {
+        Self::with_backend(InProcessBackend::new())
+    }
+
+    /// Create a new `PubSub` with the given backend.
+    pub fn with_backend<B>(backend: B) -> Self
+    where
+        B: PubSubBackend,
+    {
+        Self {
+            backend: Arc::new(backend),
+        }
+    }
+
+    /// Publish an event to all subscribers of `topic`.
+    ///
+    /// Publishing never waits for subscribers. Events published to a topic without subscribers are
+    /// dropped.
+    pub fn publish<T>(&self, topic: &str, event: &T) -> Result<(), PublishError>
+    where
+        T: Serialize,
+    {
+        let payload = serde_json::to_vec(event).map_err(PublishError)?;
+        self.backend.publish(topic, Bytes::from(payload));
+        Ok(())
+    }
+
+    /// Subscribe a view to `topic`.
+    ///
+    /// Each event published to the topic is passed to `f` and the message it returns is sent to
+    /// the view, as if by [`ViewHandle::send`]. Events that can't be deserialized into `T` are
+    /// skipped.
+    ///
+    /// The subscription ends automatically when the view is shut down.
+    pub fn subscribe<T, M, F>(&self, topic: &str, handle: &ViewHandle<M>, f: F)
+    where
+        T: DeserializeOwned,
+        M: Send + 'static,
+        F: Fn(T) -> M + Send + 'static,
+    {
+        let mut events = self.backend.subscribe(topic);
+        let handle = handle.clone();
+        let topic = topic.to_owned();
+
+        crate::util::spawn_unit(async move {
+            loop {
+                let payload = tokio::select! {
+                    payload = events.next() => match payload {
+                        Some(payload) => payload,
+                        None => break,
+                    },
+                    () = handle.closed() => break,
+                };
+
+                let event = match serde_json::from_slice::<T>(&payload) {
+                    Ok(event) => event,
+                    Err(err) => {
+                        tracing::warn!(%err, %topic, "failed to deserialize event");
+                        continue;
+                    }
+                };
+
+                if handle.send(f(event)).await.is_err() {
+                    break;
+                }
+            }
+
+            tracing::trace!(%topic, "subscription ended");
+        });
+    }
+}
+
+impl Default for PubSub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for PubSub {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PubSub").finish()
+    }
+}
+
+/// The transport used by [`PubSub`] to deliver events.
+///
+/// [`InProcessBackend`] is used by default. Implement this trait to deliver events between
+/// multiple processes, for example through Redis or Postgres.
+pub trait PubSubBackend: Send + Sync + 'static {
+    /// Deliver `payload` to all current subscribers of `topic`.
+    ///
+    /// This must not block. Backends that need to do I/O should hand the payload off to a
+    /// background task.
+    fn publish(&self, topic: &str, payload: Bytes);
+
+    /// Subscribe to `topic`.
+    ///
+    /// The stream should yield every payload published to the topic after this method was called.
+    /// The subscription ends when the stream is dropped.
+    fn subscribe(&self, topic: &str) -> BoxStream<'static, Bytes>;
+}
+
+/// Error returned from [`PubSub::publish`] if the event couldn't be serialized.
+#[derive(Debug)]
+pub struct PublishError(serde_json::Error);
+
+impl fmt::Display for PublishError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to serialize event")
+    }
+}
+
+impl std::error::Error for PublishError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn subscribe_and_publish() {
+        let pubsub = PubSub::new();
+        let (handle, mut rx) = ViewHandle::new();
+
+        pubsub.subscribe("counter", &handle, |event: Event| event.value * 2);
+        pubsub.subscribe("other", &handle, |event: Event| event.value);
+
+        pubsub.publish("counter", &Event { value: 1 }).unwrap();
+        pubsub.publish("counter", &"not an event").unwrap();
+        pubsub.publish("counter", &Event { value: 2 }).unwrap();
+
+        assert_eq!(rx.recv().await, Some(2));
+        assert_eq!(rx.recv().await, Some(4));
+        assert!(tokio::time::timeout(Duration::from_millis(10), rx.recv())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn unsubscribe_when_view_shuts_down() {
+        let backend = InProcessBackend::new();
+        let pubsub = PubSub::with_backend(backend.clone());
+        let (handle, rx) = ViewHandle::<u32>::new();
+
+        pubsub.subscribe("counter", &handle, |event: Event| event.value);
+        assert_eq!(backend.subscriber_count("counter"), 1);
+
+        drop(rx);
+        // the topic is forgotten along with its last subscriber
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while backend.has_topic("counter") {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn topics_are_kept_while_subscribed() {
+        let backend = InProcessBackend::new();
+        let first = backend.subscribe("counter");
+        let second = backend.subscribe("counter");
+
+        drop(first);
+        assert_eq!(backend.subscriber_count("counter"), 1);
+
+        drop(second);
+        assert!(!backend.has_topic("counter"));
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Event {
+        value: u32,
+    }
+}
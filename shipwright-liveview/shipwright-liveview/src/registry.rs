@@ -0,0 +1,263 @@
+//! Registry of the live views running on sockets in this process.
+//!
+//! This backs the [dashboard](crate::dashboard). Views are only registered once the registry has
+//! been enabled by creating the dashboard's router, so apps without it don't pay for the
+//! bookkeeping.
+
+#![cfg_attr(not(feature = "dashboard"), allow(dead_code))]
+
+use crate::{live_view::ViewError, telemetry};
+use http::Uri;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::SystemTime,
+};
+use tokio::sync::Notify;
+
+/// How many diff sizes are kept for each view.
+const RECENT_DIFFS: usize = 10;
+
+/// How many errors are kept across all views.
+const RECENT_ERRORS: usize = 20;
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+#[derive(Default)]
+struct Registry {
+    next_id: AtomicU64,
+    // ordered by id, which is the order the views connected in
+    views: Mutex<BTreeMap<u64, Arc<Entry>>>,
+    errors: Mutex<VecDeque<RecentError>>,
+}
+
+struct Entry {
+    id: u64,
+    view: &'static str,
+    uri: Uri,
+    connected_at: SystemTime,
+    messages: AtomicU64,
+    diffs: Mutex<VecDeque<usize>>,
+    disconnect: Notify,
+}
+
+/// Start registering views.
+pub(crate) fn enable() {
+    REGISTRY.get_or_init(Registry::default);
+}
+
+/// Register a view that is about to be mounted on a socket.
+///
+/// The view is removed from the registry when the returned [`Registration`] is dropped.
+pub(crate) fn register(view: &'static str, uri: &Uri) -> Registration {
+    let Some(registry) = REGISTRY.get() else {
+        return Registration { entry: None };
+    };
+
+    let id = registry.next_id.fetch_add(1, Ordering::Relaxed);
+    let entry = Arc::new(Entry {
+        id,
+        view,
+        uri: uri.clone(),
+        connected_at: SystemTime::now(),
+        messages: AtomicU64::new(0),
+        diffs: Mutex::new(VecDeque::with_capacity(RECENT_DIFFS)),
+        disconnect: Notify::new(),
+    });
+    registry.views.lock().unwrap().insert(id, entry.clone());
+
+    Registration { entry: Some(entry) }
+}
+
+/// Summaries of the registered views and the most recent errors, newest first.
+pub(crate) fn snapshot() -> (Vec<ViewSummary>, Vec<RecentError>) {
+    let Some(registry) = REGISTRY.get() else {
+        return Default::default();
+    };
+
+    let views = registry
+        .views
+        .lock()
+        .unwrap()
+        .values()
+        .map(|entry| ViewSummary {
+            id: entry.id,
+            view: entry.view,
+            uri: entry.uri.to_string(),
+            connected_at: entry.connected_at,
+            messages: entry.messages.load(Ordering::Relaxed),
+            recent_diffs: entry.diffs.lock().unwrap().iter().copied().collect(),
+        })
+        .collect();
+    let errors = registry.errors.lock().unwrap().iter().cloned().collect();
+
+    (views, errors)
+}
+
+/// Disconnect the socket of the view with the given id.
+///
+/// Returns `false` if there is no such view.
+pub(crate) fn disconnect(id: u64) -> bool {
+    let Some(registry) = REGISTRY.get() else {
+        return false;
+    };
+
+    match registry.views.lock().unwrap().get(&id) {
+        Some(entry) => {
+            // stores a permit so it isn't missed if the view isn't waiting yet
+            entry.disconnect.notify_one();
+            true
+        }
+        None => false,
+    }
+}
+
+/// A view in the registry.
+///
+/// All methods do nothing if the registry wasn't enabled when the view was registered.
+pub(crate) struct Registration {
+    entry: Option<Arc<Entry>>,
+}
+
+impl Registration {
+    /// Count a message sent to the view.
+    pub(crate) fn record_message(&self) {
+        if let Some(entry) = &self.entry {
+            entry.messages.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Record the size of a diff sent to the client.
+    pub(crate) fn record_diff(&self, diff: &Value) {
+        if let Some(entry) = &self.entry {
+            let mut diffs = entry.diffs.lock().unwrap();
+            if diffs.len() == RECENT_DIFFS {
+                diffs.pop_back();
+            }
+            diffs.push_front(telemetry::diff_bytes(diff));
+        }
+    }
+
+    /// Record that the view failed.
+    pub(crate) fn record_error(&self, err: &ViewError) {
+        let (Some(entry), Some(registry)) = (&self.entry, REGISTRY.get()) else {
+            return;
+        };
+
+        let mut errors = registry.errors.lock().unwrap();
+        if errors.len() == RECENT_ERRORS {
+            errors.pop_back();
+        }
+        errors.push_front(RecentError {
+            view: entry.view,
+            uri: entry.uri.to_string(),
+            at: SystemTime::now(),
+            message: err.to_string(),
+        });
+    }
+
+    /// Wait until the view is disconnected from the dashboard.
+    ///
+    /// Never resolves if the view isn't registered.
+    pub(crate) fn disconnected(&self) -> impl Future<Output = ()> + Send + 'static {
+        let entry = self.entry.clone();
+        async move {
+            match entry {
+                Some(entry) => entry.disconnect.notified().await,
+                None => std::future::pending().await,
+            }
+        }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let (Some(entry), Some(registry)) = (&self.entry, REGISTRY.get()) {
+            registry.views.lock().unwrap().remove(&entry.id);
+        }
+    }
+}
+
+/// A view in a [`snapshot`].
+#[derive(Debug, Clone)]
+pub(crate) struct ViewSummary {
+    pub(crate) id: u64,
+    pub(crate) view: &'static str,
+    pub(crate) uri: String,
+    pub(crate) connected_at: SystemTime,
+    pub(crate) messages: u64,
+    /// Sizes of the most recent diffs in bytes, newest first.
+    pub(crate) recent_diffs: Vec<usize>,
+}
+
+/// An error in a [`snapshot`].
+#[derive(Debug, Clone)]
+pub(crate) struct RecentError {
+    pub(crate) view: &'static str,
+    pub(crate) uri: String,
+    pub(crate) at: SystemTime,
+    pub(crate) message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // the registry is shared by every test in the process, so each test uses its own URI
+    fn find(uri: &str) -> Option<ViewSummary> {
+        snapshot().0.into_iter().find(|view| view.uri == uri)
+    }
+
+    #[test]
+    fn tracks_views_until_dropped() {
+        enable();
+        let registration = register("Counter", &"/tracked".parse().unwrap());
+
+        registration.record_message();
+        registration.record_message();
+        for n in 0..=RECENT_DIFFS {
+            registration.record_diff(&json!({ "0": n }));
+        }
+
+        let view = find("/tracked").unwrap();
+        assert_eq!(view.view, "Counter");
+        assert_eq!(view.messages, 2);
+        assert_eq!(view.recent_diffs.len(), RECENT_DIFFS);
+        assert_eq!(view.recent_diffs[0], json!({ "0": 10 }).to_string().len());
+
+        drop(registration);
+        assert!(find("/tracked").is_none());
+    }
+
+    #[test]
+    fn keeps_errors_after_views_are_gone() {
+        enable();
+        let registration = register("Counter", &"/failing".parse().unwrap());
+        registration.record_error(&ViewError::msg("boom"));
+        drop(registration);
+
+        let (_, errors) = snapshot();
+        assert!(errors
+            .iter()
+            .any(|err| err.uri == "/failing" && err.message == "boom"));
+    }
+
+    #[tokio::test]
+    async fn disconnects_views() {
+        enable();
+        let registration = register("Counter", &"/disconnected".parse().unwrap());
+        let id = find("/disconnected").unwrap().id;
+
+        assert!(disconnect(id));
+        registration.disconnected().await;
+
+        drop(registration);
+        assert!(!disconnect(id));
+    }
+}
@@ -0,0 +1,248 @@
+//! Drain live views when the server shuts down.
+//!
+//! [`axum::serve`] stops accepting connections on graceful shutdown but WebSockets are detached
+//! from the connection that upgraded them, so live views are cut off when the process exits.
+//! Every client then reconnects at the same moment and updates that were running are lost.
+//!
+//! A [`Shutdown`] coordinates this instead. Once shutdown starts:
+//!
+//! - new sockets are told to reconnect later rather than mounting a view.
+//! - running updates finish and their results are sent to the client.
+//! - each view's [`LiveView::terminate`] is called with [`ShutdownReason::ServerShutdown`].
+//! - the client is told the server is going away and reconnects after a random delay, so the
+//!   reconnects are spread out.
+//!
+//! Add the [`Shutdown`] to the request extensions and pass [`Shutdown::drain_on`] to
+//! [`with_graceful_shutdown`]. It waits for the views to drain before axum stops.
+//!
+//! # Example
+//!
+//! ```
+//! use axum::{routing::get, Extension, Router};
+//! use shipwright_liveview::shutdown::Shutdown;
+//! use std::time::Duration;
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let shutdown = Shutdown::new().drain_timeout(Duration::from_secs(10));
+//!
+//! let app = Router::new()
+//!     .route("/", get(|| async {}))
+//!     .layer(Extension(shutdown.clone()));
+//!
+//! let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
+//! axum::serve(listener, app)
+//!     .with_graceful_shutdown(shutdown.drain_on(async {
+//!         let _ = tokio::signal::ctrl_c().await;
+//!     }))
+//!     .await
+//! # }
+//! ```
+//!
+//! [`LiveView::terminate`]: crate::LiveView::terminate
+//! [`ShutdownReason::ServerShutdown`]: crate::live_view::ShutdownReason::ServerShutdown
+//! [`with_graceful_shutdown`]: axum::serve::Serve::with_graceful_shutdown
+
+use crate::{
+    codec::Encoding,
+    life_cycle::{close_code, MessageToSocket},
+    socket::Socket,
+};
+use futures_util::SinkExt;
+use std::{fmt, future::Future, sync::Arc, time::Duration};
+use tokio::sync::watch;
+
+/// Coordinates draining live views when the server shuts down.
+///
+/// See the [module docs](self) for more details.
+#[derive(Clone)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+    drain_timeout: Duration,
+    reconnect_jitter: Duration,
+}
+
+struct Inner {
+    // `true` once shutdown has started
+    started: watch::Sender<bool>,
+    // every running view holds a receiver, so the views have drained once all are dropped
+    views: watch::Sender<()>,
+}
+
+impl Shutdown {
+    /// Create a new coordinator.
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                started: watch::channel(false).0,
+                views: watch::channel(()).0,
+            }),
+            drain_timeout: Duration::from_secs(30),
+            reconnect_jitter: Duration::from_secs(10),
+        }
+    }
+
+    /// Set how long to wait for views to drain before giving up.
+    ///
+    /// Defaults to 30 seconds.
+    pub fn drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = timeout;
+        self
+    }
+
+    /// Set the longest a client waits before reconnecting.
+    ///
+    /// Each client picks a random delay up to this. Defaults to 10 seconds.
+    pub fn reconnect_jitter(mut self, jitter: Duration) -> Self {
+        self.reconnect_jitter = jitter;
+        self
+    }
+
+    /// Start shutting down once `signal` resolves and wait for the views to drain.
+    ///
+    /// Pass the returned future to [`with_graceful_shutdown`].
+    ///
+    /// [`with_graceful_shutdown`]: axum::serve::Serve::with_graceful_shutdown
+    pub async fn drain_on<F>(self, signal: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        signal.await;
+        self.shutdown().await;
+    }
+
+    /// Start shutting down and wait for the views to drain, or the drain timeout to pass.
+    pub async fn shutdown(&self) {
+        self.inner.started.send_replace(true);
+
+        let views = self.active_views();
+        tracing::debug!(views, "draining live views");
+
+        if tokio::time::timeout(self.drain_timeout, self.inner.views.closed())
+            .await
+            .is_err()
+        {
+            let views = self.active_views();
+            tracing::warn!(views, "live views didn't drain before the timeout");
+        }
+    }
+
+    /// Whether shutdown has started.
+    pub fn is_shutting_down(&self) -> bool {
+        *self.inner.started.borrow()
+    }
+
+    /// The number of live views that are still running.
+    pub fn active_views(&self) -> usize {
+        self.inner.views.receiver_count()
+    }
+
+    /// Keep the shutdown waiting until the returned guard is dropped.
+    pub(crate) fn track_view(&self) -> watch::Receiver<()> {
+        self.inner.views.subscribe()
+    }
+
+    /// Wait until shutdown starts.
+    pub(crate) fn started(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut started = self.inner.started.subscribe();
+        async move {
+            let _ = started.wait_for(|started| *started).await;
+        }
+    }
+
+    /// The messages telling a client the server is going away, ending with a close frame.
+    pub(crate) fn going_away(&self) -> [MessageToSocket; 2] {
+        [
+            MessageToSocket::going_away(self.reconnect_delay()),
+            MessageToSocket::close(close_code::GOING_AWAY, "server shutting down"),
+        ]
+    }
+
+    /// Tell a client that connected during shutdown to reconnect later.
+    pub(crate) async fn reject_socket(&self, mut socket: Socket, encoding: Encoding) {
+        for msg in self.going_away() {
+            let Ok(msg) = encoding.encode(&msg) else {
+                return;
+            };
+            if socket.send(msg).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    fn reconnect_delay(&self) -> Duration {
+        let max = self.reconnect_jitter.as_millis() as u64;
+        if max == 0 {
+            return Duration::ZERO;
+        }
+
+        let mut bytes = [0; 8];
+        getrandom::getrandom(&mut bytes).expect("failed to generate reconnect delay");
+        Duration::from_millis(u64::from_le_bytes(bytes) % max)
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Shutdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Shutdown")
+            .field("shutting_down", &self.is_shutting_down())
+            .field("active_views", &self.active_views())
+            .field("drain_timeout", &self.drain_timeout)
+            .field("reconnect_jitter", &self.reconnect_jitter)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn waits_for_views_to_drain() {
+        let shutdown = Shutdown::new();
+        let view = shutdown.track_view();
+        let started = shutdown.started();
+        assert_eq!(shutdown.active_views(), 1);
+
+        let draining = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.shutdown().await }
+        });
+
+        started.await;
+        assert!(shutdown.is_shutting_down());
+        assert!(!draining.is_finished());
+
+        drop(view);
+        draining.await.unwrap();
+        assert_eq!(shutdown.active_views(), 0);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_drain_timeout() {
+        let shutdown = Shutdown::new().drain_timeout(Duration::from_millis(20));
+        let _view = shutdown.track_view();
+
+        shutdown.shutdown().await;
+        assert_eq!(shutdown.active_views(), 1);
+    }
+
+    #[test]
+    fn reconnect_delay_is_within_jitter() {
+        let shutdown = Shutdown::new().reconnect_jitter(Duration::from_millis(500));
+        for _ in 0..100 {
+            assert!(shutdown.reconnect_delay() < Duration::from_millis(500));
+        }
+        assert_eq!(
+            Shutdown::new()
+                .reconnect_jitter(Duration::ZERO)
+                .reconnect_delay(),
+            Duration::ZERO
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Sign values sent to the client so they can't be tampered with.
+//!
+//! Some state, such as [flash messages](crate::flash), is stored by the browser between requests.
+//! It is signed with a [`SigningKey`] so the server can check it hasn't been changed.
+//!
+//! The key is read from the request extensions. Without one, a random key is generated when the
+//! process starts. That works for a single server but signed values don't survive restarts and
+//! aren't accepted by other instances, so production apps should configure a key:
+//!
+//! ```
+//! use axum::{routing::get, Extension, Router};
+//! use shipwright_liveview::signing::SigningKey;
+//!
+//! # let secret = [0; 32];
+//! let app = Router::new()
+//!     .route("/", get(|| async {}))
+//!     .layer(Extension(SigningKey::new(secret)));
+//! # let _: Router = app;
+//! ```
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use http::Extensions;
+use sha2::Sha256;
+use std::{
+    fmt,
+    sync::{Arc, OnceLock},
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+// bytes of the MAC kept in a tag
+const TAG_LEN: usize = 16;
+
+/// A secret key used to sign values stored by the client.
+///
+/// See the [module docs](self) for how to configure it.
+#[derive(Clone)]
+pub struct SigningKey {
+    secret: Arc<[u8]>,
+}
+
+impl SigningKey {
+    /// Minimum length of the secret in bytes.
+    pub const MIN_LEN: usize = 32;
+
+    /// Create a key from a secret.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the secret is shorter than [`SigningKey::MIN_LEN`] bytes.
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        let secret = secret.as_ref();
+        assert!(
+            secret.len() >= Self::MIN_LEN,
+            "signing key must be at least {} bytes",
+            Self::MIN_LEN
+        );
+        Self {
+            secret: secret.into(),
+        }
+    }
+
+    /// Generate a random key.
+    pub fn generate() -> Self {
+        let mut secret = [0; Self::MIN_LEN];
+        getrandom::getrandom(&mut secret).expect("failed to generate signing key");
+        Self::new(secret)
+    }
+
+    /// The key in the request extensions, or the key generated for this process.
+    pub(crate) fn from_extensions(extensions: &Extensions) -> Self {
+        static PROCESS_KEY: OnceLock<SigningKey> = OnceLock::new();
+
+        extensions
+            .get::<SigningKey>()
+            .cloned()
+            .unwrap_or_else(|| PROCESS_KEY.get_or_init(SigningKey::generate).clone())
+    }
+
+    /// Sign `value`, returning a string safe to use in cookies and URLs.
+    ///
+    /// `purpose` is included in the signature so a value signed for one purpose can't be used for
+    /// another.
+    pub(crate) fn sign(&self, purpose: &str, value: &[u8]) -> String {
+        let mac = self.mac(purpose, value);
+        format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(value),
+            URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+        )
+    }
+
+    /// Check a value produced by [`SigningKey::sign`] and return the original value.
+    pub(crate) fn verify(&self, purpose: &str, signed: &str) -> Option<Vec<u8>> {
+        let (value, signature) = signed.split_once('.')?;
+        let value = URL_SAFE_NO_PAD.decode(value).ok()?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+
+        self.mac(purpose, &value).verify_slice(&signature).ok()?;
+
+        Some(value)
+    }
+
+    /// A short tag for `value` that can't be computed without the key.
+    ///
+    /// Equal values get equal tags.
+    pub(crate) fn tag(&self, purpose: &str, value: &[u8]) -> String {
+        let mac = self.mac(purpose, value).finalize().into_bytes();
+        URL_SAFE_NO_PAD.encode(&mac[..TAG_LEN])
+    }
+
+    /// Check that `tag` was produced by [`SigningKey::tag`] for `value`, in constant time.
+    pub(crate) fn verify_tag(&self, purpose: &str, value: &[u8], tag: &str) -> bool {
+        let Ok(tag) = URL_SAFE_NO_PAD.decode(tag) else {
+            return false;
+        };
+        // shorter tags would be checked against fewer bytes of the MAC
+        tag.len() == TAG_LEN && self.mac(purpose, value).verify_truncated_left(&tag).is_ok()
+    }
+
+    fn mac(&self, purpose: &str, value: &[u8]) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(purpose.as_bytes());
+        mac.update(&[0]);
+        mac.update(value);
+        mac
+    }
+}
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigningKey").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_and_verify() {
+        let key = SigningKey::generate();
+        let signed = key.sign("test", b"hello");
+        assert_eq!(key.verify("test", &signed).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn rejects_tampered_values() {
+        let key = SigningKey::generate();
+        let signed = key.sign("test", b"hello");
+        let (_, signature) = signed.split_once('.').unwrap();
+        let tampered = format!("{}.{}", URL_SAFE_NO_PAD.encode(b"world"), signature);
+
+        assert!(key.verify("test", &tampered).is_none());
+        assert!(key.verify("other", &signed).is_none());
+        assert!(SigningKey::generate().verify("test", &signed).is_none());
+        assert!(key.verify("test", "garbage").is_none());
+    }
+
+    #[test]
+    fn verify_tags() {
+        let key = SigningKey::generate();
+        let tag = key.tag("test", b"hello");
+
+        assert!(key.verify_tag("test", b"hello", &tag));
+        assert!(!key.verify_tag("test", b"world", &tag));
+        assert!(!key.verify_tag("other", b"hello", &tag));
+        assert!(!key.verify_tag("test", b"hello", &tag[..tag.len() - 2]));
+        assert!(!key.verify_tag("test", b"hello", ""));
+        assert!(!key.verify_tag("test", b"hello", "!"));
+    }
+}
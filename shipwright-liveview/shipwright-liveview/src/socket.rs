@@ -0,0 +1,166 @@
+//! The transports a live view can run on.
+
+use crate::{
+    codec::Encoding,
+    limits::SocketLimits,
+    multiplex::ChannelSocket,
+    sse::{SseSocket, SseUpgrade},
+    transport::FrameSocket,
+};
+use axum::{
+    extract::{
+        ws::{self, WebSocket, WebSocketUpgrade},
+        FromRequestParts,
+    },
+    http::request::Parts,
+    response::{IntoResponse, Response},
+};
+use futures_util::{Sink, Stream};
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A request to connect a live view, either as a WebSocket upgrade or over the [SSE
+/// fallback](crate::sse).
+pub(crate) enum Upgrade {
+    Ws(WebSocketUpgrade),
+    Sse(SseUpgrade),
+}
+
+impl Upgrade {
+    /// Get the upgrade from a request, if it is one.
+    pub(crate) async fn from_request_parts<S>(parts: &mut Parts, state: &S) -> Option<Self>
+    where
+        S: Send + Sync,
+    {
+        if let Ok(ws) = WebSocketUpgrade::from_request_parts(parts, state).await {
+            return Some(Self::Ws(ws));
+        }
+        SseUpgrade::from_request_parts(parts).map(Self::Sse)
+    }
+
+    /// The encoding the client asked for.
+    ///
+    /// Server-Sent Events can only carry text so they always use JSON.
+    pub(crate) fn encoding(&self, parts: &Parts) -> Encoding {
+        match self {
+            Self::Ws(_) => Encoding::from_uri(&parts.uri),
+            Self::Sse(_) => Encoding::Json,
+        }
+    }
+
+    /// Make the WebSocket refuse messages and frames over the size limit before buffering them,
+    /// rather than after they have been read.
+    pub(crate) fn with_limits(self, limits: &SocketLimits) -> Self {
+        match self {
+            Self::Ws(ws) => {
+                let size = limits.message_size_limit();
+                Self::Ws(ws.max_message_size(size).max_frame_size(size))
+            }
+            Self::Sse(sse) => Self::Sse(sse),
+        }
+    }
+
+    /// Respond to the request and run `callback` with the connected socket.
+    pub(crate) fn on_upgrade<F, Fut>(self, callback: F) -> Response
+    where
+        F: FnOnce(Socket) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        match self {
+            Self::Ws(ws) => ws
+                .on_upgrade(move |socket| callback(Socket::Ws(Box::new(socket))))
+                .into_response(),
+            Self::Sse(sse) => sse.on_upgrade(move |socket| callback(Socket::Sse(socket))),
+        }
+    }
+}
+
+impl fmt::Debug for Upgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ws(ws) => f.debug_tuple("Ws").field(ws).finish(),
+            Self::Sse(sse) => f.debug_tuple("Sse").field(sse).finish(),
+        }
+    }
+}
+
+/// A connected socket.
+///
+/// Messages are WebSocket messages regardless of the transport, so the rest of the crate doesn't
+/// have to care which one the client used, whether the view [shares the
+/// socket](crate::multiplex) with others, or whether it's run by [another
+/// server](crate::transport).
+pub(crate) enum Socket {
+    Ws(Box<WebSocket>),
+    Sse(SseSocket),
+    Channel(ChannelSocket),
+    Frames(FrameSocket),
+}
+
+impl Stream for Socket {
+    type Item = Result<ws::Message, axum::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.get_mut() {
+            Self::Ws(socket) => Pin::new(&mut **socket).poll_next(cx),
+            Self::Sse(socket) => Pin::new(socket).poll_next(cx),
+            Self::Channel(socket) => Pin::new(socket).poll_next(cx),
+            Self::Frames(socket) => Pin::new(socket).poll_next(cx),
+        }
+    }
+}
+
+impl Sink<ws::Message> for Socket {
+    type Error = axum::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        match self.get_mut() {
+            Self::Ws(socket) => Pin::new(&mut **socket).poll_ready(cx),
+            Self::Sse(socket) => Pin::new(socket).poll_ready(cx),
+            Self::Channel(socket) => Pin::new(socket).poll_ready(cx),
+            Self::Frames(socket) => Pin::new(socket).poll_ready(cx),
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: ws::Message) -> Result<(), Self::Error> {
+        match self.get_mut() {
+            Self::Ws(socket) => Pin::new(&mut **socket).start_send(item),
+            Self::Sse(socket) => Pin::new(socket).start_send(item),
+            Self::Channel(socket) => Pin::new(socket).start_send(item),
+            Self::Frames(socket) => Pin::new(socket).start_send(item),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        match self.get_mut() {
+            Self::Ws(socket) => Pin::new(&mut **socket).poll_flush(cx),
+            Self::Sse(socket) => Pin::new(socket).poll_flush(cx),
+            Self::Channel(socket) => Pin::new(socket).poll_flush(cx),
+            Self::Frames(socket) => Pin::new(socket).poll_flush(cx),
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        match self.get_mut() {
+            Self::Ws(socket) => Pin::new(&mut **socket).poll_close(cx),
+            Self::Sse(socket) => Pin::new(socket).poll_close(cx),
+            Self::Channel(socket) => Pin::new(socket).poll_close(cx),
+            Self::Frames(socket) => Pin::new(socket).poll_close(cx),
+        }
+    }
+}
+
+impl fmt::Debug for Socket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ws(socket) => f.debug_tuple("Ws").field(socket).finish(),
+            Self::Sse(socket) => f.debug_tuple("Sse").field(socket).finish(),
+            Self::Channel(socket) => f.debug_tuple("Channel").field(socket).finish(),
+            Self::Frames(socket) => f.debug_tuple("Frames").field(socket).finish(),
+        }
+    }
+}
@@ -0,0 +1,517 @@
+//! Server-Sent Events fallback for clients that can't open WebSockets.
+//!
+//! Some proxies block WebSockets, which leaves a live view page dead. With [`SseLayer`] added to
+//! the router, the JavaScript client falls back to Server-Sent Events when its WebSocket can't be
+//! opened. Renders and diffs are streamed to the client as events and the client's messages are
+//! sent with `POST` requests.
+//!
+//! Views behave exactly as they do on a WebSocket. The same [CSRF checks](crate::csrf),
+//! [limits](crate::limits), [live navigation](crate::navigation) and
+//! [shutdown](crate::shutdown) apply, and [`LiveView::terminate`] is called when the event
+//! stream is closed. Messages are always encoded as JSON.
+//!
+//! # Example
+//!
+//! ```
+//! use axum::{routing::get, Router};
+//! use shipwright_liveview::sse::SseLayer;
+//!
+//! let app = Router::new()
+//!     .route("/", get(index))
+//!     .layer(SseLayer::new());
+//! # let _: Router = app;
+//!
+//! async fn index() { /* ... */ }
+//! ```
+//!
+//! # Protocol
+//!
+//! - The client opens an `EventSource` for the URL it would have opened the WebSocket for,
+//!   including the `_csrf` query parameter. The first event is a `session` event with the id of
+//!   the session. Every message the server would have sent over the WebSocket is sent as a
+//!   regular event with the same JSON. Instead of a close frame, a `close` event is sent with
+//!   `{"c": <code>, "r": <reason>}` and the stream ends.
+//! - The client sends its messages one at a time by `POST`ing them to the same URL, with the
+//!   session id in the `x-live-view-session` header. JSON messages are sent as text and upload
+//!   chunks with `content-type: application/octet-stream`. The server replies with
+//!   `202 Accepted`, or `404 Not Found` if the session has ended.
+//!
+//! [`LiveView::terminate`]: crate::LiveView::terminate
+
+use crate::util::ReceiverStream;
+use axum::{
+    extract::{ws, Request},
+    http::{header, request::Parts, HeaderName, HeaderValue, Method, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use futures_util::{future::BoxFuture, Sink, Stream};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+use tokio::sync::mpsc;
+use tokio_util::sync::PollSender;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Header with the session id of messages sent by the client.
+const SESSION_HEADER: HeaderName = HeaderName::from_static("x-live-view-session");
+
+/// The largest message accepted from the client, same as the default for WebSocket messages.
+///
+/// Smaller limits are enforced by [`SocketLimits`](crate::limits::SocketLimits).
+const MAX_BODY_SIZE: usize = 64 << 20;
+
+/// How many messages can be buffered in each direction.
+const CHANNEL_CAPACITY: usize = 32;
+
+/// [`Layer`] that lets clients connect live views over Server-Sent Events.
+///
+/// See the [module docs](self) for more details.
+#[derive(Clone, Default)]
+pub struct SseLayer {
+    sessions: SseSessions,
+}
+
+impl SseLayer {
+    /// Create a new `SseLayer`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl fmt::Debug for SseLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SseLayer").finish_non_exhaustive()
+    }
+}
+
+impl<S> Layer<S> for SseLayer {
+    type Service = SseService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SseService {
+            inner,
+            sessions: self.sessions.clone(),
+        }
+    }
+}
+
+/// Service that accepts messages for live views connected over Server-Sent Events.
+///
+/// Created with [`SseLayer`].
+#[derive(Clone)]
+pub struct SseService<S> {
+    inner: S,
+    sessions: SseSessions,
+}
+
+impl<S> fmt::Debug for SseService<S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SseService")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> Service<Request> for SseService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request) -> Self::Future {
+        if req.method() == Method::POST && req.headers().contains_key(SESSION_HEADER) {
+            let sessions = self.sessions.clone();
+            return Box::pin(async move { Ok(post_message(sessions, req).await) });
+        }
+
+        req.extensions_mut().insert(self.sessions.clone());
+
+        // the clone might not be ready, so call the instance `poll_ready` was called on
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(inner.call(req))
+    }
+}
+
+/// Pass a message `POST`ed by the client to its session.
+async fn post_message(sessions: SseSessions, req: Request) -> Response {
+    let (parts, body) = req.into_parts();
+
+    let Some(incoming) = parts
+        .headers
+        .get(SESSION_HEADER)
+        .and_then(|id| id.to_str().ok())
+        .and_then(|id| sessions.get(id))
+    else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let body = match axum::body::to_bytes(body, MAX_BODY_SIZE).await {
+        Ok(body) => body,
+        Err(err) => {
+            tracing::trace!(%err, "failed to read message from client");
+            return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+        }
+    };
+
+    let binary = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|content_type| content_type == "application/octet-stream");
+    let msg = if binary {
+        ws::Message::Binary(body)
+    } else {
+        match ws::Utf8Bytes::try_from(body) {
+            Ok(text) => ws::Message::Text(text),
+            Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+        }
+    };
+
+    match incoming.send(msg).await {
+        Ok(()) => StatusCode::ACCEPTED.into_response(),
+        // the view stopped after the session was looked up
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// The sessions of the clients connected over Server-Sent Events.
+///
+/// Inserted into the request extensions by [`SseService`] so [`LiveViewUpgrade`] knows the
+/// fallback is enabled.
+///
+/// [`LiveViewUpgrade`]: crate::LiveViewUpgrade
+#[derive(Clone, Default)]
+pub(crate) struct SseSessions {
+    // the senders for the messages from each client, by session id
+    inner: Arc<Mutex<HashMap<String, mpsc::Sender<ws::Message>>>>,
+}
+
+impl SseSessions {
+    fn get(&self, id: &str) -> Option<mpsc::Sender<ws::Message>> {
+        self.inner.lock().unwrap().get(id).cloned()
+    }
+
+    fn insert(&self, incoming: mpsc::Sender<ws::Message>) -> SessionGuard {
+        let mut bytes = [0; 16];
+        getrandom::getrandom(&mut bytes).expect("failed to generate session id");
+        let id = URL_SAFE_NO_PAD.encode(bytes);
+
+        self.inner.lock().unwrap().insert(id.clone(), incoming);
+
+        SessionGuard {
+            sessions: self.clone(),
+            id,
+        }
+    }
+}
+
+/// Removes a session when the client's event stream is dropped, which ends the messages from the
+/// client and thereby shuts the view down.
+struct SessionGuard {
+    sessions: SseSessions,
+    id: String,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        self.sessions.inner.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// A request to connect a live view over Server-Sent Events.
+pub(crate) struct SseUpgrade {
+    sessions: SseSessions,
+}
+
+impl SseUpgrade {
+    /// Get the upgrade from a request for an event stream, if [`SseLayer`] was added.
+    pub(crate) fn from_request_parts(parts: &Parts) -> Option<Self> {
+        if parts.method != Method::GET {
+            return None;
+        }
+        let accepts_event_stream = parts
+            .headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|accept| accept.to_str().ok())
+            .any(|accept| accept.contains("text/event-stream"));
+        if !accepts_event_stream {
+            return None;
+        }
+
+        let sessions = parts.extensions.get::<SseSessions>()?.clone();
+        Some(Self { sessions })
+    }
+
+    /// Respond with the event stream and run `callback` with the connected socket.
+    pub(crate) fn on_upgrade<F, Fut>(self, callback: F) -> Response
+    where
+        F: FnOnce(SseSocket) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (incoming_tx, incoming_rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let (outgoing_tx, mut outgoing_rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let session = self.sessions.insert(incoming_tx);
+
+        crate::util::spawn_unit(callback(SseSocket {
+            incoming: ReceiverStream::new(incoming_rx),
+            outgoing: PollSender::new(outgoing_tx),
+        }));
+
+        let events = async_stream::stream! {
+            let session = session;
+            yield Ok::<_, Infallible>(Event::default().event("session").data(&session.id));
+
+            while let Some(msg) = outgoing_rx.recv().await {
+                match msg {
+                    ws::Message::Text(text) => yield Ok(Event::default().data(text.as_str())),
+                    ws::Message::Close(frame) => {
+                        let (code, reason) = frame
+                            .map(|frame| (frame.code, frame.reason.to_string()))
+                            .unwrap_or((ws::close_code::NORMAL, String::new()));
+                        let data = serde_json::json!({ "c": code, "r": reason });
+                        yield Ok(Event::default().event("close").data(data.to_string()));
+                        break;
+                    }
+                    // only JSON is used, and pings are replaced by the keep-alive comments
+                    ws::Message::Binary(_) | ws::Message::Ping(_) | ws::Message::Pong(_) => {}
+                }
+            }
+        };
+
+        let mut response = Sse::new(events)
+            .keep_alive(KeepAlive::default())
+            .into_response();
+        // stops nginx from buffering the events
+        response
+            .headers_mut()
+            .insert("x-accel-buffering", HeaderValue::from_static("no"));
+        response
+    }
+}
+
+impl fmt::Debug for SseUpgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SseUpgrade").finish_non_exhaustive()
+    }
+}
+
+/// A client connected over Server-Sent Events, with the same messages as a WebSocket.
+pub(crate) struct SseSocket {
+    incoming: ReceiverStream<ws::Message>,
+    outgoing: PollSender<ws::Message>,
+}
+
+impl Stream for SseSocket {
+    type Item = Result<ws::Message, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.incoming)
+            .poll_next(cx)
+            .map(|msg| msg.map(Ok))
+    }
+}
+
+impl Sink<ws::Message> for SseSocket {
+    type Error = axum::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.outgoing)
+            .poll_ready(cx)
+            .map_err(axum::Error::new)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: ws::Message) -> Result<(), Self::Error> {
+        Pin::new(&mut self.outgoing)
+            .start_send(item)
+            .map_err(axum::Error::new)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.outgoing)
+            .poll_flush(cx)
+            .map_err(axum::Error::new)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.outgoing)
+            .poll_close(cx)
+            .map_err(axum::Error::new)
+    }
+}
+
+impl fmt::Debug for SseSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SseSocket").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        csrf,
+        signing::SigningKey,
+        test::fixtures::{app, counter},
+    };
+    use axum::{body::Body, http::Extensions, routing::get, Router};
+    use futures_util::StreamExt;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    /// Reads events from an event stream, as `(event, data)`.
+    pub(crate) struct Events {
+        body: futures_util::stream::BoxStream<'static, Result<axum::body::Bytes, axum::Error>>,
+        buffer: String,
+    }
+
+    impl Events {
+        pub(crate) async fn next(&mut self) -> (String, String) {
+            loop {
+                if let Some(end) = self.buffer.find("\n\n") {
+                    let raw = self.buffer[..end].to_owned();
+                    self.buffer.drain(..end + 2);
+
+                    let mut event = "message".to_owned();
+                    let mut data = String::new();
+                    for line in raw.lines() {
+                        if let Some(value) = line.strip_prefix("event: ") {
+                            event = value.to_owned();
+                        } else if let Some(value) = line.strip_prefix("data: ") {
+                            data = value.to_owned();
+                        }
+                    }
+                    // keep-alive comments have neither
+                    if !data.is_empty() {
+                        return (event, data);
+                    }
+                    continue;
+                }
+
+                let chunk = self.body.next().await.unwrap().unwrap();
+                self.buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+            }
+        }
+    }
+
+    pub(crate) async fn connect(app: Router) -> Events {
+        let (token, cookie) = csrf::test_token(&SigningKey::from_extensions(&Extensions::new()));
+        let request = Request::builder()
+            .uri(format!("/?_csrf={token}"))
+            .header(header::ACCEPT, "text/event-stream")
+            .header(header::COOKIE, cookie)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+
+        Events {
+            body: response.into_body().into_data_stream().boxed(),
+            buffer: String::new(),
+        }
+    }
+
+    pub(crate) async fn post(app: Router, session: &str, body: &str) -> StatusCode {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/")
+            .header(SESSION_HEADER, session)
+            .body(Body::from(body.to_owned()))
+            .unwrap();
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn runs_views_over_event_streams() {
+        let app = app(counter);
+        let mut events = connect(app.clone()).await;
+
+        let (event, session) = events.next().await;
+        assert_eq!(event, "session");
+
+        let (event, data) = events.next().await;
+        assert_eq!(event, "message");
+        assert_eq!(serde_json::from_str::<Value>(&data).unwrap()["t"], "i");
+
+        let click = r#"{"m":"%22Incr%22","t":"click"}"#;
+        assert_eq!(
+            post(app.clone(), &session, click).await,
+            StatusCode::ACCEPTED
+        );
+
+        let (_, data) = events.next().await;
+        let render = serde_json::from_str::<Value>(&data).unwrap();
+        assert_eq!(render["t"], "r");
+        assert!(data.contains('1'));
+
+        // dropping the stream ends the session
+        drop(events);
+        assert_eq!(post(app, &session, click).await, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_csrf_tokens() {
+        let request = Request::builder()
+            .uri("/?_csrf=nope")
+            .header(header::ACCEPT, "text/event-stream")
+            .body(Body::empty())
+            .unwrap();
+        let response = app(counter).oneshot(request).await.unwrap();
+        let mut events = Events {
+            body: response.into_body().into_data_stream().boxed(),
+            buffer: String::new(),
+        };
+
+        let (event, _) = events.next().await;
+        assert_eq!(event, "session");
+        let (event, data) = events.next().await;
+        assert_eq!(event, "close");
+        assert_eq!(
+            serde_json::from_str::<Value>(&data).unwrap(),
+            serde_json::json!({ "c": 4002, "r": "invalid CSRF token" })
+        );
+    }
+
+    #[tokio::test]
+    async fn ignores_requests_without_the_layer() {
+        let app = Router::new().route("/", get(counter));
+        let request = Request::builder()
+            .uri("/")
+            .header(header::ACCEPT, "text/event-stream")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+    }
+}
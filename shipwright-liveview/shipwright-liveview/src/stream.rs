@@ -0,0 +1,316 @@
+//! Render large collections without keeping them in memory.
+//!
+//! A [`Stream`] sends items to the client once and then forgets them. The client keeps the
+//! rendered rows in the DOM and applies new insertions and deletions as they arrive. This is
+//! useful for things like activity feeds, chat messages and logs where the view would otherwise
+//! have to keep every item around just to render it again.
+//!
+//! The trade-off is that the server doesn't know what the client is showing. Items can't be
+//! updated by re-rendering the view, instead they have to be inserted again with the same key.
+//!
+//! # Example
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//! use shipwright_liveview::{
+//!     event_data::EventData, html, live_view::Updated, stream::Stream, Html, LiveView,
+//! };
+//!
+//! struct Entry {
+//!     id: u64,
+//!     text: String,
+//! }
+//!
+//! struct Log {
+//!     entries: Stream<u64, Entry>,
+//! }
+//!
+//! impl LiveView for Log {
+//!     type Message = Msg;
+//!
+//!     fn update(mut self, msg: Msg, _: Option<EventData>) -> Updated<Self> {
+//!         match msg {
+//!             Msg::Append(id, text) => self.entries.insert(id, Entry { id, text }),
+//!             Msg::Delete(id) => self.entries.delete(&id),
+//!             Msg::Clear => self.entries.reset(),
+//!         }
+//!         Updated::new(self)
+//!     }
+//!
+//!     fn render(&self) -> Html<Msg> {
+//!         html! {
+//!             <ul>
+//!                 {
+//!                     self.entries.render(|entry| html! {
+//!                         <li>
+//!                             { &entry.text }
+//!                             <button axm-click={ Msg::Delete(entry.id) }>"Delete"</button>
+//!                         </li>
+//!                     })
+//!                 }
+//!             </ul>
+//!             <button axm-click={ Msg::Clear }>"Clear"</button>
+//!         }
+//!     }
+//! }
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! enum Msg {
+//!     Append(u64, String),
+//!     Delete(u64),
+//!     Clear,
+//! }
+//! ```
+//!
+//! # Protocol
+//!
+//! A stream is sent as `{"s": [<op>, ...]}`, with `"r": true` if the container must be emptied
+//! before applying the operations. Each operation is either
+//!
+//! - `{"i": "<key>", "a": <at>, "h": <html>}` to insert a row. `at` is the index to insert at, or
+//!   `-1` to append. If a row with the key already exists it is replaced in place.
+//! - `{"d": "<key>"}` to delete a row.
+//!
+//! Each batch of operations is sent once. Renders that don't change the stream don't include it
+//! in the diff.
+
+use crate::html::{DynamicFragment, HtmlBuilder, StreamFragment, StreamOp};
+use crate::Html;
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+};
+
+/// A collection whose items are sent to the client once and then forgotten.
+///
+/// `K` is used to identify items on the client, for example to delete them. It is sent as a
+/// string using its [`Display`](fmt::Display) implementation.
+///
+/// Changes are kept until the next time the stream is rendered, and dropped by the first change
+/// after that. See the [module docs](self) for more details.
+pub struct Stream<K, T> {
+    id: u64,
+    batch: u64,
+    reset: bool,
+    ops: Vec<Op<K, T>>,
+    rendered: AtomicBool,
+}
+
+enum Op<K, T> {
+    Insert { key: K, at: i64, item: T },
+    Delete { key: K },
+}
+
+impl<K, T> Stream<K, T>
+where
+    K: fmt::Display,
+{
+    /// Create a new empty stream.
+    pub fn new() -> Self {
+        Self {
+            id: next_id(),
+            batch: next_batch(),
+            reset: false,
+            ops: Vec::new(),
+            rendered: AtomicBool::new(false),
+        }
+    }
+
+    /// Append an item to the end of the stream.
+    ///
+    /// If an item with the same key has already been sent it is replaced in place.
+    pub fn insert(&mut self, key: K, item: T) {
+        self.insert_at(-1, key, item);
+    }
+
+    /// Insert an item at the start of the stream.
+    ///
+    /// If an item with the same key has already been sent it is replaced in place.
+    pub fn prepend(&mut self, key: K, item: T) {
+        self.insert_at(0, key, item);
+    }
+
+    /// Insert an item at `index`, or at the end of the stream if `index` is `-1`.
+    ///
+    /// The index refers to the rows the client currently shows. If an item with the same key has
+    /// already been sent it is replaced in place.
+    pub fn insert_at(&mut self, index: i64, key: K, item: T) {
+        self.ops_mut().push(Op::Insert {
+            key,
+            at: index,
+            item,
+        });
+    }
+
+    /// Delete the item with the given key.
+    pub fn delete(&mut self, key: &K)
+    where
+        K: Clone,
+    {
+        self.ops_mut().push(Op::Delete { key: key.clone() });
+    }
+
+    /// Remove all items, including those already sent to the client.
+    pub fn reset(&mut self) {
+        self.ops_mut().clear();
+        self.reset = true;
+    }
+
+    /// Render the changes made since the stream was last rendered.
+    ///
+    /// `render_item` is called once for each inserted item.
+    pub fn render<M, F>(&self, render_item: F) -> Html<M>
+    where
+        F: Fn(&T) -> Html<M>,
+    {
+        self.rendered.store(true, Ordering::SeqCst);
+
+        let ops = self
+            .ops
+            .iter()
+            .map(|op| match op {
+                Op::Insert { key, at, item } => StreamOp::Insert {
+                    key: key.to_string(),
+                    at: *at,
+                    html: render_item(item),
+                },
+                Op::Delete { key } => StreamOp::Delete {
+                    key: key.to_string(),
+                },
+            })
+            .collect();
+
+        HtmlBuilder {
+            fixed: &["", ""],
+            dynamic: Vec::from([DynamicFragment::Stream(StreamFragment {
+                stream: self.id,
+                batch: self.batch,
+                reset: self.reset,
+                ops,
+            })]),
+        }
+        .into_html()
+    }
+
+    /// The pending changes, starting a new batch if the current one has been rendered.
+    fn ops_mut(&mut self) -> &mut Vec<Op<K, T>> {
+        if std::mem::take(self.rendered.get_mut()) {
+            self.batch = next_batch();
+            self.reset = false;
+            self.ops.clear();
+        }
+        &mut self.ops
+    }
+}
+
+fn next_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    NEXT_ID.fetch_add(1, Ordering::SeqCst)
+}
+
+fn next_batch() -> u64 {
+    static NEXT_BATCH: AtomicU64 = AtomicU64::new(0);
+    NEXT_BATCH.fetch_add(1, Ordering::SeqCst)
+}
+
+impl<K, T> Default for Stream<K, T>
+where
+    K: fmt::Display,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, T> fmt::Debug for Stream<K, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stream")
+            .field("pending", &self.ops.len())
+            .field("reset", &self.reset)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html;
+    use serde_json::json;
+
+    fn render(stream: &Stream<u32, &'static str>) -> Html<()> {
+        html! {
+            <ul>
+                { stream.render(|item| html! { <li>{ item }</li> }) }
+            </ul>
+        }
+    }
+
+    #[test]
+    fn renders_pending_items() {
+        let mut stream = Stream::new();
+        stream.insert(1, "b");
+        stream.insert(2, "c");
+        stream.prepend(3, "a");
+        stream.insert(2, "C");
+        stream.delete(&1);
+
+        assert_eq!(render(&stream).render(), "<ul><li>a</li><li>C</li></ul>");
+    }
+
+    #[test]
+    fn changes_are_sent_once() {
+        let mut stream = Stream::new();
+        stream.insert(1, "a");
+        let first = render(&stream);
+
+        // rendering again without changes doesn't send anything
+        let second = render(&stream);
+        assert!(first.diff(&second).is_none());
+
+        stream.insert(2, "b");
+        let third = render(&stream);
+        let diff = serde_json::to_value(second.diff(&third)).unwrap();
+        assert_eq!(
+            diff,
+            json!({
+                "d": {
+                    "0": {
+                        "d": {
+                            "0": {
+                                "s": [
+                                    { "i": "2", "a": -1, "h": { "f": ["<li>", "</li>"], "d": { "0": "b" } } },
+                                ],
+                            },
+                        },
+                    },
+                },
+            })
+        );
+
+        // the first item has been forgotten
+        assert_eq!(third.render(), "<ul><li>b</li></ul>");
+    }
+
+    #[test]
+    fn reset() {
+        let mut stream = Stream::new();
+        stream.insert(1, "a");
+        let first = render(&stream);
+
+        stream.insert(2, "b");
+        stream.reset();
+        stream.insert(3, "c");
+        let second = render(&stream);
+
+        let diff = serde_json::to_value(first.diff(&second)).unwrap();
+        assert_eq!(
+            diff["d"]["0"]["d"]["0"],
+            json!({
+                "r": true,
+                "s": [
+                    { "i": "3", "a": -1, "h": { "f": ["<li>", "</li>"], "d": { "0": "c" } } },
+                ],
+            })
+        );
+    }
+}
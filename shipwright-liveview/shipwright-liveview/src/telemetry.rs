@@ -0,0 +1,315 @@
+//! Tracing spans and metrics for the live view life cycle.
+//!
+//! See the [root module docs](crate#tracing-and-metrics) for what is recorded.
+
+use serde::{ser, Serialize};
+use std::{any::type_name, fmt, time::Duration};
+
+/// The name views of type `L` are recorded as.
+pub(crate) fn view_name<L>() -> &'static str {
+    type_name::<L>()
+}
+
+/// The variant name of a message, or the name of its type if it isn't an enum.
+pub(crate) fn message_name<M>(msg: &M) -> &'static str
+where
+    M: Serialize,
+{
+    msg.serialize(VariantName)
+        .unwrap_or_else(|_| type_name::<M>())
+}
+
+/// The size of a diff as sent to the client with the JSON encoding.
+pub(crate) fn diff_bytes(diff: &serde_json::Value) -> usize {
+    struct Counter(usize);
+
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    serde_json::to_writer(&mut counter, diff).expect("failed to serialize HTML diff");
+    counter.0
+}
+
+/// Counts a view as connected until dropped.
+pub(crate) struct ConnectedView {
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    view: &'static str,
+}
+
+impl ConnectedView {
+    pub(crate) fn new(view: &'static str) -> Self {
+        #[cfg(feature = "metrics")]
+        metrics::gauge!("shipwright_liveview_views_connected", "view" => view).increment(1.0);
+        Self { view }
+    }
+}
+
+impl Drop for ConnectedView {
+    fn drop(&mut self) {
+        #[cfg(feature = "metrics")]
+        metrics::gauge!("shipwright_liveview_views_connected", "view" => self.view).decrement(1.0);
+    }
+}
+
+/// Record a message handled by a view.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_message(view: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("shipwright_liveview_messages_total", "view" => view).increment(1);
+}
+
+/// Record how long a view took to update and render, and the size of the diff.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_update(
+    view: &'static str,
+    update: Duration,
+    render: Duration,
+    diff_bytes: Option<usize>,
+) {
+    #[cfg(feature = "metrics")]
+    {
+        metrics::histogram!("shipwright_liveview_update_seconds", "view" => view)
+            .record(update.as_secs_f64());
+        metrics::histogram!("shipwright_liveview_render_seconds", "view" => view)
+            .record(render.as_secs_f64());
+        if let Some(bytes) = diff_bytes {
+            metrics::histogram!("shipwright_liveview_diff_bytes", "view" => view)
+                .record(bytes as f64);
+        }
+    }
+}
+
+/// Serializer that only gets the variant name of an enum.
+struct VariantName;
+
+#[derive(Debug)]
+struct NotAnEnum;
+
+impl fmt::Display for NotAnEnum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("not an enum")
+    }
+}
+
+impl std::error::Error for NotAnEnum {}
+
+impl ser::Error for NotAnEnum {
+    fn custom<T>(_msg: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        Self
+    }
+}
+
+/// The fields of a tuple or struct variant, which are ignored.
+struct VariantFields(&'static str);
+
+impl ser::SerializeTupleVariant for VariantFields {
+    type Ok = &'static str;
+    type Error = NotAnEnum;
+
+    fn serialize_field<T>(&mut self, _value: &T) -> Result<(), NotAnEnum>
+    where
+        T: Serialize + ?Sized,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<&'static str, NotAnEnum> {
+        Ok(self.0)
+    }
+}
+
+impl ser::SerializeStructVariant for VariantFields {
+    type Ok = &'static str;
+    type Error = NotAnEnum;
+
+    fn serialize_field<T>(&mut self, _key: &'static str, _value: &T) -> Result<(), NotAnEnum>
+    where
+        T: Serialize + ?Sized,
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<&'static str, NotAnEnum> {
+        Ok(self.0)
+    }
+}
+
+macro_rules! not_an_enum {
+    ($($method:ident($($ty:ty),*)),* $(,)?) => {
+        $(
+            fn $method(self, $(_: $ty),*) -> Result<&'static str, NotAnEnum> {
+                Err(NotAnEnum)
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for VariantName {
+    type Ok = &'static str;
+    type Error = NotAnEnum;
+    type SerializeSeq = ser::Impossible<&'static str, NotAnEnum>;
+    type SerializeTuple = ser::Impossible<&'static str, NotAnEnum>;
+    type SerializeTupleStruct = ser::Impossible<&'static str, NotAnEnum>;
+    type SerializeTupleVariant = VariantFields;
+    type SerializeMap = ser::Impossible<&'static str, NotAnEnum>;
+    type SerializeStruct = ser::Impossible<&'static str, NotAnEnum>;
+    type SerializeStructVariant = VariantFields;
+
+    not_an_enum! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(&'static str),
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<&'static str, NotAnEnum>
+    where
+        T: Serialize + ?Sized,
+    {
+        Err(NotAnEnum)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<&'static str, NotAnEnum> {
+        Ok(variant)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<&'static str, NotAnEnum>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<&'static str, NotAnEnum>
+    where
+        T: Serialize + ?Sized,
+    {
+        Ok(variant)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, NotAnEnum> {
+        Err(NotAnEnum)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, NotAnEnum> {
+        Err(NotAnEnum)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, NotAnEnum> {
+        Err(NotAnEnum)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, NotAnEnum> {
+        Ok(VariantFields(variant))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, NotAnEnum> {
+        Err(NotAnEnum)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, NotAnEnum> {
+        Err(NotAnEnum)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, NotAnEnum> {
+        Ok(VariantFields(variant))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Serialize)]
+    enum Msg {
+        Clear,
+        Delete(u64),
+        Move(u64, u64),
+        Rename { name: String },
+    }
+
+    #[derive(Serialize)]
+    struct Wrapper(Msg);
+
+    #[test]
+    fn message_names() {
+        assert_eq!(message_name(&Msg::Clear), "Clear");
+        assert_eq!(message_name(&Msg::Delete(1)), "Delete");
+        assert_eq!(message_name(&Msg::Move(1, 2)), "Move");
+        assert_eq!(
+            message_name(&Msg::Rename {
+                name: "a".to_owned()
+            }),
+            "Rename"
+        );
+        assert_eq!(message_name(&Wrapper(Msg::Clear)), "Clear");
+        assert_eq!(message_name(&()), "()");
+    }
+
+    #[test]
+    fn diff_size() {
+        let diff = json!({ "d": { "0": "hi" } });
+        assert_eq!(diff_bytes(&diff), diff.to_string().len());
+    }
+}
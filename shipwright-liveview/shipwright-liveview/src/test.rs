@@ -66,26 +66,30 @@
 
 use crate::{
     event_data::EventData,
+    flash::Flash,
     js_command::JsCommand,
     life_cycle::{UpdateResponse, ViewRequestError, ViewTaskHandle},
-    live_view::ViewHandle,
-    LiveView,
+    live_view::{AsyncLiveView, ShutdownReason, ViewHandle},
+    signing::SigningKey,
 };
-use http::{HeaderMap, Uri};
+use http::{Extensions, HeaderMap, Uri};
 use serde::Serialize;
 use std::fmt;
 
 /// Spawn a live view on a background task and get a handle that can simulate mounting the view.
 pub fn run_live_view<L>(view: L) -> TestViewHandleBuilder<L::Message>
 where
-    L: LiveView,
+    L: AsyncLiveView,
 {
-    let view_task_handle = crate::life_cycle::spawn_view(view, None);
+    let flash = Flash::new(SigningKey::from_extensions(&Default::default()));
+    let view_task_handle = crate::life_cycle::spawn_view(view, None, flash.clone(), None);
 
     TestViewHandleBuilder {
         handle: view_task_handle,
         uri: None,
         headers: None,
+        extensions: None,
+        flash,
     }
 }
 
@@ -101,25 +105,38 @@ where
     handle: ViewTaskHandle<M>,
     uri: Option<Uri>,
     headers: Option<HeaderMap>,
+    extensions: Option<Extensions>,
+    flash: Flash,
 }
 
 impl<M> TestViewHandleBuilder<M> {
     /// Set the URI [`LiveView::mount`] will be called with.
+    ///
+    /// [`LiveView::mount`]: crate::LiveView::mount
     pub fn mount_uri(mut self, uri: Uri) -> Self {
         self.uri = Some(uri);
         self
     }
 
     /// Set the headers [`LiveView::mount`] will be called with.
+    ///
+    /// [`LiveView::mount`]: crate::LiveView::mount
     pub fn mount_headers(mut self, headers: HeaderMap) -> Self {
         self.headers = Some(headers);
         self
     }
 
+    /// Set the request extensions available through [`ViewHandle::extensions`], such as those
+    /// an [`on_mount`](crate::on_mount) hook would insert.
+    pub fn mount_extensions(mut self, extensions: Extensions) -> Self {
+        self.extensions = Some(extensions);
+        self
+    }
+
     /// Call [`LiveView::mount`] on the view.
     ///
     /// If `Ok(())` is returned then you'll get a [`TestViewHandle`] which can be used to send
-    /// messages to the view.
+    /// messages to the view. Panics if mounting the view fails.
     ///
     /// Note the [`ViewHandle`] passed to [`LiveView::mount`] is a fake and wont actually be
     /// connected to the view. Thus [`ViewHandle::send`] will always return an error.
@@ -128,21 +145,25 @@ impl<M> TestViewHandleBuilder<M> {
     ///
     /// Also, the futures passed to [`Updated::spawn`] will be ignored, for the same reason.
     ///
+    /// [`LiveView::mount`]: crate::LiveView::mount
     /// [`Updated::spawn`]: crate::live_view::Updated::spawn
     pub async fn mount(self) -> TestViewHandle<M> {
         let (handle, rx) = ViewHandle::new();
         drop(rx);
+        let handle = handle.with_extensions(self.extensions.unwrap_or_default());
 
         let uri = self.uri.unwrap_or_else(|| "/".parse::<Uri>().unwrap());
         let headers = self.headers.unwrap_or_default();
 
         match self.handle.mount(uri, headers, handle).await {
             Ok(()) => {}
+            Err(ViewRequestError::View(err)) => panic!("failed to mount view: {err}"),
             Err(ViewRequestError::ChannelClosed(_)) => unreachable!(),
         }
 
         TestViewHandle {
             handle: self.handle,
+            flash: self.flash,
         }
     }
 }
@@ -153,6 +174,8 @@ impl<M> fmt::Debug for TestViewHandleBuilder<M> {
             .field("handle", &self.handle)
             .field("uri", &self.uri)
             .field("headers", &self.headers)
+            .field("extensions", &self.extensions)
+            .field("flash", &self.flash)
             .finish()
     }
 }
@@ -167,6 +190,7 @@ where
     M: 'static,
 {
     handle: ViewTaskHandle<M>,
+    flash: Flash,
 }
 
 impl<M> TestViewHandle<M>
@@ -177,6 +201,8 @@ where
     ///
     /// This calls [`LiveView::render`] on the view. This method is async because the view is
     /// running on a background task.
+    ///
+    /// [`LiveView::render`]: crate::LiveView::render
     pub async fn render(&self) -> String {
         self.handle.render_to_string().await.unwrap()
     }
@@ -185,35 +211,116 @@ where
     ///
     /// This calls [`LiveView::update`] on the view followed by [`LiveView::render`] and returns
     /// the HTML template and any [`JsCommand`]s included.
+    ///
+    /// Panics if updating or rendering the view fails.
+    ///
+    /// [`LiveView::update`]: crate::LiveView::update
+    /// [`LiveView::render`]: crate::LiveView::render
     pub async fn send(&self, msg: M, data: Option<EventData>) -> (String, Vec<JsCommand>) {
         let js_commands = match self.handle.update(msg, data).await {
             Ok(UpdateResponse::Diff(_) | UpdateResponse::Empty) => Vec::new(),
             Ok(UpdateResponse::JsCommands(cmds) | UpdateResponse::DiffAndJsCommands(_, cmds)) => {
                 cmds
             }
+            Err(ViewRequestError::View(err)) => panic!("failed to update view: {err}"),
             Err(ViewRequestError::ChannelClosed(_)) => unreachable!(),
         };
 
         let html = self.handle.render_to_string().await.unwrap();
         (html, js_commands)
     }
+
+    /// The flash messages set with [`Updated::put_flash`].
+    ///
+    /// [`Updated::put_flash`]: crate::live_view::Updated::put_flash
+    pub fn flash(&self) -> &Flash {
+        &self.flash
+    }
+
+    /// Shut the view down, calling [`LiveView::terminate`] with `reason`.
+    ///
+    /// [`LiveView::terminate`]: crate::LiveView::terminate
+    pub async fn terminate(self, reason: ShutdownReason) {
+        self.handle.terminate(reason).await;
+    }
 }
 
 impl<M> fmt::Debug for TestViewHandle<M> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("TestViewHandle")
             .field("handle", &self.handle)
+            .field("flash", &self.flash)
             .finish()
     }
 }
 
+/// A counter and the app serving it, shared by the crate's tests.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use crate::{
+        event_data::EventData, html, live_view::Updated, sse::SseLayer, Html, LiveView,
+        LiveViewUpgrade,
+    };
+    use axum::{handler::Handler, response::Response, routing::get, Router};
+    use serde::{Deserialize, Serialize};
+
+    /// Counts the clicks on its button.
+    #[derive(Default)]
+    pub(crate) struct Counter {
+        count: u32,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+    pub(crate) enum Msg {
+        Incr,
+        /// Makes the view panic.
+        Fail,
+    }
+
+    impl LiveView for Counter {
+        type Message = Msg;
+
+        fn update(mut self, msg: Msg, _data: Option<EventData>) -> Updated<Self> {
+            match msg {
+                Msg::Incr => self.count += 1,
+                Msg::Fail => panic!("failed on purpose"),
+            }
+            Updated::new(self)
+        }
+
+        fn render(&self) -> Html<Msg> {
+            html! {
+                <button axm-click={ Msg::Incr }>{ self.count }</button>
+            }
+        }
+    }
+
+    /// Serve `handler` at `/`, with Server-Sent Events for clients without WebSockets.
+    pub(crate) fn app<H, T>(handler: H) -> Router
+    where
+        H: Handler<T, ()>,
+        T: 'static,
+    {
+        Router::new()
+            .route("/", get(handler))
+            .layer(SseLayer::new())
+    }
+
+    /// Embed a [`Counter`] in the page.
+    pub(crate) async fn counter(live: LiveViewUpgrade) -> Response {
+        live.response(|embed| embed.embed(Counter::default()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate as axum_live_view;
     use crate::event_data::Input;
-    use crate::{live_view::Updated, Html};
-    use axum_live_view_macros::html;
+    use crate::html;
+    use crate::{
+        live_view::{Updated, ViewError},
+        Html, LiveView,
+    };
     use serde::Deserialize;
 
     #[tokio::test]
@@ -244,6 +351,79 @@ mod tests {
         assert!(html.contains("10"));
     }
 
+    #[tokio::test]
+    async fn test_async_view() {
+        let view = run_live_view(AsyncCounter::default()).mount().await;
+
+        let (html, _) = view.send(Msg::Incr, None).await;
+        assert!(html.contains('1'));
+
+        let (html, _) = view.send(Msg::Decr, None).await;
+        assert!(html.contains('0'));
+    }
+
+    #[tokio::test]
+    async fn flash_is_carried_over_navigate_to() {
+        let view = run_live_view(Saving).mount().await;
+
+        let (_, commands) = view.send((), None).await;
+        assert_eq!(view.flash().get("info").unwrap(), "Saved!");
+
+        let commands = serde_json::to_value(commands).unwrap();
+        assert_eq!(commands[0]["kind"]["t"], "set_cookie");
+        assert!(commands[0]["kind"]["cookie"]
+            .as_str()
+            .unwrap()
+            .starts_with("axm_flash="));
+        assert_eq!(commands[1]["kind"]["t"], "navigate_to");
+    }
+
+    struct Saving;
+
+    impl LiveView for Saving {
+        type Message = ();
+
+        fn update(self, _msg: (), _data: Option<EventData>) -> Updated<Self> {
+            Updated::new(self)
+                .put_flash("info", "Saved!")
+                .with(crate::js_command::navigate_to("/".parse().unwrap()))
+        }
+
+        fn render(&self) -> Html<Self::Message> {
+            html! { "Saving" }
+        }
+    }
+
+    #[derive(Default)]
+    struct AsyncCounter {
+        count: u64,
+    }
+
+    #[crate::live_view::async_trait]
+    impl AsyncLiveView for AsyncCounter {
+        type Message = Msg;
+
+        async fn update(
+            mut self,
+            msg: Msg,
+            _data: Option<EventData>,
+        ) -> Result<Updated<Self>, ViewError> {
+            tokio::task::yield_now().await;
+            match msg {
+                Msg::Incr => self.count += 1,
+                Msg::Decr => self.count = self.count.saturating_sub(1),
+                Msg::IncrBy => {}
+            }
+            Ok(Updated::new(self))
+        }
+
+        fn render(&self) -> Html<Self::Message> {
+            html! {
+                { self.count }
+            }
+        }
+    }
+
     #[derive(Default, Clone)]
     struct Counter {
         count: u64,
@@ -0,0 +1,406 @@
+//! Running live views on servers other than axum.
+//!
+//! [`LiveViewUpgrade`] connects views over axum's WebSockets. Other servers, such as actix-web or
+//! a plain `tokio-tungstenite` server, can run views with [`LiveViewSession`] instead. It takes
+//! the parts of the request that opened the socket along with any sink and stream of [`Frame`]s,
+//! so an adapter only has to convert between its own message type and `Frame`. In-memory
+//! channels work too, which is handy for tests.
+//!
+//! Views run exactly as they do behind [`LiveViewUpgrade`]. The [CSRF checks](crate::csrf),
+//! [limits](crate::limits), [shutdown](crate::shutdown) and error policy apply, configured
+//! through the request extensions in the same way. [Live navigation](crate::navigation) needs an
+//! axum router, so live redirects load the new page normally instead.
+//!
+//! # Example
+//!
+//! ```rust
+//! use futures_util::{Sink, Stream};
+//! use http::{request::Parts, HeaderMap};
+//! use shipwright_liveview::{
+//!     html,
+//!     transport::{Frame, LiveViewSession},
+//!     Html, LiveView,
+//! };
+//! use std::convert::Infallible;
+//!
+//! // called with the request for the page, returns the headers and body of the response
+//! fn page(parts: Parts) -> (HeaderMap, Html<()>) {
+//!     let session = LiveViewSession::new(parts);
+//!     let view = session.render(MyView);
+//!     let html = html! {
+//!         { view }
+//!         <script src="/assets/live-view.js"></script>
+//!     };
+//!     (session.response_headers(), html)
+//! }
+//!
+//! // called once the socket has been opened, with the request that opened it
+//! async fn socket<S, R>(parts: Parts, sink: S, stream: R)
+//! where
+//!     S: Sink<Frame> + Send + 'static,
+//!     S::Error: std::error::Error + Send + Sync + 'static,
+//!     R: Stream<Item = Result<Frame, Infallible>> + Send + 'static,
+//! {
+//!     LiveViewSession::new(parts).run(MyView, sink, stream).await;
+//! }
+//! # struct MyView;
+//! # impl LiveView for MyView {
+//! #     type Message = ();
+//! #     fn update(
+//! #         self,
+//! #         msg: (),
+//! #         data: Option<shipwright_liveview::event_data::EventData>,
+//! #     ) -> shipwright_liveview::live_view::Updated<Self> {
+//! #         todo!()
+//! #     }
+//! #     fn render(&self) -> shipwright_liveview::Html<()> {
+//! #         todo!()
+//! #     }
+//! # }
+//! ```
+//!
+//! # Protocol
+//!
+//! Frames are WebSocket messages. Text frames carry JSON, and binary frames carry upload chunks or
+//! MessagePack for sockets opened with `?encoding=msgpack`, the same as with
+//! [`LiveViewUpgrade`]. The session closes the socket by sending a [`Frame::Close`]. Pings are
+//! left to the transport.
+//!
+//! [`LiveViewUpgrade`]: crate::LiveViewUpgrade
+
+use crate::{
+    codec::Encoding,
+    csrf::{self, AllowedOrigins, CsrfCookie},
+    extract::{self, EmbedLiveView, ViewOptions},
+    flash::{self, Flash},
+    html::Html,
+    limits::SocketLimits,
+    live_view::{error::default_error_policy, AsyncLiveView, ErrorPolicy},
+    shutdown::Shutdown,
+    signing::SigningKey,
+    socket::Socket,
+};
+use axum::extract::ws;
+use bytes::Bytes;
+use futures_util::{Sink, SinkExt, Stream, StreamExt};
+use http::{header, request::Parts, HeaderMap};
+use std::{
+    error::Error as StdError,
+    fmt,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// A message sent over the socket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    /// A text message.
+    Text(String),
+    /// A binary message.
+    Binary(Bytes),
+    /// Close the socket.
+    Close {
+        /// The close code.
+        code: u16,
+        /// Why the socket was closed.
+        reason: String,
+    },
+}
+
+/// Runs a live view on any transport.
+///
+/// See the [module docs](self) for more details.
+pub struct LiveViewSession {
+    parts: Parts,
+    options: ViewOptions,
+    csrf_cookie: CsrfCookie,
+}
+
+impl LiveViewSession {
+    /// Create a session for a request.
+    ///
+    /// The request is the one for the page when [rendering](Self::render) it, and the one that
+    /// opened the socket when [running](Self::run) the view.
+    pub fn new(mut parts: Parts) -> Self {
+        let options = ViewOptions {
+            error_policy: default_error_policy(),
+            // needs an axum router to mount the next view
+            navigation: None,
+            flash: Flash::from_parts(&mut parts),
+            protect_messages: false,
+            limits: parts
+                .extensions
+                .get::<SocketLimits>()
+                .cloned()
+                .unwrap_or_default(),
+            shutdown: parts.extensions.get::<Shutdown>().cloned(),
+            extensions: parts.extensions.clone(),
+        };
+
+        let csrf_cookie = CsrfCookie::from_request(&parts.uri, &parts.headers);
+
+        Self {
+            parts,
+            options,
+            csrf_cookie,
+        }
+    }
+
+    /// Set the [`ErrorPolicy`] that decides what happens when the view fails.
+    ///
+    /// See [`LiveViewUpgrade::error_policy`](crate::LiveViewUpgrade::error_policy).
+    pub fn error_policy<P>(mut self, policy: P) -> Self
+    where
+        P: ErrorPolicy,
+    {
+        self.options.error_policy = Arc::new(policy);
+        self
+    }
+
+    /// Only accept messages the view has rendered.
+    ///
+    /// See [`LiveViewUpgrade::protect_messages`](crate::LiveViewUpgrade::protect_messages).
+    pub fn protect_messages(mut self) -> Self {
+        self.options.protect_messages = true;
+        self
+    }
+
+    /// Set the [`SocketLimits`] for this view, overriding those in the request extensions.
+    pub fn limits(mut self, limits: SocketLimits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+
+    /// Render a view for the page, along with the [CSRF token](crate::csrf) the client needs to
+    /// connect.
+    ///
+    /// The token is bound to a cookie, so the page's response needs the
+    /// [`response_headers`](Self::response_headers).
+    pub fn render<L>(&self, view: L) -> Html<L::Message>
+    where
+        L: AsyncLiveView,
+    {
+        let key = SigningKey::from_extensions(&self.parts.extensions);
+        let token = csrf::new_token(&key, &self.csrf_cookie);
+        EmbedLiveView::http(token, self.options.protect_messages).embed(view)
+    }
+
+    /// Headers to add to the response for the page, or the response that opens the socket.
+    ///
+    /// These set the cookie the [CSRF token](crate::csrf) is bound to if the browser doesn't have
+    /// it yet, and remove the cookie of a [flash](crate::flash) that was read for this request, so
+    /// it isn't shown again on the next page.
+    pub fn response_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(set_cookie) = self.csrf_cookie.set_cookie() {
+            headers.append(header::SET_COOKIE, set_cookie);
+        }
+        if flash::has_cookie(&self.parts.headers) {
+            headers.append(header::SET_COOKIE, flash::removal_cookie());
+        }
+        headers
+    }
+
+    /// Run a view on a connected socket until it's closed.
+    pub async fn run<L, S, R, E>(self, view: L, sink: S, stream: R)
+    where
+        L: AsyncLiveView,
+        S: Sink<Frame> + Send + 'static,
+        S::Error: Into<Box<dyn StdError + Send + Sync>>,
+        R: Stream<Item = Result<Frame, E>> + Send + 'static,
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        let Self { parts, options, .. } = self;
+        let socket = Socket::Frames(FrameSocket {
+            sink: Box::pin(sink.sink_map_err(axum::Error::new)),
+            stream: Box::pin(stream.map(|frame| frame.map_err(axum::Error::new))),
+        });
+        let encoding = Encoding::from_uri(&parts.uri);
+
+        let key = SigningKey::from_extensions(&parts.extensions);
+        let allowed_origins = parts
+            .extensions
+            .get::<AllowedOrigins>()
+            .cloned()
+            .unwrap_or_default();
+        if let Err(reason) = csrf::check_upgrade(&key, &allowed_origins, &parts.uri, &parts.headers)
+        {
+            tracing::debug!(uri = %parts.uri, reason, "rejecting live view socket");
+            extract::reject_socket(socket, reason).await;
+            return;
+        }
+
+        if let Some(shutdown) = options
+            .shutdown
+            .as_ref()
+            .filter(|shutdown| shutdown.is_shutting_down())
+        {
+            shutdown.reject_socket(socket, encoding).await;
+            return;
+        }
+
+        extract::run_view_on_socket(socket, encoding, view, parts.uri, parts.headers, options)
+            .await;
+    }
+}
+
+impl fmt::Debug for LiveViewSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LiveViewSession")
+            .field("uri", &self.parts.uri)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A socket made of a sink and stream of [`Frame`]s.
+pub(crate) struct FrameSocket {
+    sink: Pin<Box<dyn Sink<Frame, Error = axum::Error> + Send>>,
+    stream: Pin<Box<dyn Stream<Item = Result<Frame, axum::Error>> + Send>>,
+}
+
+impl Stream for FrameSocket {
+    type Item = Result<ws::Message, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.as_mut().poll_next(cx).map(|frame| {
+            frame.map(|frame| {
+                frame.map(|frame| match frame {
+                    Frame::Text(text) => ws::Message::Text(text.into()),
+                    Frame::Binary(data) => ws::Message::Binary(data),
+                    Frame::Close { code, reason } => ws::Message::Close(Some(ws::CloseFrame {
+                        code,
+                        reason: reason.into(),
+                    })),
+                })
+            })
+        })
+    }
+}
+
+impl Sink<ws::Message> for FrameSocket {
+    type Error = axum::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.sink.as_mut().poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: ws::Message) -> Result<(), Self::Error> {
+        let frame = match item {
+            ws::Message::Text(text) => Frame::Text(text.as_str().to_owned()),
+            ws::Message::Binary(data) => Frame::Binary(data),
+            ws::Message::Close(frame) => {
+                let (code, reason) = frame
+                    .map(|frame| (frame.code, frame.reason.to_string()))
+                    .unwrap_or((ws::close_code::NORMAL, String::new()));
+                Frame::Close { code, reason }
+            }
+            // answered by the transport
+            ws::Message::Ping(_) | ws::Message::Pong(_) => return Ok(()),
+        };
+        self.sink.as_mut().start_send(frame)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.sink.as_mut().poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.sink.as_mut().poll_close(cx)
+    }
+}
+
+impl fmt::Debug for FrameSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrameSocket").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test::fixtures::Counter, util::ReceiverStream};
+    use http::{Extensions, Request};
+    use serde_json::Value;
+    use std::convert::Infallible;
+    use tokio::sync::mpsc;
+    use tokio_util::sync::PollSender;
+
+    fn parts(uri: &str) -> Parts {
+        Request::builder().uri(uri).body(()).unwrap().into_parts().0
+    }
+
+    /// Run a view over in-memory channels, returning the ends the client would use.
+    fn connect(
+        uri: &str,
+        cookie: &str,
+    ) -> (
+        mpsc::Sender<Result<Frame, Infallible>>,
+        mpsc::Receiver<Frame>,
+    ) {
+        let (client_tx, server_rx) = mpsc::channel(8);
+        let (server_tx, client_rx) = mpsc::channel(8);
+
+        let mut parts = parts(uri);
+        parts
+            .headers
+            .insert(header::COOKIE, cookie.parse().unwrap());
+        let session = LiveViewSession::new(parts);
+        tokio::spawn(session.run(
+            Counter::default(),
+            PollSender::new(server_tx),
+            ReceiverStream::new(server_rx),
+        ));
+
+        (client_tx, client_rx)
+    }
+
+    fn json(frame: Frame) -> Value {
+        match frame {
+            Frame::Text(text) => serde_json::from_str(&text).unwrap(),
+            frame => panic!("expected a text frame, got {frame:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn runs_views_over_any_sink_and_stream() {
+        let (token, cookie) = csrf::test_token(&SigningKey::from_extensions(&Extensions::new()));
+        let (tx, mut rx) = connect(&format!("/?_csrf={token}"), &cookie);
+
+        let msg = json(rx.recv().await.unwrap());
+        assert_eq!(msg["t"], "i");
+
+        let click = r#"{"m":"%22Incr%22","t":"click"}"#;
+        tx.send(Ok(Frame::Text(click.to_owned()))).await.unwrap();
+
+        let msg = json(rx.recv().await.unwrap());
+        assert_eq!(msg["t"], "r");
+        assert!(msg.to_string().contains('1'));
+
+        // ending the stream ends the view
+        drop(tx);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_csrf_tokens() {
+        let (_tx, mut rx) = connect("/?_csrf=nope", "axm_csrf=nope");
+
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            Frame::Close {
+                code: 4002,
+                reason: "invalid CSRF token".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn renders_with_csrf_token() {
+        let html = LiveViewSession::new(parts("/"))
+            .render(Counter::default())
+            .render();
+
+        assert!(html.starts_with("<div id=\"live-view-container\" data-csrf-token=\""));
+    }
+}
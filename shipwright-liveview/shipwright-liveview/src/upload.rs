@@ -0,0 +1,834 @@
+//! File uploads over the live view socket.
+//!
+//! Uploads are enabled per view with [`ViewHandle::allow_upload`], usually from
+//! [`LiveView::mount`]. The returned [`Upload`] is kept in the view and used to consume files once
+//! they have been uploaded.
+//!
+//! File inputs opt in with the `axm-upload` attribute, set to the name the upload was allowed
+//! with:
+//!
+//! ```text
+//! <input type="file" axm-upload="avatar" />
+//! ```
+//!
+//! The client starts uploading as soon as files are picked. Picking files again in the same input
+//! cancels the uploads that haven't finished yet.
+//!
+//! While a file is transferred the view receives [`UploadEvent`]s: progress as chunks arrive,
+//! followed by either [`UploadEvent::Completed`] or [`UploadEvent::Failed`]. Files are written to
+//! a temporary file as they arrive, so uploads don't have to fit in memory. Files that are never
+//! consumed are deleted when the view shuts down.
+//!
+//! # Example
+//!
+//! ```
+//! use axum::http::{HeaderMap, Uri};
+//! use serde::{Deserialize, Serialize};
+//! use shipwright_liveview::{
+//!     event_data::EventData,
+//!     html,
+//!     live_view::{Updated, ViewHandle},
+//!     upload::{Upload, UploadConfig, UploadEvent},
+//!     Html, LiveView,
+//! };
+//!
+//! #[derive(Default)]
+//! struct Profile {
+//!     avatar: Option<Upload>,
+//!     progress: u8,
+//! }
+//!
+//! impl LiveView for Profile {
+//!     type Message = Msg;
+//!
+//!     fn mount(&mut self, _: Uri, _: &HeaderMap, handle: ViewHandle<Msg>) {
+//!         let config = UploadConfig::new()
+//!             .accept(["image/png", "image/jpeg"])
+//!             .max_file_size(5 * 1024 * 1024)
+//!             .max_entries(1);
+//!         self.avatar = Some(handle.allow_upload("avatar", config, Msg::Avatar));
+//!     }
+//!
+//!     fn update(mut self, msg: Msg, _: Option<EventData>) -> Updated<Self> {
+//!         match msg {
+//!             Msg::Avatar(UploadEvent::Progress { progress, .. }) => self.progress = progress,
+//!             Msg::Avatar(UploadEvent::Completed(entry)) => {
+//!                 if let Some(file) = self.avatar.as_ref().and_then(|avatar| avatar.consume(&entry)) {
+//!                     return Updated::new(self).spawn(async move {
+//!                         let _ = file.persist("avatars/user.png").await;
+//!                         Msg::Saved
+//!                     });
+//!                 }
+//!             }
+//!             Msg::Avatar(_) | Msg::Saved => {}
+//!         }
+//!         Updated::new(self)
+//!     }
+//!
+//!     fn render(&self) -> Html<Msg> {
+//!         html! {
+//!             <input type="file" axm-upload="avatar" />
+//!             <progress max="100" value={ self.progress }></progress>
+//!         }
+//!     }
+//! }
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! enum Msg {
+//!     Avatar(UploadEvent),
+//!     Saved,
+//! }
+//! ```
+//!
+//! # Protocol
+//!
+//! For each file the client first sends a message
+//!
+//! ```text
+//! {"t": "us", "n": "<upload name>", "r": <id>, "f": "<file name>", "ct": "<content type>", "s": <size>}
+//! ```
+//!
+//! where `id` is a number below 2^63 the client picks to identify the file on this socket. The
+//! server replies with `{"t": "u", "d": {"r": <id>, "e": null}}` if the file was accepted, or
+//! with the reason (such as `"too_large"`) in `e` if it wasn't. After that the client sends the
+//! file as binary messages, each starting with the id as a big endian `u64` followed by the next
+//! chunk of the file. The upload completes when `size` bytes have been received.
+//! `{"t": "uc", "r": <id>}` cancels an upload.
+//!
+//! [`ViewHandle::allow_upload`]: crate::live_view::ViewHandle::allow_upload
+//! [`LiveView::mount`]: crate::LiveView::mount
+
+use bytes::Bytes;
+use futures_util::stream::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::{fs, io::AsyncWriteExt, sync::mpsc};
+
+/// Configuration for an upload, passed to [`ViewHandle::allow_upload`].
+///
+/// By default any type of file is accepted, files can be at most 8 MB, and one file can be
+/// uploaded at a time.
+///
+/// [`ViewHandle::allow_upload`]: crate::live_view::ViewHandle::allow_upload
+#[derive(Debug, Clone)]
+pub struct UploadConfig {
+    accept: Vec<String>,
+    max_file_size: u64,
+    max_entries: usize,
+}
+
+impl UploadConfig {
+    /// Create a new `UploadConfig` with the default limits.
+    pub fn new() -> Self {
+        Self {
+            accept: Vec::new(),
+            max_file_size: 8 * 1024 * 1024,
+            max_entries: 1,
+        }
+    }
+
+    /// Only accept files of the given types.
+    ///
+    /// Each type is either a MIME type such as `image/png`, a wildcard such as `image/*`, or a
+    /// file extension such as `.png`. MIME types are matched against the content type reported by
+    /// the browser.
+    pub fn accept<I, S>(mut self, types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.accept = types
+            .into_iter()
+            .map(|ty| ty.into().to_ascii_lowercase())
+            .collect();
+        self
+    }
+
+    /// Set the maximum size of each file in bytes.
+    pub fn max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = bytes;
+        self
+    }
+
+    /// Set how many files can be in progress or waiting to be consumed at once.
+    pub fn max_entries(mut self, max: usize) -> Self {
+        self.max_entries = max;
+        self
+    }
+
+    fn accepts(&self, file_name: &str, content_type: &str) -> bool {
+        if self.accept.is_empty() {
+            return true;
+        }
+
+        let file_name = file_name.to_ascii_lowercase();
+        let content_type = content_type.to_ascii_lowercase();
+
+        self.accept.iter().any(|accept| {
+            if accept.starts_with('.') {
+                file_name.ends_with(accept.as_str())
+            } else if let Some(prefix) = accept.strip_suffix("/*") {
+                content_type
+                    .split_once('/')
+                    .is_some_and(|(ty, _)| ty == prefix)
+            } else {
+                *accept == content_type
+            }
+        })
+    }
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A file the client has uploaded or is uploading.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadEntry {
+    id: u64,
+    name: String,
+    file_name: String,
+    content_type: String,
+    size: u64,
+}
+
+impl UploadEntry {
+    /// The name of the upload, as passed to [`ViewHandle::allow_upload`].
+    ///
+    /// [`ViewHandle::allow_upload`]: crate::live_view::ViewHandle::allow_upload
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The name of the file, as reported by the browser.
+    ///
+    /// This is controlled by the client and shouldn't be used as a path without sanitizing it.
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// The content type of the file, as reported by the browser.
+    pub fn content_type(&self) -> &str {
+        &self.content_type
+    }
+
+    /// The size of the file in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// Events sent to a view about its uploads.
+///
+/// These are converted into messages for the view by the function passed to
+/// [`ViewHandle::allow_upload`].
+///
+/// [`ViewHandle::allow_upload`]: crate::live_view::ViewHandle::allow_upload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum UploadEvent {
+    /// More of a file has been received.
+    Progress {
+        /// The file being uploaded.
+        entry: UploadEntry,
+        /// How much of the file has been received, in percent.
+        progress: u8,
+    },
+    /// A file has been received and can be consumed with [`Upload::consume`].
+    Completed(UploadEntry),
+    /// A file was rejected or the upload failed.
+    Failed {
+        /// The file that failed.
+        entry: UploadEntry,
+        /// Why it failed.
+        error: UploadError,
+    },
+}
+
+/// Why an upload failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum UploadError {
+    /// The view hasn't allowed uploads with that name.
+    NotAllowed,
+    /// The type of the file isn't accepted.
+    NotAccepted,
+    /// The file is larger than the maximum file size.
+    TooLarge,
+    /// Too many files are already being uploaded.
+    TooManyEntries,
+    /// The client cancelled the upload.
+    Cancelled,
+    /// The file couldn't be written to disk.
+    Io,
+}
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAllowed => write!(f, "upload not allowed"),
+            Self::NotAccepted => write!(f, "file type not accepted"),
+            Self::TooLarge => write!(f, "file too large"),
+            Self::TooManyEntries => write!(f, "too many files"),
+            Self::Cancelled => write!(f, "upload cancelled"),
+            Self::Io => write!(f, "failed to write file"),
+        }
+    }
+}
+
+impl std::error::Error for UploadError {}
+
+/// An upload allowed with [`ViewHandle::allow_upload`].
+///
+/// Used to consume files once they've been uploaded.
+///
+/// [`ViewHandle::allow_upload`]: crate::live_view::ViewHandle::allow_upload
+#[derive(Clone)]
+pub struct Upload {
+    name: String,
+    registry: Registry,
+}
+
+impl Upload {
+    pub(crate) fn new(name: String, registry: Registry) -> Self {
+        Self { name, registry }
+    }
+
+    /// The name of the upload.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Take a completed file.
+    ///
+    /// Returns `None` if the file hasn't been completed, was already consumed, or belongs to a
+    /// different upload.
+    pub fn consume(&self, entry: &UploadEntry) -> Option<UploadedFile> {
+        if entry.name != self.name {
+            return None;
+        }
+
+        self.registry.0.lock().unwrap().completed.remove(&entry.id)
+    }
+}
+
+impl fmt::Debug for Upload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Upload").field("name", &self.name).finish()
+    }
+}
+
+/// A file that has been uploaded.
+///
+/// The file is stored in a temporary file which is deleted when the `UploadedFile` is dropped,
+/// unless it's moved somewhere else with [`UploadedFile::persist`].
+#[derive(Debug)]
+pub struct UploadedFile {
+    entry: UploadEntry,
+    path: TempPath,
+}
+
+impl UploadedFile {
+    /// The uploaded file.
+    pub fn entry(&self) -> &UploadEntry {
+        &self.entry
+    }
+
+    /// The path of the temporary file.
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Move the file to `path`.
+    pub async fn persist(self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+
+        if fs::rename(self.path(), path).await.is_err() {
+            // renaming doesn't work across file systems
+            fs::copy(self.path(), path).await?;
+            return Ok(());
+        }
+
+        self.path.keep();
+        Ok(())
+    }
+
+    /// Read the whole file into memory.
+    pub async fn bytes(self) -> io::Result<Bytes> {
+        fs::read(self.path()).await.map(Bytes::from)
+    }
+
+    /// Read the file as a stream of chunks.
+    ///
+    /// The temporary file is deleted once the stream is dropped.
+    pub fn into_stream(self) -> impl Stream<Item = io::Result<Bytes>> + Send + 'static {
+        async_stream::stream! {
+            let file = match fs::File::open(self.path()).await {
+                Ok(file) => file,
+                Err(err) => {
+                    yield Err(err);
+                    return;
+                }
+            };
+
+            let mut chunks = tokio_util::io::ReaderStream::new(file);
+            while let Some(chunk) = chunks.next().await {
+                yield chunk;
+            }
+
+            drop(self);
+        }
+    }
+}
+
+/// Path to a temporary file that is deleted on drop.
+#[derive(Debug)]
+struct TempPath(Option<PathBuf>);
+
+impl TempPath {
+    fn new() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.subsec_nanos());
+        let file_name = format!(
+            "shipwright-upload-{}-{}-{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            nanos,
+        );
+
+        Self(Some(std::env::temp_dir().join(file_name)))
+    }
+
+    fn as_path(&self) -> &Path {
+        self.0.as_deref().expect("temp path already kept")
+    }
+
+    fn keep(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        if let Some(path) = self.0.take() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Uploads allowed by a view, and completed files waiting to be consumed.
+///
+/// Shared by all [`ViewHandle`]s of a view.
+///
+/// [`ViewHandle`]: crate::live_view::ViewHandle
+#[derive(Clone, Default)]
+pub(crate) struct Registry(Arc<Mutex<RegistryState>>);
+
+#[derive(Default)]
+struct RegistryState {
+    allowed: HashMap<String, Allowed>,
+    completed: HashMap<u64, UploadedFile>,
+}
+
+struct Allowed {
+    config: UploadConfig,
+    events: mpsc::UnboundedSender<UploadEvent>,
+}
+
+impl Registry {
+    /// Allow uploads with the given name. Events are sent to the returned channel.
+    pub(crate) fn allow(
+        &self,
+        name: &str,
+        config: UploadConfig,
+    ) -> mpsc::UnboundedReceiver<UploadEvent> {
+        let (events, rx) = mpsc::unbounded_channel();
+        self.0
+            .lock()
+            .unwrap()
+            .allowed
+            .insert(name.to_owned(), Allowed { config, events });
+        rx
+    }
+}
+
+/// Message from the client to start uploading a file.
+#[derive(Debug, Deserialize, PartialEq)]
+pub(crate) struct UploadStart {
+    #[serde(rename = "n")]
+    name: String,
+    #[serde(rename = "r")]
+    id: u64,
+    #[serde(rename = "f")]
+    file_name: String,
+    #[serde(rename = "ct")]
+    content_type: String,
+    #[serde(rename = "s")]
+    size: u64,
+}
+
+impl UploadStart {
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+/// A binary message from the client with part of a file.
+#[derive(Debug, PartialEq)]
+pub(crate) struct UploadChunk {
+    id: u64,
+    data: Bytes,
+}
+
+impl UploadChunk {
+    /// Parse a binary message. The first eight bytes are the upload id.
+    pub(crate) fn parse(mut data: Bytes) -> Option<Self> {
+        if data.len() < 8 {
+            return None;
+        }
+
+        let id = data.split_to(8);
+        let id = u64::from_be_bytes(id.as_ref().try_into().ok()?);
+        Some(Self { id, data })
+    }
+}
+
+/// Files currently being uploaded on a socket.
+pub(crate) struct ActiveUploads {
+    registry: Registry,
+    active: HashMap<u64, ActiveUpload>,
+}
+
+struct ActiveUpload {
+    entry: UploadEntry,
+    file: fs::File,
+    path: TempPath,
+    received: u64,
+    progress: u8,
+    events: mpsc::UnboundedSender<UploadEvent>,
+}
+
+impl ActiveUploads {
+    pub(crate) fn new(registry: Registry) -> Self {
+        Self {
+            registry,
+            active: HashMap::new(),
+        }
+    }
+
+    /// Validate and start an upload.
+    ///
+    /// The view is sent [`UploadEvent::Failed`] if the upload is rejected.
+    pub(crate) async fn start(&mut self, start: UploadStart) -> Result<(), UploadError> {
+        let UploadStart {
+            name,
+            id,
+            file_name,
+            content_type,
+            size,
+        } = start;
+
+        if let Some(previous) = self.active.remove(&id) {
+            tracing::debug!(id, "client reused upload id, cancelling previous upload");
+            previous.fail(UploadError::Cancelled);
+        }
+
+        let entry = UploadEntry {
+            id,
+            name,
+            file_name,
+            content_type,
+            size,
+        };
+
+        let events = {
+            let registry = self.registry.0.lock().unwrap();
+
+            let allowed = match registry.allowed.get(&entry.name) {
+                Some(allowed) => allowed,
+                None => return Err(UploadError::NotAllowed),
+            };
+
+            let in_use = self
+                .active
+                .values()
+                .filter(|upload| upload.entry.name == entry.name)
+                .count()
+                + registry
+                    .completed
+                    .values()
+                    .filter(|file| file.entry.name == entry.name)
+                    .count();
+
+            let result = if !allowed
+                .config
+                .accepts(&entry.file_name, &entry.content_type)
+            {
+                Err(UploadError::NotAccepted)
+            } else if entry.size > allowed.config.max_file_size {
+                Err(UploadError::TooLarge)
+            } else if in_use >= allowed.config.max_entries {
+                Err(UploadError::TooManyEntries)
+            } else {
+                Ok(())
+            };
+
+            if let Err(error) = result {
+                let _ = allowed.events.send(UploadEvent::Failed { entry, error });
+                return Err(error);
+            }
+
+            allowed.events.clone()
+        };
+
+        let path = TempPath::new();
+        let file = match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path.as_path())
+            .await
+        {
+            Ok(file) => file,
+            Err(err) => {
+                tracing::error!(%err, "failed to create file for upload");
+                let _ = events.send(UploadEvent::Failed {
+                    entry,
+                    error: UploadError::Io,
+                });
+                return Err(UploadError::Io);
+            }
+        };
+
+        let upload = ActiveUpload {
+            entry,
+            file,
+            path,
+            received: 0,
+            progress: 0,
+            events,
+        };
+
+        if upload.entry.size == 0 {
+            self.complete(upload).await;
+        } else {
+            self.active.insert(id, upload);
+        }
+
+        Ok(())
+    }
+
+    /// Write a chunk of a file.
+    pub(crate) async fn write(&mut self, chunk: UploadChunk) {
+        let UploadChunk { id, data } = chunk;
+
+        let mut upload = match self.active.remove(&id) {
+            Some(upload) => upload,
+            None => {
+                tracing::trace!(id, "ignoring chunk for unknown upload");
+                return;
+            }
+        };
+
+        let received = upload.received + data.len() as u64;
+        if received > upload.entry.size {
+            upload.fail(UploadError::TooLarge);
+            return;
+        }
+
+        if let Err(err) = upload.file.write_all(&data).await {
+            tracing::error!(%err, "failed to write upload");
+            upload.fail(UploadError::Io);
+            return;
+        }
+
+        upload.received = received;
+        let progress = (received * 100 / upload.entry.size) as u8;
+        if progress > upload.progress {
+            upload.progress = progress;
+            let _ = upload.events.send(UploadEvent::Progress {
+                entry: upload.entry.clone(),
+                progress,
+            });
+        }
+
+        if received == upload.entry.size {
+            self.complete(upload).await;
+        } else {
+            self.active.insert(id, upload);
+        }
+    }
+
+    /// Cancel an upload.
+    pub(crate) fn cancel(&mut self, id: u64) {
+        if let Some(upload) = self.active.remove(&id) {
+            upload.fail(UploadError::Cancelled);
+        }
+    }
+
+    async fn complete(&mut self, upload: ActiveUpload) {
+        let ActiveUpload {
+            entry,
+            mut file,
+            path,
+            events,
+            ..
+        } = upload;
+
+        if let Err(err) = file.flush().await {
+            tracing::error!(%err, "failed to write upload");
+            let _ = events.send(UploadEvent::Failed {
+                entry,
+                error: UploadError::Io,
+            });
+            return;
+        }
+
+        self.registry.0.lock().unwrap().completed.insert(
+            entry.id,
+            UploadedFile {
+                entry: entry.clone(),
+                path,
+            },
+        );
+        let _ = events.send(UploadEvent::Completed(entry));
+    }
+}
+
+impl ActiveUpload {
+    fn fail(self, error: UploadError) {
+        let _ = self.events.send(UploadEvent::Failed {
+            entry: self.entry,
+            error,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live_view::ViewHandle;
+
+    #[tokio::test]
+    async fn upload_and_consume() {
+        let (handle, mut rx) = ViewHandle::new();
+        let upload = handle.allow_upload(
+            "avatar",
+            UploadConfig::new().accept([".png"]).max_file_size(8),
+            |event| event,
+        );
+        let mut uploads = ActiveUploads::new(handle.uploads().clone());
+
+        uploads
+            .start(start("avatar", 1, "me.png", 6))
+            .await
+            .unwrap();
+        uploads.write(chunk(1, b"abc")).await;
+        uploads.write(chunk(1, b"def")).await;
+
+        let entry = match rx.recv().await.unwrap() {
+            UploadEvent::Progress { entry, progress } => {
+                assert_eq!(progress, 50);
+                entry
+            }
+            other => panic!("unexpected event: {other:?}"),
+        };
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            UploadEvent::Progress {
+                entry: entry.clone(),
+                progress: 100,
+            }
+        );
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            UploadEvent::Completed(entry.clone())
+        );
+
+        let file = upload.consume(&entry).unwrap();
+        assert!(upload.consume(&entry).is_none());
+        let path = file.path().to_owned();
+        assert_eq!(file.bytes().await.unwrap(), "abcdef");
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn reject_invalid_uploads() {
+        let (handle, mut rx) = ViewHandle::new();
+        let _upload = handle.allow_upload(
+            "avatar",
+            UploadConfig::new()
+                .accept(["image/*"])
+                .max_file_size(8)
+                .max_entries(1),
+            |event| event,
+        );
+        let mut uploads = ActiveUploads::new(handle.uploads().clone());
+
+        async fn failure(rx: &mut mpsc::Receiver<UploadEvent>) -> UploadError {
+            match rx.recv().await.unwrap() {
+                UploadEvent::Failed { error, .. } => error,
+                other => panic!("unexpected event: {other:?}"),
+            }
+        }
+
+        let err = uploads.start(start("other", 1, "me.png", 1)).await;
+        assert_eq!(err, Err(UploadError::NotAllowed));
+
+        let err = uploads.start(start("avatar", 1, "notes.txt", 1)).await;
+        assert_eq!(err, Err(UploadError::NotAccepted));
+        assert_eq!(failure(&mut rx).await, UploadError::NotAccepted);
+
+        let err = uploads.start(start("avatar", 1, "me.png", 9)).await;
+        assert_eq!(err, Err(UploadError::TooLarge));
+        assert_eq!(failure(&mut rx).await, UploadError::TooLarge);
+
+        uploads
+            .start(start("avatar", 1, "me.png", 2))
+            .await
+            .unwrap();
+        let err = uploads.start(start("avatar", 2, "me.png", 2)).await;
+        assert_eq!(err, Err(UploadError::TooManyEntries));
+        assert_eq!(failure(&mut rx).await, UploadError::TooManyEntries);
+
+        // sending more than announced fails the upload
+        uploads.write(chunk(1, b"abc")).await;
+        assert_eq!(failure(&mut rx).await, UploadError::TooLarge);
+    }
+
+    fn start(name: &str, id: u64, file_name: &str, size: u64) -> UploadStart {
+        let content_type = if file_name.ends_with(".png") {
+            "image/png"
+        } else {
+            "text/plain"
+        };
+
+        UploadStart {
+            name: name.to_owned(),
+            id,
+            file_name: file_name.to_owned(),
+            content_type: content_type.to_owned(),
+            size,
+        }
+    }
+
+    fn chunk(id: u64, data: &'static [u8]) -> UploadChunk {
+        let mut bytes = id.to_be_bytes().to_vec();
+        bytes.extend_from_slice(data);
+        UploadChunk::parse(Bytes::from(bytes)).unwrap()
+    }
+}
@@ -3,6 +3,18 @@ mod stream_ext;
 
 pub(crate) use self::{receiver_stream::ReceiverStream, stream_ext::StreamExt};
 
+/// The value of the cookie called `name`, if the request has one that isn't empty.
+pub(crate) fn cookie<'a>(headers: &'a axum::http::HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(axum::http::header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(cookie_name, value)| *cookie_name == name && !value.is_empty())
+        .map(|(_, value)| value)
+}
+
 /// Spawn a future that is required to yield `()`.
 ///
 /// This means the future is required to handle all errors.
@@ -12,3 +24,27 @@ where
 {
     tokio::spawn(future)
 }
+
+/// Check the cases in `assets/test/fixtures/<file>` are those written by the crate.
+///
+/// The fixtures are used by the tests of the client. Run with `UPDATE_CLIENT_FIXTURES=1` to write
+/// the fixture after changing what the crate sends.
+#[cfg(test)]
+pub(crate) fn assert_client_fixture(file: &str, cases: Vec<serde_json::Value>) {
+    let fixture = serde_json::Value::from(cases);
+
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../assets/test/fixtures")
+        .join(file);
+    if std::env::var_os("UPDATE_CLIENT_FIXTURES").is_some() {
+        let json = serde_json::to_string_pretty(&fixture).unwrap();
+        std::fs::write(&path, json + "\n").unwrap();
+    }
+
+    let checked_in = std::fs::read_to_string(&path).unwrap();
+    let checked_in = serde_json::from_str::<serde_json::Value>(&checked_in).unwrap();
+    assert!(
+        checked_in == fixture,
+        "{file} is out of date, run the test with UPDATE_CLIENT_FIXTURES=1 to update it"
+    );
+}